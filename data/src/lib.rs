@@ -1,4 +1,9 @@
 use std::fmt;
+use std::ops::Range;
+
+use serde::{Serialize, Deserialize};
+
+pub mod theory;
 
 /// The literal type for guitar tab notation.
 /// 
@@ -11,24 +16,57 @@ use std::fmt;
 /// let op_lit = Literal::Options(String::from("time=4/4; fidelity=16"));
 /// let no_lit = Literal::None;
 /// ```
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum Literal {
     /// A literal number.
     Number(u32),
     /// A literal string of options.
     Options(String),
+    /// A literal list of front matter entries, each with the line it is written on, its key, and its
+    /// value. Values are kept apart so they can contain the `;` and `=` chars that separate options.
+    FrontMatter(Vec<(u32, String, String)>),
+    /// A literal string of text, such as a lyric syllable.
+    Text(String),
+    /// A literal list of frets played on every string of a chord, from the lowest string; muted strings
+    /// are `None`.
+    Chord(Vec<Option<u32>>),
+    /// A literal note duration, where `4` is a quarter note and `8` an eighth note, and whether the
+    /// duration is dotted to last half as long again.
+    Duration(u32, bool),
     /// No literal.
     None,
 }
 
 /// The token type for guitar tab notation.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum TokenType {
     /* single character tokens */
     /// A single char representing a blank space: `.`
     Empty,
     /// A single char command that fills in the rest of the tab with empty chars: `,`
     Next,
+    /// A single char marker that highlights the number following it: `*`
+    Highlight,
+    /// A single char between two numbers marking a hammer-on from the first to the second: `h`
+    HammerOn,
+    /// A single char between two numbers marking a pull-off from the first to the second: `p`
+    PullOff,
+    /// A single char between two numbers marking a bend from the first to the second: `b`
+    Bend,
+    /// A single char between two numbers marking a release from the first to the second: `r`
+    Release,
+    /// A single char between two numbers marking a slide up from the first to the second: `/`
+    SlideUp,
+    /// A single char between two numbers marking a slide down from the first to the second: `\`
+    SlideDown,
+    /// A single char after a number marking it as played with vibrato: `~`
+    Vibrato,
+    /// A single char after a number marking it as accented: `>`
+    Accent,
+    /// A single char after a number marking it as played staccato: `'`
+    Staccato,
+    /// A single char starting the next voice of the staff from its first beat: `@`
+    Voice,
     /* one or two character tokens */
     /// A single or two char representation of a note: `[A-G][b#]?`
     Note,
@@ -37,11 +75,71 @@ pub enum TokenType {
     SpreadEmpty,
     /// A multi-char representation of next commands: `;[0-9]+`
     SpreadNext,
+    /// A multi-char representation of a rest and its duration: `r[0-9]+`
+    Rest,
+    /// A single char bar-line checking that the measure before it is complete: `|`
+    BarLine,
+    /// A two char bar-line marking the start of a repeated section: `|:`
+    RepeatStart,
+    /// A multi-char bar-line marking the end of a repeated section and how many times it is played: `:|(x[0-9]+)?`
+    RepeatEnd,
+    /// A single-char representation of the end of a tuplet or a technique span: `}`
+    GroupEnd,
     /* literals */
     /// A multi-char representation of a number: `[0-9]+`
     Number,
     /// A multi-char representation of option commands: `[time=4/4; fidelity=16]`
     Options,
+    /// A multi-char representation of option commands that apply to the next staff only: `[[fidelity=32]]`
+    ScopedOptions,
+    /// A multi-char representation of frets played together on every string: `(3 2 0 0 3 3)` or `x32010`
+    Chord,
+    /// A single-char representation of a tap on the number after it: `t[0-9]+`
+    Tap,
+    /// A single-char representation of a slap on the number after it: `s[0-9]+`
+    Slap,
+    /// A single-char representation of a pop on the number after it: `P[0-9]+`
+    Pop,
+    /// A single-char representation of a tie continuing the tab before it on the same string: `=` or `_`
+    Tie,
+    /// A representation of a grace note leading into the number after it: `g[0-9]+~`
+    GraceNote,
+    /// A two char representation of a pinch harmonic played on the number before it: `[0-9]+ph`
+    PinchHarmonic,
+    /// A multi-char representation of a natural harmonic played over the fret within it: `<12>`
+    NaturalHarmonic,
+    /// A multi-char representation of the start of a tuplet fitting the amount of beats it is written
+    /// with into the space of fewer beats: `[0-9]+{`
+    TupletStart,
+    /// A multi-char representation of the start of a span of beats played with a technique: `pm{` for
+    /// palm muting or `lr{` to let notes ring
+    SpanStart,
+    /// A multi-char representation of how long the number before it rings: `:[0-9]+\.?`
+    Duration,
+    /// A multi-char representation of the finger fretting the number before it: `([1-4T])`
+    Fingering,
+    /// A multi-char representation of a lyric syllable sung at the current beat: `"la"`
+    Lyric,
+    /// A multi-char representation of a chord name printed above the current beat: `<Am7>`
+    ChordName,
+    /// A multi-char representation of a barre across every string from the current beat: `B[0-9]+`
+    Barre,
+    /// A multi-char representation of the fret the index finger is placed at from the current beat:
+    /// `pos[0-9]+`
+    HandPosition,
+    /// A multi-char directive labeling the staffs that follow as a section of the song: `@verse` or
+    /// `@section("Bridge")`
+    Section,
+    /// A single-char representation of a drum hit in drum mode: `x` for a hit, `o` for an open hit, or
+    /// `g` for a ghost note
+    Hit,
+    /// A blank line separating staffs.
+    StaffBreak,
+    /// A multi-line block of `key: value` options at the start of the file enclosed by `---` lines.
+    FrontMatter,
+    /// A comment running to the end of the line, `// text`, or enclosed in a block that may span lines,
+    /// `/* text */`
+    Comment,
     /* others */
     /// The end of the file.
     EndOfFile,
@@ -52,11 +150,49 @@ impl fmt::Display for TokenType {
         write!(f, "{}", match self {
             TokenType::Empty => "Empty",
             TokenType::Next => "Next",
+            TokenType::Highlight => "Highlight",
+            TokenType::HammerOn => "Hammer On",
+            TokenType::PullOff => "Pull Off",
+            TokenType::Bend => "Bend",
+            TokenType::Release => "Release",
+            TokenType::SlideUp => "Slide Up",
+            TokenType::SlideDown => "Slide Down",
+            TokenType::Vibrato => "Vibrato",
+            TokenType::Accent => "Accent",
+            TokenType::Staccato => "Staccato",
+            TokenType::Voice => "Voice",
             TokenType::Note => "Note",
             TokenType::SpreadEmpty => "Spread Empty",
             TokenType::SpreadNext => "Spread Next",
+            TokenType::Rest => "Rest",
+            TokenType::BarLine => "Bar Line",
+            TokenType::RepeatStart => "Repeat Start",
+            TokenType::RepeatEnd => "Repeat End",
+            TokenType::GroupEnd => "Group End",
             TokenType::Number => "Number",
             TokenType::Options => "Options",
+            TokenType::ScopedOptions => "Scoped Options",
+            TokenType::Chord => "Chord",
+            TokenType::Tap => "Tap",
+            TokenType::Slap => "Slap",
+            TokenType::Pop => "Pop",
+            TokenType::Tie => "Tie",
+            TokenType::GraceNote => "Grace Note",
+            TokenType::PinchHarmonic => "Pinch Harmonic",
+            TokenType::NaturalHarmonic => "Natural Harmonic",
+            TokenType::TupletStart => "Tuplet Start",
+            TokenType::SpanStart => "Span Start",
+            TokenType::Duration => "Duration",
+            TokenType::Fingering => "Fingering",
+            TokenType::Lyric => "Lyric",
+            TokenType::ChordName => "Chord Name",
+            TokenType::Barre => "Barre",
+            TokenType::HandPosition => "Hand Position",
+            TokenType::Section => "Section",
+            TokenType::Hit => "Hit",
+            TokenType::StaffBreak => "Staff Break",
+            TokenType::FrontMatter => "Front Matter",
+            TokenType::Comment => "Comment",
             TokenType::EndOfFile => "EndOfFile",
         })
     }
@@ -73,7 +209,9 @@ impl fmt::Display for TokenType {
 ///     type_of: TokenType::Number,
 ///     value: String::from("4"),
 ///     literal: Literal::Number(4),
-///     line: 1
+///     line: 1,
+///     column: 0,
+///     span: 0..0,
 /// };
 /// let from_new = Token::new(TokenType::Number, String::from("4"), Literal::Number(4), 1);
 ///
@@ -83,7 +221,10 @@ impl fmt::Display for TokenType {
 ///
 /// assert_ne!(from_new, diff);
 /// ```
-#[derive(Debug, PartialEq)]
+/// 
+/// Tokens are compared by their type, value, literal, and line; where a token was found within its
+/// line is not compared.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Token {
     /// The token's type.
     pub type_of: TokenType,
@@ -94,6 +235,13 @@ pub struct Token {
     pub literal: Literal,
     /// The line the token was found on in the file.
     pub line: u32,
+    /// The column the token starts at within its line, counted in characters from `1`; `0` when
+    /// unknown.
+    #[serde(default)]
+    pub column: u32,
+    /// The byte range of the token within the file.
+    #[serde(default)]
+    pub span: Range<usize>,
 }
 
 impl Token {
@@ -108,7 +256,62 @@ impl Token {
     /// let note_token = Token::new(TokenType::Note, String::from("A#"), Literal::None, 2);
     /// ```
     pub fn new(type_of: TokenType, value: String, literal: Literal, line: u32) -> Token {
-        Token { type_of, value, literal, line }
+        Token { type_of, value, literal, line, column: 0, span: 0..0 }
+    }
+
+    /// Returns the token with the column it starts at and its byte range within the file set.
+    pub fn at(self, column: u32, span: Range<usize>) -> Token {
+        Token { column, span, ..self }
+    }
+}
+
+impl PartialEq for Token {
+    fn eq(&self, other: &Token) -> bool {
+        self.type_of == other.type_of && self.value == other.value && self.literal == other.literal && self.line == other.line
+    }
+}
+
+/// A token that borrows its value from the source it was generated from, so no `String` is allocated for
+/// the value of every token.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use data::{BorrowedToken, Token, TokenType, Literal};
+/// 
+/// let source = "E 27";
+/// let token = BorrowedToken {
+///     type_of: TokenType::Number,
+///     value: &source[2..4],
+///     literal: Literal::Number(27),
+///     line: 1,
+///     column: 3,
+///     span: 2..4,
+/// };
+/// 
+/// assert_eq!(Token::new(TokenType::Number, String::from("27"), Literal::Number(27), 1), token.to_token());
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct BorrowedToken<'a> {
+    /// The token's type.
+    pub type_of: TokenType,
+    /// The token's value; the slice of the source it was read from.
+    pub value: &'a str,
+    /// The literal representation of the token's value. Could be `Literal::None` meaning a literal
+    /// representation is not possible for this token.
+    pub literal: Literal,
+    /// The line the token was found on in the file.
+    pub line: u32,
+    /// The column the token starts at within its line, counted in characters from `1`.
+    pub column: u32,
+    /// The byte range of the token within the file.
+    pub span: Range<usize>,
+}
+
+impl BorrowedToken<'_> {
+    /// Returns an owned token with a copy of the value of this token.
+    pub fn to_token(&self) -> Token {
+        Token::new(self.type_of.clone(), self.value.to_string(), self.literal.clone(), self.line).at(self.column, self.span.clone())
     }
 }
 
@@ -118,47 +321,250 @@ impl fmt::Display for Token {
     }
 }
 
-/// Struct for logging errors.
+/// How serious a diagnostic is. Errors stop output from being generated while warnings do not.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Severity {
+    /// A problem that stops output from being generated.
+    Error,
+    /// A possible mistake that does not stop output from being generated.
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", match self {
+            Severity::Error => "Error",
+            Severity::Warning => "Warning",
+        })
+    }
+}
+
+/// A single error or warning found while interpreting a guitar tab file.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use data::{Diagnostic, Severity};
+/// 
+/// let diagnostic = Diagnostic::new(Severity::Error, 3, "L001", String::from("Unknown character value: ?"));
+/// 
+/// assert_eq!("L001", diagnostic.code);
+/// assert_eq!("[3] Error: Unknown character value: ?", diagnostic.to_string());
+/// assert_eq!("[3:7] Error: Unknown character value: ?", diagnostic.clone().with_column(7).to_string());
+/// 
+/// let located = diagnostic.with_column(3).with_source_line(String::from("E ? 3"));
+/// 
+/// assert_eq!(
+///     "[3:3] Error: Unknown character value: ?\n  |\n3 | E ? 3\n  |   ^",
+///     located.render()
+/// );
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct Diagnostic {
+    /// How serious the diagnostic is.
+    pub severity: Severity,
+    /// The line the diagnostic was found on in the file.
+    pub line: u32,
+    /// The column the diagnostic was found at within its line, if known.
+    pub column: Option<u32>,
+    /// The amount of characters from the column that are underlined when rendered; at least `1`.
+    pub length: u32,
+    /// The text of the line the diagnostic was found on, if known.
+    pub source_line: Option<String>,
+    /// A short code identifying the kind of diagnostic: `L` codes come from the lexer and `P` codes
+    /// from the parser.
+    pub code: &'static str,
+    /// The message to display to the user.
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// Creates a new diagnostic without a column.
+    pub fn new(severity: Severity, line: u32, code: &'static str, message: String) -> Diagnostic {
+        Diagnostic { severity, line, column: None, length: 1, code, message, source_line: None }
+    }
+
+    /// Returns the diagnostic with its column set.
+    pub fn with_column(self, column: u32) -> Diagnostic {
+        Diagnostic { column: Some(column), ..self }
+    }
+
+    /// Returns the diagnostic with the amount of underlined characters set.
+    pub fn with_length(self, length: u32) -> Diagnostic {
+        Diagnostic { length: length.max(1), ..self }
+    }
+
+    /// Returns the diagnostic with the text of its line set.
+    pub fn with_source_line(self, source_line: String) -> Diagnostic {
+        Diagnostic { source_line: Some(source_line), ..self }
+    }
+
+    /// Returns the diagnostic with the text of its line taken from the provided source, unless the text
+    /// of its line is already set.
+    pub fn with_source(self, source: &str) -> Diagnostic {
+        if self.source_line.is_some() {
+            return self
+        }
+        match source.lines().nth(self.line.saturating_sub(1) as usize) {
+            Some(source_line) => self.with_source_line(source_line.trim_end_matches('\r').to_string()),
+            None => self,
+        }
+    }
+
+    /// Renders the diagnostic followed by the line it was found on, with the columns it covers
+    /// underlined by carets when its column is known. Diagnostics without a source line render the same
+    /// as their display.
+    pub fn render(&self) -> String {
+        let source_line = match &self.source_line {
+            Some(source_line) => source_line,
+            None => return self.to_string(),
+        };
+        let line_number = self.line.to_string();
+        let gutter = " ".repeat(line_number.len());
+        let mut rendered = format!("{}\n{} |\n{} | {}", self, gutter, line_number, source_line);
+        if let Some(column) = self.column {
+            // carets stop at the end of the line
+            let width = source_line.chars().count() as u32;
+            let length = self.length.min((width + 1).saturating_sub(column)).max(1);
+            rendered += &format!(
+                "\n{} | {}{}",
+                gutter,
+                " ".repeat(column.saturating_sub(1) as usize),
+                "^".repeat(length as usize)
+            );
+        }
+        rendered
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.column {
+            Some(column) => write!(f, "[{}:{}] {}: {}", self.line, column, self.severity, self.message),
+            None => write!(f, "[{}] {}: {}", self.line, self.severity, self.message),
+        }
+    }
+}
+
+/// Struct for logging errors and warnings as diagnostics.
 /// 
 /// # Examples
 /// 
 /// ```
-/// use data::Watcher;
+/// use data::{Watcher, Severity};
 /// 
 /// let mut watcher = Watcher::new();
 /// 
-/// watcher.error(1, String::from("An error occurred here."));
-/// watcher.error(5, String::from("This was an error."));
+/// watcher.error(1, "L001", String::from("An error occurred here."));
+/// watcher.error(5, "L002", String::from("This was an error."));
+/// watcher.warning(7, "P003", String::from("This might be a mistake."));
 /// 
 /// assert_eq!(
 ///     "[1] Error: An error occurred here.\n[5] Error: This was an error.",
 ///     watcher.to_string()
 /// );
+/// assert_eq!("[7] Warning: This might be a mistake.", watcher.warnings());
+/// assert_eq!(3, watcher.diagnostics().len());
+/// assert_eq!(1, watcher.count(Severity::Warning));
+/// assert!(watcher.had_error());
 /// ```
 pub struct Watcher {
-    error_log: Vec<String>,
-    pub had_error: bool,
+    diagnostics: Vec<Diagnostic>,
+    /// The amount of diagnostics logged of every severity, indexed by severity.
+    counts: [usize; 2],
+    deny_warnings: bool,
 }
 
 impl Watcher {
     /// Creates a new watcher struct with default settings:
     /// 
-    /// `error_log = vec![], had_error = false`
+    /// `diagnostics = vec![], counts = [0, 0], deny_warnings = false`
     pub fn new() -> Watcher {
-        Watcher { error_log: vec![], had_error: false }
+        Watcher { diagnostics: vec![], counts: [0, 0], deny_warnings: false }
+    }
+
+    /// Sets whether warnings are promoted to errors when they are logged.
+    pub fn set_deny_warnings(&mut self, deny_warnings: bool) {
+        self.deny_warnings = deny_warnings;
+    }
+
+    /// Logs a diagnostic, counting it by its severity. Warnings are logged as errors when warnings are
+    /// denied.
+    pub fn report(&mut self, mut diagnostic: Diagnostic) {
+        if self.deny_warnings {
+            diagnostic.severity = Severity::Error;
+        }
+        self.counts[diagnostic.severity as usize] += 1;
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// Returns the amount of diagnostics of the provided severity that were logged.
+    pub fn count(&self, severity: Severity) -> usize {
+        self.counts[severity as usize]
+    }
+
+    /// Returns true if an error was logged.
+    pub fn had_error(&self) -> bool {
+        self.count(Severity::Error) > 0
+    }
+
+    /// Logs an error; line is the line number the error occurred at, code identifies the kind of error,
+    /// and message is the error message to display to the user.
+    pub fn error(&mut self, line: u32, code: &'static str, message: String) {
+        self.report(Diagnostic::new(Severity::Error, line, code, message));
+    }
+
+    /// Logs a warning; line is the line number the warning occurred at, code identifies the kind of
+    /// warning, and message is the warning message to display to the user. Warnings do not count as errors.
+    pub fn warning(&mut self, line: u32, code: &'static str, message: String) {
+        self.report(Diagnostic::new(Severity::Warning, line, code, message));
+    }
+
+    /// Returns every logged diagnostic in the order they were logged.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
     }
 
-    /// Logs an error; line is the line number the error occurred at, message is the error message
-    /// to display to the user.
-    pub fn error(&mut self, line: u32, message: String) {
-        self.error_log.push(format!("[{}] Error: {}", line, message));
-        self.had_error = true;
+    /// Returns every logged error diagnostic in the order they were logged.
+    pub fn errors(&self) -> Vec<Diagnostic> {
+        self.diagnostics.iter().filter(|diagnostic| diagnostic.severity == Severity::Error).cloned().collect()
+    }
+
+    /// Returns all logged warnings, one per line.
+    pub fn warnings(&self) -> String {
+        self.joined(Severity::Warning, Diagnostic::to_string)
+    }
+
+    /// Returns every logged error rendered with the line it was found on, separated by newlines.
+    pub fn render(&self) -> String {
+        self.joined(Severity::Error, Diagnostic::render)
+    }
+
+    /// Returns every logged warning rendered with the line it was found on, separated by newlines.
+    pub fn render_warnings(&self) -> String {
+        self.joined(Severity::Warning, Diagnostic::render)
+    }
+
+    /// Returns every logged diagnostic of the provided severity formatted by the provided function, one
+    /// per line.
+    fn joined(&self, severity: Severity, format: fn(&Diagnostic) -> String) -> String {
+        self.diagnostics.iter()
+            .filter(|diagnostic| diagnostic.severity == severity)
+            .map(format)
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+impl Default for Watcher {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 impl fmt::Display for Watcher {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.error_log.join("\n"))
+        write!(f, "{}", self.joined(Severity::Error, Diagnostic::to_string))
     }
 }
 
@@ -172,7 +578,9 @@ mod data_tests {
             type_of: TokenType::Number,
             value: String::from("4"),
             literal: Literal::Number(4),
-            line: 1
+            line: 1,
+            column: 0,
+            span: 0..0,
         };
         let from_new = Token::new(TokenType::Number, String::from("4"), Literal::Number(4), 1);
         
@@ -181,5 +589,68 @@ mod data_tests {
         let diff = Token::new(TokenType::Empty, String::from("."), Literal::None, 2);
 
         assert_ne!(from_new, diff);
+
+        // where a token was found within its line is not compared
+        assert_eq!(from_new.clone().at(5, 12..13), from_new);
+    }
+
+    #[test]
+    fn render_diagnostics() {
+        let error = Diagnostic::new(Severity::Error, 2, "L004", String::from("Unterminated options sequence."));
+        let warning = Diagnostic::new(Severity::Warning, 9, "P003", String::from("\"3\" was ignored.")).with_column(4);
+
+        assert_eq!("[2] Error: Unterminated options sequence.", error.to_string());
+        assert_eq!("[9:4] Warning: \"3\" was ignored.", warning.to_string());
+
+        let mut watcher = Watcher::new();
+        watcher.report(warning.clone());
+        assert!(!watcher.had_error());
+        watcher.report(error.clone());
+        assert!(watcher.had_error());
+        assert_eq!((1, 1), (watcher.count(Severity::Error), watcher.count(Severity::Warning)));
+
+        assert_eq!(&[warning, error], watcher.diagnostics());
+        assert_eq!("[2] Error: Unterminated options sequence.", watcher.to_string());
+        assert_eq!("[9:4] Warning: \"3\" was ignored.", watcher.warnings());
+    }
+
+    #[test]
+    fn render_source_lines() {
+        let error = Diagnostic::new(Severity::Error, 12, "L004", String::from("Unterminated options sequence."))
+            .with_column(3)
+            .with_length(20)
+            .with_source_line(String::from("E [time=4/4"));
+        let warning = Diagnostic::new(Severity::Warning, 4, "P003", String::from("\"3\" was ignored."))
+            .with_source_line(String::from("3 3"));
+
+        // carets stop at the end of the line, and diagnostics without a column only show their line
+        assert_eq!(
+            "[12:3] Error: Unterminated options sequence.\n   |\n12 | E [time=4/4\n   |   ^^^^^^^^^",
+            error.render()
+        );
+        assert_eq!("[4] Warning: \"3\" was ignored.\n  |\n4 | 3 3", warning.render());
+        assert_eq!(
+            warning.render(),
+            Diagnostic::new(Severity::Warning, 4, "P003", String::from("\"3\" was ignored.")).with_source("E A\n\n0\n3 3").render()
+        );
+
+        let mut watcher = Watcher::new();
+        watcher.report(error.clone());
+        watcher.report(warning.clone());
+        assert_eq!(error.render(), watcher.render());
+        assert_eq!(warning.render(), watcher.render_warnings());
+        assert_eq!("[12:3] Error: Unterminated options sequence.", watcher.to_string());
+    }
+
+    #[test]
+    fn deny_warnings() {
+        let mut watcher = Watcher::new();
+        watcher.set_deny_warnings(true);
+        watcher.warning(3, "P003", String::from("\"3\" was ignored."));
+
+        assert!(watcher.had_error());
+        assert_eq!((1, 0), (watcher.count(Severity::Error), watcher.count(Severity::Warning)));
+        assert_eq!("[3] Error: \"3\" was ignored.", watcher.to_string());
+        assert_eq!("P003", watcher.errors()[0].code);
     }
 }
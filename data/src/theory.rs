@@ -0,0 +1,245 @@
+use std::fmt;
+
+/// The name of every pitch class spelled with sharps, from `C` up to `B`.
+pub const SHARPS: [&str; 12] = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
+
+/// The interval of a perfect fifth in semitones, which chords of four or more notes often leave out.
+const FIFTH: u8 = 7;
+
+/// Returns the pitch class (`0` for C up to `11` for B) of a note name made up of a letter from `A` to `G`
+/// followed by an optional `#` or `b` modifier. Returns `None` if the note name is not valid.
+///
+/// # Examples
+///
+/// ```
+/// use data::theory::pitch_class;
+///
+/// assert_eq!(Some(10), pitch_class("Bb"));
+/// assert_eq!(Some(10), pitch_class("A#"));
+/// assert_eq!(None, pitch_class("H"));
+/// ```
+pub fn pitch_class(note: &str) -> Option<u8> {
+    let mut chars = note.chars();
+    let natural = match chars.next()? {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return None,
+    };
+    match (chars.next(), chars.next()) {
+        (None, _) => Some(natural),
+        (Some('#'), None) => Some((natural + 1) % 12),
+        (Some('b'), None) => Some((natural + 11) % 12),
+        _ => None,
+    }
+}
+
+/// Returns the interval in semitones from one pitch class up to another, from `0` up to `11`.
+///
+/// # Examples
+///
+/// ```
+/// use data::theory::interval;
+///
+/// assert_eq!(7, interval(0, 7));
+/// assert_eq!(3, interval(9, 0));
+/// ```
+pub fn interval(from: u8, to: u8) -> u8 {
+    (to % 12 + 12 - from % 12) % 12
+}
+
+/// The quality of a chord, made up of the intervals of its notes above its root.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Quality {
+    Power,
+    Major,
+    Minor,
+    Diminished,
+    Augmented,
+    Sus2,
+    Sus4,
+    Add9,
+    Major6,
+    Minor6,
+    Dominant7,
+    Major7,
+    Minor7,
+    MinorMajor7,
+    HalfDiminished7,
+    Diminished7,
+}
+
+impl Quality {
+    /// Every chord quality that can be detected.
+    pub const ALL: [Quality; 16] = [
+        Quality::Power, Quality::Major, Quality::Minor, Quality::Diminished, Quality::Augmented, Quality::Sus2,
+        Quality::Sus4, Quality::Add9, Quality::Major6, Quality::Minor6, Quality::Dominant7, Quality::Major7,
+        Quality::Minor7, Quality::MinorMajor7, Quality::HalfDiminished7, Quality::Diminished7,
+    ];
+
+    /// Returns the intervals of the notes of the quality above the root in semitones, in ascending order
+    /// and starting with the root itself.
+    pub fn intervals(&self) -> &'static [u8] {
+        match self {
+            Quality::Power => &[0, 7],
+            Quality::Major => &[0, 4, 7],
+            Quality::Minor => &[0, 3, 7],
+            Quality::Diminished => &[0, 3, 6],
+            Quality::Augmented => &[0, 4, 8],
+            Quality::Sus2 => &[0, 2, 7],
+            Quality::Sus4 => &[0, 5, 7],
+            Quality::Add9 => &[0, 2, 4, 7],
+            Quality::Major6 => &[0, 4, 7, 9],
+            Quality::Minor6 => &[0, 3, 7, 9],
+            Quality::Dominant7 => &[0, 4, 7, 10],
+            Quality::Major7 => &[0, 4, 7, 11],
+            Quality::Minor7 => &[0, 3, 7, 10],
+            Quality::MinorMajor7 => &[0, 3, 7, 11],
+            Quality::HalfDiminished7 => &[0, 3, 6, 10],
+            Quality::Diminished7 => &[0, 3, 6, 9],
+        }
+    }
+
+    /// Returns the suffix written after the root of a chord of the quality.
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            Quality::Power => "5",
+            Quality::Major => "",
+            Quality::Minor => "m",
+            Quality::Diminished => "dim",
+            Quality::Augmented => "aug",
+            Quality::Sus2 => "sus2",
+            Quality::Sus4 => "sus4",
+            Quality::Add9 => "add9",
+            Quality::Major6 => "6",
+            Quality::Minor6 => "m6",
+            Quality::Dominant7 => "7",
+            Quality::Major7 => "maj7",
+            Quality::Minor7 => "m7",
+            Quality::MinorMajor7 => "mMaj7",
+            Quality::HalfDiminished7 => "m7b5",
+            Quality::Diminished7 => "dim7",
+        }
+    }
+
+    /// Returns true if the provided ascending intervals above a root spell the quality. With `omit_fifth`,
+    /// qualities of four notes with a perfect fifth also match their intervals without it.
+    fn matches(&self, intervals: &[u8], omit_fifth: bool) -> bool {
+        let own = self.intervals();
+        if !omit_fifth {
+            return own == intervals
+        }
+        own.len() == 4 && own.contains(&FIFTH)
+            && own.iter().filter(|interval| **interval != FIFTH).eq(intervals.iter())
+    }
+}
+
+/// A chord spelled by a root and quality, played over a bass note that may not be its root.
+///
+/// # Examples
+///
+/// ```
+/// use data::theory::{Chord, Quality};
+///
+/// // A C E from the open A string
+/// let chord = Chord::from_pitches(&[45, 52, 57, 60, 64]).unwrap();
+/// assert_eq!(Chord { root: 9, quality: Quality::Minor, bass: 9 }, chord);
+/// assert_eq!("Am", chord.to_string());
+/// assert_eq!("Am/C", Chord::from_pitches(&[48, 52, 57]).unwrap().to_string());
+/// assert_eq!(None, Chord::from_pitches(&[48, 49, 50]));
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Chord {
+    /// The pitch class of the root of the chord.
+    pub root: u8,
+    /// The quality of the chord.
+    pub quality: Quality,
+    /// The pitch class of the lowest note of the chord.
+    pub bass: u8,
+}
+
+impl Chord {
+    /// Returns the chord spelled by the provided MIDI pitches, or `None` if they do not spell a known
+    /// chord quality. Every pitch class is tried as the root, starting from the bass note and going up,
+    /// and qualities matched exactly are preferred over qualities missing their fifth, so inversions are
+    /// named as a slash chord over their bass note.
+    pub fn from_pitches(pitches: &[u32]) -> Option<Chord> {
+        let mut sorted = pitches.to_vec();
+        sorted.sort_unstable();
+        let mut classes: Vec<u8> = vec![];
+        for pitch in sorted {
+            let class = (pitch % 12) as u8;
+            if !classes.contains(&class) {
+                classes.push(class);
+            }
+        }
+        let bass = *classes.first()?;
+
+        [false, true].iter().find_map(|omit_fifth| classes.iter().find_map(|root| {
+            let mut intervals: Vec<u8> = classes.iter().map(|class| interval(*root, *class)).collect();
+            intervals.sort_unstable();
+            Quality::ALL.iter()
+                .find(|quality| quality.matches(&intervals, *omit_fifth))
+                .map(|quality| Chord { root: *root, quality: *quality, bass })
+        }))
+    }
+
+    /// Returns the name of the chord with its root and bass note spelled by the provided function from
+    /// their names spelled with sharps.
+    pub fn name(&self, spell: impl Fn(&str) -> String) -> String {
+        let root = spell(SHARPS[self.root as usize]);
+        match self.bass {
+            bass if bass == self.root => format!("{}{}", root, self.quality.suffix()),
+            bass => format!("{}{}/{}", root, self.quality.suffix(), spell(SHARPS[bass as usize])),
+        }
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name(str::to_string))
+    }
+}
+
+#[cfg(test)]
+mod theory_tests {
+    use super::*;
+
+    #[test]
+    fn chord_names() {
+        let name = |pitches: &[u32]| Chord::from_pitches(pitches).map(|chord| chord.to_string());
+
+        // open E, G, and D shapes from the low E string
+        assert_eq!(Some(String::from("E")), name(&[40, 47, 52, 56, 59, 64]));
+        assert_eq!(Some(String::from("G")), name(&[43, 47, 50, 55, 59, 67]));
+        assert_eq!(Some(String::from("D")), name(&[50, 57, 62, 66]));
+        // a power chord and chords of four notes, with and without their fifth
+        assert_eq!(Some(String::from("A5")), name(&[45, 52, 57]));
+        assert_eq!(Some(String::from("C7")), name(&[48, 52, 58]));
+        assert_eq!(Some(String::from("Cmaj7")), name(&[48, 52, 55, 59]));
+        assert_eq!(Some(String::from("Bm7b5")), name(&[47, 53, 57, 62]));
+        // the bass note is preferred as the root of ambiguous chords
+        assert_eq!(Some(String::from("C6")), name(&[48, 52, 55, 57]));
+        assert_eq!(Some(String::from("Am7")), name(&[45, 48, 52, 55]));
+        assert_eq!(Some(String::from("Csus2")), name(&[48, 50, 55]));
+        // inversions are named over their bass note
+        assert_eq!(Some(String::from("C/E")), name(&[52, 55, 60]));
+        // single notes and clusters are not chords
+        assert_eq!(None, name(&[]));
+        assert_eq!(None, name(&[40, 52]));
+        assert_eq!(None, name(&[48, 49, 50]));
+    }
+
+    #[test]
+    fn spelled_names() {
+        let chord = Chord::from_pitches(&[46, 53, 58, 62]).unwrap();
+        assert_eq!("A#", chord.to_string());
+        let flats = |name: &str| name.replace("A#", "Bb").replace("D#", "Eb");
+        assert_eq!("Bb", chord.name(flats));
+        assert_eq!("Eb/Bb", Chord::from_pitches(&[46, 51, 55]).unwrap().name(flats));
+    }
+}
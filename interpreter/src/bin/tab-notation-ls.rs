@@ -0,0 +1,16 @@
+use std::{io, process};
+
+fn main() {
+    // the client talks to the server over stdin and stdout
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    match interpreter::language_server::serve(&mut stdin.lock(), &mut stdout.lock()) {
+        // exiting without being shut down first is an error
+        Ok(true) => (),
+        Ok(false) => process::exit(1),
+        Err(e) => {
+            eprintln!("Language server failed:\n{}", e);
+            process::exit(2);
+        },
+    }
+}
@@ -0,0 +1,330 @@
+use std::{collections::HashMap, ops::Range};
+
+use data::{Diagnostic, Token, TokenType};
+use lexer::Lexer;
+use parser::{Parser, ast::{Event, Score}};
+
+/// A tab notation source kept along with its tokens and the score parsed from them, for editors that
+/// change a source a little at a time. Edits only lex the lines they touch again, until the tokens
+/// generated line up with the tokens already found after the edit, and the score is only parsed again
+/// if the tokens of the edited lines changed rather than just moved or had comments added.
+///
+/// # Examples
+///
+/// ```
+/// use interpreter::document::Document;
+///
+/// let mut document = Document::from("E A\n0 3\n2 5");
+/// let change = document.apply_edit(5..5, "  ");
+///
+/// assert_eq!("E A\n0   3\n2 5", document.text());
+/// assert!(!change.reparsed);
+/// assert_eq!(4, document.score().unwrap().staffs[0].measures[0].events.len());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Document {
+    text: String,
+    tokens: Vec<Token>,
+    /// The diagnostics logged while generating tokens.
+    lex_diagnostics: Vec<Diagnostic>,
+    /// The score parsed from the tokens, or nothing if the text has syntax errors or could not be parsed.
+    score: Option<Score>,
+    /// The diagnostics logged while parsing the tokens.
+    parse_diagnostics: Vec<Diagnostic>,
+}
+
+/// What an edit to a document lexed and parsed again.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Change {
+    /// The indices of the tokens generated again, within the tokens of the edited document.
+    pub tokens: Range<usize>,
+    /// Whether the score was parsed again rather than kept with the lines of its notes moved.
+    pub reparsed: bool,
+}
+
+impl Document {
+    /// Creates a new document, generating the tokens of the provided text and parsing its score.
+    pub fn new(text: String) -> Document {
+        let (tokens, lex_diagnostics) = lex(Lexer::from(text.as_str()));
+        let mut document = Document { text, tokens, lex_diagnostics, score: None, parse_diagnostics: vec![] };
+        document.parse();
+        document
+    }
+
+    /// Returns the text of the document.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the tokens of the document, or nothing if it has syntax errors.
+    pub fn tokens(&self) -> Option<&[Token]> {
+        if self.lex_diagnostics.is_empty() { Some(&self.tokens) } else { None }
+    }
+
+    /// Returns the score of the document, or nothing if it has syntax errors or could not be parsed.
+    pub fn score(&self) -> Option<&Score> {
+        self.score.as_ref()
+    }
+
+    /// Returns every diagnostic logged while generating the tokens of the document and parsing them.
+    pub fn diagnostics(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.lex_diagnostics.iter().chain(self.parse_diagnostics.iter())
+    }
+
+    /// Replaces the provided byte range of the text with new text. Lexing resumes from the start of the
+    /// first line holding a token the edit touches and stops at the first token starting a line past the
+    /// edit that matches the token found there before the edit, after which the old tokens are kept with
+    /// their lines and spans moved. The score is kept with the lines of its notes and hits moved when the
+    /// tokens generated again only moved or had comments added and every parse diagnostic is found before
+    /// them, and is parsed again otherwise.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds of the text or does not lie on char boundaries.
+    pub fn apply_edit(&mut self, range: Range<usize>, new_text: &str) -> Change {
+        let delta = new_text.len() as isize - range.len() as isize;
+        let line_delta = new_text.matches('\n').count() as i64 - self.text[range.clone()].matches('\n').count() as i64;
+        self.text.replace_range(range.clone(), new_text);
+        let edit_end = range.start + new_text.len();
+
+        // lexing resumes at the start of a line before the edit and the first token it touches, which
+        // is moved back past any token reaching onto that line, such as front matter, and past any syntax
+        // error before it, such as a block comment that is never closed
+        let old = std::mem::take(&mut self.tokens);
+        let touched = old.partition_point(|token| token.span.end < range.start);
+        let mut start = old.get(touched).map_or(range.start, |token| token.span.start.min(range.start));
+        let (kept, first_line) = loop {
+            let line_start = line_start(&self.text, start);
+            let line = self.text[..line_start].matches('\n').count() as u32 + 1;
+            let kept = old.partition_point(|token| token.span.start < line_start && token.span.end <= line_start);
+            if let Some(error) = self.lex_diagnostics.iter().map(|diagnostic| diagnostic.line).filter(|error| *error < line).min() {
+                start = self.text.split('\n').take(error as usize - 1).map(|line| line.len() + 1).sum();
+                continue
+            }
+            match old.get(kept) {
+                Some(token) if token.span.start < line_start => start = token.span.start,
+                _ => break (kept, line),
+            }
+        };
+        // front matter and options that set the mode change how every line after them is lexed
+        let modal = |token: &Token| matches!(token.type_of, TokenType::Options | TokenType::FrontMatter);
+
+        let mut tokens: Vec<Token> = old[..kept].to_vec();
+        let mut errors = vec![];
+        let mut resync = None;
+        for result in Lexer::resume(&self.text, start, &tokens) {
+            let token = match result {
+                Ok(token) => token,
+                Err(e) => {
+                    errors.extend(e.diagnostics().iter().cloned());
+                    continue
+                },
+            };
+            // tokens only line up at the start of a line past the edit, where the lexer starts afresh
+            let line_start = line_start(&self.text, token.span.start);
+            let starts_line = tokens.last().is_none_or(|before| before.span.end <= line_start);
+            if starts_line && line_start > edit_end {
+                let old_start = (token.span.start as isize - delta) as usize;
+                let i = old.partition_point(|other| other.span.start < old_start);
+                let found = old.get(i).is_some_and(|other| other.span.start == old_start && same_token(other, &token));
+                if found && !tokens[kept..].iter().chain(old[kept..i].iter()).any(modal) {
+                    resync = Some((i, token));
+                    break
+                }
+            }
+            tokens.push(token);
+        }
+        let relexed = kept..tokens.len();
+        let old_end = resync.as_ref().map_or(old.len(), |(i, _)| *i);
+
+        // the old tokens and lex errors after the edit are kept, moved by the lines and bytes added
+        let shift = |line: u32| (line as i64 + line_delta) as u32;
+        if let Some((i, token)) = &resync {
+            tokens.extend(old[*i..].iter().map(|token| {
+                let span = (token.span.start as isize + delta) as usize..(token.span.end as isize + delta) as usize;
+                Token::new(token.type_of.clone(), token.value.clone(), token.literal.clone(), shift(token.line)).at(token.column, span)
+            }));
+            errors.retain(|diagnostic| found_before(diagnostic, token));
+        }
+        let resync = resync.map(|(i, _)| &old[i]);
+        let mut lex_diagnostics: Vec<Diagnostic> = self.lex_diagnostics.iter().filter(|diagnostic| diagnostic.line < first_line).cloned().collect();
+        lex_diagnostics.extend(errors);
+        lex_diagnostics.extend(self.lex_diagnostics.iter()
+            .filter(|diagnostic| resync.is_some_and(|token| !found_before(diagnostic, token)))
+            .map(|diagnostic| Diagnostic { line: shift(diagnostic.line), ..diagnostic.clone() }));
+        let was_lexed = self.lex_diagnostics.is_empty();
+        self.tokens = tokens;
+        self.lex_diagnostics = lex_diagnostics;
+
+        // the score only needs its lines moved if the same tokens were generated again apart from
+        // comments, which are not parsed, with each old line moving to a single new line, and the parse
+        // diagnostics are all found before the edit
+        let parsed = |tokens: &[Token]| -> Vec<Token> {
+            tokens.iter().filter(|token| token.type_of != TokenType::Comment).cloned().collect()
+        };
+        let (before, after) = (parsed(&old[kept..old_end]), parsed(&self.tokens[relexed.clone()]));
+        let mut lines = HashMap::new();
+        let moved = was_lexed
+            && self.lex_diagnostics.is_empty()
+            && self.parse_diagnostics.iter().all(|diagnostic| diagnostic.line < first_line)
+            && before.len() == after.len()
+            && before.iter().zip(after.iter())
+                .all(|(old, new)| same_token(old, new) && *lines.entry(old.line).or_insert(new.line) == new.line);
+        if moved {
+            let last_line = old[kept..old_end].iter().map(|token| token.line).max().unwrap_or(first_line);
+            let line = |line: u32| match lines.get(&line) {
+                _ if line < first_line => line,
+                Some(new) => *new,
+                None if line > last_line => shift(line),
+                None => line,
+            };
+            for measure in self.score.iter_mut().flat_map(|score| score.staffs.iter_mut()).flat_map(|staff| staff.measures.iter_mut()) {
+                for event in measure.events.iter_mut() {
+                    match event {
+                        Event::Note(note) => note.line = line(note.line),
+                        Event::Hit(hit) => hit.line = line(hit.line),
+                        Event::Rest(_) => (),
+                    }
+                }
+            }
+        } else {
+            self.parse();
+        }
+
+        Change { tokens: relexed, reparsed: !moved }
+    }
+
+    /// Parses the score of the tokens, or forgets it if the text has syntax errors.
+    fn parse(&mut self) {
+        if !self.lex_diagnostics.is_empty() {
+            self.score = None;
+            self.parse_diagnostics.clear();
+            return
+        }
+        let mut par = Parser::new(&self.tokens);
+        self.score = par.parse().ok();
+        self.parse_diagnostics = par.diagnostics().to_vec();
+    }
+}
+
+impl From<&str> for Document {
+    /// Creates a new document from a copy of the provided text.
+    fn from(text: &str) -> Self {
+        Document::new(text.to_string())
+    }
+}
+
+/// Generates every token a lexer finds, along with the diagnostics of every syntax error found along the
+/// way.
+fn lex(lexer: Lexer) -> (Vec<Token>, Vec<Diagnostic>) {
+    let mut tokens = vec![];
+    let mut diagnostics = vec![];
+    for result in lexer {
+        match result {
+            Ok(token) => tokens.push(token),
+            Err(e) => diagnostics.extend(e.diagnostics().iter().cloned()),
+        }
+    }
+    (tokens, diagnostics)
+}
+
+/// Returns the byte index of the start of the line holding the provided byte index.
+fn line_start(text: &str, index: usize) -> usize {
+    text[..index].rfind('\n').map_or(0, |newline| newline + 1)
+}
+
+/// Returns whether a diagnostic was found before a token.
+fn found_before(diagnostic: &Diagnostic, token: &Token) -> bool {
+    (diagnostic.line, diagnostic.column.unwrap_or(0)) < (token.line, token.column)
+}
+
+/// Returns whether two tokens were read the same, wherever they were found.
+fn same_token(token: &Token, other: &Token) -> bool {
+    token.type_of == other.type_of && token.value == other.value && token.literal == other.literal
+}
+
+#[cfg(test)]
+mod document_tests {
+    use super::*;
+
+    /// Returns the tokens of a text lexed from scratch.
+    fn tokens(text: &str) -> Vec<Token> {
+        lex(Lexer::from(text)).0
+    }
+
+    #[test]
+    fn edits_only_lex_the_lines_they_touch() {
+        let mut document = Document::from("E A D\n0 2 2\n3 . 5\n\n1 . .");
+        let change = document.apply_edit(16..17, "7");
+        assert_eq!("E A D\n0 2 2\n3 . 7\n\n1 . .", document.text());
+        assert_eq!(6..9, change.tokens);
+        assert!(change.reparsed);
+        assert_eq!(tokens(document.text()), document.tokens().unwrap());
+        assert_eq!(document.tokens().unwrap().iter().map(|token| &token.span).collect::<Vec<_>>(), tokens(document.text()).iter().map(|token| &token.span).collect::<Vec<_>>());
+        assert_eq!(Some(7), document.score().unwrap().staffs[0].measures[0].events.iter().find_map(|event| match event {
+            Event::Note(note) if note.line == 3 && note.string == 2 => Some(note.fret),
+            _ => None,
+        }));
+    }
+
+    #[test]
+    fn moved_lines_keep_the_score() {
+        let mut document = Document::from("E A\n0 3\n2 5");
+        let change = document.apply_edit(3..3, "\n// intro");
+        assert_eq!("E A\n// intro\n0 3\n2 5", document.text());
+        assert!(!change.reparsed);
+        assert_eq!(tokens(document.text()), document.tokens().unwrap());
+        assert_eq!(Document::from(document.text()).score(), document.score());
+
+        let mut document = Document::from("E A\n0 3\n2 5");
+        let change = document.apply_edit(7..7, "\n");
+        assert_eq!("E A\n0 3\n\n2 5", document.text());
+        assert!(change.reparsed);
+
+        let mut document = Document::from("E A\n0 3\n2 5");
+        let change = document.apply_edit(4..4, "  ");
+        assert!(!change.reparsed);
+        assert_eq!(tokens(document.text()), document.tokens().unwrap());
+        assert_eq!(Document::from(document.text()).score(), document.score());
+    }
+
+    #[test]
+    fn errors_follow_their_lines() {
+        let mut document = Document::from("E A\n0 ?\n2 5\n3 ?");
+        assert_eq!(vec![2, 4], document.diagnostics().map(|diagnostic| diagnostic.line).collect::<Vec<_>>());
+        assert!(document.tokens().is_none() && document.score().is_none());
+
+        document.apply_edit(6..7, "3");
+        assert_eq!(vec![4], document.diagnostics().map(|diagnostic| diagnostic.line).collect::<Vec<_>>());
+        document.apply_edit(0..0, "[title=Riffs]\n");
+        assert_eq!(vec![5], document.diagnostics().map(|diagnostic| diagnostic.line).collect::<Vec<_>>());
+        document.apply_edit(document.text().len() - 1..document.text().len(), "0");
+        assert_eq!(0, document.diagnostics().count());
+        assert_eq!(Some("Riffs"), document.score().unwrap().metadata.title.as_deref());
+        assert_eq!(tokens(document.text()), document.tokens().unwrap());
+    }
+
+    #[test]
+    fn modes_are_lexed_again() {
+        let mut document = Document::from("[mode=tabs]\nHH SD\nx x");
+        assert!(document.tokens().is_none());
+        document.apply_edit(6..10, "drums");
+        assert_eq!("[mode=drums]\nHH SD\nx x", document.text());
+        assert_eq!(tokens(document.text()), document.tokens().unwrap());
+        assert!(document.score().is_some());
+    }
+
+    #[test]
+    fn edits_match_a_new_document() {
+        let mut document = Document::from("[title=Riffs]\nE A D\n0 2 2 // open\n/* a\nriff */ 3 . 5\n\n[mode=drums]\nHH SD\nx x");
+        let edits = [(14..14, "// strings\n"), (40..43, ""), (0..0, "\n"), (33..33, " 12h"), (65..70, "[time=3/4]"), (20..20, "/*")];
+        for (range, text) in edits.iter() {
+            document.apply_edit(range.clone(), text);
+            let new = Document::from(document.text());
+            let spans = |document: &Document| document.tokens.iter().map(|token| (token.clone(), token.column, token.span.clone())).collect::<Vec<_>>();
+            assert_eq!(spans(&new), spans(&document));
+            assert_eq!(new.diagnostics().collect::<Vec<_>>(), document.diagnostics().collect::<Vec<_>>());
+            assert_eq!(new.score(), document.score());
+        }
+    }
+}
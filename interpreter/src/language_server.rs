@@ -0,0 +1,394 @@
+use std::{collections::HashMap, io::{self, BufRead, Write}};
+
+use data::{Diagnostic, Literal, Severity, Token, TokenType};
+use parser::{Parser, ast::{Event, Note, Score}};
+use serde_json::{json, Value};
+
+use crate::document::Document;
+
+/// The JSON-RPC error codes of a message that is not valid JSON and of a request the server does not handle.
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// A language server for tab notation documents, publishing their diagnostics as they change, showing
+/// the beat position and pitch of a fret when it is hovered, and formatting them.
+#[derive(Default)]
+pub struct Server {
+    documents: HashMap<String, Document>,
+    shutdown: bool,
+}
+
+impl Server {
+    /// Creates a new server without any open documents.
+    pub fn new() -> Server {
+        Server::default()
+    }
+
+    /// Handles a JSON-RPC message from the client and returns every message to send back to it: the
+    /// response of a request and any notifications.
+    pub fn handle(&mut self, message: &Value) -> Vec<Value> {
+        let id = message.get("id").cloned();
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or("").to_string();
+        let result = match message["method"].as_str().unwrap_or("") {
+            "initialize" => json!({
+                "capabilities": {
+                    // changes are sent as the ranges that were edited
+                    "textDocumentSync": { "openClose": true, "change": 2 },
+                    "hoverProvider": true,
+                    "documentFormattingProvider": true,
+                },
+                "serverInfo": { "name": "tab-notation-ls", "version": env!("CARGO_PKG_VERSION") },
+            }),
+            "shutdown" => {
+                self.shutdown = true;
+                Value::Null
+            },
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or("").to_string();
+                self.documents.insert(uri.clone(), Document::new(text));
+                return vec![self.analyse(&uri)];
+            },
+            "textDocument/didChange" => {
+                if let Some(document) = self.documents.get_mut(&uri) {
+                    for change in params["contentChanges"].as_array().into_iter().flatten() {
+                        let text = change["text"].as_str().unwrap_or("");
+                        match change.get("range") {
+                            Some(range) => {
+                                let start = offset(document.text(), &range["start"]);
+                                let end = offset(document.text(), &range["end"]).max(start);
+                                document.apply_edit(start..end, text);
+                            },
+                            None => *document = Document::from(text),
+                        }
+                    }
+                }
+                return vec![self.analyse(&uri)];
+            },
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                return vec![publish(&uri, vec![])];
+            },
+            "textDocument/hover" => self.hover(&uri, &params["position"]).unwrap_or(Value::Null),
+            "textDocument/formatting" => self.format(&uri).unwrap_or(Value::Null),
+            // notifications without a handler are ignored
+            _ if id.is_none() => return vec![],
+            method => return vec![json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": METHOD_NOT_FOUND, "message": format!("Unknown method: {}", method) },
+            })],
+        };
+        match id {
+            Some(id) => vec![json!({ "jsonrpc": "2.0", "id": id, "result": result })],
+            None => vec![],
+        }
+    }
+
+    /// Returns the notification publishing every diagnostic logged while generating the tokens of a
+    /// document and parsing them.
+    fn analyse(&self, uri: &str) -> Value {
+        let document = match self.documents.get(uri) {
+            Some(document) => document,
+            None => return publish(uri, vec![]),
+        };
+        let lines: Vec<&str> = document.text().lines().collect();
+        publish(uri, document.diagnostics().map(|diagnostic| lsp_diagnostic(diagnostic, &lines)).collect())
+    }
+
+    /// Returns the hover of the fret at the provided position of a document: the string it is played on,
+    /// the pitch it sounds at, the finger it is fretted with when written or suggested by a hand position,
+    /// and the measure and beat it is played at.
+    fn hover(&self, uri: &str, position: &Value) -> Option<Value> {
+        let document = self.documents.get(uri)?;
+        let tokens = document.tokens()?;
+        let line = position["line"].as_u64()? as usize;
+        let text = document.text().lines().nth(line)?;
+        let column = char_column(text, position["character"].as_u64()? as usize) as u32 + 1;
+        let index = tokens.iter().position(|token| {
+            token.type_of == TokenType::Number
+                && token.line as usize == line + 1
+                && token.column > 0
+                && (token.column..token.column + token.value.chars().count() as u32).contains(&column)
+        })?;
+        let (score, staff, note) = played_note(tokens, index)?;
+
+        let staff = &score.staffs[staff];
+        let pitch = staff.pitch(&note, score.capo.unwrap_or(0));
+        let measure = staff.measures.iter().rev().find(|measure| measure.start_beat <= note.position.beat)?;
+        let beat = note.position.beat - measure.start_beat + 1;
+        let slot = if note.position.slot > 0 { format!(", slot {}", note.position.slot + 1) } else { String::new() };
+        let capo = score.capo.map_or(String::new(), |capo| format!(" with the capo at fret {}", capo));
+        let finger = staff.finger(&note).map_or(String::new(), |finger| format!("\n\nFinger {}", finger));
+        let token = &tokens[index];
+        Some(json!({
+            "contents": {
+                "kind": "markdown",
+                "value": format!(
+                    "**Fret {}** on the {} string\n\nSounds {} (MIDI {}){}{}\n\nMeasure {}, beat {}{}",
+                    note.fret, staff.strings[note.string], parser::pitch_name(pitch), pitch, capo, finger, measure.number, beat, slot
+                ),
+            },
+            "range": range(text, line, token.column as usize - 1, token.column as usize - 1 + token.value.chars().count()),
+        }))
+    }
+
+    /// Returns the edit replacing a document with its formatted source, no edits if it is already
+    /// formatted, or nothing if it has syntax errors.
+    fn format(&self, uri: &str) -> Option<Value> {
+        let document = self.documents.get(uri)?;
+        let formatted = parser::format_source(document.tokens()?);
+        if formatted == document.text() {
+            return Some(json!([]));
+        }
+        let lines = document.text().split('\n').count();
+        Some(json!([{
+            "range": { "start": { "line": 0, "character": 0 }, "end": { "line": lines, "character": 0 } },
+            "newText": formatted,
+        }]))
+    }
+}
+
+/// Returns the score parsed from the tokens up to and including the number token at the provided index
+/// and the marks written directly after it, along with the note the number plays and the index of its
+/// staff. The note is found as the note added by the number to the score parsed from the tokens before it.
+fn played_note(tokens: &[Token], index: usize) -> Option<(Score, usize, Note)> {
+    let token = &tokens[index];
+    let parse = |end: usize| {
+        let mut prefix = tokens[..end].to_vec();
+        prefix.push(Token::new(TokenType::EndOfFile, String::new(), Literal::None, token.line));
+        let score = Parser::new(&prefix).parse().ok();
+        score
+    };
+    let notes = |score: &Score| -> Vec<(usize, Note)> {
+        score.staffs.iter().enumerate()
+            .flat_map(|(i, staff)| staff.measures.iter().flat_map(move |measure| measure.events.iter().filter_map(move |event| match event {
+                Event::Note(note) => Some((i, note.clone())),
+                Event::Rest(_) | Event::Hit(_) => None,
+            })))
+            .collect()
+    };
+    let marks = tokens[index + 1..].iter()
+        .take_while(|token| matches!(
+            token.type_of,
+            TokenType::Fingering | TokenType::Duration | TokenType::Vibrato | TokenType::Accent | TokenType::Staccato
+        ))
+        .count();
+    let score = parse(index + 1 + marks)?;
+    let mut added = notes(&score);
+    for note in parse(index).map(|before| notes(&before)).unwrap_or_default() {
+        if let Some(i) = added.iter().position(|other| other == &note) {
+            added.remove(i);
+        }
+    }
+    let fret = match token.literal {
+        Literal::Number(fret) => fret,
+        _ => return None,
+    };
+    let (staff, note) = added.into_iter().rfind(|(_, note)| note.line == token.line && note.fret == fret)?;
+    Some((score, staff, note))
+}
+
+/// Returns the notification publishing the diagnostics of a document.
+fn publish(uri: &str, diagnostics: Vec<Value>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": uri, "diagnostics": diagnostics },
+    })
+}
+
+/// Converts a diagnostic into an LSP diagnostic, underlining its columns or its whole line if its column
+/// is not known.
+fn lsp_diagnostic(diagnostic: &Diagnostic, lines: &[&str]) -> Value {
+    let line = diagnostic.line.saturating_sub(1) as usize;
+    let text = lines.get(line).copied().unwrap_or("");
+    let (start, end) = match diagnostic.column {
+        Some(column) => (column.saturating_sub(1) as usize, (column.saturating_sub(1) + diagnostic.length) as usize),
+        None => (0, text.chars().count()),
+    };
+    json!({
+        "range": range(text, line, start, end),
+        "severity": match diagnostic.severity {
+            Severity::Error => 1,
+            Severity::Warning => 2,
+        },
+        "code": diagnostic.code,
+        "source": "tab-notation",
+        "message": diagnostic.message.trim(),
+    })
+}
+
+/// Returns the LSP range of a line between two char columns, which LSP counts in UTF-16 code units.
+fn range(text: &str, line: usize, start: usize, end: usize) -> Value {
+    let utf16 = |column: usize| text.chars().take(column).map(char::len_utf16).sum::<usize>();
+    json!({
+        "start": { "line": line, "character": utf16(start) },
+        "end": { "line": line, "character": utf16(end) },
+    })
+}
+
+/// Returns the char column of a line at an amount of UTF-16 code units.
+fn char_column(text: &str, character: usize) -> usize {
+    let mut units = 0;
+    text.chars().take_while(|c| {
+        units += c.len_utf16();
+        units <= character
+    }).count()
+}
+
+/// Returns the byte offset of an LSP position within a text, clamped to the end of its line.
+fn offset(text: &str, position: &Value) -> usize {
+    let line = position["line"].as_u64().unwrap_or(0) as usize;
+    let character = position["character"].as_u64().unwrap_or(0) as usize;
+    let start: usize = text.split('\n').take(line).map(|line| line.len() + 1).sum::<usize>().min(text.len());
+    let rest = text[start..].split('\n').next().unwrap_or("");
+    start + rest.chars().take(char_column(rest, character)).map(char::len_utf8).sum::<usize>()
+}
+
+/// Reads the next message sent over the base protocol: headers ending with a blank line, followed by as
+/// many bytes of JSON as the `Content-Length` header sets. Returns nothing at the end of the input.
+fn read_message(input: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(value) = header.strip_prefix("Content-Length:") {
+            length = value.trim().parse::<usize>().ok();
+        }
+    }
+    let length = length.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "A message is missing its Content-Length header."))?;
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    String::from_utf8(body).map(Some).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Writes a message over the base protocol.
+fn write_message(output: &mut impl Write, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+/// Serves a client over the provided input and output until it sends the exit notification or the input
+/// ends. Returns whether the client asked the server to shut down before it exited.
+///
+/// # Errors
+///
+/// This function will error if a message cannot be read or written.
+pub fn serve(input: &mut impl BufRead, output: &mut impl Write) -> io::Result<bool> {
+    let mut server = Server::new();
+    while let Some(message) = read_message(input)? {
+        let message: Value = match serde_json::from_str(&message) {
+            Ok(message) => message,
+            Err(e) => {
+                write_message(output, &json!({ "jsonrpc": "2.0", "id": null, "error": { "code": PARSE_ERROR, "message": e.to_string() } }))?;
+                continue
+            },
+        };
+        if message["method"] == "exit" {
+            break;
+        }
+        for reply in server.handle(&message) {
+            write_message(output, &reply)?;
+        }
+    }
+    Ok(server.shutdown)
+}
+
+#[cfg(test)]
+mod language_server_tests {
+    use super::*;
+
+    fn open(server: &mut Server, text: &str) -> Value {
+        server.handle(&json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/didOpen",
+            "params": { "textDocument": { "uri": "file:///song.tab", "languageId": "tab", "version": 1, "text": text } },
+        })).remove(0)
+    }
+
+    fn request(server: &mut Server, method: &str, params: Value) -> Value {
+        server.handle(&json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params })).remove(0)["result"].clone()
+    }
+
+    #[test]
+    fn diagnostics_are_published() {
+        let mut server = Server::new();
+        let published = open(&mut server, "E A\n0 3\nE ? 3");
+        assert_eq!("textDocument/publishDiagnostics", published["method"]);
+        assert_eq!(json!([{
+            "range": { "start": { "line": 2, "character": 2 }, "end": { "line": 2, "character": 3 } },
+            "severity": 1,
+            "code": "L001",
+            "source": "tab-notation",
+            "message": "Unknown character value: ?",
+        }]), published["params"]["diagnostics"]);
+
+        // the edit removes the unknown char, leaving the short measure as a warning
+        let published = server.handle(&json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/didChange",
+            "params": {
+                "textDocument": { "uri": "file:///song.tab", "version": 2 },
+                "contentChanges": [{ "range": { "start": { "line": 2, "character": 2 }, "end": { "line": 2, "character": 4 } }, "text": "" }],
+            },
+        })).remove(0);
+        assert_eq!("E A\n0 3\nE 3", server.documents["file:///song.tab"].text());
+        let diagnostics = published["params"]["diagnostics"].as_array().unwrap();
+        assert!(diagnostics.iter().all(|diagnostic| diagnostic["severity"] == 2));
+    }
+
+    #[test]
+    fn frets_are_hovered() {
+        let mut server = Server::new();
+        open(&mut server, "[capo=2]\nE A\n0 3, 5\n7 0 2");
+        let hover = |server: &mut Server, line: u32, character: u32| request(server, "textDocument/hover", json!({
+            "textDocument": { "uri": "file:///song.tab" },
+            "position": { "line": line, "character": character },
+        }));
+        let value = hover(&mut server, 2, 2);
+        assert_eq!("**Fret 3** on the A string\n\nSounds D3 (MIDI 50) with the capo at fret 2\n\nMeasure 1, beat 1", value["contents"]["value"]);
+        assert_eq!(json!({ "start": { "line": 2, "character": 2 }, "end": { "line": 2, "character": 3 } }), value["range"]);
+        assert_eq!("**Fret 5** on the E string\n\nSounds B2 (MIDI 47) with the capo at fret 2\n\nMeasure 1, beat 1, slot 3", hover(&mut server, 2, 5)["contents"]["value"]);
+        // a fret played again on the same string is told apart by where it was written
+        assert_eq!("**Fret 0** on the E string\n\nSounds F#2 (MIDI 42) with the capo at fret 2\n\nMeasure 1, beat 1, slot 4", hover(&mut server, 3, 2)["contents"]["value"]);
+        assert_eq!(Value::Null, hover(&mut server, 1, 0));
+
+        // fingers are suggested by the last barre or hand position before the fret
+        open(&mut server, "E A\nB5 5 7, 8(2)");
+        assert_eq!("**Fret 7** on the A string\n\nSounds E3 (MIDI 52)\n\nFinger 3\n\nMeasure 1, beat 1", hover(&mut server, 1, 5)["contents"]["value"]);
+        assert_eq!("**Fret 8** on the E string\n\nSounds C3 (MIDI 48)\n\nFinger 2\n\nMeasure 1, beat 1, slot 3", hover(&mut server, 1, 8)["contents"]["value"]);
+    }
+
+    #[test]
+    fn documents_are_formatted() {
+        let mut server = Server::new();
+        open(&mut server, "E   A\n0  3");
+        let edits = request(&mut server, "textDocument/formatting", json!({ "textDocument": { "uri": "file:///song.tab" }, "options": {} }));
+        assert_eq!("E A\n0 3\n", edits[0]["newText"]);
+        assert_eq!(json!({ "line": 2, "character": 0 }), edits[0]["range"]["end"]);
+    }
+
+    #[test]
+    fn messages_are_framed() {
+        let messages = [
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} }),
+            json!({ "jsonrpc": "2.0", "id": 2, "method": "shutdown" }),
+            json!({ "jsonrpc": "2.0", "method": "exit" }),
+        ];
+        let input: String = messages.iter().map(|message| format!("Content-Length: {}\r\n\r\n{}", message.to_string().len(), message)).collect();
+        let mut output = vec![];
+        assert!(serve(&mut input.as_bytes(), &mut output).unwrap());
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("Content-Length: "));
+        assert!(output.contains("\"hoverProvider\":true"));
+        assert!(output.ends_with("{\"id\":2,\"jsonrpc\":\"2.0\",\"result\":null}"));
+    }
+}
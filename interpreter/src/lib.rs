@@ -1,101 +1,1650 @@
-use std::{fs, error::Error};
+use std::{fmt, fs, thread, io::{self, BufRead, Read, Write}, error::Error, rc::Rc, cell::RefCell, ffi::OsString, borrow::Cow, time::Duration, path::{Path, PathBuf}, hash::{Hash, Hasher}};
 
-use lexer::Lexer;
-use parser::Parser;
+use data::{Token, Severity};
+use lexer::{Lexer, LexError};
+use parser::{Parser, ParseError, Summary};
+use serde::Serialize;
 
-pub use file_config::Config;
+pub use file_config::{Config, Command, OutputFormat, Emit};
+use file_config::STDIO;
+
+pub mod document;
+pub mod language_server;
+
+/// Re-exports everything needed to interpret guitar tab notation, so embedding only needs a single
+/// `use interpreter::prelude::*;`.
+pub mod prelude {
+    pub use data::{Token, TokenType, Literal, Watcher};
+    pub use lexer::{Lexer, LexError};
+    pub use parser::{Parser, ParseError, Summary};
+    pub use crate::{compile, run_str, InterpreterError};
+}
 
 pub mod file_config {
-    use std::{env, path::PathBuf, ffi::OsString};
+    use std::{fs, path::{Path, PathBuf}, ffi::OsString};
+
+    use crate::InterpreterError;
+
+    /// The filename standing for stdin as the input file and stdout as the output file.
+    pub(crate) const STDIO: &str = "-";
+
+    /// What the interpreter does with the input files, selected by the first argument.
+    #[derive(Debug, PartialEq, Clone, Copy)]
+    pub enum Command {
+        /// Interpret the input files into tabs, or a MIDI file with `--format midi`. Used when no command
+        /// is provided.
+        Render,
+        /// Report the warnings and errors of the input files without writing any output.
+        Check,
+        /// Write the tokens generated from the input files as JSON.
+        Tokens,
+        /// Export the structured score of the input files as a MIDI file, or as JSON with `--format json`.
+        Export,
+        /// Convert plain ASCII tabs in the input files into tab notation source.
+        Import,
+        /// Rewrite the input files in their canonical form, the same as the `--format-source` flag.
+        Fmt,
+    }
+
+    /// The kind of file written as output.
+    #[derive(Debug, PartialEq, Clone, Copy, Hash)]
+    pub enum OutputFormat {
+        /// ASCII guitar tabs written to a `.txt` file.
+        Text,
+        /// A Standard MIDI File written to a `.mid` file.
+        Midi,
+        /// Audio of every note synthesized as a plucked string, written to a `.wav` file. Only written
+        /// when built with the `playback` feature.
+        Wav,
+        /// alphaTex, the text format of alphaTab and Guitar Pro compatible renderers, written to an
+        /// `.alphatex` file.
+        AlphaTex,
+        /// A LilyPond file engraving a staff and a tab staff, written to a `.ly` file.
+        LilyPond,
+        /// ASCII guitar tabs marked up as an HTML document, written to an `.html` file.
+        Html,
+        /// An image drawing a staff system for every staff, written to an `.svg` file.
+        Svg,
+        /// ASCII guitar tabs laid out on numbered pages, written to a `.pdf` file.
+        Pdf,
+    }
+
+    /// An intermediate stage of interpreting written in place of the output.
+    #[derive(Debug, PartialEq, Clone, Copy, Hash)]
+    pub enum Emit {
+        /// The tokens generated by the lexer as a readable table, written to stdout unless an output
+        /// filename is provided.
+        Tokens,
+        /// The tokens generated by the lexer as JSON.
+        TokensJson,
+        /// The structured score generated by the parser.
+        ScoreJson,
+    }
 
     /// File configuration struct used for verifying environment arguments and storing a filename.
+    #[derive(Clone)]
     pub struct Config {
+        /// What is done with the input files.
+        pub command: Command,
         pub input_filename: PathBuf,
         pub output_filename: PathBuf,
+        /// Every input file provided, in order, with glob patterns expanded. When there is more than one,
+        /// each is interpreted in turn and written next to itself.
+        pub input_filenames: Vec<PathBuf>,
+        /// Print the supported options instead of interpreting a file.
+        pub list_options: bool,
+        /// Print the usage of the command line instead of interpreting a file.
+        pub help: bool,
+        /// Only print warnings and errors, leaving out progress messages.
+        pub quiet: bool,
+        /// The amount of beats to skip before output begins.
+        pub seek: u32,
+        /// The char width every staff is wrapped at, unless the `width` option is set.
+        pub width: Option<usize>,
+        /// Append a fret diagram of every distinct chord below the tabs.
+        pub chord_diagrams: bool,
+        /// Rewrite the input file in its canonical form instead of interpreting it.
+        pub format_source: bool,
+        /// Report the input files that are not in their canonical form instead of rewriting them.
+        pub check_format: bool,
+        /// Respell note names with the accidentals of the key set by the `key` option.
+        pub normalize_accidentals: bool,
+        /// Skip unknown options with a warning instead of stopping with an error.
+        pub ignore_unknown_options: bool,
+        /// Promote warnings to errors that stop the output from being generated.
+        pub deny_warnings: bool,
+        /// Print comments as lines of text above the staff they were written in.
+        pub keep_comments: bool,
+        /// The char used for the next beat spread operator.
+        pub spread_next: char,
+        /// The first and last measure, counted from `1`, and the amount of times to repeat them in place
+        /// of the whole score.
+        pub practice_loop: Option<(usize, usize, u32)>,
+        /// The amount of semitones every tab is transposed by.
+        pub transpose: i32,
+        /// Play a metronome click along with exported notes, unless the `click` option is set.
+        pub click: bool,
+        /// Write the output to stdout instead of the output file. Set by the `--stdout` flag or an output
+        /// filename of `-`, or when reading from stdin without an output filename.
+        pub stdout: bool,
+        /// Interpret the input files again whenever one of them is modified, until the process is stopped.
+        pub watch: bool,
+        /// Interpret the input file even if its output is already up to date.
+        pub force: bool,
+        /// Print a JSON summary of what was generated after interpreting the input file.
+        pub summary: bool,
+        /// The kind of file written as output.
+        pub format: OutputFormat,
+        /// Write an intermediate stage instead of the output.
+        pub emit: Option<Emit>,
+        /// Play the score as synthesized audio instead of writing any output. Only available when built
+        /// with the `playback` feature.
+        pub play: bool,
+        /// The number or part of the name of the MIDI output port the score is played on in real time
+        /// instead of writing any output. Only available when built with the `midi-output` feature.
+        pub play_midi: Option<String>,
+    }
+
+    impl Default for Config {
+        /// Creates a configuration with no filenames and every flag turned off.
+        fn default() -> Self {
+            Config {
+                command: Command::Render,
+                input_filename: PathBuf::new(),
+                output_filename: PathBuf::new(),
+                input_filenames: vec![],
+                list_options: false,
+                help: false,
+                quiet: false,
+                seek: 0,
+                width: None,
+                chord_diagrams: false,
+                format_source: false,
+                check_format: false,
+                normalize_accidentals: false,
+                ignore_unknown_options: false,
+                deny_warnings: false,
+                keep_comments: false,
+                spread_next: ';',
+                practice_loop: None,
+                transpose: 0,
+                click: false,
+                stdout: false,
+                watch: false,
+                force: false,
+                summary: false,
+                format: OutputFormat::Text,
+                emit: None,
+                play: false,
+                play_midi: None,
+            }
+        }
     }
 
     impl Config {
-        /// Creates a new file configuration struct using arguments from the command line
-        /// as the file info. Command line must have executable name followed by the filename. An
-        /// optional output filename can be added in addition to the input filename.
+        /// Parses a file configuration from command line arguments, starting with the executable name. The
+        /// executable name may be followed by a command (`render`, `check`, `tokens`, `export`, `import`, or
+        /// `fmt`), which defaults to `render`, then any flags and one or more filenames or glob patterns in any
+        /// order. An output filename can be provided with the `--output` flag when there is a single input
+        /// file; otherwise every output is written next to its input. The filename is not required when the
+        /// `--list-options` or `--help` flag is provided. When the `--format-source` flag is provided the
+        /// output filename defaults to the input filename. A filename of `-` reads the input from stdin or
+        /// writes the output to stdout.
         /// 
         /// # Errors
         /// 
-        /// This function will error if no filename is provided.
-        pub fn new(mut args: env::Args) -> Result<Config, &'static str> {
-            args.next();
-
-            match Config::extract_filenames(args.next(), args.next()) {
-                Ok(names) => Ok(Config { input_filename: names.0, output_filename: names.1 }),
-                Err(e) => Err(e)
-            }
+        /// This function will error if no filename is provided, a glob pattern matches no files, an
+        /// unknown flag is provided, or a flag is not followed by a valid value.
+        /// 
+        /// # Examples
+        /// 
+        /// ```
+        /// use interpreter::{Config, Command, OutputFormat};
+        /// 
+        /// let args = ["interpreter", "export", "song.txt", "--transpose", "+2"].map(String::from);
+        /// let config = Config::parse(args).unwrap();
+        /// 
+        /// assert_eq!(Command::Export, config.command);
+        /// assert_eq!(OutputFormat::Midi, config.format);
+        /// assert_eq!(2, config.transpose);
+        /// ```
+        pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Config, InterpreterError> {
+            Config::from_args(args.into_iter().skip(1)).map_err(InterpreterError::Args)
         }
 
-        /// Retrieves the input and output filenames from the provided `Option` parameters. The output
-        /// filename is optional.
+        /// Creates a new file configuration struct from the provided arguments, excluding the
+        /// executable name.
         /// 
         /// # Errors
         /// 
-        /// This function will error if no filename is provided.
-        fn extract_filenames(input: Option<String>, output: Option<String>) -> Result<(PathBuf, PathBuf), &'static str> {
-            // check if a filename was provided
-            let input_path = match input {
-                Some(filename) => PathBuf::from(filename),
-                None => return Err("No filename was provided."),
+        /// This function will error if no filename is provided, a glob pattern matches no files, an
+        /// unknown flag is provided, or a flag is not followed by a valid value.
+        pub(crate) fn from_args(args: impl Iterator<Item = String>) -> Result<Config, &'static str> {
+            let mut config = Config::default();
+            let mut filenames = vec![];
+            let mut output = None;
+            let mut format = None;
+
+            // the command is only recognized as the first argument
+            let mut args = args.peekable();
+            let command = match args.peek().map(String::as_str) {
+                Some("render") => Some(Command::Render),
+                Some("check") => Some(Command::Check),
+                Some("tokens") => Some(Command::Tokens),
+                Some("export") => Some(Command::Export),
+                Some("import") => Some(Command::Import),
+                Some("fmt") => Some(Command::Fmt),
+                _ => None,
             };
+            if let Some(command) = command {
+                config.command = command;
+                args.next();
+            }
 
-            // check if an output filename was provided
-            let mut output_path = match output {
-                Some(filename) => PathBuf::from(filename),
-                // if no name was provided, create one from the input filename
-                None => PathBuf::from(
-                    match input_path.file_stem() {
-                        Some(name) => {
-                            let mut n = OsString::from(name);
-                            n.push("-output");
-                            n
-                        },
-                        None => OsString::from("output"),
-                    }
-                ),
+            // flags start with "-"; every other argument, or a lone "-", is a filename
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--help" | "-h" => config.help = true,
+                    "--quiet" | "-q" => config.quiet = true,
+                    "--list-options" => config.list_options = true,
+                    "--chord-diagrams" => config.chord_diagrams = true,
+                    "--format-source" => config.format_source = true,
+                    "--check" => config.check_format = true,
+                    "--force" => config.force = true,
+                    "--stdout" => config.stdout = true,
+                    "--watch" => config.watch = true,
+                    "--normalize-accidentals" => config.normalize_accidentals = true,
+                    "--ignore-unknown-options" => config.ignore_unknown_options = true,
+                    "--deny-warnings" => config.deny_warnings = true,
+                    "--keep-comments" => config.keep_comments = true,
+                    "--play" => config.play = true,
+                    "--click" => config.click = true,
+                    "--output" | "-o" => {
+                        output = match args.next() {
+                            Some(filename) => Some(filename),
+                            None => return Err("The --output flag must be followed by an output filename."),
+                        };
+                    },
+                    "--spread-next" => {
+                        config.spread_next = match args.next().map(|c| c.chars().collect::<Vec<char>>()) {
+                            Some(c) if c.len() == 1 => c[0],
+                            _ => return Err("The --spread-next flag must be followed by a single char."),
+                        };
+                    },
+                    "--loop" => {
+                        config.practice_loop = match (args.next(), args.next()) {
+                            (Some(range), Some(times)) => Some(Config::parse_loop(&range, &times)?),
+                            _ => return Err("The --loop flag must be followed by a measure range and a repeat count: --loop M-N xC."),
+                        };
+                    },
+                    "--summary" => {
+                        config.summary = match args.next().as_deref() {
+                            Some("json") => true,
+                            _ => return Err("The --summary flag must be followed by a summary format: --summary json."),
+                        };
+                    },
+                    "--format" => {
+                        format = match args.next() {
+                            Some(format) => Some(format),
+                            None => return Err("The --format flag must be followed by an output format: text, html, svg, pdf, midi, wav, json, alphatex, or lilypond."),
+                        };
+                    },
+                    "--emit" => {
+                        config.emit = match args.next().as_deref() {
+                            Some("tokens") => Some(Emit::Tokens),
+                            Some("tokens-json") => Some(Emit::TokensJson),
+                            Some("score-json") => Some(Emit::ScoreJson),
+                            _ => return Err("The --emit flag must be followed by a stage to emit: tokens, tokens-json, or score-json."),
+                        };
+                    },
+                    "--play-midi" => {
+                        config.play_midi = match args.next() {
+                            Some(port) => Some(port),
+                            None => return Err("The --play-midi flag must be followed by the number or part of the name of a MIDI output port."),
+                        };
+                    },
+                    "--seek" => {
+                        config.seek = match args.next().map(|beats| beats.parse::<u32>()) {
+                            Some(Ok(beats)) => beats,
+                            _ => return Err("The --seek flag must be followed by a whole number of beats."),
+                        };
+                    },
+                    "--width" => {
+                        config.width = match args.next().map(|width| width.parse::<usize>()) {
+                            Some(Ok(width)) if width > 0 => Some(width),
+                            _ => return Err("The --width flag must be followed by a whole number of chars greater than 0."),
+                        };
+                    },
+                    "--transpose" => {
+                        config.transpose = match args.next().map(|semitones| semitones.parse::<i32>()) {
+                            Some(Ok(semitones)) => semitones,
+                            _ => return Err("The --transpose flag must be followed by a whole number of semitones, such as +2 or -3."),
+                        };
+                    },
+                    flag if flag.starts_with('-') && flag != STDIO => return Err("An unknown flag was provided."),
+                    _ => filenames.push(arg),
+                }
+            }
+
+            // listing options and the usage does not read or write any files
+            if config.list_options || config.help {
+                return Ok(config)
+            }
+
+            // formatting writes the source back in place of the tabs
+            config.format_source |= config.command == Command::Fmt;
+            if config.check_format && !config.format_source {
+                return Err("The --check flag can only be used with the fmt command or the --format-source flag.")
+            }
+
+            // tokens and exported scores are written in place of the tabs
+            match (config.command, format.as_deref()) {
+                (Command::Render | Command::Check, None | Some("text")) => config.format = OutputFormat::Text,
+                (Command::Render | Command::Check, Some("html")) => config.format = OutputFormat::Html,
+                (Command::Render | Command::Check, Some("pdf")) => config.format = OutputFormat::Pdf,
+                (Command::Render | Command::Check, Some("midi")) => config.format = OutputFormat::Midi,
+                (Command::Render | Command::Check | Command::Export, Some("wav")) => config.format = OutputFormat::Wav,
+                (Command::Render | Command::Check | Command::Export, Some("alphatex")) => config.format = OutputFormat::AlphaTex,
+                (Command::Render | Command::Check | Command::Export, Some("lilypond")) => config.format = OutputFormat::LilyPond,
+                (Command::Render | Command::Check | Command::Export, Some("svg")) => config.format = OutputFormat::Svg,
+                (Command::Tokens, None | Some("json")) => config.emit = Some(Emit::TokensJson),
+                (Command::Tokens, Some("text")) => config.emit = Some(Emit::Tokens),
+                (Command::Export, None | Some("midi")) => config.format = OutputFormat::Midi,
+                (Command::Export, Some("json")) => config.emit = Some(Emit::ScoreJson),
+                (Command::Import | Command::Fmt, None | Some("text")) => config.format = OutputFormat::Text,
+                (Command::Render | Command::Check, _) => return Err("The render command writes the text, html, svg, pdf, midi, wav, alphatex, or lilypond formats."),
+                (Command::Tokens, _) => return Err("The tokens command writes the text or json formats."),
+                (Command::Export, _) => return Err("The export command writes the midi, wav, json, svg, alphatex, or lilypond formats."),
+                (Command::Import, _) => return Err("The import command writes the text format."),
+                (Command::Fmt, _) => return Err("The fmt command writes the text format."),
+            }
+
+            // audio is only synthesized by the playback feature, and only scores can be played
+            if (config.play || config.format == OutputFormat::Wav) && !cfg!(feature = "playback") {
+                return Err("The wav format and the --play flag need the interpreter to be built with the playback feature.")
+            }
+            if config.play && (!matches!(config.command, Command::Render | Command::Export) || config.emit.is_some() || config.format_source) {
+                return Err("The --play flag can only be used to render or export a score.")
+            }
+            if config.play_midi.is_some() && !cfg!(feature = "midi-output") {
+                return Err("The --play-midi flag needs the interpreter to be built with the midi-output feature.")
+            }
+            if config.play_midi.is_some() && (!matches!(config.command, Command::Render | Command::Export) || config.emit.is_some() || config.format_source || config.play) {
+                return Err("The --play-midi flag can only be used to render or export a score, and not along with --play.")
+            }
+
+            let mut inputs = vec![];
+            for filename in filenames {
+                inputs.extend(expand_glob(&filename)?);
+            }
+            match inputs.len() {
+                0 => return Err("No filename was provided."),
+                1 => (),
+                _ if output.is_some() => return Err("The --output flag can only be used with a single input file."),
+                _ if inputs.iter().any(|input| input == Path::new(STDIO)) => {
+                    return Err("Stdin can only be read when it is the only input file.")
+                },
+                _ => (),
+            }
+            if config.watch && inputs[0] == Path::new(STDIO) {
+                return Err("The --watch flag cannot be used when reading from stdin.")
+            }
+
+            // a "-" output filename writes to stdout, as does reading from stdin without an output filename
+            // the readable token table is printed unless an output file is given
+            config.stdout |= match &output {
+                Some(output) => output == STDIO,
+                None => inputs[0] == Path::new(STDIO) || config.emit == Some(Emit::Tokens),
+            };
+            config.input_filename = inputs[0].clone();
+            config.output_filename = match output.filter(|output| output != STDIO) {
+                Some(output) => config.with_extension(PathBuf::from(output)),
+                None => config.default_output(&inputs[0]),
             };
+            config.input_filenames = inputs;
+            Ok(config)
+        }
 
-            // the output file extension will always be a .txt
-            output_path.set_extension("txt");
+        /// Creates the output filename an input file is written to when no output filename is provided.
+        /// Formatted source is written back to the input file; everything else is written next to the
+        /// input file with `-output` added to its name.
+        pub(crate) fn default_output(&self, input: &Path) -> PathBuf {
+            if self.format_source {
+                return input.to_path_buf()
+            }
+            let name = match input.file_stem() {
+                Some(name) => {
+                    let mut n = OsString::from(name);
+                    n.push("-output");
+                    n
+                },
+                None => OsString::from("output"),
+            };
+            self.with_extension(input.with_file_name(name))
+        }
 
-            Ok((input_path, output_path))
+        /// Sets the extension of the provided output filename to the one written by the output format.
+        /// Formatted source keeps the extension it was given.
+        fn with_extension(&self, mut output: PathBuf) -> PathBuf {
+            if self.format_source {
+                return output
+            }
+            // midi files and emitted stages are written with their own extension
+            output.set_extension(match (self.emit, self.format) {
+                (Some(Emit::Tokens), _) => "txt",
+                (Some(_), _) => "json",
+                (None, OutputFormat::Midi) => "mid",
+                (None, OutputFormat::Wav) => "wav",
+                (None, OutputFormat::AlphaTex) => "alphatex",
+                (None, OutputFormat::LilyPond) => "ly",
+                (None, OutputFormat::Html) => "html",
+                (None, OutputFormat::Svg) => "svg",
+                (None, OutputFormat::Pdf) => "pdf",
+                (None, OutputFormat::Text) => "txt",
+            });
+            output
         }
+
+        /// Parses a practice loop from a measure range formatted as `M-N` and a repeat count formatted as `xC`.
+        /// 
+        /// # Errors
+        /// 
+        /// This function will error if the range or count are improperly formatted, the range does not
+        /// start at measure `1` or later, the range ends before it starts, or the count is `0`.
+        fn parse_loop(range: &str, times: &str) -> Result<(usize, usize, u32), &'static str> {
+            let range = range.split_once('-').map(|(first, last)| (first.parse::<usize>(), last.parse::<usize>()));
+            let times = times.strip_prefix('x').map(|times| times.parse::<u32>());
+            match (range, times) {
+                (Some((Ok(first), Ok(last))), Some(Ok(times))) if first >= 1 && first <= last && times > 0 => {
+                    Ok((first, last, times))
+                },
+                _ => Err("The --loop flag must be followed by a measure range and a repeat count: --loop M-N xC."),
+            }
+        }
+    }
+
+    /// Expands a glob pattern into every file it matches, sorted by name. Only the file name of the
+    /// pattern may contain wildcards: `*` matches any run of chars and `?` matches a single char.
+    /// Filenames without wildcards are returned as they are.
+    /// 
+    /// # Errors
+    /// 
+    /// This function will error if a directory of the pattern contains wildcards, the directory cannot
+    /// be read, or no files match the pattern.
+    fn expand_glob(pattern: &str) -> Result<Vec<PathBuf>, &'static str> {
+        let is_glob = |s: &str| s.contains(['*', '?']);
+        if !is_glob(pattern) {
+            return Ok(vec![PathBuf::from(pattern)])
+        }
+        let path = Path::new(pattern);
+        let name = path.file_name().and_then(|name| name.to_str()).filter(|name| is_glob(name))
+            .ok_or("Glob patterns may only contain wildcards in the file name.")?;
+        let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty());
+        if dir.and_then(|dir| dir.to_str()).is_some_and(is_glob) {
+            return Err("Glob patterns may only contain wildcards in the file name.")
+        }
+
+        let entries = fs::read_dir(dir.unwrap_or(Path::new("."))).map_err(|_| "The directory of a glob pattern could not be read.")?;
+        let mut matches: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_file())
+            .filter(|entry| entry.file_name().to_str().is_some_and(|file| wildcard_match(name, file)))
+            .map(|entry| dir.map_or(PathBuf::from(entry.file_name()), |dir| dir.join(entry.file_name())))
+            .collect();
+        if matches.is_empty() {
+            return Err("A glob pattern did not match any files.")
+        }
+        matches.sort();
+        Ok(matches)
     }
+
+    /// Checks if the provided name matches a pattern where `*` matches any run of chars and `?` matches a
+    /// single char.
+    pub(crate) fn wildcard_match(pattern: &str, name: &str) -> bool {
+        let pattern: Vec<char> = pattern.chars().collect();
+        let name: Vec<char> = name.chars().collect();
+        // the position after the last `*` and the name position it was last matched up to
+        let (mut p, mut n, mut star) = (0, 0, None);
+        while n < name.len() {
+            match pattern.get(p) {
+                Some('*') => {
+                    star = Some((p + 1, n));
+                    p += 1;
+                },
+                Some(&c) if c == '?' || c == name[n] => {
+                    p += 1;
+                    n += 1;
+                },
+                // a mismatch lets the last `*` match one more char
+                _ => match star {
+                    Some((after, matched)) => {
+                        p = after;
+                        n = matched + 1;
+                        star = Some((after, matched + 1));
+                    },
+                    None => return false,
+                },
+            }
+        }
+        pattern[p..].iter().all(|&c| c == '*')
+    }
+}
+
+/// An error returned while interpreting a file or source string.
+#[derive(Debug)]
+pub enum InterpreterError {
+    /// The command line arguments are not valid.
+    Args(&'static str),
+    /// A file could not be read or written.
+    Io(io::Error),
+    /// Tokens could not be generated from the source.
+    Lex(LexError),
+    /// Tabs or a score could not be generated from the tokens.
+    Parse(ParseError),
+    /// An intermediate stage could not be written as JSON.
+    Json(serde_json::Error),
+    /// Some of the input files could not be interpreted, along with the error of each of them.
+    Batch { errors: Vec<(PathBuf, InterpreterError)>, total: usize },
 }
 
-/// Runs the file configuration and reads the provided filename's contents.
+impl fmt::Display for InterpreterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InterpreterError::Args(message) => write!(f, "{}", message),
+            InterpreterError::Io(e) => write!(f, "{}", e),
+            // diagnostics are shown along with the line they were found on
+            InterpreterError::Lex(e) => write!(f, "{}", e.render()),
+            InterpreterError::Parse(e) => write!(f, "{}", e.render()),
+            InterpreterError::Json(e) => write!(f, "{}", e),
+            InterpreterError::Batch { errors, total } => {
+                for (input, e) in errors.iter() {
+                    writeln!(f, "Interpreting {:?} failed:\n{}", input, e)?;
+                }
+                write!(f, "{} of {} files could not be interpreted.", errors.len(), total)
+            },
+        }
+    }
+}
+
+impl Error for InterpreterError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            InterpreterError::Args(_) | InterpreterError::Batch { .. } => None,
+            InterpreterError::Io(e) => Some(e),
+            InterpreterError::Lex(e) => Some(e),
+            InterpreterError::Parse(e) => Some(e),
+            InterpreterError::Json(e) => Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for InterpreterError {
+    fn from(e: io::Error) -> Self {
+        InterpreterError::Io(e)
+    }
+}
+
+impl From<LexError> for InterpreterError {
+    fn from(e: LexError) -> Self {
+        InterpreterError::Lex(e)
+    }
+}
+
+impl From<ParseError> for InterpreterError {
+    fn from(e: ParseError) -> Self {
+        InterpreterError::Parse(e)
+    }
+}
+
+impl From<serde_json::Error> for InterpreterError {
+    fn from(e: serde_json::Error) -> Self {
+        InterpreterError::Json(e)
+    }
+}
+
+/// Prints a progress message to stderr, unless the provided configuration is quiet.
+macro_rules! progress {
+    ($config:expr, $($arg:tt)*) => {
+        if !$config.quiet {
+            eprintln!($($arg)*);
+        }
+    };
+}
+
+/// The usage of the command line, printed by the `--help` flag.
+const USAGE: &str = "\
+Usage: interpreter [COMMAND] [FLAGS] <FILES>...
+
+Commands:
+    render      Interpret the files into tabs (default)
+    check       Report warnings and errors without writing any output
+    tokens      Write the tokens of the files as JSON
+    export      Export the score of the files as MIDI, or as JSON with --format json
+    import      Convert plain ASCII tabs in the files into tab notation source
+    fmt         Rewrite the files in their canonical form
+
+Flags:
+    -o, --output <FILE>             Write the output to FILE, or stdout for -
+    -q, --quiet                     Only print warnings and errors
+    -h, --help                      Print this usage
+        --format <FORMAT>           The output format: text, html, svg, pdf, midi, wav, json, alphatex, or lilypond
+        --width <CHARS>             Wrap staffs at a char width
+        --transpose <SEMITONES>     Transpose every fret, such as +2 or -3
+        --seek <BEATS>              Skip beats before the output begins
+        --loop <M-N> <xC>           Repeat a range of measures C times
+        --chord-diagrams            Append a fret diagram of every chord
+        --normalize-accidentals     Respell notes with the accidentals of the key
+        --ignore-unknown-options    Skip unknown options with a warning
+        --deny-warnings             Treat warnings as errors
+        --keep-comments             Print comments above the staff they were written in
+        --click                     Add a metronome click to MIDI and audio
+        --play                      Play the files as audio instead of writing any output
+        --play-midi <PORT>          Play the files on a MIDI output port, by number or part of its name
+        --spread-next <CHAR>        The char of the next beat spread operator
+        --summary json              Print a JSON summary of every file
+        --format-source             Rewrite the files in their canonical form
+        --check                     With fmt, report files that are not formatted instead of rewriting them
+        --emit <STAGE>              Write tokens, tokens-json, or score-json instead of the output
+        --stdout                    Write the output to stdout
+        --watch                     Interpret the files again when they change
+        --force                     Interpret files even if they are unchanged
+        --list-options              Print the supported options
+";
+
+/// How often watched input files are checked for changes.
+const WATCH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Runs the file configuration and reads the provided filename's contents. When more than one input file
+/// is provided, each is interpreted in turn; a file that fails does not stop the rest from being
+/// interpreted. The `watch` flag is not followed; use `watch` to interpret the files again as they change.
 /// 
 /// # Errors
 /// 
 /// This function will error if the file cannot be read, there is an issue generating tokens, or the tokens
-/// cannot be parsed.
-pub fn run(config: Config) -> Result<(), Box<dyn Error>> {
-    println!("Reading contents from {:?}.", config.input_filename);
+/// cannot be parsed. With more than one input file it errors if any of the files failed.
+pub fn run(config: Config) -> Result<(), InterpreterError> {
+    if config.list_options {
+        print!("{}", parser::list_options());
+        return Ok(())
+    }
+    if config.help {
+        print!("{}", USAGE);
+        return Ok(())
+    }
 
-    let file_contents = fs::read_to_string(config.input_filename)?;
+    if config.command == Command::Check {
+        return check(config).map(|_| ())
+    }
+    if config.check_format {
+        return check_format(config).map(|_| ())
+    }
 
-    println!("Generating tokens...");
+    each_input(config, run_file).map(|_| ())
+}
 
-    let mut lex = Lexer::new(file_contents);
-    let tokens = lex.generate_tokens()?;
+/// Checks the input files of the configuration for warnings and errors without writing any output,
+/// returning the amount of warnings found across every file. Every warning and error is printed to
+/// stderr along with the line it was found on.
+/// 
+/// # Errors
+/// 
+/// This function will error if a file cannot be read, there is an issue generating tokens, or the tokens
+/// cannot be parsed. With more than one input file it errors if any of the files failed.
+pub fn check(config: Config) -> Result<usize, InterpreterError> {
+    Ok(each_input(config, check_file)?.into_iter().sum())
+}
 
-    println!("Generating tabs...");
+/// Checks whether the input files of the configuration are in their canonical form without rewriting
+/// them, returning the amount of files that are not. Every file that is not formatted is printed to
+/// stderr along with the first line that differs from its canonical form.
+/// 
+/// # Errors
+/// 
+/// This function will error if a file cannot be read or there is an issue generating tokens. With more
+/// than one input file it errors if any of the files failed.
+pub fn check_format(config: Config) -> Result<usize, InterpreterError> {
+    Ok(each_input(config, check_format_file)?.into_iter().filter(|formatted| !formatted).count())
+}
 
-    let mut par = Parser::new(tokens);
-    let tabs = par.generate_tabs()?;
+/// Calls the provided function with the configuration of every input file in turn and returns their
+/// results in order. A file that fails does not stop the rest from being called.
+/// 
+/// # Errors
+/// 
+/// This function will error with the error of a single input file, or with the error of every file that
+/// failed if any of multiple input files failed.
+fn each_input<T>(config: Config, f: fn(Config) -> Result<T, InterpreterError>) -> Result<Vec<T>, InterpreterError> {
+    if config.input_filenames.len() <= 1 {
+        return f(config).map(|result| vec![result])
+    }
+
+    let total = config.input_filenames.len();
+    let mut results = vec![];
+    let mut errors = vec![];
+    for input in config.input_filenames.iter() {
+        let file_config = Config {
+            input_filename: input.clone(),
+            output_filename: config.default_output(input),
+            input_filenames: vec![],
+            ..config.clone()
+        };
+        match f(file_config) {
+            Ok(result) => results.push(result),
+            Err(e) => errors.push((input.clone(), e)),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(InterpreterError::Batch { errors, total })
+    }
+    Ok(results)
+}
+
+/// Interprets the input files of the configuration, then interprets them again every time one of them is
+/// modified. The result of every run is returned by the iterator once it finishes, so an error does not
+/// stop the watch; the iterator never ends.
+pub fn watch(config: Config) -> impl Iterator<Item = Result<(), InterpreterError>> {
+    // every save is interpreted, even if the contents did not change
+    let config = Config { watch: false, force: true, ..config };
+    let inputs = if config.input_filenames.is_empty() {
+        vec![config.input_filename.clone()]
+    } else {
+        config.input_filenames.clone()
+    };
+    // editors may briefly remove a file while saving it, so unreadable times are compared as well
+    let modified = move || -> Vec<Option<_>> {
+        inputs.iter().map(|input| fs::metadata(input).and_then(|metadata| metadata.modified()).ok()).collect()
+    };
+
+    let mut last = None;
+    std::iter::from_fn(move || {
+        if let Some(last) = last.as_ref() {
+            progress!(config, "Watching for changes...");
+            while modified() == *last {
+                thread::sleep(WATCH_INTERVAL);
+            }
+        }
+        last = Some(modified());
+        Some(run(config.clone()))
+    })
+}
+
+/// A reader that keeps a copy of every byte read through it, so a source lexed as it is read can still be
+/// quoted by the diagnostics of its errors.
+struct Recorded<R> {
+    reader: R,
+    read: Rc<RefCell<Vec<u8>>>,
+}
+
+impl<R: BufRead> Read for Recorded<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let amount = self.reader.read(buf)?;
+        self.read.borrow_mut().extend_from_slice(&buf[..amount]);
+        Ok(amount)
+    }
+}
+
+impl<R: BufRead> BufRead for Recorded<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.reader.fill_buf()
+    }
+
+    fn consume(&mut self, amount: usize) {
+        // the buffer is already filled, so this does not read anything new
+        if let Ok(buffer) = self.reader.fill_buf() {
+            self.read.borrow_mut().extend_from_slice(&buffer[..amount.min(buffer.len())]);
+        }
+        self.reader.consume(amount);
+    }
+}
+
+/// Generates the tokens of the input file of the configuration and returns them along with its
+/// contents. Stdin is lexed line by line as it is read instead of being read into memory first, while a
+/// copy of it is kept for error messages.
+/// 
+/// # Errors
+/// 
+/// This function errors if the input cannot be read or there is an issue generating tokens.
+fn lex_input(config: &Config) -> Result<(String, Vec<Token>), InterpreterError> {
+    if config.input_filename != Path::new(STDIO) {
+        let file_contents = read_input(config)?;
+        progress!(config, "Generating tokens...");
+        let mut lex = Lexer::from(file_contents.as_str());
+        lex.set_spread_next(config.spread_next)?;
+        let tokens = lex.generate_tokens()?.clone();
+        return Ok((file_contents, tokens))
+    }
+
+    progress!(config, "Generating tokens from stdin...");
+    let read = Rc::new(RefCell::new(vec![]));
+    let mut lex = Lexer::from_reader(Recorded { reader: io::stdin().lock(), read: Rc::clone(&read) });
+    lex.set_spread_next(config.spread_next)?;
+    let tokens = lex.generate_tokens()?.clone();
+    let file_contents = String::from_utf8_lossy(&read.borrow()).into_owned();
+    Ok((file_contents, tokens))
+}
+
+/// Reads the contents of the input file of the configuration, or of stdin if the input filename is `-`.
+fn read_input(config: &Config) -> io::Result<String> {
+    // progress is written to stderr so the output can be written to stdout
+    if config.input_filename == Path::new(STDIO) {
+        progress!(config, "Reading contents from stdin.");
+        io::read_to_string(io::stdin())
+    } else {
+        progress!(config, "Reading contents from {:?}.", config.input_filename);
+        fs::read_to_string(&config.input_filename)
+    }
+}
+
+/// Checks the single input file of the configuration and returns the amount of warnings found.
+fn check_file(config: Config) -> Result<usize, InterpreterError> {
+    let (file_contents, tokens) = lex_input(&config)?;
+
+    progress!(config, "Checking tabs...");
+
+    let (tabs, summary) = interpret(&file_contents, &tokens, &config);
+    if config.summary {
+        print_summary(&config, &summary)?;
+    }
+    tabs?;
+
+    progress!(config, "{:?} checked with {} warning(s).", config.input_filename, summary.warnings);
+
+    Ok(summary.warnings)
+}
+
+/// Checks whether the single input file of the configuration is in its canonical form.
+fn check_format_file(config: Config) -> Result<bool, InterpreterError> {
+    let (file_contents, tokens) = lex_input(&config)?;
+    let formatted = parser::format_source(&tokens);
+
+    // the first differing line is reported, or the line after the end of the shorter text
+    let mut lines = file_contents.lines().zip(formatted.lines());
+    let line = match lines.position(|(written, canonical)| written != canonical) {
+        Some(i) => i + 1,
+        None if file_contents == formatted => {
+            progress!(config, "{:?} is formatted.", config.input_filename);
+            return Ok(true)
+        },
+        None => file_contents.lines().count().min(formatted.lines().count()) + 1,
+    };
+    eprintln!("{:?} is not formatted, starting on line {}.", config.input_filename, line);
+    Ok(false)
+}
+
+/// Converts a score into the text of an output format, such as alphaTex.
+type Exporter = fn(&parser::ast::Score) -> String;
+
+/// Interprets the single input file of the configuration and writes its output.
+fn run_file(config: Config) -> Result<(), InterpreterError> {
+    if config.command == Command::Import {
+        // ASCII tabs are not tab notation, so the whole input is read before it is imported
+        let file_contents = read_input(&config)?;
+
+        progress!(config, "Importing tabs...");
+
+        let tokens = parser::import::ascii(&file_contents).map_err(|e| e.with_source(&file_contents))?;
+        write_output(&config, parser::format_source(&tokens).as_bytes(), None)?;
+
+        progress!(config, "Tabs imported successfully!");
+
+        return Ok(())
+    }
+
+    let (file_contents, tokens) = lex_input(&config)?;
+    let tokens = &tokens;
+
+    // skip interpreting files whose output was generated from the same contents and flags
+    let hash = content_hash(&file_contents, &config);
+    if !config.force && !config.format_source && !config.summary && !config.stdout && !config.play && config.play_midi.is_none() && config.output_filename.exists()
+        && fs::read_to_string(hash_filename(&config.output_filename)).is_ok_and(|stored| stored == hash) {
+        progress!(config, "{:?} is unchanged, skipping. Use --force to interpret it anyway.", config.input_filename);
+        return Ok(())
+    }
+
+    if config.format_source {
+        write_output(&config, parser::format_source(tokens).as_bytes(), None)?;
+
+        progress!(config, "Source formatted successfully!");
+
+        return Ok(())
+    }
+
+    if let Some(emit) = config.emit {
+        let json = match emit {
+            Emit::Tokens => format_tokens(tokens),
+            Emit::TokensJson => serde_json::to_string_pretty(tokens)?,
+            Emit::ScoreJson => {
+                progress!(config, "Generating score...");
+
+                let mut par = parser(tokens, &config);
+                let score = par.parse().map_err(|e| e.with_source(&file_contents));
+                print_warnings(&par, &file_contents);
+                serde_json::to_string_pretty(&score?)?
+            },
+        };
+
+        write_output(&config, json.as_bytes(), Some(&hash))?;
+
+        progress!(config, "{:?} emitted successfully!", emit);
+
+        return Ok(())
+    }
+
+    #[cfg(feature = "midi-output")]
+    if let Some(port) = &config.play_midi {
+        progress!(config, "Generating MIDI...");
+
+        let mut par = parser(tokens, &config);
+        let score = par.parse().map_err(|e| e.with_source(&file_contents));
+        print_warnings(&par, &file_contents);
+        if config.summary {
+            print_summary(&config, &par.summary())?;
+        }
+
+        play_midi(&parser::export::midi_timeline(&score?), port, &config)?;
+
+        progress!(config, "{:?} played successfully!", config.input_filename);
+
+        return Ok(())
+    }
+
+    #[cfg(feature = "playback")]
+    if config.play || config.format == OutputFormat::Wav {
+        progress!(config, "Synthesizing audio...");
+
+        let mut par = parser(tokens, &config);
+        let score = par.parse().map_err(|e| e.with_source(&file_contents));
+        print_warnings(&par, &file_contents);
+        if config.summary {
+            print_summary(&config, &par.summary())?;
+        }
+        let wav = parser::export::wav(&score?);
+
+        if config.play {
+            progress!(config, "Playing {:?}...", config.input_filename);
+            play(&wav)?;
+        } else {
+            write_output(&config, &wav, Some(&hash))?;
+
+            progress!(config, "WAV exported successfully!");
+        }
+
+        return Ok(())
+    }
+
+    if config.format == OutputFormat::Midi {
+        progress!(config, "Generating MIDI...");
+
+        let mut par = parser(tokens, &config);
+        let score = par.parse().map_err(|e| e.with_source(&file_contents));
+        print_warnings(&par, &file_contents);
+        if config.summary {
+            print_summary(&config, &par.summary())?;
+        }
+
+        write_output(&config, &parser::export::midi(&score?), Some(&hash))?;
+
+        progress!(config, "MIDI exported successfully!");
+
+        return Ok(())
+    }
+
+    // text exports of the score are written the same way, each by its own exporter
+    let export: Option<(&str, Exporter)> = match config.format {
+        OutputFormat::AlphaTex => Some(("alphaTex", parser::export::alphatex)),
+        OutputFormat::LilyPond => Some(("LilyPond", parser::export::to_lilypond)),
+        OutputFormat::Svg => Some(("SVG", parser::export::svg)),
+        OutputFormat::Text | OutputFormat::Html | OutputFormat::Pdf | OutputFormat::Midi | OutputFormat::Wav => None,
+    };
+    if let Some((name, export)) = export {
+        progress!(config, "Generating {}...", name);
+
+        let mut par = parser(tokens, &config);
+        let score = par.parse().map_err(|e| e.with_source(&file_contents));
+        print_warnings(&par, &file_contents);
+        if config.summary {
+            print_summary(&config, &par.summary())?;
+        }
+
+        write_output(&config, export(&score?).as_bytes(), Some(&hash))?;
+
+        progress!(config, "{} exported successfully!", name);
+
+        return Ok(())
+    }
+
+    if config.format == OutputFormat::Pdf {
+        progress!(config, "Generating PDF...");
+
+        let mut par = parser(tokens, &config);
+        let pdf = par.generate_pdf().map_err(|e| e.with_source(&file_contents));
+        print_warnings(&par, &file_contents);
+        if config.summary {
+            print_summary(&config, &par.summary())?;
+        }
+
+        write_output(&config, &pdf?, Some(&hash))?;
+
+        progress!(config, "PDF exported successfully!");
+
+        return Ok(())
+    }
+
+    progress!(config, "Generating tabs...");
+
+    let (tabs, summary) = interpret(&file_contents, tokens, &config);
+    if config.summary {
+        print_summary(&config, &summary)?;
+    }
+    let tabs = tabs?;
 
     // println!("{}", tabs);
 
-    println!("Writing output to {:?}.", config.output_filename);
+    write_output(&config, tabs.as_bytes(), Some(&hash))?;
+
+    progress!(config, "Guitar tabs interpreted successfully!");
 
-    fs::write(config.output_filename, tabs)?;
+    Ok(())
+}
 
-    println!("Guitar tabs interpreted successfully!");
+/// Plays the audio of a WAV file with the audio player of the platform, waiting until it has finished:
+/// `afplay` on macOS, PowerShell on Windows, and the first of `paplay`, `aplay`, or `ffplay` found
+/// elsewhere.
+/// 
+/// # Errors
+/// 
+/// This function will error if the audio cannot be written to a temporary file, no audio player is
+/// found, or the audio player fails.
+#[cfg(feature = "playback")]
+fn play(wav: &[u8]) -> io::Result<()> {
+    let path = std::env::temp_dir().join(format!("tab_notation_play_{}.wav", std::process::id()));
+    fs::write(&path, wav)?;
+    let file = path.to_string_lossy();
+    let players: Vec<(&str, Vec<String>)> = if cfg!(target_os = "macos") {
+        vec![("afplay", vec![file.to_string()])]
+    } else if cfg!(windows) {
+        vec![("powershell", vec![String::from("-NoProfile"), String::from("-Command"), format!("(New-Object Media.SoundPlayer '{}').PlaySync()", file)])]
+    } else {
+        vec![
+            ("paplay", vec![file.to_string()]),
+            ("aplay", vec![String::from("-q"), file.to_string()]),
+            ("ffplay", ["-nodisp", "-autoexit", "-loglevel", "quiet"].iter().map(|arg| arg.to_string()).chain([file.to_string()]).collect()),
+        ]
+    };
 
+    let mut played = Err(io::Error::new(io::ErrorKind::NotFound, "No audio player was found; write the audio to a file with --format wav instead."));
+    for (player, args) in players {
+        match std::process::Command::new(player).args(&args).status() {
+            // the next player is tried if this one is not installed
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => played = Err(e),
+            Ok(status) if status.success() => played = Ok(()),
+            Ok(status) => played = Err(io::Error::other(format!("{} failed to play the audio: {}", player, status))),
+        }
+        break
+    }
+    let _ = fs::remove_file(&path);
+    played
+}
+
+/// Sends the messages of a MIDI timeline to a MIDI output port as they are played, waiting for each
+/// until its time has come. The port is chosen by its number, counted from `0`, or by part of its name,
+/// ignoring case. Every note still sounding is stopped once the timeline ends.
+/// 
+/// # Errors
+/// 
+/// This function will error if MIDI output is not available, no port matches, or the port cannot be
+/// connected to or sent to. When no port matches, the error lists every port.
+#[cfg(feature = "midi-output")]
+fn play_midi(timeline: &[(f64, Vec<u8>)], port: &str, config: &Config) -> io::Result<()> {
+    let output = midir::MidiOutput::new("tab_notation").map_err(io::Error::other)?;
+    let ports = output.ports();
+    let names: Vec<String> = ports.iter().map(|port| output.port_name(port).unwrap_or_default()).collect();
+    let index = port.parse::<usize>().ok().filter(|i| *i < ports.len())
+        .or_else(|| names.iter().position(|name| name.to_lowercase().contains(&port.to_lowercase())));
+    let index = match index {
+        Some(index) => index,
+        None if names.is_empty() => return Err(io::Error::new(io::ErrorKind::NotFound, "No MIDI output ports were found.")),
+        None => {
+            let list: Vec<String> = names.iter().enumerate().map(|(i, name)| format!("    {}: {}", i, name)).collect();
+            return Err(io::Error::new(io::ErrorKind::NotFound, format!("No MIDI output port matches {:?}. The ports are:\n{}", port, list.join("\n"))))
+        },
+    };
+    let mut connection = output.connect(&ports[index], "tab_notation").map_err(|e| io::Error::other(e.to_string()))?;
+
+    progress!(config, "Playing {:?} on {}...", config.input_filename, names[index]);
+
+    let start = std::time::Instant::now();
+    for (seconds, message) in timeline.iter() {
+        if let Some(wait) = Duration::from_secs_f64(*seconds).checked_sub(start.elapsed()) {
+            thread::sleep(wait);
+        }
+        connection.send(message).map_err(io::Error::other)?;
+    }
+    // all notes off on the channel every note is played on
+    connection.send(&[0xB0, 123, 0]).map_err(io::Error::other)?;
+    connection.close();
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Interprets a source string into guitar tabs using the flags of the provided configuration; the
+/// configuration's filenames are not used. Warnings are printed to stderr.
+/// 
+/// # Errors
+/// 
+/// This function will error if there is an issue generating tokens or the tokens cannot be parsed.
+pub fn run_str(source: &str, config: &Config) -> Result<String, InterpreterError> {
+    let mut lex = Lexer::new(source.to_string());
+    lex.set_spread_next(config.spread_next)?;
+    let tokens = lex.generate_tokens()?;
+
+    Ok(interpret(source, tokens, config).0?)
+}
+
+/// Interprets a source string into guitar tabs using the default flags. Warnings are printed to stderr.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use interpreter::prelude::*;
+/// 
+/// let tabs = compile("E A\n0 3").unwrap();
+/// 
+/// assert_eq!("A  |-3-\nE  |-0-\n\n     1 \n\n", tabs);
+/// ```
+/// 
+/// # Errors
+/// 
+/// This function will error if there is an issue generating tokens or the tokens cannot be parsed.
+pub fn compile(source: &str) -> Result<String, InterpreterError> {
+    run_str(source, &Config::default())
+}
+
+/// Parses the tokens of the provided source into guitar tabs using the flags of the provided
+/// configuration, printing any warnings to stderr. The summary of what was generated is returned even if
+/// the tabs could not be.
+fn interpret(source: &str, tokens: &[Token], config: &Config) -> (Result<String, ParseError>, Summary) {
+    let mut par = parser(tokens, config);
+    let tabs = par.generate_tabs().map(str::to_string).map_err(|e| e.with_source(source));
+
+    print_warnings(&par, source);
+    (tabs, par.summary())
+}
+
+/// Creates a parser for the tokens using the flags of the provided configuration.
+fn parser<'a>(tokens: &'a [Token], config: &Config) -> Parser<'a> {
+    let mut par = Parser::new(tokens);
+    par.set_seek(config.seek);
+    par.set_line_width(config.width);
+    par.set_html(config.format == OutputFormat::Html);
+    par.set_chord_diagrams(config.chord_diagrams);
+    par.set_normalize_accidentals(config.normalize_accidentals);
+    par.set_ignore_unknown_options(config.ignore_unknown_options);
+    par.set_deny_warnings(config.deny_warnings);
+    par.set_keep_comments(config.keep_comments);
+    if let Some((first, last, times)) = config.practice_loop {
+        par.set_loop(first, last, times);
+    }
+    par.set_transpose(config.transpose);
+    par.set_click(config.click);
+    par
+}
+
+/// Prints every warning logged by the parser to stderr, along with the line of the provided source it was
+/// found on.
+fn print_warnings(par: &Parser, source: &str) {
+    for warning in par.diagnostics().iter().filter(|diagnostic| diagnostic.severity == Severity::Warning) {
+        eprintln!("{}", warning.clone().with_source(source).render());
+    }
+}
+
+/// Writes the output to stdout if the configuration writes to stdout, or to its output file otherwise
+/// along with the provided content hash of the input, if any, so an unchanged input can be skipped.
+fn write_output(config: &Config, output: &[u8], hash: Option<&str>) -> io::Result<()> {
+    if config.stdout {
+        progress!(config, "Writing output to stdout.");
+        return io::stdout().write_all(output)
+    }
+    progress!(config, "Writing output to {:?}.", config.output_filename);
+    fs::write(&config.output_filename, output)?;
+    if let Some(hash) = hash {
+        fs::write(hash_filename(&config.output_filename), hash)?;
+    }
+    Ok(())
+}
+
+/// Prints the summary of the input file as a single line JSON object to stdout, or to stderr if the output
+/// is written to stdout.
+fn print_summary(config: &Config, summary: &Summary) -> Result<(), serde_json::Error> {
+    let json = summary_json(&config.input_filename, summary)?;
+    if config.stdout {
+        eprintln!("{}", json);
+    } else {
+        println!("{}", json);
+    }
+    Ok(())
+}
+
+/// Formats the tokens as a table with a row for every token, holding the line and column it starts at,
+/// its type, its value, and its literal. Values are quoted so whitespace can be seen.
+fn format_tokens(tokens: &[Token]) -> String {
+    let rows: Vec<[String; 4]> = tokens.iter().map(|token| [
+        // a column of 0 is unknown
+        match token.column {
+            0 => format!("{}", token.line),
+            column => format!("{}:{}", token.line, column),
+        },
+        format!("{:?}", token.type_of),
+        format!("{:?}", token.value),
+        format!("{:?}", token.literal),
+    ]).collect();
+
+    let header = ["LINE:COL", "TYPE", "VALUE", "LITERAL"].map(String::from);
+    let widths: Vec<usize> = (0..3)
+        .map(|i| rows.iter().chain([&header]).map(|row| row[i].chars().count()).max().unwrap_or(0))
+        .collect();
+    let mut table = String::new();
+    for row in std::iter::once(&header).chain(rows.iter()) {
+        table.push_str(&format!(
+            "{:<w0$}  {:<w1$}  {:<w2$}  {}\n",
+            row[0], row[1], row[2], row[3], w0 = widths[0], w1 = widths[1], w2 = widths[2],
+        ));
+    }
+    table
+}
+
+/// The summary of an input file as it is printed, with the name of the file before its counts.
+#[derive(Serialize)]
+struct FileSummary<'a> {
+    file: Cow<'a, str>,
+    #[serde(flatten)]
+    summary: &'a Summary,
+}
+
+/// Formats the summary of the provided input file as a single line JSON object.
+fn summary_json(input_filename: &Path, summary: &Summary) -> Result<String, serde_json::Error> {
+    serde_json::to_string(&FileSummary { file: input_filename.to_string_lossy(), summary })
+}
+
+/// A 64-bit FNV-1a hasher. Unlike the hasher of the standard library its output never changes between
+/// Rust releases, so stored hashes stay valid when the interpreter is built with a newer compiler.
+struct FnvHasher(u64);
+
+impl FnvHasher {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    fn new() -> FnvHasher {
+        FnvHasher(FnvHasher::OFFSET_BASIS)
+    }
+}
+
+impl Hasher for FnvHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ *byte as u64).wrapping_mul(FnvHasher::PRIME);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Hashes the file contents along with the interpreter version and every flag that changes the output,
+/// returning the hash as a hex string.
+fn content_hash(contents: &str, config: &Config) -> String {
+    let mut hasher = FnvHasher::new();
+    env!("CARGO_PKG_VERSION").hash(&mut hasher);
+    contents.hash(&mut hasher);
+    config.seek.hash(&mut hasher);
+    config.width.hash(&mut hasher);
+    config.chord_diagrams.hash(&mut hasher);
+    config.normalize_accidentals.hash(&mut hasher);
+    config.ignore_unknown_options.hash(&mut hasher);
+    config.deny_warnings.hash(&mut hasher);
+    config.keep_comments.hash(&mut hasher);
+    config.spread_next.hash(&mut hasher);
+    config.practice_loop.hash(&mut hasher);
+    config.transpose.hash(&mut hasher);
+    config.click.hash(&mut hasher);
+    config.format.hash(&mut hasher);
+    config.emit.hash(&mut hasher);
+    config.summary.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// Creates the filename of the sidecar file storing the content hash of the provided output file.
+fn hash_filename(output_filename: &Path) -> PathBuf {
+    let mut filename = OsString::from(output_filename);
+    filename.push(".hash");
+    PathBuf::from(filename)
+}
+
+#[cfg(test)]
+mod interpreter_tests {
+    use std::{env, fs};
+    use crate::Config;
+
+    fn config(input_filename: &std::path::Path, output_filename: &std::path::Path) -> Config {
+        Config {
+            input_filename: input_filename.to_path_buf(),
+            output_filename: output_filename.to_path_buf(),
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn recorded_reader() {
+        use std::{cell::RefCell, io::Cursor, rc::Rc};
+
+        // a streamed source is lexed line by line while every byte read is kept for error messages
+        let read = Rc::new(RefCell::new(vec![]));
+        let source = "E A\n0 3\n5 é";
+        let mut lex = lexer::Lexer::from_reader(crate::Recorded { reader: Cursor::new(source), read: Rc::clone(&read) });
+        assert!(lex.generate_tokens().is_err());
+        assert_eq!(source, String::from_utf8_lossy(&read.borrow()));
+    }
+
+    #[test]
+    fn skips_unchanged_files() {
+        let dir = env::temp_dir().join(format!("tab_notation_cache_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("song.txt");
+        let output = dir.join("song-output.txt");
+
+        fs::write(&input, "E A\n0 3,").unwrap();
+        crate::run(config(&input, &output)).unwrap();
+        let rendered = fs::read_to_string(&output).unwrap();
+
+        // an unchanged file is skipped, leaving the existing output in place
+        fs::write(&output, "stale").unwrap();
+        crate::run(config(&input, &output)).unwrap();
+        assert_eq!("stale", fs::read_to_string(&output).unwrap());
+
+        // forcing an unchanged file interprets it again
+        crate::run(Config { force: true, ..config(&input, &output) }).unwrap();
+        assert_eq!(rendered, fs::read_to_string(&output).unwrap());
+
+        // a modified file is interpreted again
+        fs::write(&output, "stale").unwrap();
+        fs::write(&input, "E A\n0 5,").unwrap();
+        crate::run(config(&input, &output)).unwrap();
+        assert_ne!("stale", fs::read_to_string(&output).unwrap());
+        assert_ne!(rendered, fs::read_to_string(&output).unwrap());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn batch_errors() {
+        let dir = env::temp_dir().join(format!("tab_notation_batch_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let good = dir.join("good.txt");
+        let missing = dir.join("missing.txt");
+        fs::write(&good, "E A\n0 3,").unwrap();
+
+        // the error of every failed file is returned, and the other files are still interpreted
+        let config = Config { input_filenames: vec![missing.clone(), good.clone()], ..config(&missing, &dir.join("missing-output.txt")) };
+        match crate::run(config) {
+            Err(crate::InterpreterError::Batch { errors, total }) => {
+                assert_eq!((vec![missing], 2), (errors.into_iter().map(|(input, _)| input).collect(), total));
+            },
+            other => panic!("expected a batch error, got {:?}", other),
+        }
+        assert!(dir.join("good-output.txt").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn content_hashes() {
+        use std::hash::Hasher;
+
+        // the hasher follows the published FNV-1a test vectors
+        let mut hasher = crate::FnvHasher::new();
+        hasher.write(b"a");
+        assert_eq!(0xaf63_dc4c_8601_ec8c, hasher.finish());
+
+        let config = Config::default();
+        assert_eq!(crate::content_hash("E A\n0 3,", &config), crate::content_hash("E A\n0 3,", &config));
+        assert_ne!(crate::content_hash("E A\n0 3,", &config), crate::content_hash("E A\n0 5,", &config));
+        assert_ne!(crate::content_hash("E A\n0 3,", &config), crate::content_hash("E A\n0 3,", &Config { summary: true, ..Config::default() }));
+    }
+
+    #[test]
+    fn summary_json() {
+        let source = include_str!("../../tab_input/example.txt");
+        let mut lex = lexer::Lexer::from(source);
+        let tokens = lex.generate_tokens().unwrap();
+
+        let (tabs, summary) = crate::interpret(source, tokens, &Config::default());
+        assert!(tabs.is_ok());
+        assert_eq!(
+            "{\"file\":\"tab_input/example.txt\",\"staffs\":2,\"measures\":4,\"notes\":35,\"warnings\":0,\"errors\":0}",
+            crate::summary_json(std::path::Path::new("tab_input/example.txt"), &summary).unwrap()
+        );
+
+        // filenames are escaped so the summary is always valid JSON
+        let json = crate::summary_json(std::path::Path::new("say \"hi\".txt"), &summary).unwrap();
+        assert!(json.starts_with("{\"file\":\"say \\\"hi\\\".txt\","), "{}", json);
+    }
+
+    #[test]
+    fn midi_output() {
+        let dir = env::temp_dir().join(format!("tab_notation_midi_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("song.txt");
+        let output = dir.join("song-output.mid");
+
+        fs::write(&input, "E A\n0 3,").unwrap();
+        crate::run(Config { format: crate::OutputFormat::Midi, ..config(&input, &output) }).unwrap();
+        assert_eq!(b"MThd", &fs::read(&output).unwrap()[..4]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let mut lex = lexer::Lexer::from("[tempo=120]\nE A\n0 *3 5h7,");
+        let tokens = lex.generate_tokens().unwrap();
+
+        let json = serde_json::to_string(tokens).unwrap();
+        assert_eq!(tokens, &serde_json::from_str::<Vec<data::Token>>(&json).unwrap());
+
+        let score = parser::Parser::new(tokens).parse().unwrap();
+        let json = serde_json::to_string(&score).unwrap();
+        assert_eq!(score, serde_json::from_str::<parser::ast::Score>(&json).unwrap());
+    }
+
+    #[test]
+    fn structured_errors() {
+        use crate::{compile, InterpreterError};
+        use lexer::LexError;
+        use parser::ParseError;
+
+        // lexing errors keep the diagnostics of every syntax error
+        match compile("E\n?") {
+            Err(InterpreterError::Lex(LexError::Syntax(diagnostics))) => {
+                assert_eq!(vec![(2, "L001")], diagnostics.iter().map(|d| (d.line, d.code)).collect::<Vec<_>>());
+            },
+            other => panic!("Expected a lexing error, found {:?}", other),
+        }
+
+        // parsing errors keep the diagnostics of every option error
+        match compile("[time=x]\nE\n0") {
+            Err(InterpreterError::Parse(ParseError::Invalid(diagnostics))) => assert_eq!("P001", diagnostics[0].code),
+            other => panic!("Expected a parsing error, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rendered_errors() {
+        use crate::compile;
+
+        assert_eq!("[2:3] Error: Unknown character value: ?\n  |\n2 | 0 ? 3\n  |   ^", compile("E\n0 ? 3").unwrap_err().to_string());
+        assert!(compile("E\n[tempo=fast]\n0").unwrap_err().to_string().ends_with("\n  |\n2 | [tempo=fast]\n  | ^^^^^^^^^^^^"));
+    }
+
+    #[test]
+    fn stdio_filenames() {
+        let args = |args: &[&str]| Config::from_args(args.iter().map(|arg| arg.to_string())).unwrap();
+
+        // reading from stdin writes to stdout unless an output file is given
+        assert!(args(&["-"]).stdout);
+        assert!(!args(&["-", "-o", "song.txt"]).stdout);
+        assert!(args(&["song.txt", "--output", "-"]).stdout);
+        assert!(args(&["song.txt", "--stdout"]).stdout);
+        assert!(!args(&["song.txt"]).stdout);
+        assert!(args(&["song.txt", "-o", "-", "--format-source"]).stdout);
+    }
+
+    #[test]
+    fn batch_filenames() {
+        let args = |args: &[&str]| Config::from_args(args.iter().map(|arg| arg.to_string()));
+
+        // outputs are written next to their inputs
+        let config = args(&["songs/a.tab", "b.txt", "--format", "midi"]).unwrap();
+        assert_eq!(vec!["songs/a.tab", "b.txt"], config.input_filenames.iter().map(|f| f.to_str().unwrap()).collect::<Vec<_>>());
+        assert_eq!(std::path::Path::new("songs/a-output.mid"), config.output_filename);
+        assert_eq!(std::path::Path::new("b-output.mid"), config.default_output(std::path::Path::new("b.txt")));
+        assert!(args(&["a.txt", "b.txt", "-o", "out.txt"]).is_err());
+        assert!(args(&["a.txt", "-"]).is_err());
+        assert!(args(&["-", "--watch"]).is_err());
+
+        // glob patterns are expanded to the matching files in order
+        let config = args(&["../tab_input/*.txt"]).unwrap();
+        assert!(config.input_filenames.len() > 1);
+        assert!(config.input_filenames.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(config.input_filenames.iter().all(|f| f.extension().unwrap() == "txt"));
+        assert!(args(&["../tab_input/*.nothing"]).is_err());
+
+        use crate::file_config::wildcard_match;
+        assert!(wildcard_match("*.tab", "song.tab"));
+        assert!(wildcard_match("s?ng*", "song.tab"));
+        assert!(wildcard_match("*o*o*", "foobar"));
+        assert!(!wildcard_match("*.tab", "song.txt"));
+        assert!(!wildcard_match("s?ng", "sing.tab"));
+    }
+
+    #[test]
+    fn commands() {
+        use crate::{Command, Emit, OutputFormat};
+        let args = |args: &[&str]| Config::from_args(args.iter().map(|arg| arg.to_string()));
+
+        // the command defaults to rendering tabs
+        let config = args(&["song.txt", "--width", "80", "-q"]).unwrap();
+        assert_eq!((Command::Render, OutputFormat::Text, Some(80), true), (config.command, config.format, config.width, config.quiet));
+        assert_eq!(Command::Check, args(&["check", "song.txt"]).unwrap().command);
+        assert_eq!(Command::Import, args(&["import", "song.txt"]).unwrap().command);
+
+        // formatting rewrites the input file unless it only checks it
+        let config = args(&["fmt", "song.tab", "--check"]).unwrap();
+        assert_eq!((Command::Fmt, true, true), (config.command, config.format_source, config.check_format));
+        assert_eq!(std::path::Path::new("song.tab"), config.output_filename.as_path());
+        assert!(args(&["fmt", "song.tab", "--format", "midi"]).is_err());
+        assert!(args(&["song.tab", "--check"]).is_err());
+
+        // tokens and exported scores are written as json or midi
+        assert_eq!(Some(Emit::TokensJson), args(&["tokens", "song.txt"]).unwrap().emit);
+        assert_eq!(OutputFormat::Midi, args(&["export", "song.txt"]).unwrap().format);
+        assert_eq!(OutputFormat::AlphaTex, args(&["export", "song.txt", "--format", "alphatex"]).unwrap().format);
+        assert_eq!(OutputFormat::LilyPond, args(&["render", "song.txt", "--format", "lilypond"]).unwrap().format);
+        assert_eq!(OutputFormat::Html, args(&["song.txt", "--format", "html"]).unwrap().format);
+        assert_eq!(OutputFormat::Svg, args(&["export", "song.txt", "--format", "svg"]).unwrap().format);
+        assert_eq!(OutputFormat::Pdf, args(&["render", "song.txt", "--format", "pdf"]).unwrap().format);
+        let config = args(&["export", "song.txt", "--format", "json"]).unwrap();
+        assert_eq!((Some(Emit::ScoreJson), std::path::Path::new("song-output.json")), (config.emit, config.output_filename.as_path()));
+        assert!(args(&["render", "song.txt", "--format", "json"]).is_err());
+
+        // audio is only synthesized by the playback feature
+        let wav = args(&["export", "song.txt", "--format", "wav"]);
+        let play = args(&["song.txt", "--play"]);
+        if cfg!(feature = "playback") {
+            assert_eq!(std::path::Path::new("song-output.wav"), wav.unwrap().output_filename.as_path());
+            assert!(play.unwrap().play);
+        } else {
+            assert!(wav.is_err() && play.is_err());
+        }
+        assert!(args(&["check", "song.txt", "--play"]).is_err());
+        assert!(args(&["export", "song.txt", "--click"]).unwrap().click);
+        let play_midi = args(&["song.txt", "--play-midi", "fluid"]);
+        if cfg!(feature = "midi-output") {
+            assert_eq!(Some("fluid"), play_midi.unwrap().play_midi.as_deref());
+        } else {
+            assert!(play_midi.is_err());
+        }
+        assert!(args(&["tokens", "song.txt", "--play-midi", "0"]).is_err());
+        assert!(args(&["song.txt", "--play-midi"]).is_err());
+        let config = args(&["tokens", "song.txt", "--format", "text"]).unwrap();
+        assert_eq!((Some(Emit::Tokens), true), (config.emit, config.stdout));
+        assert!(args(&["tokens", "song.txt", "--format", "midi"]).is_err());
+
+        // a command is only recognized as the first argument
+        assert_eq!(std::path::Path::new("check"), args(&["song.txt", "check"]).unwrap().input_filenames[1]);
+        assert!(args(&["--help"]).unwrap().help);
+        assert!(args(&["song.txt", "-x"]).is_err());
+    }
+
+    #[test]
+    fn check_writes_nothing() {
+        let dir = env::temp_dir().join(format!("tab_notation_check_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("song.txt");
+        let output = dir.join("song-output.txt");
+
+        let check = || crate::check(Config { command: crate::Command::Check, ..config(&input, &output) });
+
+        fs::write(&input, "E A\n0 3,").unwrap();
+        assert_eq!(0, check().unwrap());
+
+        // short measures are warnings
+        fs::write(&input, "[time=2/4; fidelity=4]\nE A D\n0 3 5 7 2 1 0 0").unwrap();
+        assert_ne!(0, check().unwrap());
+        assert!(crate::check(Config { deny_warnings: true, ..config(&input, &output) }).is_err());
+
+        fs::write(&input, "E A\n0 ? 3,").unwrap();
+        assert!(check().is_err());
+        assert!(!output.exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn fmt_check() {
+        let dir = env::temp_dir().join(format!("tab_notation_fmt_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("song.tab");
+        let fmt = |check_format| Config { command: crate::Command::Fmt, format_source: true, check_format, ..config(&input, &input) };
+
+        fs::write(&input, "E A\n0 3,\n").unwrap();
+        assert_eq!(0, crate::check_format(fmt(true)).unwrap());
+
+        // checking reports the file without rewriting it
+        fs::write(&input, "E   A\n0  3,\n").unwrap();
+        assert_eq!(1, crate::check_format(fmt(true)).unwrap());
+        assert_eq!("E   A\n0  3,\n", fs::read_to_string(&input).unwrap());
+
+        crate::run(fmt(false)).unwrap();
+        assert_eq!("E A\n0 3,\n", fs::read_to_string(&input).unwrap());
+        assert_eq!(0, crate::check_format(fmt(true)).unwrap());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn token_table() {
+        let tokens = lexer::Lexer::from("E\n12 ,").generate_tokens().unwrap().clone();
+
+        assert_eq!(
+            concat!(
+                "LINE:COL  TYPE       VALUE  LITERAL\n",
+                "1:1       Note       \"E\"    None\n",
+                "2:1       Number     \"12\"   Number(12)\n",
+                "2:4       Next       \",\"    None\n",
+                "2:5       EndOfFile  \"\"     None\n",
+            ),
+            crate::format_tokens(&tokens)
+        );
+    }
+}
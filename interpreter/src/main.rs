@@ -1,15 +1,51 @@
 use std::{env, process};
 
-use interpreter::Config;
+use interpreter::{Config, Command};
 
 fn main() {
     // set up a configuration based on the arguments passed in
     // or fail if no arguments were passed in
-    let config = Config::new(env::args()).unwrap_or_else(|err| {
+    let config = Config::parse(env::args()).unwrap_or_else(|err| {
         eprintln!("Could not parse arguments: {}", err);
         process::exit(1);
     });
 
+    // watching interprets the files again every time they change, printing the error of every run
+    if config.watch {
+        for result in interpreter::watch(config) {
+            if let Err(e) = result {
+                eprintln!("Interpreter failed:\n{}", e);
+            }
+        }
+        return;
+    }
+
+    // checking exits with 1 if any warnings were found and 2 if any errors were found
+    if config.command == Command::Check {
+        match interpreter::check(config) {
+            Ok(0) => (),
+            Ok(_) => process::exit(1),
+            Err(e) => {
+                eprintln!("Check failed:\n{}", e);
+                process::exit(2);
+            },
+        }
+        return;
+    }
+
+    // checking the format exits with 1 if any files are not formatted and 2 if any files failed
+    if config.check_format {
+        match interpreter::check_format(config) {
+            Ok(0) => (),
+            Ok(_) => process::exit(1),
+            Err(e) => {
+                eprintln!("Check failed:\n{}", e);
+                process::exit(2);
+            },
+        }
+        return;
+    }
+
     // execute the file reading operation
     // or fail if the file cannot be read
     if let Err(e) = interpreter::run(config) {
@@ -1,4 +1,66 @@
-use data::{Token, TokenType, Literal, Watcher};
+use std::{fmt, error::Error, io::BufRead, ops::Range, collections::VecDeque};
+
+use data::{Token, BorrowedToken, TokenType, Literal, Watcher, Diagnostic, Severity};
+
+/// The chars that can be used for the next beat spread operator: `;` and the ASCII punctuation that has
+/// no other meaning in tab notation. Every other char is matched before any other operator when lexing, so
+/// it would hide the operator it shadows.
+const SPREAD_NEXT_CHARS: &str = ";&!$%+?^";
+
+/// An error returned by a `Lexer`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum LexError {
+    /// The source has incorrect tab notation syntax. Holds every error diagnostic logged, each with
+    /// the line it was found on and its code.
+    Syntax(Vec<Diagnostic>),
+    /// The char cannot be used for the next beat spread operator.
+    InvalidSpreadNext(char),
+}
+
+impl LexError {
+    /// Returns the error diagnostics of a syntax error, or nothing for any other error.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        match self {
+            LexError::Syntax(diagnostics) => diagnostics,
+            LexError::InvalidSpreadNext(_) => &[],
+        }
+    }
+
+    /// Returns the error with every diagnostic rendered along with the line it was found on, or the
+    /// same as its display for any other error.
+    pub fn render(&self) -> String {
+        match self {
+            LexError::Syntax(diagnostics) => diagnostics.iter().map(Diagnostic::render).collect::<Vec<String>>().join("\n"),
+            LexError::InvalidSpreadNext(_) => self.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LexError::Syntax(diagnostics) => {
+                let lines: Vec<String> = diagnostics.iter().map(|diagnostic| diagnostic.to_string()).collect();
+                write!(f, "{}", lines.join("\n"))
+            },
+            LexError::InvalidSpreadNext(c) => write!(f, "\"{}\" cannot be used for the next beat spread operator.", c),
+        }
+    }
+}
+
+impl Error for LexError {}
+
+/// A token generated by a `Lexer`, holding the byte range of its value within the source instead of a copy
+/// of the value whenever the source is kept whole.
+struct Lexeme {
+    type_of: TokenType,
+    /// A copy of the value, only kept for streamed sources that drop their lines once they are read.
+    value: Option<String>,
+    literal: Literal,
+    line: u32,
+    column: u32,
+    span: Range<usize>,
+}
 
 /// Keeps track of the position within a string of text contained in a `Lexer` struct.
 struct Cursor {
@@ -20,6 +82,12 @@ impl Cursor {
     fn is_at_end(&self) -> bool {
         self.current >= self.length as u32
     }
+
+    /// Returns the byte range from the cursor's start position to its current position, within the
+    /// provided string length.
+    fn span(&self) -> Range<usize> {
+        (self.start as usize).min(self.length)..(self.current as usize).min(self.length)
+    }
 }
 
 /// The lexical analyzer struct for generating tokens from a source string.
@@ -27,16 +95,38 @@ impl Cursor {
 /// # Examples
 /// 
 /// ```
-/// use lexer::Lexer;
+/// use lexer::{Lexer, LexError};
 /// use data::{Token, TokenType, Literal};
 /// 
 /// let mut lexer = Lexer::new("[time=4/4; fidelity=16] E A D G B E\n. 2 7,\n:2 5 ;4 1".to_string());
-/// let tokens_or_error: Result<&Vec<Token>, String> = lexer.generate_tokens();
+/// let tokens_or_error: Result<&Vec<Token>, LexError> = lexer.generate_tokens();
 /// ```
 pub struct Lexer {
     source: String,
+    lexemes: VecDeque<Lexeme>,
     tokens: Vec<Token>,
+    /// Whether the shebang line and front matter at the start of the source were checked for.
+    started: bool,
+    /// Whether the end of the source was reached and the end of file token was added.
+    finished: bool,
+    /// The amount of logged errors that were already returned by the token iterator.
+    errors_returned: usize,
     cursor: Cursor,
+    blank_line: bool,
+    spread_next: char,
+    /// Whether the `mode` option was set to `drums`, so drum lane labels and hits are tokenized.
+    drums: bool,
+    /// The byte the last fingering, accent, or staccato mark written after a number ends at, so more
+    /// marks can follow it.
+    mark_end: Option<usize>,
+    /// The amount of notes written on the most recent note line along with the line they were written
+    /// on, so digit runs with a digit for every string can be read as compact chords.
+    strings: (usize, u32),
+    /// The reader the rest of the source is read from one line at a time, if the source is streamed.
+    reader: Option<Box<dyn BufRead>>,
+    /// The amount of bytes of a streamed source that were dropped from the start of the source once
+    /// every token on them was generated.
+    offset: usize,
     watcher: Watcher,
 }
 
@@ -44,7 +134,110 @@ impl Lexer {
     /// Creates a new Lexer struct using the provided string as its source.
     pub fn new(source: String) -> Lexer {
         let length = source.len();
-        Lexer { source, tokens: Vec::new(), cursor: Cursor::new(length), watcher: Watcher::new() }
+        Lexer {
+            source,
+            lexemes: VecDeque::new(),
+            tokens: Vec::new(),
+            started: false,
+            finished: false,
+            errors_returned: 0,
+            cursor: Cursor::new(length),
+            blank_line: true,
+            spread_next: ';',
+            drums: false,
+            mark_end: None,
+            strings: (0, 0),
+            reader: None,
+            offset: 0,
+            watcher: Watcher::new(),
+        }
+    }
+
+    /// Creates a new Lexer struct that reads its source from the provided reader one line at a time as
+    /// tokens are generated. Lines are dropped once every token on them has been generated, so large
+    /// files and piped input are never held in memory all at once.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use std::io::Cursor;
+    /// use lexer::Lexer;
+    /// 
+    /// let mut lex = Lexer::from_reader(Cursor::new("E A\n0 3"));
+    /// 
+    /// assert_eq!(5, lex.generate_tokens().unwrap().len());
+    /// ```
+    pub fn from_reader<R: BufRead + 'static>(reader: R) -> Lexer {
+        let mut lexer = Lexer::new(String::new());
+        lexer.reader = Some(Box::new(reader));
+        lexer
+    }
+
+    /// Creates a new Lexer that resumes lexing a source from the start of the line holding the provided
+    /// byte index, carrying on from the tokens already generated from every line before it instead of
+    /// lexing those lines again. Tokens keep the line and byte span they have within the whole source.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use lexer::Lexer;
+    /// 
+    /// let source = "[mode=drums]\nHH SD\nx x\nx .";
+    /// let tokens = Lexer::from(source).generate_tokens().unwrap().clone();
+    /// 
+    /// // only the last line is lexed again, still as drums
+    /// let mut lex = Lexer::resume(source, 24, &tokens[..5]);
+    /// 
+    /// assert_eq!(&tokens[5..], &lex.generate_tokens().unwrap()[..]);
+    /// ```
+    pub fn resume(source: &str, start: usize, before: &[Token]) -> Lexer {
+        let start = source[..start].rfind('\n').map_or(0, |newline| newline + 1);
+        let mut lexer = Lexer::new(source[start..].to_string());
+        // a shebang line and front matter are only checked for at the start of the source
+        lexer.started = start > 0;
+        lexer.offset = start;
+        lexer.cursor.line = source[..start].matches('\n').count() as u32 + 1;
+        for token in before.iter() {
+            match (&token.type_of, &token.literal) {
+                (TokenType::Options, Literal::Options(options)) => lexer.set_mode(options),
+                (TokenType::FrontMatter, Literal::FrontMatter(entries)) => for (_, key, value) in entries.iter() {
+                    lexer.set_mode_option(key, value);
+                },
+                _ => (),
+            }
+        }
+        // the strings of the last note line are kept to read compact chords after it
+        if let Some(line) = before.iter().rev().find(|token| token.type_of == TokenType::Note).map(|token| token.line) {
+            let strings = before.iter().filter(|token| token.type_of == TokenType::Note && token.line == line).count();
+            lexer.strings = (strings, line);
+        }
+        lexer
+    }
+
+    /// Sets the char used for the next beat spread operator in place of `;`.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use lexer::Lexer;
+    /// 
+    /// let mut lex = Lexer::new(String::from("E\n&4"));
+    /// lex.set_spread_next('&').unwrap();
+    /// 
+    /// assert!(lex.generate_tokens().is_ok());
+    /// assert!(Lexer::new(String::from("E")).set_spread_next(',').is_err());
+    /// ```
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if the char is not one of `;`, `&`, `!`, `$`, `%`, `+`, `?`, or `^`, as every
+    /// other char already has a meaning in tab notation.
+    pub fn set_spread_next(&mut self, c: char) -> Result<(), LexError> {
+        if !SPREAD_NEXT_CHARS.contains(c) {
+            return Err(LexError::InvalidSpreadNext(c))
+        }
+        self.spread_next = c;
+        Ok(())
     }
 
     /// Return a reference to the token output generated from the source string.
@@ -52,26 +245,151 @@ impl Lexer {
     /// # Errors
     /// 
     /// This function errors if the provided source string has incorrect tab notation syntax.
-    pub fn generate_tokens(&mut self) -> Result<&Vec<Token>, String> {
+    pub fn generate_tokens(&mut self) -> Result<&Vec<Token>, LexError> {
+        self.lex()?;
         if self.tokens.is_empty() {
-            while !self.cursor.is_at_end() {
-                // reset the start position of the cursor to the current cursor position
-                // this allows new tokens to be tokenized from the source string
-                self.cursor.start = self.cursor.current;
-                self.consume_next();
+            self.tokens = self.lexemes.iter().map(|lexeme| self.token(lexeme)).collect();
+        }
+        Ok(&self.tokens)
+    }
+
+    /// Returns the tokens generated from the source string, each borrowing its value from the source
+    /// instead of holding a copy of it.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use lexer::Lexer;
+    /// 
+    /// let mut lex = Lexer::from("E A\n0 12");
+    /// let tokens = lex.borrowed_tokens().unwrap();
+    /// 
+    /// assert_eq!("12", tokens[3].value);
+    /// assert_eq!((2, 3), (tokens[3].line, tokens[3].column));
+    /// ```
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if the provided source string has incorrect tab notation syntax.
+    pub fn borrowed_tokens(&mut self) -> Result<Vec<BorrowedToken<'_>>, LexError> {
+        self.lex()?;
+        let lexer = &*self;
+        Ok(lexer.lexemes.iter().map(|lexeme| BorrowedToken {
+            type_of: lexeme.type_of.clone(),
+            value: lexer.value(lexeme),
+            literal: lexeme.literal.clone(),
+            line: lexeme.line,
+            column: lexeme.column,
+            span: lexeme.span.clone(),
+        }).collect())
+    }
+
+    /// Returns an owned token with a copy of the value of the provided lexeme.
+    fn token(&self, lexeme: &Lexeme) -> Token {
+        let token = Token::new(lexeme.type_of.clone(), self.value(lexeme).to_string(), lexeme.literal.clone(), lexeme.line);
+        token.at(lexeme.column, lexeme.span.clone())
+    }
+
+    /// Returns the value of the provided lexeme from the source, or its copy for a streamed source.
+    fn value<'a>(&'a self, lexeme: &'a Lexeme) -> &'a str {
+        match &lexeme.value {
+            Some(value) => value,
+            // lines are no longer dropped once the end of a streamed source is reached
+            None => self.source.get(lexeme.span.start - self.offset..lexeme.span.end - self.offset).unwrap_or_default(),
+        }
+    }
+
+    /// Generates every remaining lexeme of the source.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if the provided source string has incorrect tab notation syntax.
+    fn lex(&mut self) -> Result<(), LexError> {
+        while !self.finished {
+            self.step();
+        }
+
+        // if there was a syntax error, return an error
+        if self.watcher.had_error() {
+            Err(LexError::Syntax(self.watcher.errors()))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns every diagnostic logged while generating tokens.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        self.watcher.diagnostics()
+    }
+
+    /// Consumes the next token of the source. The start of the source is first checked for a shebang line
+    /// and front matter, and the end of file token is added once the end of the source is reached.
+    fn step(&mut self) {
+        if !self.started {
+            self.started = true;
+            // a shebang line is ignored and front matter is only allowed at the start of the file; a
+            // streamed source reads the lines it checks first
+            self.is_at_end();
+            if self.source.starts_with("#!") {
+                self.read_line();
+                self.is_at_end();
+            }
+            if self.source.get(self.cursor.current as usize..).unwrap_or_default().starts_with("---") {
+                self.front_matter();
             }
+            return
+        }
 
+        self.drop_consumed();
+        if self.is_at_end() {
             // add an EOF token to the token list to signify the end of the file has been reached
-            self.tokens.push(
-                Token::new(TokenType::EndOfFile, String::new(), Literal::None, self.cursor.line)
-            );
+            let end = self.source.len();
+            self.push_lexeme(TokenType::EndOfFile, Literal::None, end..end, self.cursor.line);
+            self.finished = true;
+            return
         }
+        // reset the start position of the cursor to the current cursor position
+        // this allows new tokens to be tokenized from the source string
+        self.cursor.start = self.cursor.current;
+        self.consume_next();
+    }
 
-        // if there was a syntax error, return an error; otherwise return the token list
-        if self.watcher.had_error {
-            Err(self.watcher.to_string())
-        } else {
-            Ok(&self.tokens)
+    /// Returns true if the cursor is at the end of the source. The next line of a streamed source is read
+    /// first if every line read so far has been consumed.
+    fn is_at_end(&mut self) -> bool {
+        if self.cursor.is_at_end() {
+            self.read_source_line();
+        }
+        self.cursor.is_at_end()
+    }
+
+    /// Reads the next line of a streamed source onto the end of the source. The reader is dropped once
+    /// the end of it is reached.
+    /// 
+    /// # Logs Errors
+    /// 
+    /// This function logs an error if the line cannot be read, such as when it is not valid UTF-8.
+    fn read_source_line(&mut self) {
+        if let Some(reader) = self.reader.as_mut() {
+            match reader.read_line(&mut self.source) {
+                Ok(0) => self.reader = None,
+                Ok(_) => (),
+                Err(e) => {
+                    self.reader = None;
+                    self.error("L017", format!("Could not read the next line of the source: {}", e));
+                },
+            }
+            self.cursor.length = self.source.len();
+        }
+    }
+
+    /// Drops the lines of a streamed source that every token has been generated from. Token spans keep
+    /// counting from the start of the source.
+    fn drop_consumed(&mut self) {
+        if self.reader.is_some() && self.cursor.current as usize >= self.source.len() {
+            self.offset += self.source.len();
+            self.source.clear();
+            self.cursor = Cursor { start: 0, current: 0, length: 0, ..self.cursor };
         }
     }
 
@@ -82,28 +400,86 @@ impl Lexer {
     /// This function logs an error if the consumed character is not expected within the tab notation syntax.
     fn consume_next(&mut self) {
         let c: char = self.advance();
+        // any char other than whitespace means the current line is not blank
+        let was_blank = self.blank_line;
+        if !c.is_whitespace() { self.blank_line = false; }
+
         match c {
+            c if c == self.spread_next => self.spread(TokenType::SpreadNext),
+            // drum mode replaces note names with drum lane labels and frets with hit symbols
+            'x' | 'o' | 'g' if self.drums => self.add_token(TokenType::Hit, Literal::None),
+            'A'..='Z' if self.drums => self.drum_lane(),
             '.' => self.add_token(TokenType::Empty, Literal::None),
             ',' => self.add_token(TokenType::Next, Literal::None),
+            // a '*' directly after a number is a pinch harmonic rather than highlighting the next number
+            '*' if self.follows_number() => self.add_token(TokenType::PinchHarmonic, Literal::None),
+            '*' => self.add_token(TokenType::Highlight, Literal::None),
+            // a 'B' directly followed by a fret number is a barre rather than a note
+            'B' if self.peek().is_ascii_digit() => self.hand_position(TokenType::Barre, "B"),
             'A'..='G' => {
-                if self.next_matches_modifier() {
-                    self.add_token(TokenType::Note, Literal::None);
-                } else {
-                    self.add_token(TokenType::Note, Literal::None);
-                }
+                // consume the 'b' or '#' modifier if there is one
+                self.next_matches_modifier();
+                self.note();
             },
+            ':' if self.peek() == '|' => self.repeat_end(),
+            // a ':' directly after a number is the duration of the number rather than empty spaces
+            ':' if self.follows_number() => self.duration(),
+            '|' => self.repeat_start(),
             ':' => self.spread(TokenType::SpreadEmpty),
-            ';' => self.spread(TokenType::SpreadNext),
-            '\n' => { self.cursor.line += 1; },
+            // an 'r' directly after a number is a release rather than a rest
+            'r' if self.follows_number() => self.articulation(TokenType::Release),
+            'r' => self.spread(TokenType::Rest),
+            'h' => self.articulation(TokenType::HammerOn),
+            't' => self.stroke(TokenType::Tap),
+            's' => self.stroke(TokenType::Slap),
+            'P' => self.stroke(TokenType::Pop),
+            'g' => self.grace_note(),
+            // a 'p' directly followed by an 'h' after a number is a pinch harmonic rather than a pull-off
+            'p' if self.peek() == 'h' && self.follows_number() => {
+                self.advance();
+                self.add_token(TokenType::PinchHarmonic, Literal::None);
+            },
+            // a 'p' directly followed by an 'm' starts a palm mute span rather than a pull-off
+            'p' if self.peek() == 'm' => self.span_start(),
+            'p' if self.peek() == 'o' => self.hand_position(TokenType::HandPosition, "pos"),
+            'l' if self.peek() == 'r' => self.span_start(),
+            'p' => self.articulation(TokenType::PullOff),
+            'b' => self.articulation(TokenType::Bend),
+            '/' if self.peek() == '/' => self.line_comment(was_blank),
+            '/' if self.peek() == '*' => self.block_comment(was_blank),
+            '/' => self.articulation(TokenType::SlideUp),
+            '\\' => self.articulation(TokenType::SlideDown),
+            '~' => self.vibrato(),
+            '>' => self.mark(TokenType::Accent),
+            '\'' => self.mark(TokenType::Staccato),
+            '=' | '_' => self.add_token(TokenType::Tie, Literal::None),
+            // an '@' directly followed by a word is a section directive rather than a voice
+            '@' if self.peek().is_ascii_alphabetic() => self.section(),
+            '@' => self.add_token(TokenType::Voice, Literal::None),
+            '\n' => {
+                // a blank line separates staffs
+                if self.blank_line { self.add_token(TokenType::StaffBreak, Literal::None); }
+                self.blank_line = true;
+                self.cursor.line += 1;
+            },
             '\0'..=' ' => (),
+            '[' if self.peek() == '[' => self.scoped_options(),
             '[' => self.options(),
+            // a '(' directly after a number is the finger fretting the number rather than a chord
+            '(' if self.follows_number() => self.fingering(),
+            '(' => self.chord(),
+            '"' => self.lyric(),
+            '<' => self.chord_name(),
+            'x' => self.compact_chord(),
             '0'..='9' => self.number(),
-            _ => self.watcher.error(self.cursor.line, format!("Unknown character value: {}", c)),
+            '}' => self.add_token(TokenType::GroupEnd, Literal::None),
+            _ => self.error("L001", format!("Unknown character value: {}", c)),
         }
     }
 
     /// Moves the cursor's current position to the next character and returns it.
     fn advance(&mut self) -> char {
+        self.is_at_end();
         // get the current cursor position and store it; increment the current position
         let current: usize = self.cursor.current as usize;
         self.cursor.current += 1;
@@ -132,7 +508,8 @@ impl Lexer {
     }
 
     /// Looks ahead at the next character and returns it.
-    fn peek(&self) -> char {
+    fn peek(&mut self) -> char {
+        self.is_at_end();
         // get the current cursor position
         let current: usize = self.cursor.current as usize;
 
@@ -153,16 +530,67 @@ impl Lexer {
         // get a selection from the cursor's start position and its current position
         let index_range = self.cursor.start as usize..self.cursor.current as usize;
         // add a new token with the current selection range as its value
-        if let Some(text) = self.source.get(index_range) {
-            self.tokens.push(Token::new(type_of, String::from(text), literal, self.cursor.line));
+        if self.source.get(index_range.clone()).is_some() {
+            self.push_lexeme(type_of, literal, index_range, self.cursor.line);
         }
     }
 
+    /// Adds a new lexeme found on the provided line to the lexeme list, with the provided byte range of the
+    /// source as its value. The value is only copied for a streamed source, as its lines are dropped once
+    /// they are read.
+    fn push_lexeme(&mut self, type_of: TokenType, literal: Literal, range: Range<usize>, line: u32) {
+        let value = self.reader.as_ref().map(|_| String::from(self.source.get(range.clone()).unwrap_or_default()));
+        self.lexemes.push_back(Lexeme {
+            type_of,
+            value,
+            literal,
+            line,
+            column: self.column(range.start),
+            span: self.offset + range.start..self.offset + range.end,
+        });
+    }
+
+    /// Logs an error at the token being read, underlining the token within the line it starts on.
+    fn error(&mut self, code: &'static str, message: String) {
+        let span = self.char_boundary(self.cursor.span().start)..self.char_boundary(self.cursor.span().end);
+        let line_start = self.line_start(span.start);
+        let line_end = self.source[line_start..].find('\n').map_or(self.source.len(), |end| line_start + end);
+        // tokens read across lines are reported on the line they start on
+        let line = self.cursor.line - self.source[span.clone()].matches('\n').count() as u32;
+        let length = self.source[span.start..span.end.min(line_end)].chars().count() as u32;
+        let source_line = self.source[line_start..line_end].trim_end_matches('\r').to_string();
+
+        self.watcher.report(
+            Diagnostic::new(Severity::Error, line, code, message)
+                .with_column(self.column(span.start))
+                .with_length(length)
+                .with_source_line(source_line)
+        );
+    }
+
+    /// Returns the provided byte index, moved back to the start of the character it falls within.
+    fn char_boundary(&self, at: usize) -> usize {
+        (0..=at.min(self.source.len())).rev().find(|&at| self.source.is_char_boundary(at)).unwrap_or_default()
+    }
+
+    /// Returns the byte index of the start of the line containing the provided byte index.
+    fn line_start(&self, at: usize) -> usize {
+        let at = self.char_boundary(at);
+        self.source[..at].rfind('\n').map_or(0, |newline| newline + 1)
+    }
+
+    /// Returns the column of the provided byte index within its line, counted in characters from `1`.
+    fn column(&self, at: usize) -> u32 {
+        let at = self.char_boundary(at);
+        self.source[self.line_start(at)..at].chars().count() as u32 + 1
+    }
+
     /// Adds a spread token to the token list.
     /// 
     /// # Logs Errors
     /// 
-    /// This function logs an error if the spread amount cannot be parsed into a `u32` number.
+    /// This function logs an error if the spread is not followed by an amount or the spread amount
+    /// cannot be parsed into a `u32` number.
     fn spread(&mut self, spread_type: TokenType) {
         // move cursor's current position over all numbers following the spread token
         while let '0'..='9' = self.peek() {
@@ -173,17 +601,221 @@ impl Lexer {
         let index_range = (self.cursor.start + 1) as usize..self.cursor.current as usize;
         // add a new token with the current selection range as its value
         if let Some(text) = self.source.get(index_range) {
+            // a spread directly followed by anything other than a number has no amount
+            if text.is_empty() {
+                let symbol = String::from(self.source.get(self.cursor.start as usize..(self.cursor.start + 1) as usize).unwrap_or_default());
+                let found = match self.peek() {
+                    '\0' => String::from("the end of the file"),
+                    '\n' => String::from("the end of the line"),
+                    c => format!("\"{}\"", c),
+                };
+                self.error(
+                    "L002",
+                    format!("Expected an amount after \"{}\" for \"{}\" but found {}.", symbol, spread_type, found)
+                );
+                return
+            }
+
             // attempt to parse the value into a `u32` number to use as the token's literal
             match String::from(text).parse::<u32>() {
                 Ok(num_literal) => self.add_token(spread_type, Literal::Number(num_literal)),
-                Err(e) => self.watcher.error(
-                    self.cursor.line,
+                Err(e) => self.error(
+                    "L003",
                     format!("Could not parse amount \"{}\" for \"{}\": {}", text, spread_type, e)
                 ),
             }
         }
     }
 
+    /// Adds an articulation token to the token list.
+    /// 
+    /// # Logs Errors
+    /// 
+    /// This function logs an error if the articulation is not written directly between two numbers.
+    fn articulation(&mut self, type_of: TokenType) {
+        if self.follows_number() && self.peek().is_ascii_digit() {
+            self.add_token(type_of, Literal::None);
+        } else {
+            let symbol = self.source.get(self.cursor.start as usize..self.cursor.current as usize).unwrap_or_default();
+            self.error(
+                "L008",
+                format!("Expected \"{}\" for \"{}\" to be written directly between two fret numbers.", symbol, type_of)
+            );
+        }
+    }
+
+    /// Adds a stroke token to the token list for a tap, slap, or pop played on the number after it.
+    /// 
+    /// # Logs Errors
+    /// 
+    /// This function logs an error if the stroke is not written directly before a number.
+    fn stroke(&mut self, type_of: TokenType) {
+        if self.peek().is_ascii_digit() {
+            self.add_token(type_of, Literal::None);
+        } else {
+            let symbol = self.source.get(self.cursor.start as usize..self.cursor.current as usize).unwrap_or_default();
+            self.error(
+                "L008",
+                format!("Expected \"{}\" for \"{}\" to be written directly before a fret number.", symbol, type_of)
+            );
+        }
+    }
+
+    /// Adds a grace note token to the token list for the fret played just before the number after it:
+    /// `g3~5`.
+    /// 
+    /// # Logs Errors
+    /// 
+    /// This function logs an error if the grace note is not a fret number followed by a '~' that is
+    /// written directly before a number.
+    fn grace_note(&mut self) {
+        while let '0'..='9' = self.peek() {
+            self.advance();
+        }
+        let text = String::from(self.source.get((self.cursor.start + 1) as usize..self.cursor.current as usize).unwrap_or_default());
+        let tilde = self.peek() == '~';
+        if tilde { self.advance(); }
+        match text.parse::<u32>() {
+            Ok(fret) if tilde && self.peek().is_ascii_digit() => self.add_token(TokenType::GraceNote, Literal::Number(fret)),
+            _ => self.error(
+                "L008",
+                String::from("Expected \"g\" for \"Grace Note\" to be written as a fret number and a '~' directly before a fret number: g3~5.")
+            ),
+        }
+    }
+
+    /// Adds a vibrato token to the token list.
+    /// 
+    /// # Logs Errors
+    /// 
+    /// This function logs an error if the vibrato is not written directly after a number.
+    fn vibrato(&mut self) {
+        if self.follows_number() {
+            self.add_token(TokenType::Vibrato, Literal::None);
+        } else {
+            self.error(
+                "L008",
+                String::from("Expected \"~\" for \"Vibrato\" to be written directly after a fret number.")
+            );
+        }
+    }
+
+    /// Adds an accent or staccato token to the token list for the number before it.
+    /// 
+    /// # Logs Errors
+    /// 
+    /// This function logs an error if the mark is not written directly after a fret number.
+    fn mark(&mut self, type_of: TokenType) {
+        if self.follows_number() {
+            self.add_mark(type_of, Literal::None);
+        } else {
+            let symbol = self.source.get(self.cursor.start as usize..self.cursor.current as usize).unwrap_or_default();
+            self.error(
+                "L008",
+                format!("Expected \"{}\" for \"{}\" to be written directly after a fret number.", symbol, type_of)
+            );
+        }
+    }
+
+    /// Adds a duration token to the token list for the number before it, optionally dotted: `:4.`.
+    /// 
+    /// # Logs Errors
+    /// 
+    /// This function logs an error if the duration is not a number greater than 0.
+    fn duration(&mut self) {
+        while let '0'..='9' = self.peek() {
+            self.advance();
+        }
+        let text = String::from(self.source.get((self.cursor.start + 1) as usize..self.cursor.current as usize).unwrap_or_default());
+        // a '.' directly after the duration dots it rather than adding an empty space
+        let dotted = self.peek() == '.';
+        if dotted { self.advance(); }
+
+        match text.parse::<u32>() {
+            Ok(0) | Err(_) => self.error(
+                "L011",
+                format!("Expected a duration greater than 0 after \":\" but found \"{}\".", text)
+            ),
+            Ok(duration) => self.add_token(TokenType::Duration, Literal::Duration(duration, dotted)),
+        }
+    }
+
+    /// Adds a fingering token to the token list for the number before it, with the finger as its
+    /// literal text: `5(1)`.
+    /// 
+    /// # Logs Errors
+    /// 
+    /// This function logs an error if the parentheses do not hold a single finger of `1` to `4` or `T`.
+    fn fingering(&mut self) {
+        while !matches!(self.peek(), ')' | '\n') && !self.is_at_end() {
+            self.advance();
+        }
+        let finger = String::from(self.source.get((self.cursor.start + 1) as usize..self.cursor.current as usize).unwrap_or_default());
+        let closed = self.peek() == ')';
+        if closed { self.advance(); }
+
+        match finger.as_str() {
+            "1" | "2" | "3" | "4" | "T" if closed => self.add_mark(TokenType::Fingering, Literal::Text(finger)),
+            _ => self.error(
+                "L020",
+                format!("Expected a finger of 1, 2, 3, 4, or T within parentheses after a fret number but found \"{}\".", finger)
+            ),
+        }
+    }
+
+    /// Adds a repeat start token to the token list, or a bar-line token if the `|` is not followed by a `:`.
+    fn repeat_start(&mut self) {
+        if self.peek() == ':' {
+            self.advance();
+            self.add_token(TokenType::RepeatStart, Literal::None);
+        } else {
+            self.add_token(TokenType::BarLine, Literal::None);
+        }
+    }
+
+    /// Adds a repeat end token to the token list with the amount of times the repeated section is played
+    /// as its literal, `2` unless a count such as `x3` follows the `:|`.
+    /// 
+    /// # Logs Errors
+    /// 
+    /// This function logs an error if the `x` is not followed by a count of at least 1.
+    fn repeat_end(&mut self) {
+        // consume the '|' character
+        self.advance();
+        if self.peek() != 'x' {
+            self.add_token(TokenType::RepeatEnd, Literal::Number(2));
+            return
+        }
+
+        self.advance();
+        while let '0'..='9' = self.peek() {
+            self.advance();
+        }
+        let text = String::from(self.source.get((self.cursor.start + 3) as usize..self.cursor.current as usize).unwrap_or_default());
+        match text.parse::<u32>() {
+            Ok(0) | Err(_) => self.error(
+                "L012",
+                format!("Expected a repeat count of at least 1 after \":|x\" but found \"{}\".", text)
+            ),
+            Ok(times) => self.add_token(TokenType::RepeatEnd, Literal::Number(times)),
+        }
+    }
+
+    /// Checks if the char before the current token is a digit, or the end of a fingering, accent, or
+    /// staccato mark written directly after a number.
+    fn follows_number(&self) -> bool {
+        self.mark_end == Some(self.offset + self.cursor.start as usize)
+            || self.source.get(..self.cursor.start as usize)
+                .is_some_and(|before| before.ends_with(|c: char| c.is_ascii_digit()))
+    }
+
+    /// Adds a token for a fingering, accent, or staccato mark written after a number, keeping where it
+    /// ends so more marks can follow it.
+    fn add_mark(&mut self, type_of: TokenType, literal: Literal) {
+        self.add_token(type_of, literal);
+        self.mark_end = Some(self.offset + self.cursor.current as usize);
+    }
+
     /// Adds an option token to the token list.
     /// 
     /// # Logs Errors
@@ -192,16 +824,16 @@ impl Lexer {
     fn options(&mut self) {
         // move cursor's current position over all characters up until a terminating ']'
         // character is found
-        while self.peek() != ']' && !self.cursor.is_at_end() {
+        while self.peek() != ']' && !self.is_at_end() {
             if self.peek() == '\n' { self.cursor.line += 1; }
             self.advance();
         }
 
         // if the end of the source string is found before the terminating ']' character is found,
         // report a syntax error
-        if self.cursor.is_at_end() {
-            self.watcher.error(
-                self.cursor.line,
+        if self.is_at_end() {
+            self.error(
+                "L004",
                 String::from("Unterminated options sequence. Close options sequences with \"]\".")
             );
         } else {
@@ -212,69 +844,1171 @@ impl Lexer {
             let index_range = (self.cursor.start + 1) as usize..(self.cursor.current - 1) as usize;
 
             // add an options token with the token literal
-            self.add_token(TokenType::Options, Literal::Options(
-                String::from(match self.source.get(index_range) {
-                    Some(t) => t,
-                    _ => "",
-                })
-            ));
+            let options = String::from(self.source.get(index_range).unwrap_or_default());
+            self.set_mode(&options);
+            self.add_token(TokenType::Options, Literal::Options(options));
         }
     }
 
-    /// Adds a number token to the token list.
+    /// Adds a span start token to the token list for the two char technique name before a `{`, with the
+    /// name as its literal.
     /// 
     /// # Logs Errors
     /// 
-    /// This function logs an error if the string slice cannot be parsed into a `u32` number.
-    fn number(&mut self) {
-        // move cursor's current position over all uninterrupted numbers
-        while let '0'..='9' = self.peek() {
-            self.advance();
+    /// This function logs an error if the technique name is not directly followed by a `{`.
+    fn span_start(&mut self) {
+        // consume the second char of the technique name
+        self.advance();
+        let name = self.source.get(self.cursor.start as usize..self.cursor.current as usize).unwrap_or_default().to_string();
+        if self.peek() != '{' {
+            return self.error("L019", format!("Span \"{}\" must be directly followed by \"{{\" to start it.", name))
         }
+        self.advance();
+        self.add_token(TokenType::SpanStart, Literal::Text(name));
+    }
 
-        // get a selection from the cursor's start position and its current position
-        let index_range = self.cursor.start as usize..self.cursor.current as usize;
-        // add a new token with the current selection range as its value
-        if let Some(text) = self.source.get(index_range) {
-            // attempt to parse the value into a `u32` number to use as the token's literal
-            match String::from(text).parse::<u32>() {
-                Ok(num_literal) => self.add_token(TokenType::Number, Literal::Number(num_literal)),
-                Err(e) => self.watcher.error(
-                    self.cursor.line,
-                    format!("String \"{}\" could not be parsed into a number: {}", text, e)
-                ),
+    /// Adds a scoped options token to the token list for options enclosed in `[[` and `]]`.
+    /// 
+    /// # Logs Errors
+    /// 
+    /// This function logs an error if the scoped options sequence is not terminated.
+    fn scoped_options(&mut self) {
+        // consume the second '[' character
+        self.advance();
+
+        loop {
+            if self.is_at_end() {
+                self.error(
+                    "L004",
+                    String::from("Unterminated scoped options sequence. Close scoped options sequences with \"]]\".")
+                );
+                return
+            }
+            match self.advance() {
+                ']' if self.peek() == ']' => {
+                    self.advance();
+                    break
+                },
+                '\n' => self.cursor.line += 1,
+                _ => (),
             }
         }
+
+        // get a selection between the enclosing "[[" and "]]"
+        let index_range = (self.cursor.start + 2) as usize..(self.cursor.current - 2) as usize;
+        let options = String::from(self.source.get(index_range).unwrap_or_default());
+        self.add_token(TokenType::ScopedOptions, Literal::Options(options));
     }
-}
 
-#[cfg(test)]
-mod lexer_tests {
-    use super::*;
+    /// Adds a comment token to the token list for a line comment running from `//` to the end of the line,
+    /// with the trimmed text after the `//` as its literal.
+    fn line_comment(&mut self, was_blank: bool) {
+        while !matches!(self.peek(), '\n' | '\r') && !self.is_at_end() {
+            self.advance();
+        }
 
-    #[test]
-    fn token_output() {
-        let mut lex = Lexer::new("E C# Gb\n27 . ,\n:2 ;4 [options]".to_string());
-        let expected_tokens = vec![
-            Token::new(TokenType::Note, String::from("E"), Literal::None, 1),
-            Token::new(TokenType::Note, String::from("C#"), Literal::None, 1),
-            Token::new(TokenType::Note, String::from("Gb"), Literal::None, 1),
-            Token::new(TokenType::Number, String::from("27"), Literal::Number(27), 2),
-            Token::new(TokenType::Empty, String::from("."), Literal::None, 2),
-            Token::new(TokenType::Next, String::from(","), Literal::None, 2),
-            Token::new(TokenType::SpreadEmpty, String::from(":2"), Literal::Number(2), 3),
-            Token::new(TokenType::SpreadNext, String::from(";4"), Literal::Number(4), 3),
-            Token::new(TokenType::Options, String::from("[options]"), Literal::Options(String::from("options")), 3),
-            Token::new(TokenType::EndOfFile, String::new(), Literal::None, 3),
-        ];
+        let text = self.source.get((self.cursor.start + 2) as usize..self.cursor.current as usize).unwrap_or_default();
+        self.add_token(TokenType::Comment, Literal::Text(text.trim().to_string()));
+        self.end_comment(was_blank);
+    }
 
-        match lex.generate_tokens() {
-            Ok(tokens) => {
-                for (found, expected) in tokens.iter().zip(expected_tokens.iter()) {
-                    assert_eq!(expected, found);
-                }
-            },
-            Err(e) => panic!("Could not generate tokens: {}", e),
+    /// Adds a comment token to the token list for a block comment enclosed by `/*` and `*/`, which may span
+    /// lines, with the trimmed text between them as its literal. The token is placed on the line the
+    /// comment starts on.
+    /// 
+    /// # Logs Errors
+    /// 
+    /// This function logs an error if the end of the file is found before the closing `*/`.
+    fn block_comment(&mut self, was_blank: bool) {
+        let line = self.cursor.line;
+        // consume the '*' of the opening "/*"
+        self.advance();
+        loop {
+            if self.is_at_end() {
+                self.error(
+                    "L018",
+                    String::from("Unterminated block comment. Close block comments with \"*/\".")
+                );
+                return
+            }
+            match self.advance() {
+                '*' if self.peek() == '/' => {
+                    self.advance();
+                    break
+                },
+                '\n' => self.cursor.line += 1,
+                _ => (),
+            }
         }
+
+        let range = self.cursor.start as usize..self.cursor.current as usize;
+        let text = self.source.get(range.start + 2..range.end - 2).unwrap_or_default().trim().to_string();
+        self.push_lexeme(TokenType::Comment, Literal::Text(text), range, line);
+        self.end_comment(was_blank);
     }
-}
\ No newline at end of file
+
+    /// Ends a comment without changing whether the line it was written on is blank. A comment written on
+    /// an otherwise blank line takes the line break after it along with it, so the line does not separate
+    /// staffs.
+    fn end_comment(&mut self, was_blank: bool) {
+        self.blank_line = was_blank;
+        if !was_blank { return }
+
+        while matches!(self.peek(), ' ' | '\t' | '\r') {
+            self.advance();
+        }
+        if self.peek() == '\n' {
+            self.advance();
+            self.cursor.line += 1;
+        }
+    }
+
+    /// Adds a chord token to the token list from frets separated by whitespace within parentheses, where
+    /// an `x` is a muted string: `(3 2 0 0 3 3)`.
+    /// 
+    /// # Logs Errors
+    /// 
+    /// This function logs an error if the chord is not terminated on the same line, has no frets, or has
+    /// a fret that is neither a number nor an `x`.
+    fn chord(&mut self) {
+        // move cursor's current position over all characters up until a terminating ')' character is
+        // found on the same line
+        while !matches!(self.peek(), ')' | '\n') && !self.is_at_end() {
+            self.advance();
+        }
+
+        if self.peek() != ')' {
+            self.error(
+                "L009",
+                String::from("Unterminated chord. Close chords with \")\" on the same line.")
+            );
+            return
+        }
+        // consume the ')' character
+        self.advance();
+
+        let text = String::from(self.source.get(self.cursor.start as usize..self.cursor.current as usize).unwrap_or_default());
+        let mut frets = vec![];
+        for fret in text[1..text.len() - 1].split_whitespace() {
+            match fret {
+                "x" => frets.push(None),
+                fret => match fret.parse::<u32>() {
+                    Ok(fret) => frets.push(Some(fret)),
+                    Err(_) => {
+                        self.error(
+                            "L010",
+                            format!("Chord \"{}\" has an invalid fret \"{}\". Frets must be numbers or \"x\" for muted strings.", text, fret)
+                        );
+                        return
+                    },
+                },
+            }
+        }
+
+        if frets.is_empty() {
+            self.error("L010", format!("Chord \"{}\" has no frets.", text));
+        } else {
+            self.add_token(TokenType::Chord, Literal::Chord(frets));
+        }
+    }
+
+    /// Adds a lyric token to the token list with the text between the quotes as its literal: `"la"`.
+    /// 
+    /// # Logs Errors
+    /// 
+    /// This function logs an error if the lyric is not terminated on the same line.
+    fn lyric(&mut self) {
+        while !matches!(self.peek(), '"' | '\n') && !self.is_at_end() {
+            self.advance();
+        }
+
+        if self.peek() != '"' {
+            self.error(
+                "L013",
+                String::from("Unterminated lyric. Close lyrics with a quote on the same line.")
+            );
+            return
+        }
+        // consume the closing '"' character
+        self.advance();
+
+        let text = self.source.get((self.cursor.start + 1) as usize..(self.cursor.current - 1) as usize).unwrap_or_default();
+        self.add_token(TokenType::Lyric, Literal::Text(text.to_string()));
+    }
+
+    /// Adds a chord name token to the token list with the name between the angle brackets as its
+    /// literal: `<Am7>`.
+    /// 
+    /// # Logs Errors
+    /// 
+    /// This function logs an error if the chord name is empty or not terminated on the same line.
+    fn chord_name(&mut self) {
+        while !matches!(self.peek(), '>' | '\n') && !self.is_at_end() {
+            self.advance();
+        }
+
+        if self.peek() != '>' {
+            self.error(
+                "L014",
+                String::from("Unterminated chord name. Close chord names with \">\" on the same line.")
+            );
+            return
+        }
+        // consume the '>' character
+        self.advance();
+
+        let name = self.source.get((self.cursor.start + 1) as usize..(self.cursor.current - 1) as usize).unwrap_or_default().trim();
+        if name.is_empty() {
+            self.error("L014", String::from("Chord name \"<>\" cannot be empty."));
+        } else if let Ok(fret) = name.parse::<u32>() {
+            // a fret number within angle brackets is a natural harmonic rather than a chord name
+            self.add_token(TokenType::NaturalHarmonic, Literal::Number(fret));
+        } else {
+            self.add_token(TokenType::ChordName, Literal::Text(name.to_string()));
+        }
+    }
+
+    /// Adds a barre or hand position token to the token list with the fret it is placed at as its literal:
+    /// `B5` or `pos5`.
+    /// 
+    /// # Logs Errors
+    /// 
+    /// This function logs an error if the name is misspelled or not directly followed by a fret number.
+    fn hand_position(&mut self, type_of: TokenType, name: &str) {
+        while self.peek().is_ascii_alphabetic() {
+            self.advance();
+        }
+        let word_end = self.cursor.current as usize;
+        while self.peek().is_ascii_digit() {
+            self.advance();
+        }
+        let word = self.source.get(self.cursor.start as usize..word_end).unwrap_or_default();
+        let fret = self.source.get(word_end..self.cursor.current as usize).unwrap_or_default();
+
+        match fret.parse::<u32>() {
+            Ok(fret) if word == name => self.add_token(type_of, Literal::Number(fret)),
+            _ => self.error(
+                "L021",
+                format!("Expected \"{}\" for \"{}\" to be directly followed by a fret number: {}5.", name, type_of, name)
+            ),
+        }
+    }
+
+    /// Adds a section token to the token list with the section label as its literal. A word directive
+    /// is labeled with the capitalized word (`@verse` is labeled `Verse`), while `@section("Bridge")`
+    /// is labeled with the quoted text.
+    /// 
+    /// # Logs Errors
+    /// 
+    /// This function logs an error if a `@section` directive is not followed by a quoted label within
+    /// parentheses.
+    fn section(&mut self) {
+        while self.peek().is_ascii_alphanumeric() || self.peek() == '-' {
+            self.advance();
+        }
+        let word = String::from(self.source.get((self.cursor.start + 1) as usize..self.cursor.current as usize).unwrap_or_default());
+
+        if word != "section" {
+            let mut chars = word.chars();
+            let label = chars.next().map(|first| first.to_ascii_uppercase().to_string() + chars.as_str()).unwrap_or_default();
+            self.add_token(TokenType::Section, Literal::Text(label));
+            return
+        }
+
+        // the label of a section directive is quoted text within parentheses on the same line
+        let label_start = self.cursor.current as usize + 2;
+        if self.peek() == '(' {
+            self.advance();
+            if self.peek() == '"' {
+                self.advance();
+                while !matches!(self.peek(), '"' | '\n') && !self.is_at_end() {
+                    self.advance();
+                }
+            }
+        }
+        let label_end = self.cursor.current as usize;
+        if !self.source.get(label_end..).is_some_and(|rest| rest.starts_with("\")")) || label_start > label_end {
+            // the rest of the line is skipped so a malformed label is not read as tabs
+            while self.peek() != '\n' && !self.is_at_end() {
+                self.advance();
+            }
+            self.error(
+                "L015",
+                String::from("Expected \"@section\" to be followed by a quoted label within parentheses, such as @section(\"Bridge\").")
+            );
+            return
+        }
+        // consume the closing '"' and ')' characters
+        self.advance();
+        self.advance();
+
+        let label = String::from(self.source.get(label_start..label_end).unwrap_or_default().trim());
+        self.add_token(TokenType::Section, Literal::Text(label));
+    }
+
+    /// Adds a chord token to the token list from single digit frets and `x` muted strings written
+    /// together: `x32010`.
+    /// 
+    /// # Logs Errors
+    /// 
+    /// This function logs an error if a muted string is written on its own rather than within a chord.
+    fn compact_chord(&mut self) {
+        while let '0'..='9' | 'x' = self.peek() {
+            self.advance();
+        }
+
+        let text = self.source.get(self.cursor.start as usize..self.cursor.current as usize).unwrap_or_default();
+        if text == "x" {
+            self.error(
+                "L022",
+                String::from("A muted string \"x\" must be written within a chord of two or more strings (\"x32010\").")
+            );
+            return
+        }
+        let frets = text.chars().map(|fret| fret.to_digit(10)).collect();
+        self.add_token(TokenType::Chord, Literal::Chord(frets));
+    }
+
+    /// Adds a front matter token to the token list. Each `key: value` line within the front matter
+    /// is kept as an entry of the token's front matter literal along with the line it is written on.
+    /// 
+    /// # Logs Errors
+    /// 
+    /// This function logs an error if a line is not formatted as `key: value` or if the front matter
+    /// is not terminated.
+    fn front_matter(&mut self) {
+        let line = self.cursor.line;
+        let mut entries = vec![];
+        self.cursor.start = self.cursor.current;
+
+        // consume the opening "---" line
+        self.read_line();
+
+        loop {
+            // if the end of the source string is found before the closing "---" line is found,
+            // report a syntax error
+            if self.is_at_end() {
+                self.error(
+                    "L005",
+                    String::from("Unterminated front matter. Close front matter with a \"---\" line.")
+                );
+                return
+            }
+
+            let entry_line = self.cursor.line;
+            let entry = self.read_line();
+            let entry = entry.trim();
+            if entry == "---" { break }
+            if entry.is_empty() { continue }
+
+            match entry.split_once(':') {
+                Some((key, value)) => {
+                    self.set_mode_option(key.trim(), value.trim());
+                    entries.push((entry_line, key.trim().to_string(), value.trim().to_string()));
+                },
+                None => self.watcher.error(
+                    entry_line,
+                    "L006",
+                    format!("Front matter line \"{}\" is not formatted as \"key: value\".", entry)
+                ),
+            }
+        }
+
+        self.push_lexeme(TokenType::FrontMatter, Literal::FrontMatter(entries), self.cursor.span(), line);
+    }
+
+    /// Switches between tokenizing tabs and drums if the provided options set the `mode` option. Any
+    /// other value is left for the parser to report.
+    fn set_mode(&mut self, options: &str) {
+        for option in options.split(';') {
+            if let Some((name, value)) = option.split_once('=') {
+                self.set_mode_option(name.trim(), value.trim());
+            }
+        }
+    }
+
+    /// Switches between tokenizing tabs and drums if the provided option is the `mode` option.
+    fn set_mode_option(&mut self, name: &str, value: &str) {
+        match (name, value) {
+            ("mode", "drums") => self.drums = true,
+            ("mode", "tabs") => self.drums = false,
+            _ => (),
+        }
+    }
+
+    /// Adds a note token to the token list from a drum lane label made up of uppercase letters and digits,
+    /// such as `HH` for the hi-hat or `SD` for the snare drum.
+    /// 
+    /// # Logs Errors
+    /// 
+    /// This function logs an error if the label is longer than 2 chars, as it would not fit the margin.
+    fn drum_lane(&mut self) {
+        while self.peek().is_ascii_uppercase() || self.peek().is_ascii_digit() {
+            self.advance();
+        }
+        let label = self.source.get(self.cursor.start as usize..self.cursor.current as usize).unwrap_or_default();
+        if label.len() > 2 {
+            self.error(
+                "L016",
+                format!("Drum lane label \"{}\" must be 1 or 2 chars long.", label)
+            );
+        } else {
+            self.note();
+        }
+    }
+
+    /// Adds a note token to the token list, counting it as a string of the note line it is written on.
+    fn note(&mut self) {
+        let (strings, line) = self.strings;
+        self.strings = match line == self.cursor.line {
+            true => (strings + 1, line),
+            false => (1, self.cursor.line),
+        };
+        self.add_token(TokenType::Note, Literal::None);
+    }
+
+    /// Moves the cursor's current position past the end of the current line and returns the line
+    /// without its terminating newline character.
+    fn read_line(&mut self) -> String {
+        let start = self.cursor.current as usize;
+        while self.peek() != '\n' && !self.is_at_end() {
+            self.advance();
+        }
+        let line = String::from(self.source.get(start..self.cursor.current as usize).unwrap_or_default());
+
+        // consume the '\n' character
+        if !self.is_at_end() {
+            self.advance();
+            self.cursor.line += 1;
+        }
+        line
+    }
+
+    /// Adds a number token to the token list.
+    /// 
+    /// # Logs Errors
+    /// 
+    /// This function logs an error if the string slice cannot be parsed into a `u32` number.
+    fn number(&mut self) {
+        // move cursor's current position over all uninterrupted numbers
+        while let '0'..='9' = self.peek() {
+            self.advance();
+        }
+        // numbers directly followed by a muted string are a compact chord, as are runs of more than
+        // three digits with a digit for every string of the last note line
+        let digits = (self.cursor.current - self.cursor.start) as usize;
+        if self.peek() == 'x' || (digits > 3 && digits == self.strings.0) {
+            return self.compact_chord()
+        }
+
+        // get a selection from the cursor's start position and its current position
+        let index_range = self.cursor.start as usize..self.cursor.current as usize;
+        // numbers directly followed by a '{' start a tuplet of that many beats
+        let type_of = if self.peek() == '{' {
+            self.advance();
+            TokenType::TupletStart
+        } else {
+            TokenType::Number
+        };
+        // add a new token with the current selection range as its value
+        if let Some(text) = self.source.get(index_range) {
+            // attempt to parse the value into a `u32` number to use as the token's literal
+            match String::from(text).parse::<u32>() {
+                Ok(num_literal) => self.add_token(type_of, Literal::Number(num_literal)),
+                Err(e) => self.error(
+                    "L007",
+                    format!("String \"{}\" could not be parsed into a number: {}", text, e)
+                ),
+            }
+        }
+    }
+}
+
+impl Iterator for Lexer {
+    type Item = Result<Token, LexError>;
+
+    /// Generates and returns the next token of the source, or a syntax error holding the diagnostic of the
+    /// next error found. Tokens are generated lazily, one at a time, and are not kept once they are
+    /// returned, so a streamed source can be processed without holding all of its tokens in memory.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use data::TokenType;
+    /// use lexer::Lexer;
+    /// 
+    /// let mut lex = Lexer::from("E\n? 3");
+    /// 
+    /// assert_eq!(TokenType::Note, lex.next().unwrap().unwrap().type_of);
+    /// assert!(lex.next().unwrap().is_err());
+    /// assert_eq!("3", lex.next().unwrap().unwrap().value);
+    /// assert_eq!(TokenType::EndOfFile, lex.next().unwrap().unwrap().type_of);
+    /// assert!(lex.next().is_none());
+    /// ```
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            // errors are returned as soon as they are found; the lexer only logs errors
+            if let Some(error) = self.watcher.diagnostics().get(self.errors_returned) {
+                self.errors_returned += 1;
+                return Some(Err(LexError::Syntax(vec![error.clone()])))
+            }
+            if let Some(lexeme) = self.lexemes.pop_front() {
+                return Some(Ok(self.token(&lexeme)))
+            }
+            if self.finished {
+                return None
+            }
+            self.step();
+        }
+    }
+}
+
+impl From<&str> for Lexer {
+    /// Creates a new Lexer struct using a copy of the provided string as its source.
+    fn from(source: &str) -> Self {
+        Lexer::new(source.to_string())
+    }
+}
+
+#[cfg(test)]
+mod lexer_tests {
+    use super::*;
+
+    #[test]
+    fn token_output() {
+        let mut lex = Lexer::new("E C# Gb\n27 . ,\n:2 ;4 [options]".to_string());
+        let expected_tokens = vec![
+            Token::new(TokenType::Note, String::from("E"), Literal::None, 1),
+            Token::new(TokenType::Note, String::from("C#"), Literal::None, 1),
+            Token::new(TokenType::Note, String::from("Gb"), Literal::None, 1),
+            Token::new(TokenType::Number, String::from("27"), Literal::Number(27), 2),
+            Token::new(TokenType::Empty, String::from("."), Literal::None, 2),
+            Token::new(TokenType::Next, String::from(","), Literal::None, 2),
+            Token::new(TokenType::SpreadEmpty, String::from(":2"), Literal::Number(2), 3),
+            Token::new(TokenType::SpreadNext, String::from(";4"), Literal::Number(4), 3),
+            Token::new(TokenType::Options, String::from("[options]"), Literal::Options(String::from("options")), 3),
+            Token::new(TokenType::EndOfFile, String::new(), Literal::None, 3),
+        ];
+
+        match lex.generate_tokens() {
+            Ok(tokens) => {
+                for (found, expected) in tokens.iter().zip(expected_tokens.iter()) {
+                    assert_eq!(expected, found);
+                }
+            },
+            Err(e) => panic!("Could not generate tokens: {}", e),
+        }
+    }
+
+    #[test]
+    fn front_matter_output() {
+        let mut lex = Lexer::new("---\ntitle: Verse; Chorus=2\ntempo: 120\n---\nE A\n".to_string());
+        let expected_tokens = vec![
+            Token::new(
+                TokenType::FrontMatter,
+                String::from("---\ntitle: Verse; Chorus=2\ntempo: 120\n---\n"),
+                Literal::FrontMatter(vec![
+                    (2, String::from("title"), String::from("Verse; Chorus=2")),
+                    (3, String::from("tempo"), String::from("120")),
+                ]),
+                1
+            ),
+            Token::new(TokenType::Note, String::from("E"), Literal::None, 5),
+            Token::new(TokenType::Note, String::from("A"), Literal::None, 5),
+            Token::new(TokenType::EndOfFile, String::new(), Literal::None, 6),
+        ];
+
+        match lex.generate_tokens() {
+            Ok(tokens) => assert_eq!(&expected_tokens, tokens),
+            Err(e) => panic!("Could not generate tokens: {}", e),
+        }
+    }
+
+    #[test]
+    fn spread_without_amount() {
+        let mut lex = Lexer::new("E\n:".to_string());
+        match lex.generate_tokens() {
+            Ok(_) => panic!("Expected an error for a spread at the end of the file."),
+            Err(e) => assert_eq!(
+                "[2:1] Error: Expected an amount after \":\" for \"Spread Empty\" but found the end of the file.",
+                e.to_string()
+            ),
+        }
+
+        let mut lex = Lexer::new("E\n:\n. 3".to_string());
+        match lex.generate_tokens() {
+            Ok(_) => panic!("Expected an error for a spread at the end of the line."),
+            Err(e) => assert_eq!(
+                "[2:1] Error: Expected an amount after \":\" for \"Spread Empty\" but found the end of the line.",
+                e.to_string()
+            ),
+        }
+
+        let mut lex = Lexer::new("E\n;\n. 3".to_string());
+        match lex.generate_tokens() {
+            Ok(_) => panic!("Expected an error for a spread at the end of the line."),
+            Err(e) => assert_eq!(
+                "[2:1] Error: Expected an amount after \";\" for \"Spread Next\" but found the end of the line.",
+                e.to_string()
+            ),
+        }
+    }
+
+    #[test]
+    fn spread_next_char() {
+        let mut lex = Lexer::new("E\n&4 3,".to_string());
+        lex.set_spread_next('&').unwrap();
+        let expected_tokens = vec![
+            Token::new(TokenType::Note, String::from("E"), Literal::None, 1),
+            Token::new(TokenType::SpreadNext, String::from("&4"), Literal::Number(4), 2),
+            Token::new(TokenType::Number, String::from("3"), Literal::Number(3), 2),
+            Token::new(TokenType::Next, String::from(","), Literal::None, 2),
+            Token::new(TokenType::EndOfFile, String::new(), Literal::None, 2),
+        ];
+        assert_eq!(Ok(&expected_tokens), lex.generate_tokens());
+
+        // the default char is no longer a spread once another char is chosen
+        let mut lex = Lexer::new("E\n;4".to_string());
+        lex.set_spread_next('&').unwrap();
+        assert_eq!(Err(String::from("[2:1] Error: Unknown character value: ;")), lex.generate_tokens().map_err(|e| e.to_string()));
+
+        // chars of other operators, notes, and numbers would shadow them
+        for c in ".,*:|/\\~>'=_@[]()\"<{}#-xgr5A ".chars() {
+            assert_eq!(Err(LexError::InvalidSpreadNext(c)), Lexer::new(String::from("E")).set_spread_next(c));
+        }
+    }
+
+    #[test]
+    fn articulation_tokens() {
+        let mut lex = Lexer::new("5h7 7p5".to_string());
+        assert_eq!(
+            vec![TokenType::Number, TokenType::HammerOn, TokenType::Number, TokenType::Number, TokenType::PullOff, TokenType::Number, TokenType::EndOfFile],
+            lex.generate_tokens().unwrap().iter().map(|token| token.type_of.clone()).collect::<Vec<TokenType>>()
+        );
+
+        let mut lex = Lexer::new("5 h7".to_string());
+        assert_eq!(
+            Err(String::from("[1:3] Error: Expected \"h\" for \"Hammer On\" to be written directly between two fret numbers.")),
+            lex.generate_tokens().map(|_| ()).map_err(|e| e.to_string())
+        );
+    }
+
+    #[test]
+    fn bend_release_vibrato_tokens() {
+        let mut lex = Lexer::new("7b9 9r7 5~ r8".to_string());
+        assert_eq!(
+            vec![
+                TokenType::Number, TokenType::Bend, TokenType::Number,
+                TokenType::Number, TokenType::Release, TokenType::Number,
+                TokenType::Number, TokenType::Vibrato,
+                TokenType::Rest,
+                TokenType::EndOfFile,
+            ],
+            lex.generate_tokens().unwrap().iter().map(|token| token.type_of.clone()).collect::<Vec<TokenType>>()
+        );
+    }
+
+    #[test]
+    fn slide_tokens() {
+        let mut lex = Lexer::new("5/7 7\\5".to_string());
+        assert_eq!(
+            vec![TokenType::Number, TokenType::SlideUp, TokenType::Number, TokenType::Number, TokenType::SlideDown, TokenType::Number, TokenType::EndOfFile],
+            lex.generate_tokens().unwrap().iter().map(|token| token.type_of.clone()).collect::<Vec<TokenType>>()
+        );
+    }
+
+    #[test]
+    fn chord_tokens() {
+        let mut lex = Lexer::new("x32010 (x x 12 14)".to_string());
+        let expected_tokens = vec![
+            Token::new(TokenType::Chord, String::from("x32010"), Literal::Chord(vec![None, Some(3), Some(2), Some(0), Some(1), Some(0)]), 1),
+            Token::new(TokenType::Chord, String::from("(x x 12 14)"), Literal::Chord(vec![None, None, Some(12), Some(14)]), 1),
+            Token::new(TokenType::EndOfFile, String::new(), Literal::None, 1),
+        ];
+        assert_eq!(&expected_tokens, lex.generate_tokens().unwrap());
+
+        let mut lex = Lexer::new("(3 2 o)\n(3 2".to_string());
+        assert_eq!(
+            Err(String::from(
+                "[1:1] Error: Chord \"(3 2 o)\" has an invalid fret \"o\". Frets must be numbers or \"x\" for muted strings.\n\
+                [2:1] Error: Unterminated chord. Close chords with \")\" on the same line."
+            )),
+            lex.generate_tokens().map(|_| ()).map_err(|e| e.to_string())
+        );
+
+        // digit runs with a digit for every string of the note line before them are chords, while
+        // shorter runs are still frets
+        let mut lex = Lexer::new("E A D G B E\n022100 12 1000".to_string());
+        assert_eq!(
+            vec![
+                (TokenType::Chord, Literal::Chord(vec![Some(0), Some(2), Some(2), Some(1), Some(0), Some(0)])),
+                (TokenType::Number, Literal::Number(12)),
+                (TokenType::Number, Literal::Number(1000)),
+                (TokenType::EndOfFile, Literal::None),
+            ],
+            lex.generate_tokens().unwrap()[6..].iter().map(|token| (token.type_of.clone(), token.literal.clone())).collect::<Vec<_>>()
+        );
+
+        let mut lex = Lexer::new("E A\n5 x 3".to_string());
+        assert_eq!(
+            Err(String::from("[2:3] Error: A muted string \"x\" must be written within a chord of two or more strings (\"x32010\").")),
+            lex.generate_tokens().map(|_| ()).map_err(|e| e.to_string())
+        );
+    }
+
+    #[test]
+    fn duration_tokens() {
+        let mut lex = Lexer::new("5:8 3:4. :2".to_string());
+        let expected_tokens = vec![
+            Token::new(TokenType::Number, String::from("5"), Literal::Number(5), 1),
+            Token::new(TokenType::Duration, String::from(":8"), Literal::Duration(8, false), 1),
+            Token::new(TokenType::Number, String::from("3"), Literal::Number(3), 1),
+            Token::new(TokenType::Duration, String::from(":4."), Literal::Duration(4, true), 1),
+            Token::new(TokenType::SpreadEmpty, String::from(":2"), Literal::Number(2), 1),
+            Token::new(TokenType::EndOfFile, String::new(), Literal::None, 1),
+        ];
+        assert_eq!(&expected_tokens, lex.generate_tokens().unwrap());
+
+        let mut lex = Lexer::new("5:0".to_string());
+        assert_eq!(
+            Err(String::from("[1:2] Error: Expected a duration greater than 0 after \":\" but found \"0\".")),
+            lex.generate_tokens().map(|_| ()).map_err(|e| e.to_string())
+        );
+    }
+
+    #[test]
+    fn mark_tokens() {
+        let mut lex = Lexer::new("5> 3' 7>'(2) 2:8'".to_string());
+        assert_eq!(
+            vec![
+                TokenType::Number, TokenType::Accent,
+                TokenType::Number, TokenType::Staccato,
+                TokenType::Number, TokenType::Accent, TokenType::Staccato, TokenType::Fingering,
+                TokenType::Number, TokenType::Duration, TokenType::Staccato,
+                TokenType::EndOfFile,
+            ],
+            lex.generate_tokens().unwrap().iter().map(|token| token.type_of.clone()).collect::<Vec<TokenType>>()
+        );
+
+        let mut lex = Lexer::new("5 >3".to_string());
+        assert_eq!(
+            Err(String::from("[1:3] Error: Expected \">\" for \"Accent\" to be written directly after a fret number.")),
+            lex.generate_tokens().map(|_| ()).map_err(|e| e.to_string())
+        );
+    }
+
+    #[test]
+    fn fingering_tokens() {
+        let mut lex = Lexer::new("5(1):8 7(T)~ (0 2)".to_string());
+        let expected_tokens = vec![
+            Token::new(TokenType::Number, String::from("5"), Literal::Number(5), 1),
+            Token::new(TokenType::Fingering, String::from("(1)"), Literal::Text(String::from("1")), 1),
+            Token::new(TokenType::Duration, String::from(":8"), Literal::Duration(8, false), 1),
+            Token::new(TokenType::Number, String::from("7"), Literal::Number(7), 1),
+            Token::new(TokenType::Fingering, String::from("(T)"), Literal::Text(String::from("T")), 1),
+            Token::new(TokenType::Vibrato, String::from("~"), Literal::None, 1),
+            Token::new(TokenType::Chord, String::from("(0 2)"), Literal::Chord(vec![Some(0), Some(2)]), 1),
+            Token::new(TokenType::EndOfFile, String::new(), Literal::None, 1),
+        ];
+        assert_eq!(&expected_tokens, lex.generate_tokens().unwrap());
+
+        let mut lex = Lexer::new("5(5) 3(1".to_string());
+        assert_eq!(
+            Err(String::from(
+                "[1:2] Error: Expected a finger of 1, 2, 3, 4, or T within parentheses after a fret number but found \"5\".\n\
+                [1:7] Error: Expected a finger of 1, 2, 3, 4, or T within parentheses after a fret number but found \"1\"."
+            )),
+            lex.generate_tokens().map(|_| ()).map_err(|e| e.to_string())
+        );
+    }
+
+    #[test]
+    fn hand_position_tokens() {
+        let mut lex = Lexer::new("B5 5 7 pos12 12 B 0".to_string());
+        let expected_tokens = vec![
+            Token::new(TokenType::Barre, String::from("B5"), Literal::Number(5), 1),
+            Token::new(TokenType::Number, String::from("5"), Literal::Number(5), 1),
+            Token::new(TokenType::Number, String::from("7"), Literal::Number(7), 1),
+            Token::new(TokenType::HandPosition, String::from("pos12"), Literal::Number(12), 1),
+            Token::new(TokenType::Number, String::from("12"), Literal::Number(12), 1),
+            Token::new(TokenType::Note, String::from("B"), Literal::None, 1),
+            Token::new(TokenType::Number, String::from("0"), Literal::Number(0), 1),
+            Token::new(TokenType::EndOfFile, String::new(), Literal::None, 1),
+        ];
+        assert_eq!(&expected_tokens, lex.generate_tokens().unwrap());
+
+        let mut lex = Lexer::new("pos 5".to_string());
+        assert_eq!(
+            Err(String::from("[1:1] Error: Expected \"pos\" for \"Hand Position\" to be directly followed by a fret number: pos5.")),
+            lex.generate_tokens().map(|_| ()).map_err(|e| e.to_string())
+        );
+    }
+
+    #[test]
+    fn repeat_tokens() {
+        let mut lex = Lexer::new("|: 0:| 3 :|x3".to_string());
+        let expected_tokens = vec![
+            Token::new(TokenType::RepeatStart, String::from("|:"), Literal::None, 1),
+            Token::new(TokenType::Number, String::from("0"), Literal::Number(0), 1),
+            Token::new(TokenType::RepeatEnd, String::from(":|"), Literal::Number(2), 1),
+            Token::new(TokenType::Number, String::from("3"), Literal::Number(3), 1),
+            Token::new(TokenType::RepeatEnd, String::from(":|x3"), Literal::Number(3), 1),
+            Token::new(TokenType::EndOfFile, String::new(), Literal::None, 1),
+        ];
+        assert_eq!(&expected_tokens, lex.generate_tokens().unwrap());
+
+        // a '|' without a ':' after it is a bar-line
+        let mut lex = Lexer::new("0 | 3".to_string());
+        assert_eq!(
+            vec![TokenType::Number, TokenType::BarLine, TokenType::Number, TokenType::EndOfFile],
+            lex.generate_tokens().unwrap().iter().map(|token| token.type_of.clone()).collect::<Vec<_>>()
+        );
+
+        let mut lex = Lexer::new("0\n:|x".to_string());
+        assert_eq!(
+            Err(String::from("[2:1] Error: Expected a repeat count of at least 1 after \":|x\" but found \"\".")),
+            lex.generate_tokens().map(|_| ()).map_err(|e| e.to_string())
+        );
+    }
+
+    #[test]
+    fn lyric_tokens() {
+        let mut lex = Lexer::new("\"hel\" 0 \"lo there\"".to_string());
+        let expected_tokens = vec![
+            Token::new(TokenType::Lyric, String::from("\"hel\""), Literal::Text(String::from("hel")), 1),
+            Token::new(TokenType::Number, String::from("0"), Literal::Number(0), 1),
+            Token::new(TokenType::Lyric, String::from("\"lo there\""), Literal::Text(String::from("lo there")), 1),
+            Token::new(TokenType::EndOfFile, String::new(), Literal::None, 1),
+        ];
+        assert_eq!(&expected_tokens, lex.generate_tokens().unwrap());
+
+        let mut lex = Lexer::new("\"la\n".to_string());
+        assert_eq!(
+            Err(String::from("[1:1] Error: Unterminated lyric. Close lyrics with a quote on the same line.")),
+            lex.generate_tokens().map(|_| ()).map_err(|e| e.to_string())
+        );
+    }
+
+    #[test]
+    fn chord_name_tokens() {
+        let mut lex = Lexer::new("<Am7> 0 < C >".to_string());
+        let expected_tokens = vec![
+            Token::new(TokenType::ChordName, String::from("<Am7>"), Literal::Text(String::from("Am7")), 1),
+            Token::new(TokenType::Number, String::from("0"), Literal::Number(0), 1),
+            Token::new(TokenType::ChordName, String::from("< C >"), Literal::Text(String::from("C")), 1),
+            Token::new(TokenType::EndOfFile, String::new(), Literal::None, 1),
+        ];
+        assert_eq!(&expected_tokens, lex.generate_tokens().unwrap());
+
+        let mut lex = Lexer::new("<>\n<G".to_string());
+        assert_eq!(
+            Err(String::from(
+                "[1:1] Error: Chord name \"<>\" cannot be empty.\n\
+                [2:1] Error: Unterminated chord name. Close chord names with \">\" on the same line."
+            )),
+            lex.generate_tokens().map(|_| ()).map_err(|e| e.to_string())
+        );
+    }
+
+    #[test]
+    fn section_tokens() {
+        let mut lex = Lexer::new("@verse\n@section(\"Bridge 2\") @".to_string());
+        let expected_tokens = vec![
+            Token::new(TokenType::Section, String::from("@verse"), Literal::Text(String::from("Verse")), 1),
+            Token::new(TokenType::Section, String::from("@section(\"Bridge 2\")"), Literal::Text(String::from("Bridge 2")), 2),
+            Token::new(TokenType::Voice, String::from("@"), Literal::None, 2),
+            Token::new(TokenType::EndOfFile, String::new(), Literal::None, 2),
+        ];
+        assert_eq!(&expected_tokens, lex.generate_tokens().unwrap());
+
+        let mut lex = Lexer::new("@section(Bridge)".to_string());
+        assert_eq!(
+            Err(String::from("[1:1] Error: Expected \"@section\" to be followed by a quoted label within parentheses, such as @section(\"Bridge\").")),
+            lex.generate_tokens().map(|_| ()).map_err(|e| e.to_string())
+        );
+    }
+
+    #[test]
+    fn drum_tokens() {
+        let mut lex = Lexer::new("[mode=drums]\nBD HH\nx o g\n[mode=tabs]\nE".to_string());
+        let expected_tokens = vec![
+            Token::new(TokenType::Options, String::from("[mode=drums]"), Literal::Options(String::from("mode=drums")), 1),
+            Token::new(TokenType::Note, String::from("BD"), Literal::None, 2),
+            Token::new(TokenType::Note, String::from("HH"), Literal::None, 2),
+            Token::new(TokenType::Hit, String::from("x"), Literal::None, 3),
+            Token::new(TokenType::Hit, String::from("o"), Literal::None, 3),
+            Token::new(TokenType::Hit, String::from("g"), Literal::None, 3),
+            Token::new(TokenType::Options, String::from("[mode=tabs]"), Literal::Options(String::from("mode=tabs")), 4),
+            Token::new(TokenType::Note, String::from("E"), Literal::None, 5),
+            Token::new(TokenType::EndOfFile, String::new(), Literal::None, 5),
+        ];
+        assert_eq!(&expected_tokens, lex.generate_tokens().unwrap());
+
+        let mut lex = Lexer::new("---\nmode: drums\n---\nHHO".to_string());
+        assert_eq!(
+            Err(String::from("[4:1] Error: Drum lane label \"HHO\" must be 1 or 2 chars long.")),
+            lex.generate_tokens().map(|_| ()).map_err(|e| e.to_string())
+        );
+    }
+
+    #[test]
+    fn lex_error_diagnostics() {
+        let mut lex = Lexer::new("E\n?\n<Am".to_string());
+        let codes: Vec<(u32, &str)> = match lex.generate_tokens() {
+            Err(LexError::Syntax(diagnostics)) => diagnostics.iter().map(|diagnostic| (diagnostic.line, diagnostic.code)).collect(),
+            other => panic!("Expected a syntax error, found {:?}", other),
+        };
+        assert_eq!(vec![(2, "L001"), (3, "L014")], codes);
+
+        assert_eq!(Err(LexError::InvalidSpreadNext(',')), Lexer::new(String::from("E")).set_spread_next(','));
+    }
+
+    #[test]
+    fn token_positions() {
+        let mut lex = Lexer::new("E A\n. 12 \"é\" 3".to_string());
+        let positions: Vec<(u32, Range<usize>)> = lex.generate_tokens().unwrap().iter()
+            .map(|token| (token.column, token.span.clone()))
+            .collect();
+
+        // columns are counted in characters while spans are counted in bytes
+        assert_eq!(vec![(1, 0..1), (3, 2..3), (1, 4..5), (3, 6..8), (6, 9..13), (10, 14..15), (11, 15..15)], positions);
+
+        let mut lex = Lexer::new("E A\n3 [time=4/4\n5".to_string());
+        let error = lex.generate_tokens().unwrap_err();
+        assert_eq!(
+            "[2:3] Error: Unterminated options sequence. Close options sequences with \"]\".\n  |\n2 | 3 [time=4/4\n  |   ^^^^^^^^^",
+            error.diagnostics()[0].render()
+        );
+
+        let mut lex = Lexer::new("é ?".to_string());
+        let error = lex.generate_tokens().unwrap_err();
+        assert_eq!(
+            "[1:1] Error: Unknown character value: é\n  |\n1 | é ?\n  | ^\n[1:3] Error: Unknown character value: ?\n  |\n1 | é ?\n  |   ^",
+            error.diagnostics().iter().map(Diagnostic::render).collect::<Vec<String>>().join("\n")
+        );
+    }
+
+    #[test]
+    fn streamed_tokens() {
+        let sources = [
+            include_str!("../../tab_input/example.txt"),
+            "#!/usr/bin/env tabs\n---\ntitle: Song\n---\n[time=3/4;\nfidelity=8]\nE A\n0 3 :2\n\n\"la\" 5 ?",
+        ];
+        for source in sources.iter() {
+            let mut lex = Lexer::from(*source);
+            let mut streamed = Lexer::from_reader(std::io::Cursor::new(source.to_string()));
+            let positions = |tokens: &Vec<Token>| tokens.iter().map(|token| (token.column, token.span.clone())).collect::<Vec<_>>();
+
+            match (lex.generate_tokens(), streamed.generate_tokens()) {
+                (Ok(tokens), Ok(streamed)) => {
+                    assert_eq!(tokens, streamed);
+                    assert_eq!(positions(tokens), positions(streamed));
+                },
+                (Err(e), Err(streamed)) => assert_eq!(e.render(), streamed.render()),
+                (tokens, streamed) => panic!("Expected the same result, found {:?} and {:?}", tokens, streamed),
+            }
+        }
+
+        let mut lex = Lexer::from_reader(&b"E A\n0 \xFF"[..]);
+        let error = lex.generate_tokens().unwrap_err();
+        assert_eq!(vec![(2, "L017")], error.diagnostics().iter().map(|d| (d.line, d.code)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn borrowed_tokens() {
+        let source = include_str!("../../tab_input/example.txt");
+        let tokens = Lexer::from(source).generate_tokens().unwrap().clone();
+
+        let mut lex = Lexer::from(source);
+        let borrowed = lex.borrowed_tokens().unwrap();
+        assert_eq!(tokens, borrowed.iter().map(BorrowedToken::to_token).collect::<Vec<Token>>());
+        // borrowed values are slices of the source itself
+        assert!(borrowed.iter().all(|token| token.value == &source[token.span.clone()]));
+
+        // streamed sources keep a copy of the values of the lines they drop
+        let mut streamed = Lexer::from_reader(std::io::Cursor::new(source.to_string()));
+        let borrowed = streamed.borrowed_tokens().unwrap();
+        assert_eq!(tokens, borrowed.iter().map(BorrowedToken::to_token).collect::<Vec<Token>>());
+    }
+
+    #[test]
+    fn token_iterator() {
+        let source = include_str!("../../tab_input/example.txt");
+        let tokens = Lexer::from(source).generate_tokens().unwrap().clone();
+
+        assert_eq!(tokens, Lexer::from(source).collect::<Result<Vec<Token>, LexError>>().unwrap());
+        let streamed = Lexer::from_reader(std::io::Cursor::new(source.to_string()));
+        assert_eq!(tokens, streamed.collect::<Result<Vec<Token>, LexError>>().unwrap());
+
+        // every error is returned where it was found, and tokens after it are still generated
+        let results: Vec<Result<String, String>> = Lexer::from("E\n? 3 ;")
+            .map(|result| result.map(|token| token.value).map_err(|e| e.to_string()))
+            .collect();
+        assert_eq!(
+            vec![
+                Ok(String::from("E")),
+                Err(String::from("[2:1] Error: Unknown character value: ?")),
+                Ok(String::from("3")),
+                Err(String::from("[2:5] Error: Expected an amount after \";\" for \"Spread Next\" but found the end of the file.")),
+                Ok(String::new()),
+            ],
+            results
+        );
+    }
+
+    #[test]
+    fn comment_tokens() {
+        let mut lex = Lexer::new("// intro\nE A\n0 /* open */ 3 // fill\n/* two\nlines */\n5".to_string());
+        let expected_tokens = vec![
+            Token::new(TokenType::Comment, String::from("// intro"), Literal::Text(String::from("intro")), 1),
+            Token::new(TokenType::Note, String::from("E"), Literal::None, 2),
+            Token::new(TokenType::Note, String::from("A"), Literal::None, 2),
+            Token::new(TokenType::Number, String::from("0"), Literal::Number(0), 3),
+            Token::new(TokenType::Comment, String::from("/* open */"), Literal::Text(String::from("open")), 3),
+            Token::new(TokenType::Number, String::from("3"), Literal::Number(3), 3),
+            Token::new(TokenType::Comment, String::from("// fill"), Literal::Text(String::from("fill")), 3),
+            Token::new(TokenType::Comment, String::from("/* two\nlines */"), Literal::Text(String::from("two\nlines")), 4),
+            Token::new(TokenType::Number, String::from("5"), Literal::Number(5), 6),
+            Token::new(TokenType::EndOfFile, String::new(), Literal::None, 6),
+        ];
+        assert_eq!(&expected_tokens, lex.generate_tokens().unwrap());
+
+        // a line of only a comment neither separates staffs nor stops a blank line from separating them
+        let staff_breaks = |source: &str| Lexer::from(source).generate_tokens().unwrap().iter()
+            .filter(|token| token.type_of == TokenType::StaffBreak)
+            .count();
+        assert_eq!(0, staff_breaks("0 3\n// solo\n5 7"));
+        assert_eq!(1, staff_breaks("0 3\n\n// solo\n5 7"));
+        assert_eq!(1, staff_breaks("0 3\n// solo\n\n5 7"));
+
+        // slides are still written between two fret numbers
+        assert_eq!(TokenType::SlideUp, Lexer::from("5/7").generate_tokens().unwrap()[1].type_of);
+
+        let mut lex = Lexer::new("0 /* open\n3".to_string());
+        assert_eq!(
+            Err(String::from("[1:3] Error: Unterminated block comment. Close block comments with \"*/\".")),
+            lex.generate_tokens().map(|_| ()).map_err(|e| e.to_string())
+        );
+    }
+
+    #[test]
+    fn scoped_options_token() {
+        let mut lex = Lexer::new("[[fidelity=32]]\nE\n0".to_string());
+        let expected_tokens = vec![
+            Token::new(TokenType::ScopedOptions, String::from("[[fidelity=32]]"), Literal::Options(String::from("fidelity=32")), 1),
+            Token::new(TokenType::Note, String::from("E"), Literal::None, 2),
+            Token::new(TokenType::Number, String::from("0"), Literal::Number(0), 3),
+            Token::new(TokenType::EndOfFile, String::new(), Literal::None, 3),
+        ];
+        assert_eq!(&expected_tokens, lex.generate_tokens().unwrap());
+
+        let mut lex = Lexer::new("[[fidelity=32]".to_string());
+        assert_eq!(
+            Err(String::from("[1:1] Error: Unterminated scoped options sequence. Close scoped options sequences with \"]]\".")),
+            lex.generate_tokens().map(|_| ()).map_err(|e| e.to_string())
+        );
+    }
+
+    #[test]
+    fn tuplet_tokens() {
+        let mut lex = Lexer::new("3{5 7 8}".to_string());
+        let expected_tokens = vec![
+            Token::new(TokenType::TupletStart, String::from("3{"), Literal::Number(3), 1),
+            Token::new(TokenType::Number, String::from("5"), Literal::Number(5), 1),
+            Token::new(TokenType::Number, String::from("7"), Literal::Number(7), 1),
+            Token::new(TokenType::Number, String::from("8"), Literal::Number(8), 1),
+            Token::new(TokenType::GroupEnd, String::from("}"), Literal::None, 1),
+            Token::new(TokenType::EndOfFile, String::new(), Literal::None, 1),
+        ];
+        assert_eq!(&expected_tokens, lex.generate_tokens().unwrap());
+    }
+
+    #[test]
+    fn span_tokens() {
+        let mut lex = Lexer::new("pm{0 3} lr{5}".to_string());
+        let expected_tokens = vec![
+            Token::new(TokenType::SpanStart, String::from("pm{"), Literal::Text(String::from("pm")), 1),
+            Token::new(TokenType::Number, String::from("0"), Literal::Number(0), 1),
+            Token::new(TokenType::Number, String::from("3"), Literal::Number(3), 1),
+            Token::new(TokenType::GroupEnd, String::from("}"), Literal::None, 1),
+            Token::new(TokenType::SpanStart, String::from("lr{"), Literal::Text(String::from("lr")), 1),
+            Token::new(TokenType::Number, String::from("5"), Literal::Number(5), 1),
+            Token::new(TokenType::GroupEnd, String::from("}"), Literal::None, 1),
+            Token::new(TokenType::EndOfFile, String::new(), Literal::None, 1),
+        ];
+        assert_eq!(&expected_tokens, lex.generate_tokens().unwrap());
+
+        // a pull-off is still written between two fret numbers
+        assert_eq!(TokenType::PullOff, Lexer::from("7p5").generate_tokens().unwrap()[1].type_of);
+
+        let mut lex = Lexer::new("pm 0".to_string());
+        assert_eq!(
+            Err(String::from("[1:1] Error: Span \"pm\" must be directly followed by \"{\" to start it.")),
+            lex.generate_tokens().map(|_| ()).map_err(|e| e.to_string())
+        );
+    }
+
+    #[test]
+    fn harmonic_tokens() {
+        let mut lex = Lexer::new("<12> 5ph <Am>".to_string());
+        let expected_tokens = vec![
+            Token::new(TokenType::NaturalHarmonic, String::from("<12>"), Literal::Number(12), 1),
+            Token::new(TokenType::Number, String::from("5"), Literal::Number(5), 1),
+            Token::new(TokenType::PinchHarmonic, String::from("ph"), Literal::None, 1),
+            Token::new(TokenType::ChordName, String::from("<Am>"), Literal::Text(String::from("Am")), 1),
+            Token::new(TokenType::EndOfFile, String::new(), Literal::None, 1),
+        ];
+        assert_eq!(&expected_tokens, lex.generate_tokens().unwrap());
+
+        // a '*' directly after a number is a pinch harmonic, while a '*' after a space highlights the next number
+        let mut lex = Lexer::new("5* 7 *9".to_string());
+        let expected_tokens = vec![
+            Token::new(TokenType::Number, String::from("5"), Literal::Number(5), 1),
+            Token::new(TokenType::PinchHarmonic, String::from("*"), Literal::None, 1),
+            Token::new(TokenType::Number, String::from("7"), Literal::Number(7), 1),
+            Token::new(TokenType::Highlight, String::from("*"), Literal::None, 1),
+            Token::new(TokenType::Number, String::from("9"), Literal::Number(9), 1),
+            Token::new(TokenType::EndOfFile, String::new(), Literal::None, 1),
+        ];
+        assert_eq!(&expected_tokens, lex.generate_tokens().unwrap());
+    }
+
+    #[test]
+    fn stroke_tokens() {
+        let mut lex = Lexer::new("t12 s3 P5".to_string());
+        let expected_tokens = vec![
+            Token::new(TokenType::Tap, String::from("t"), Literal::None, 1),
+            Token::new(TokenType::Number, String::from("12"), Literal::Number(12), 1),
+            Token::new(TokenType::Slap, String::from("s"), Literal::None, 1),
+            Token::new(TokenType::Number, String::from("3"), Literal::Number(3), 1),
+            Token::new(TokenType::Pop, String::from("P"), Literal::None, 1),
+            Token::new(TokenType::Number, String::from("5"), Literal::Number(5), 1),
+            Token::new(TokenType::EndOfFile, String::new(), Literal::None, 1),
+        ];
+        assert_eq!(&expected_tokens, lex.generate_tokens().unwrap());
+
+        assert!(Lexer::new("t 12".to_string()).generate_tokens().is_err());
+    }
+
+    #[test]
+    fn grace_note_tokens() {
+        let mut lex = Lexer::new("g3~5".to_string());
+        let expected_tokens = vec![
+            Token::new(TokenType::GraceNote, String::from("g3~"), Literal::Number(3), 1),
+            Token::new(TokenType::Number, String::from("5"), Literal::Number(5), 1),
+            Token::new(TokenType::EndOfFile, String::new(), Literal::None, 1),
+        ];
+        assert_eq!(&expected_tokens, lex.generate_tokens().unwrap());
+
+        assert!(Lexer::new("g3 5".to_string()).generate_tokens().is_err());
+    }
+
+    #[test]
+    fn tie_tokens() {
+        let mut lex = Lexer::new("5 = _".to_string());
+        assert_eq!(
+            vec![TokenType::Number, TokenType::Tie, TokenType::Tie, TokenType::EndOfFile],
+            lex.generate_tokens().unwrap().iter().map(|token| token.type_of.clone()).collect::<Vec<TokenType>>()
+        );
+    }
+}
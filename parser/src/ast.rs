@@ -0,0 +1,397 @@
+use serde::{Serialize, Deserialize};
+
+use crate::{transpose_fret, ParseError, Cell, Dynamic, Finger, HandPosition, Harmonic, Marking, Metadata, Repeat, StaffManager, Technique, TempoChange};
+
+/// The structured form of a whole tab notation source, created by `Parser::parse`.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Score {
+    /// The title, artist, and album set by the `title`, `artist`, and `album` options.
+    #[serde(flatten)]
+    pub metadata: Metadata,
+    /// The tempo in beats per minute set before any tabs, if any.
+    pub tempo: Option<u32>,
+    /// The fret the capo is placed at, if a capo is used. Frets are written relative to the capo.
+    pub capo: Option<u32>,
+    /// Whether a metronome click is played along with the exported notes, set by the `click` option.
+    pub click: bool,
+    /// The starting tempo and every tempo change of the score in order.
+    pub tempo_changes: Vec<TempoChange>,
+    /// Every staff in the order they were written.
+    pub staffs: Vec<Staff>,
+}
+
+/// A single staff of a score.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Staff {
+    /// The label of the song section the staff starts, if any.
+    pub section: Option<String>,
+    /// The note name of every string in the order they were written.
+    pub strings: Vec<String>,
+    /// The time signature as beats per measure and the dominant beat.
+    pub time_signature: (u32, u32),
+    /// The amount of beat slots within a single counted beat.
+    pub slots_per_beat: u32,
+    /// The beat the staff starts at, counted from the start of the score.
+    pub start_beat: u32,
+    /// The amount of beats counted by the staff, including a partially filled last beat.
+    pub beats: u32,
+    /// Every dynamic marking of the staff along with where it is placed.
+    pub dynamics: Vec<(Position, Dynamic)>,
+    /// Every lyric syllable of the staff along with where it is sung.
+    pub lyrics: Vec<(Position, String)>,
+    /// Every chord name annotation of the staff along with where it is placed.
+    pub chord_names: Vec<(Position, String)>,
+    /// Every barre and hand position of the staff along with where it is placed from.
+    pub hand_positions: Vec<(Position, HandPosition)>,
+    /// Whether the staff is a drum staff, whose lanes are labeled with drums and hold hits instead of notes.
+    pub drums: bool,
+    /// The ratio of the first eighth note of every pair to the second, if the staff is played with a
+    /// swing feel.
+    pub swing: Option<(u32, u32)>,
+    /// Every measure of the staff in order.
+    pub measures: Vec<Measure>,
+}
+
+/// A single measure of a staff.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Measure {
+    /// The measure number, counted from `1` across every staff.
+    pub number: usize,
+    /// The beat the measure starts at, counted from the start of the score.
+    pub start_beat: u32,
+    /// Whether a repeated section starts at the measure.
+    pub repeat_start: bool,
+    /// The amount of times the repeated section ending with the measure is played, if a repeat ends
+    /// with the measure.
+    pub repeat_end: Option<u32>,
+    /// Every note, drum hit, and rest of the measure, ordered by position and then by string.
+    pub events: Vec<Event>,
+}
+
+/// Something played, or intentionally not played, within a measure.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum Event {
+    /// A fret played on a single string.
+    Note(Note),
+    /// A rest across every string.
+    Rest(Rest),
+    /// A drum hit on a single lane of a drum staff.
+    Hit(Hit),
+}
+
+/// A fret played on a single string.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Note {
+    /// The index of the string within the strings of the staff.
+    pub string: usize,
+    /// The fret played.
+    pub fret: u32,
+    /// Whether the note was highlighted to make it stand out.
+    pub highlight: bool,
+    /// The articulation the note is played with from the note before it: `h` for a hammer-on, `p` for a
+    /// pull-off, `b` for a bend, `r` for a release, `/` and `\` for a slide up or down, or `=` for a tie
+    /// that sustains the note before it on the same string; or the stroke the note is played with: `t`
+    /// for a tap, `s` for a slap, or `P` for a pop.
+    pub articulation: Option<char>,
+    /// Whether the note is played with vibrato.
+    pub vibrato: bool,
+    /// Whether the note is accented to be played louder than the notes around it.
+    pub accent: bool,
+    /// Whether the note is played staccato, cut short to half of the time it would otherwise ring.
+    pub staccato: bool,
+    /// How long the note rings, if a duration was written after it.
+    pub duration: Option<Duration>,
+    /// The harmonic the note is played as, if it is not fretted normally.
+    pub harmonic: Option<Harmonic>,
+    /// The fret of the grace note played on the same string just before the note, if any.
+    pub grace: Option<u32>,
+    /// The finger of the fretting hand the note is played with, if a fingering was written after it.
+    pub finger: Option<Finger>,
+    /// Every technique the note is played with from the technique spans it falls within.
+    pub techniques: Vec<Technique>,
+    /// Where the note is played.
+    pub position: Position,
+    /// The source line the note was written on.
+    pub line: u32,
+}
+
+/// How long a note rings, written after its fret number (`5:8` or `3:4.`).
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub struct Duration {
+    /// The length of the note, where `4` is a quarter note and `8` an eighth note.
+    pub length: u32,
+    /// Whether the note lasts half as long again as its length.
+    pub dotted: bool,
+}
+
+/// A drum hit on a single lane of a drum staff, written in drum mode.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Hit {
+    /// The index of the lane within the strings of the staff.
+    pub lane: usize,
+    /// The hit symbol: `x` for a hit, `o` for an open hit, or `g` for a ghost note.
+    pub symbol: char,
+    /// Whether the hit was highlighted to make it stand out.
+    pub highlight: bool,
+    /// Where the hit is played.
+    pub position: Position,
+    /// The source line the hit was written on.
+    pub line: u32,
+}
+
+/// An intentional rest across every string.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Rest {
+    /// Where the rest starts.
+    pub position: Position,
+    /// The amount of beat slots the rest lasts for.
+    pub slots: u32,
+}
+
+/// A beat position within a score.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Serialize, Deserialize)]
+pub struct Position {
+    /// The beat, counted from `0` at the start of the score.
+    pub beat: u32,
+    /// The beat slot within the beat, counted from `0`.
+    pub slot: u32,
+}
+
+impl Score {
+    /// Transposes every note of the score by the provided amount of semitones, up for positive amounts
+    /// and down for negative amounts. A note that would go below fret `0` is moved to the highest lower
+    /// string that is free to play it at the same position.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if a note would go below fret `0` and no lower string is free to play it.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use data::{Token, TokenType, Literal};
+    /// use parser::{Parser, ast::Event};
+    /// 
+    /// let tokens = vec![
+    ///     Token::new(TokenType::Note, String::from("E"), Literal::None, 1),
+    ///     Token::new(TokenType::Number, String::from("3"), Literal::Number(3), 2),
+    ///     Token::new(TokenType::EndOfFile, String::new(), Literal::None, 2),
+    /// ];
+    /// 
+    /// let mut score = Parser::new(&tokens).parse().unwrap();
+    /// score.transpose(2).unwrap();
+    /// 
+    /// assert!(matches!(&score.staffs[0].measures[0].events[0], Event::Note(note) if note.fret == 5));
+    /// assert!(score.transpose(-6).is_err());
+    /// ```
+    pub fn transpose(&mut self, semitones: i32) -> Result<(), ParseError> {
+        for staff in self.staffs.iter_mut() {
+            for measure in staff.measures.iter_mut() {
+                for i in 0..measure.events.len() {
+                    let (string, fret, position, line) = match &measure.events[i] {
+                        Event::Note(note) => (note.string, note.fret, note.position, note.line),
+                        Event::Rest(_) | Event::Hit(_) => continue,
+                    };
+                    let events = &measure.events;
+                    let is_free = |lower: usize| !events.iter().any(|event| matches!(
+                        event,
+                        Event::Note(other) if other.position == position && other.string == lower
+                    ));
+                    let (to, transposed) = transpose_fret(&staff.strings, string, fret, line, semitones, is_free)?;
+
+                    if let Event::Note(note) = &mut measure.events[i] {
+                        note.string = to;
+                        note.fret = transposed;
+                        // grace notes keep their interval to the note they lead into
+                        note.grace = note.grace.map(|grace| (grace + transposed).saturating_sub(fret));
+                    }
+                }
+                // notes moved to another string are put back in order
+                measure.events.sort_by_key(|event| match event {
+                    Event::Note(note) => (note.position, note.string),
+                    Event::Rest(rest) => (rest.position, 0),
+                    Event::Hit(hit) => (hit.position, hit.lane),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Creates a score from the staffs of the provided staff manager.
+    pub(crate) fn from_staffs(staff_manager: &StaffManager) -> Score {
+        let mut staffs = vec![];
+        // beats and measures are counted across staffs, so every staff starts where the previous one ended
+        let mut beat_offset = 0;
+        let mut measure_offset = 0;
+        for staff in staff_manager.staffs.iter() {
+            let slots_per_beat = staff.time.slots_per_beat();
+            let position = |column: usize| Position {
+                beat: beat_offset + column as u32 / slots_per_beat,
+                slot: column as u32 % slots_per_beat,
+            };
+            let end = staff.tabs.iter().map(|lane| lane.len()).max().unwrap_or(0);
+            let measure_length = staff.time.total_beats_per_measure() as usize;
+
+            let measures: Vec<Measure> = staff.time.measures(0, end).into_iter().enumerate().map(|(i, columns)| {
+                let mut events = vec![];
+                for column in columns.clone() {
+                    // lanes are stored in reverse note order
+                    let cells: Vec<Option<&Cell>> = staff.tabs.iter().rev().map(|lane| lane.get(column)).collect();
+                    if cells.iter().all(|cell| cell == &Some(&Cell::Rest)) {
+                        // consecutive rest columns within a measure are a single rest
+                        match events.last_mut() {
+                            Some(Event::Rest(rest)) if column > columns.start && staff.tabs[0][column - 1] == Cell::Rest => {
+                                rest.slots += 1
+                            },
+                            _ => events.push(Event::Rest(Rest { position: position(column), slots: 1 })),
+                        }
+                        continue
+                    }
+                    for (string, cell) in cells.into_iter().enumerate() {
+                        if let Some(Cell::Tab { value, highlight, articulation, vibrato, accent, staccato, duration, harmonic, grace, finger, line }) = cell {
+                            // the tabs of drum staffs are hits with a hit symbol in place of a fret
+                            if let Some(symbol) = value.chars().next().filter(|_| staff.drums) {
+                                events.push(Event::Hit(Hit {
+                                    lane: string,
+                                    symbol,
+                                    highlight: *highlight,
+                                    position: position(column),
+                                    line: *line,
+                                }));
+                                continue
+                            }
+                            events.push(Event::Note(Note {
+                                string,
+                                fret: value.parse().unwrap_or_default(),
+                                highlight: *highlight,
+                                articulation: *articulation,
+                                vibrato: *vibrato,
+                                accent: *accent,
+                                staccato: *staccato,
+                                duration: *duration,
+                                harmonic: *harmonic,
+                                grace: *grace,
+                                finger: *finger,
+                                techniques: staff.spans.iter()
+                                    .filter(|(columns, _)| columns.contains(&column))
+                                    .map(|(_, technique)| *technique)
+                                    .collect(),
+                                position: position(column),
+                                line: *line,
+                            }));
+                        }
+                    }
+                }
+                Measure {
+                    number: measure_offset + i + 1,
+                    start_beat: position(columns.start).beat,
+                    repeat_start: staff.repeats.contains(&(columns.start, Repeat::Start)),
+                    repeat_end: staff.repeats.iter().find_map(|(column, repeat)| match repeat {
+                        Repeat::End(times) if *column == columns.start + measure_length => Some(*times),
+                        _ => None,
+                    }),
+                    events,
+                }
+            }).collect();
+
+            let dynamics = staff.markings.iter()
+                .filter_map(|(column, marking)| match marking {
+                    Marking::Dynamic(dynamic) => Some((position(*column), *dynamic)),
+                    _ => None,
+                })
+                .collect();
+
+            measure_offset += measures.len();
+            staffs.push(Staff {
+                section: staff.section.clone(),
+                strings: staff.notes.clone(),
+                time_signature: staff.time.get_signature(),
+                slots_per_beat,
+                start_beat: beat_offset,
+                beats: staff.time.beats_counted(),
+                dynamics,
+                lyrics: staff.lyrics.iter().map(|(column, lyric)| (position(*column), lyric.clone())).collect(),
+                chord_names: staff.chord_names.iter().map(|(column, name)| (position(*column), name.clone())).collect(),
+                hand_positions: staff.hand_positions.iter().map(|(column, hand_position)| (position(*column), *hand_position)).collect(),
+                drums: staff.drums,
+                swing: staff.swing,
+                measures,
+            });
+            beat_offset += staff.time.beats_counted();
+        }
+
+        Score {
+            metadata: staff_manager.metadata.clone(),
+            tempo: staff_manager.start_tempo,
+            capo: staff_manager.options.capo,
+            click: staff_manager.options.click,
+            tempo_changes: staff_manager.tempo_changes(),
+            staffs,
+        }
+    }
+}
+
+impl Staff {
+    /// Returns the MIDI pitch a note of the staff sounds at, with every string pitched from its note name
+    /// the same as MIDI export and raised by the provided capo fret. Harmonics sound at the pitch of their
+    /// node rather than their fret.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use data::{Token, TokenType, Literal};
+    /// use parser::{Parser, ast::Event};
+    /// 
+    /// let tokens = vec![
+    ///     Token::new(TokenType::Note, String::from("E"), Literal::None, 1),
+    ///     Token::new(TokenType::Note, String::from("A"), Literal::None, 1),
+    ///     Token::new(TokenType::Number, String::from("0"), Literal::Number(0), 2),
+    ///     Token::new(TokenType::Number, String::from("3"), Literal::Number(3), 2),
+    ///     Token::new(TokenType::EndOfFile, String::new(), Literal::None, 2),
+    /// ];
+    /// 
+    /// let score = Parser::new(&tokens).parse().unwrap();
+    /// let staff = &score.staffs[0];
+    /// 
+    /// match &staff.measures[0].events[1] {
+    ///     Event::Note(note) => assert_eq!((48, 50), (staff.pitch(note, 0), staff.pitch(note, 2))),
+    ///     _ => panic!("expected a note"),
+    /// }
+    /// ```
+    pub fn pitch(&self, note: &Note, capo: u32) -> u32 {
+        let open = crate::export::pitches(&self.strings).get(note.string).copied().unwrap_or(0) as u32;
+        open + capo + note.harmonic.map_or(note.fret, |harmonic| harmonic.semitones(note.fret))
+    }
+
+    /// Returns the finger a note of the staff is fretted with: the fingering written after it, or else the
+    /// finger suggested by the last barre or hand position placed at or before it.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use data::{Token, TokenType, Literal};
+    /// use parser::{Finger, Parser, ast::Event};
+    /// 
+    /// let tokens = vec![
+    ///     Token::new(TokenType::Note, String::from("E"), Literal::None, 1),
+    ///     Token::new(TokenType::Barre, String::from("B5"), Literal::Number(5), 2),
+    ///     Token::new(TokenType::Number, String::from("7"), Literal::Number(7), 2),
+    ///     Token::new(TokenType::EndOfFile, String::new(), Literal::None, 2),
+    /// ];
+    /// 
+    /// let score = Parser::new(&tokens).parse().unwrap();
+    /// let staff = &score.staffs[0];
+    /// 
+    /// match &staff.measures[0].events[0] {
+    ///     Event::Note(note) => assert_eq!(Some(Finger::Ring), staff.finger(note)),
+    ///     _ => panic!("expected a note"),
+    /// }
+    /// ```
+    pub fn finger(&self, note: &Note) -> Option<Finger> {
+        note.finger.or_else(|| {
+            self.hand_positions.iter().rev()
+                .find(|(position, _)| *position <= note.position)
+                .and_then(|(_, hand_position)| hand_position.finger(note.fret))
+        })
+    }
+}
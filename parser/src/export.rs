@@ -0,0 +1,1012 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{html::escape, pitch_class, Dynamic, Finger, Harmonic, Key, Technique, ast::{Score, Staff, Measure, Event, Note, Duration, Position}};
+#[cfg(feature = "playback")]
+use crate::synth;
+
+/// The amount of MIDI ticks within a quarter note.
+const TICKS_PER_QUARTER: u32 = 480;
+/// The amount of MIDI ticks a grace note is played for before the note it leads into: a 32nd note.
+const GRACE_TICKS: u32 = TICKS_PER_QUARTER / 8;
+/// The amount of MIDI ticks within a whole note, also used to split beats into alphaTex durations.
+const TICKS_PER_WHOLE: u32 = TICKS_PER_QUARTER * 4;
+/// The General MIDI program played by every note: acoustic guitar (steel).
+const GUITAR_PROGRAM: u8 = 25;
+/// How much louder than the notes around it an accented note is played, in MIDI velocity.
+const ACCENT_VELOCITY: u8 = 20;
+/// The General MIDI percussion keys of the click track: a high wood block on the first beat of every
+/// measure and a low wood block on every other beat and slot.
+const CLICK_ACCENT: u8 = 76;
+const CLICK: u8 = 77;
+/// The space in SVG pixels around the drawn score, between staff systems, and between the strings of
+/// a staff system.
+const SVG_MARGIN: u32 = 20;
+const SVG_SYSTEM_GAP: u32 = 36;
+const STRING_GAP: u32 = 12;
+/// The width in SVG pixels of a single beat slot, and the space left after every bar-line.
+const SLOT_WIDTH: u32 = 16;
+const BAR_PADDING: u32 = 8;
+
+/// Converts a score into the bytes of a Standard MIDI File with a single track. Every string is pitched
+/// from its note name, starting with the lowest string in the second octave (an `E` string is `E2`) and
+/// placing every higher string at the first pitch above the string before it, then raised by the capo
+/// if one is used. Notes last until the next note on the same string, the next rest, or the end of
+/// their staff, and notes with a duration are stopped once it has passed. Repeated sections are played
+/// as many times as their repeat end sets. Section labels are written as marker events at the start of
+/// their staff. A staff with a swing feel delays the second eighth note of every quarter note by its
+/// swing ratio. Harmonics are played at the pitch they sound rather than the pitch of their fret. Grace
+/// notes are played for a 32nd note just before the beat of the note they lead into. Tied notes sustain
+/// the note sounding on their string rather than playing it again. Accented notes are played louder
+/// than the dynamic of their staff, and staccato notes are stopped halfway to where they would
+/// otherwise stop. A score with the click turned on also plays a metronome on the percussion channel at
+/// every slot of its staffs, accenting the first beat of every measure and playing the slots between
+/// beats quieter than the beats.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use data::{Token, TokenType, Literal};
+/// use parser::Parser;
+/// 
+/// let tokens = vec![
+///     Token::new(TokenType::Note, String::from("E"), Literal::None, 1),
+///     Token::new(TokenType::Number, String::from("3"), Literal::Number(3), 2),
+///     Token::new(TokenType::EndOfFile, String::new(), Literal::None, 2),
+/// ];
+/// 
+/// let score = Parser::new(&tokens).parse().unwrap();
+/// let midi = parser::export::midi(&score);
+/// 
+/// assert_eq!(b"MThd", &midi[..4]);
+/// ```
+pub fn midi(score: &Score) -> Vec<u8> {
+    let mut track = vec![];
+    let mut last = 0;
+    for (tick, _, bytes) in midi_events(score) {
+        track.extend(variable_length(tick - last));
+        track.extend(bytes);
+        last = tick;
+    }
+    track.extend([0x00, 0xFF, 0x2F, 0x00]);
+
+    let mut file = vec![];
+    file.extend(b"MThd");
+    file.extend(6u32.to_be_bytes());
+    // format 0 with a single track
+    file.extend(0u16.to_be_bytes());
+    file.extend(1u16.to_be_bytes());
+    file.extend((TICKS_PER_QUARTER as u16).to_be_bytes());
+    file.extend(b"MTrk");
+    file.extend((track.len() as u32).to_be_bytes());
+    file.extend(track);
+    file
+}
+
+/// Synthesizes a score into the bytes of a WAV file of 16-bit mono audio, playing every note as a
+/// plucked string with the Karplus-Strong algorithm. Notes are played at the pitches, times, and
+/// loudness of the MIDI file written by `midi`, at the tempo of the score or 120 beats per minute if
+/// it sets none. The metronome of a score with the click turned on is played as short blips.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use data::{Token, TokenType, Literal};
+/// use parser::Parser;
+/// 
+/// let tokens = vec![
+///     Token::new(TokenType::Note, String::from("E"), Literal::None, 1),
+///     Token::new(TokenType::Number, String::from("3"), Literal::Number(3), 2),
+///     Token::new(TokenType::EndOfFile, String::new(), Literal::None, 2),
+/// ];
+/// 
+/// let score = Parser::new(&tokens).parse().unwrap();
+/// let wav = parser::export::wav(&score);
+/// 
+/// assert_eq!(b"RIFF", &wav[..4]);
+/// ```
+#[cfg(feature = "playback")]
+pub fn wav(score: &Score) -> Vec<u8> {
+    // the start and velocity of the notes sounding at every pitch, in the order they started
+    let mut sounding: Vec<Vec<(f64, u8)>> = vec![vec![]; 128];
+    let (mut plucks, mut clicks) = (vec![], vec![]);
+    for (seconds, bytes) in midi_timeline(score) {
+        match bytes[..] {
+            [0x99, key, velocity] => clicks.push(synth::Click { start: seconds, accent: key == CLICK_ACCENT, velocity }),
+            [0x90, pitch, velocity] => sounding[pitch as usize].push((seconds, velocity)),
+            [0x80, pitch, _] if !sounding[pitch as usize].is_empty() => {
+                let (start, velocity) = sounding[pitch as usize].remove(0);
+                plucks.push(synth::Pluck { start, end: seconds, pitch, velocity });
+            },
+            _ => (),
+        }
+    }
+    synth::wav(&synth::render(&plucks, &clicks))
+}
+
+/// Returns the channel messages of the MIDI file written by `midi` in the order they are played, each
+/// along with the amount of seconds from the start of the score it is played at, for sending to a MIDI
+/// device as the score is played. Beats last as long as their time signature and the tempo at the time
+/// set, or 120 beats per minute until a tempo is set.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use data::{Token, TokenType, Literal};
+/// use parser::Parser;
+/// 
+/// let tokens = vec![
+///     Token::new(TokenType::Note, String::from("E"), Literal::None, 1),
+///     Token::new(TokenType::Number, String::from("3"), Literal::Number(3), 2),
+///     Token::new(TokenType::EndOfFile, String::new(), Literal::None, 2),
+/// ];
+/// 
+/// let score = Parser::new(&tokens).parse().unwrap();
+/// let timeline = parser::export::midi_timeline(&score);
+/// 
+/// // G2 is played at the start and stopped after a quarter note at 120 beats per minute
+/// assert_eq!((0.0, vec![0x90, 43, 80]), timeline[1]);
+/// assert_eq!((0.5, vec![0x80, 43, 0]), timeline[2]);
+/// ```
+pub fn midi_timeline(score: &Score) -> Vec<(f64, Vec<u8>)> {
+    // MIDI tempos are measured in microseconds per quarter note
+    let mut micros_per_quarter = 500_000.0;
+    let (mut seconds, mut last) = (0.0, 0);
+    let mut timeline = vec![];
+    for (tick, _, bytes) in midi_events(score) {
+        seconds += (tick - last) as f64 * micros_per_quarter / 1_000_000.0 / TICKS_PER_QUARTER as f64;
+        last = tick;
+        match bytes[..] {
+            [0xFF, 0x51, 0x03, a, b, c] => micros_per_quarter = ((a as u32) << 16 | (b as u32) << 8 | c as u32) as f64,
+            // meta events are only meaningful within a file
+            [0xFF, ..] => (),
+            _ => timeline.push((seconds, bytes)),
+        }
+    }
+    timeline
+}
+
+/// Returns the events of the MIDI file of a score in the order they are played, each as the tick it
+/// is played at, its order among the events of the same tick, and its bytes.
+fn midi_events(score: &Score) -> Vec<(u32, u8, Vec<u8>)> {
+    // events are stored as (tick, order, bytes) so events at the same tick are written in order:
+    // meta events first, then note offs, then note ons
+    let mut events: Vec<(u32, u8, Vec<u8>)> = vec![(0, 0, vec![0xC0, GUITAR_PROGRAM - 1])];
+
+    let mut staff_tick = 0;
+    let mut signature = None;
+    for staff in score.staffs.iter() {
+        let first_event = events.len();
+        let (beats_per_measure, dominant_beat) = staff.time_signature;
+        let ticks_per_beat = TICKS_PER_QUARTER * 4 / dominant_beat.max(1);
+        let ticks_per_slot = ticks_per_beat / staff.slots_per_beat.max(1);
+        let staff_end = staff.start_beat + staff.beats;
+
+        // the time signature is written whenever it changes
+        if signature != Some(staff.time_signature) {
+            signature = Some(staff.time_signature);
+            let power = 31 - dominant_beat.max(1).leading_zeros();
+            events.push((staff_tick, 0, vec![0xFF, 0x58, 0x04, beats_per_measure as u8, power as u8, 24, 8]));
+        }
+
+        if let Some(section) = &staff.section {
+            let mut marker = vec![0xFF, 0x06];
+            marker.extend(variable_length(section.len() as u32));
+            marker.extend(section.as_bytes());
+            events.push((staff_tick, 0, marker));
+        }
+
+        let capo = score.capo.unwrap_or(0);
+        let pitches: Vec<u8> = pitches(&staff.strings).into_iter().map(|pitch| (pitch as u32 + capo).min(127) as u8).collect();
+        let mut sounding: Vec<Option<Sounding>> = vec![None; pitches.len()];
+
+        let mut measure_tick = staff_tick;
+        for measure in play_order(staff) {
+            // measures end where the next measure starts, or with the staff
+            let measure_end = staff.measures.iter()
+                .map(|next| next.start_beat)
+                .find(|start| *start > measure.start_beat)
+                .unwrap_or(staff_end);
+            let tick = |beat: u32, slot: u32| measure_tick + (beat - measure.start_beat) * ticks_per_beat + slot * ticks_per_slot;
+
+            for event in measure.events.iter() {
+                match event {
+                    Event::Note(note) => {
+                        let at = tick(note.position.beat, note.position.slot);
+                        let velocity = staff.dynamics.iter()
+                            .rfind(|(position, _)| *position <= note.position)
+                            .map_or(Dynamic::MezzoForte.velocity(), |(_, dynamic)| dynamic.velocity());
+                        let velocity = if note.accent { velocity.saturating_add(ACCENT_VELOCITY).min(127) } else { velocity };
+                        // harmonics sound above the open string by the interval of their node instead of their fret
+                        let semitones = note.harmonic.map_or(note.fret, |harmonic| harmonic.semitones(note.fret));
+                        let pitch = (pitches[note.string] as u32 + semitones).min(127) as u8;
+                        let until = note.duration.map_or(u32::MAX, |duration| at + ticks(duration));
+
+                        // a tie merges the note into the note still sounding at the same pitch
+                        if let (Some('='), Some(tied)) = (note.articulation, &mut sounding[note.string]) {
+                            if tied.pitch == pitch {
+                                tied.until = until;
+                                continue
+                            }
+                        }
+
+                        match note.grace {
+                            Some(grace) => {
+                                let before = at.saturating_sub(GRACE_TICKS);
+                                let grace = (pitches[note.string] as u32 + grace).min(127) as u8;
+                                stop(&mut sounding[note.string], before, &mut events);
+                                events.push((before, 2, vec![0x90, grace, velocity]));
+                                events.push((at, 1, vec![0x80, grace, 0]));
+                            },
+                            None => stop(&mut sounding[note.string], at, &mut events),
+                        }
+                        events.push((at, 2, vec![0x90, pitch, velocity]));
+                        sounding[note.string] = Some(Sounding { pitch, start: at, until, staccato: note.staccato });
+                    },
+                    // drum hits have no pitch on the guitar program, so they are not played
+                    Event::Hit(_) => (),
+                    Event::Rest(rest) => {
+                        let at = tick(rest.position.beat, rest.position.slot);
+                        for string in sounding.iter_mut() {
+                            stop(string, at, &mut events);
+                        }
+                    },
+                }
+            }
+            // the click is played on the percussion channel at every slot of the measure, stopped after a
+            // 32nd note or the slot, whichever is shorter
+            if score.click {
+                let length = GRACE_TICKS.min(ticks_per_slot).max(1);
+                for beat in measure.start_beat..measure_end {
+                    for slot in 0..staff.slots_per_beat {
+                        let (key, velocity) = match (beat == measure.start_beat, slot) {
+                            (true, 0) => (CLICK_ACCENT, 110),
+                            (false, 0) => (CLICK, 90),
+                            _ => (CLICK, 50),
+                        };
+                        events.push((tick(beat, slot), 2, vec![0x99, key, velocity]));
+                        events.push((tick(beat, slot) + length, 1, vec![0x89, key, 0]));
+                    }
+                }
+            }
+            // tempo changes are placed at the tick their beat starts at; tempo events are always measured
+            // in quarter notes, so beats of other lengths are scaled
+            let beats = measure.start_beat..measure_end;
+            for change in score.tempo_changes.iter().filter(|change| beats.contains(&change.beat)) {
+                let micros = change.micros_per_beat() * dominant_beat.max(1) / 4;
+                events.push((tick(change.beat, 0), 0, vec![0xFF, 0x51, 0x03, (micros >> 16) as u8, (micros >> 8) as u8, micros as u8]));
+            }
+            measure_tick += (measure_end - measure.start_beat) * ticks_per_beat;
+        }
+        for string in sounding.iter_mut() {
+            stop(string, measure_tick, &mut events);
+        }
+        if let Some(ratio) = staff.swing {
+            for (tick, _, _) in events[first_event..].iter_mut() {
+                *tick = swing(*tick, staff_tick, ratio);
+            }
+        }
+        staff_tick = measure_tick;
+    }
+    events.sort_by_key(|(tick, order, _)| (*tick, *order));
+    events
+}
+
+/// Converts a score into alphaTex, the text format read by alphaTab and other Guitar Pro compatible
+/// renderers. The title, artist, album, tempo, capo, and the tuning of the first staff are written as
+/// metadata, and every measure is written as a bar with its time signature, section label, repeats, and
+/// tempo changes. Every beat lasts until the next beat of its measure; a length that no single duration
+/// can fill is played for the longest duration that fits, followed by rests. Vibrato, hammer-ons,
+/// pull-offs, slides, harmonics, palm mutes, let ring, taps, slaps, pops, ties, accents, staccato notes,
+/// and fingerings are written as effects; bends, releases, grace notes, and drum hits are left out.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use data::{Token, TokenType, Literal};
+/// use parser::Parser;
+/// 
+/// let tokens = vec![
+///     Token::new(TokenType::Note, String::from("E"), Literal::None, 1),
+///     Token::new(TokenType::Number, String::from("3"), Literal::Number(3), 2),
+///     Token::new(TokenType::EndOfFile, String::new(), Literal::None, 2),
+/// ];
+/// 
+/// let score = Parser::new(&tokens).parse().unwrap();
+/// 
+/// assert_eq!(".\n\\tuning E2\n\\ts 4 4 (3.1).4\n", parser::export::alphatex(&score));
+/// ```
+pub fn alphatex(score: &Score) -> String {
+    let mut tex = String::new();
+    for (name, value) in [("title", &score.metadata.title), ("artist", &score.metadata.artist), ("album", &score.metadata.album)] {
+        if let Some(value) = value {
+            tex.push_str(&format!("\\{} \"{}\"\n", name, value.replace('"', "\\\"")));
+        }
+    }
+    if let Some(tempo) = score.tempo {
+        tex.push_str(&format!("\\tempo {}\n", tempo));
+    }
+    tex.push_str(".\n");
+    // alphaTex lists the tuning from the highest string
+    if let Some(staff) = score.staffs.first() {
+        let tuning: Vec<String> = pitches(&staff.strings).into_iter().rev()
+            .map(|pitch| format!("{}{}", Key::SHARPS[pitch as usize % 12], (pitch / 12) as i32 - 1))
+            .collect();
+        tex.push_str(&format!("\\tuning {}\n", tuning.join(" ")));
+    }
+    if let Some(capo) = score.capo {
+        tex.push_str(&format!("\\capo {}\n", capo));
+    }
+
+    let mut bars = vec![];
+    let mut signature = None;
+    for staff in score.staffs.iter() {
+        let (_, dominant_beat) = staff.time_signature;
+        let origins = legato_origins(staff);
+
+        for (i, measure) in staff.measures.iter().enumerate() {
+            let mut bar = vec![];
+            if signature != Some(staff.time_signature) {
+                signature = Some(staff.time_signature);
+                bar.push(format!("\\ts {} {}", staff.time_signature.0, dominant_beat));
+            }
+            if let (0, Some(section)) = (i, &staff.section) {
+                bar.push(format!("\\section \"{}\"", section.replace('"', "\\\"")));
+            }
+            if measure.repeat_start {
+                bar.push(String::from("\\ro"));
+            }
+            if let Some(times) = measure.repeat_end {
+                bar.push(format!("\\rc {}", times));
+            }
+            let measure_end = measure_end(staff, i);
+            // the starting tempo is already written as metadata
+            for change in score.tempo_changes.iter().filter(|change| (measure.start_beat..measure_end).contains(&change.beat)) {
+                if change.beat > 0 || score.tempo.is_none() {
+                    bar.push(format!("\\tempo {}", change.bpm));
+                }
+            }
+
+            for Beat { notes, durations } in beats(staff, measure, measure_end) {
+                let mut durations = durations.into_iter();
+                if let Some(duration) = durations.next() {
+                    bar.push(beat(&notes, staff.strings.len(), duration, &origins));
+                }
+                bar.extend(durations.map(|duration| beat(&[], 0, duration, &origins)));
+            }
+            bars.push(bar.join(" "));
+        }
+    }
+    if !bars.is_empty() {
+        tex.push_str(&bars.join(" |\n"));
+        tex.push('\n');
+    }
+    tex
+}
+
+/// Returns the beat the measure at the provided index of the staff ends at: where the next measure starts,
+/// or the end of the staff, and at least after the beat of its last event.
+fn measure_end(staff: &Staff, index: usize) -> u32 {
+    let measure = &staff.measures[index];
+    staff.measures.get(index + 1).map_or(staff.start_beat + staff.beats, |next| next.start_beat)
+        .max(measure.events.iter().map(|event| position(event).beat + 1).max().unwrap_or(0))
+}
+
+/// The notes played together at the start of a beat of a written measure, or none for a rest, along with
+/// the durations that fill the beat until the next one. The notes are played for the first duration and
+/// the rest of the durations are rests.
+struct Beat<'a> {
+    notes: Vec<&'a Note>,
+    durations: Vec<(u32, bool)>,
+}
+
+/// Returns every beat of the measure that ends at the provided beat in order. A beat is started by every
+/// note and rest, and by the start of the measure; drum hits are left out.
+fn beats<'a>(staff: &Staff, measure: &'a Measure, measure_end: u32) -> Vec<Beat<'a>> {
+    let (_, dominant_beat) = staff.time_signature;
+    let ticks_per_slot = TICKS_PER_WHOLE / (dominant_beat * staff.slots_per_beat).max(1);
+    let slot = |position: Position| (position.beat - measure.start_beat) * staff.slots_per_beat + position.slot;
+
+    let mut beats: Vec<(u32, Vec<&Note>)> = vec![];
+    for event in measure.events.iter() {
+        let (at, note) = match event {
+            Event::Note(note) => (slot(note.position), Some(note)),
+            Event::Rest(rest) => (slot(rest.position), None),
+            Event::Hit(_) => continue,
+        };
+        match beats.last_mut() {
+            Some((last, notes)) if *last == at => notes.extend(note),
+            _ => beats.push((at, note.into_iter().collect())),
+        }
+    }
+    if beats.first().is_none_or(|(at, _)| *at > 0) {
+        beats.insert(0, (0, vec![]));
+    }
+    let measure_slots = (measure_end - measure.start_beat) * staff.slots_per_beat;
+    let ends: Vec<u32> = beats.iter().skip(1).map(|(at, _)| *at).chain(std::iter::once(measure_slots)).collect();
+    beats.into_iter().zip(ends)
+        .map(|((at, notes), until)| Beat { notes, durations: durations((until - at) * ticks_per_slot) })
+        .collect()
+}
+
+/// Returns the position of an event.
+fn position(event: &Event) -> Position {
+    match event {
+        Event::Note(note) => note.position,
+        Event::Rest(rest) => rest.position,
+        Event::Hit(hit) => hit.position,
+    }
+}
+
+/// Returns the string and position of every note of the staff that a hammer-on, pull-off, or slide
+/// starts from, along with the alphaTex effect it starts: `h` or `sl`. Articulations are written on the
+/// note they end at, while alphaTex writes them on the note they start from.
+fn legato_origins(staff: &Staff) -> HashMap<(usize, Position), &'static str> {
+    let mut origins = HashMap::new();
+    let mut last: HashMap<usize, Position> = HashMap::new();
+    for event in staff.measures.iter().flat_map(|measure| measure.events.iter()) {
+        if let Event::Note(note) = event {
+            let effect = match note.articulation {
+                Some('h' | 'p') => Some("h"),
+                Some('/' | '\\') => Some("sl"),
+                _ => None,
+            };
+            if let (Some(effect), Some(from)) = (effect, last.get(&note.string)) {
+                origins.insert((note.string, *from), effect);
+            }
+            last.insert(note.string, note.position);
+        }
+    }
+    origins
+}
+
+/// Returns a single alphaTex beat: the notes played together with their effects, or a rest if there are
+/// none, followed by its duration and beat effects. Strings are counted from the highest of the provided
+/// amount of strings.
+fn beat(notes: &[&Note], strings: usize, (length, dotted): (u32, bool), origins: &HashMap<(usize, Position), &str>) -> String {
+    let mut beat_effects = vec![];
+    if dotted {
+        beat_effects.push("d");
+    }
+    if notes.is_empty() {
+        return format!("r.{}{}", length, effect_list(&beat_effects))
+    }
+
+    let mut played = vec![];
+    for note in notes.iter() {
+        let mut effects = vec![];
+        if note.vibrato {
+            effects.push("v");
+        }
+        if note.accent {
+            effects.push("ac");
+        }
+        if note.staccato {
+            effects.push("st");
+        }
+        if let Some(effect) = origins.get(&(note.string, note.position)) {
+            effects.push(*effect);
+        }
+        match note.harmonic {
+            Some(Harmonic::Natural) => effects.push("nh"),
+            Some(Harmonic::Pinch) => effects.push("ph"),
+            None => (),
+        }
+        // alphaTex counts the fingers of the fretting hand from the thumb
+        match note.finger {
+            Some(Finger::Thumb) => effects.push("lf 1"),
+            Some(Finger::Index) => effects.push("lf 2"),
+            Some(Finger::Middle) => effects.push("lf 3"),
+            Some(Finger::Ring) => effects.push("lf 4"),
+            Some(Finger::Pinky) => effects.push("lf 5"),
+            None => (),
+        }
+        for technique in note.techniques.iter() {
+            effects.push(match technique {
+                Technique::PalmMute => "pm",
+                Technique::LetRing => "lr",
+            });
+        }
+        // taps, slaps, and pops are effects of the whole beat
+        match note.articulation {
+            Some('t') => beat_effects.push("t"),
+            Some('s') => beat_effects.push("s"),
+            Some('P') => beat_effects.push("p"),
+            _ => (),
+        }
+        // a tie is written in place of the fret
+        let fret = if note.articulation == Some('=') { String::from("-") } else { note.fret.to_string() };
+        played.push(format!("{}.{}{}", fret, strings - note.string, effect_list(&effects)));
+    }
+    beat_effects.dedup();
+    format!("({}).{}{}", played.join(" "), length, effect_list(&beat_effects))
+}
+
+/// Returns alphaTex effects wrapped in braces, or nothing if there are none.
+fn effect_list(effects: &[&str]) -> String {
+    if effects.is_empty() { String::new() } else { format!("{{{}}}", effects.join(" ")) }
+}
+
+/// Splits an amount of ticks into the alphaTex durations that fill it, longest first, where each duration
+/// is a note length (`4` for a quarter note) and whether it is dotted. Ticks shorter than a 64th note are
+/// left out.
+fn durations(mut ticks: u32) -> Vec<(u32, bool)> {
+    let mut durations = vec![];
+    for length in [1, 2, 4, 8, 16, 32, 64] {
+        let plain = TICKS_PER_WHOLE / length;
+        for (dotted, duration) in [(true, plain * 3 / 2), (false, plain)] {
+            while ticks >= duration {
+                durations.push((length, dotted));
+                ticks -= duration;
+            }
+        }
+    }
+    durations
+}
+
+/// Converts a score into a LilyPond file that engraves it as a standard staff above a tab staff, so it can
+/// be typeset into sheet music with the LilyPond toolchain. The title, artist, and album are written to
+/// the header and the tuning of the first staff, raised by the capo, to the tab staff. Every measure is
+/// written on its own line with its time signature, section label, repeats, and tempo changes, and every
+/// note is written at the pitch it sounds along with its string. Beats last the same as in alphaTex.
+/// Hammer-ons and pull-offs are written as slurs, slides as glissandos, ties as ties, grace notes as grace
+/// notes, and taps, slaps, and pops as `T`, `S`, and `P` above the staff; natural harmonics are written
+/// as harmonics on their fret, accents and staccato notes as articulations, and fingerings as fingering
+/// digits, with the thumb as `\thumb`. Vibrato, bends, releases, pinch harmonics, techniques, and drum
+/// hits are left out.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use data::{Token, TokenType, Literal};
+/// use parser::Parser;
+/// 
+/// let tokens = vec![
+///     Token::new(TokenType::Note, String::from("E"), Literal::None, 1),
+///     Token::new(TokenType::Number, String::from("3"), Literal::Number(3), 2),
+///     Token::new(TokenType::EndOfFile, String::new(), Literal::None, 2),
+/// ];
+/// 
+/// let score = Parser::new(&tokens).parse().unwrap();
+/// let lilypond = parser::export::to_lilypond(&score);
+/// 
+/// assert!(lilypond.contains("  \\time 4/4\n  g,4\\1 |\n"));
+/// assert!(lilypond.contains("\\stringTuning <e,>"));
+/// ```
+pub fn to_lilypond(score: &Score) -> String {
+    let mut ly = String::from("\\version \"2.24.0\"\n\n");
+    let header: Vec<String> = [("title", &score.metadata.title), ("composer", &score.metadata.artist), ("subtitle", &score.metadata.album)].iter()
+        .filter_map(|(name, value)| value.as_ref().map(|value| format!("  {} = {}\n", name, lilypond_string(value))))
+        .collect();
+    if !header.is_empty() {
+        ly.push_str(&format!("\\header {{\n{}}}\n\n", header.concat()));
+    }
+
+    let capo = score.capo.unwrap_or(0);
+    let mut lines = vec![];
+    let mut signature = None;
+    let mut slur = false;
+    let mut repeating = false;
+    for staff in score.staffs.iter() {
+        let (beats_per_measure, dominant_beat) = staff.time_signature;
+        let pitches: Vec<u32> = pitches(&staff.strings).into_iter().map(|pitch| pitch as u32 + capo).collect();
+        let origins = legato_origins(staff);
+        // a tie is written on the note it sustains, the note before it on the same string
+        let mut tied = HashSet::new();
+        let mut last: HashMap<usize, Position> = HashMap::new();
+        for event in staff.measures.iter().flat_map(|measure| measure.events.iter()) {
+            if let Event::Note(note) = event {
+                if let (Some('='), Some(from)) = (note.articulation, last.get(&note.string)) {
+                    tied.insert((note.string, *from));
+                }
+                last.insert(note.string, note.position);
+            }
+        }
+
+        // a repeat end repeats the measures from the last repeat start, or from the start of the staff or
+        // the last repeat end
+        let mut repeats = HashMap::new();
+        let mut from = 0;
+        for (i, measure) in staff.measures.iter().enumerate() {
+            if measure.repeat_start { from = i; }
+            if let Some(times) = measure.repeat_end {
+                repeats.insert(from, times);
+                from = i + 1;
+            }
+        }
+
+        for (i, measure) in staff.measures.iter().enumerate() {
+            // the measures of a repeated section are indented within it
+            let indent = if repeating { "  " } else { "" };
+            if signature != Some(staff.time_signature) {
+                signature = Some(staff.time_signature);
+                lines.push(format!("{}\\time {}/{}", indent, beats_per_measure, dominant_beat));
+            }
+            if let (0, Some(section)) = (i, &staff.section) {
+                lines.push(format!("{}\\sectionLabel {}", indent, lilypond_string(section)));
+            }
+            if let Some(times) = repeats.get(&i) {
+                lines.push(format!("\\repeat volta {} {{", times));
+                repeating = true;
+            }
+            let mut bar = vec![];
+            let measure_end = measure_end(staff, i);
+            // the starting tempo is the tempo change at the first beat
+            for change in score.tempo_changes.iter().filter(|change| (measure.start_beat..measure_end).contains(&change.beat)) {
+                bar.push(format!("\\tempo {} = {}", dominant_beat, change.bpm));
+            }
+
+            for Beat { notes, durations } in beats(staff, measure, measure_end) {
+                for (j, (length, dotted)) in durations.into_iter().enumerate() {
+                    let duration = format!("{}{}", length, if dotted { "." } else { "" });
+                    if j > 0 || notes.is_empty() {
+                        bar.push(format!("r{}", duration));
+                        continue
+                    }
+                    let graces: Vec<(String, usize)> = notes.iter()
+                        .filter_map(|note| note.grace.map(|grace| (lilypond_pitch(pitches[note.string] + grace), staff.strings.len() - note.string)))
+                        .collect();
+                    match graces.as_slice() {
+                        [] => (),
+                        [(pitch, string)] => bar.push(format!("\\grace {{ {}16\\{} }}", pitch, string)),
+                        graces => {
+                            let graces: Vec<String> = graces.iter().map(|(pitch, string)| format!("{}\\{}", pitch, string)).collect();
+                            bar.push(format!("\\grace {{ <{}>16 }}", graces.join(" ")));
+                        },
+                    }
+
+                    // every note is written as its pitch followed by its string and note effects
+                    let mut played = vec![];
+                    let mut effects = String::new();
+                    for note in notes.iter() {
+                        let mut marks = format!("\\{}", staff.strings.len() - note.string);
+                        if note.harmonic == Some(Harmonic::Natural) {
+                            marks.push_str("\\harmonic");
+                        }
+                        if note.accent {
+                            marks.push_str("->");
+                        }
+                        if note.staccato {
+                            marks.push_str("-.");
+                        }
+                        match note.finger {
+                            Some(Finger::Thumb) => marks.push_str("\\thumb"),
+                            Some(finger) => marks.push_str(&format!("-{}", finger)),
+                            None => (),
+                        }
+                        if tied.contains(&(note.string, note.position)) {
+                            marks.push('~');
+                        }
+                        played.push((lilypond_pitch(pitches[note.string] + note.fret), marks));
+                        match note.articulation {
+                            Some('t') => effects.push_str("^\"T\""),
+                            Some('s') => effects.push_str("^\"S\""),
+                            Some('P') => effects.push_str("^\"P\""),
+                            _ => (),
+                        }
+                    }
+                    // a slur ends on the note a hammer-on or pull-off leads into and starts on the note it
+                    // leads from
+                    if slur && notes.iter().any(|note| matches!(note.articulation, Some('h' | 'p'))) {
+                        effects.push(')');
+                        slur = false;
+                    }
+                    let starts: Vec<&str> = notes.iter().filter_map(|note| origins.get(&(note.string, note.position)).copied()).collect();
+                    if !slur && starts.contains(&"h") {
+                        effects.push('(');
+                        slur = true;
+                    }
+                    if starts.contains(&"sl") {
+                        effects.push_str("\\glissando");
+                    }
+                    match played.as_slice() {
+                        [(pitch, marks)] => bar.push(format!("{}{}{}{}", pitch, duration, marks, effects)),
+                        played => {
+                            let played: Vec<String> = played.iter().map(|(pitch, marks)| format!("{}{}", pitch, marks)).collect();
+                            bar.push(format!("<{}>{}{}", played.join(" "), duration, effects));
+                        },
+                    }
+                }
+            }
+            bar.push(String::from("|"));
+            lines.push(format!("{}{}", if repeating { "  " } else { "" }, bar.join(" ")));
+            if measure.repeat_end.is_some() {
+                lines.push(String::from("}"));
+                repeating = false;
+            }
+        }
+    }
+
+    ly.push_str("music = {\n");
+    for line in lines {
+        ly.push_str(&format!("  {}\n", line));
+    }
+    ly.push_str("}\n\n");
+
+    // the tab staff lists the tuning from the lowest string
+    let tuning: Vec<String> = score.staffs.first()
+        .map(|staff| pitches(&staff.strings).into_iter().map(|pitch| lilypond_pitch(pitch as u32 + capo)).collect())
+        .unwrap_or_default();
+    ly.push_str(&format!(concat!(
+        "\\score {{\n",
+        "  \\new StaffGroup <<\n",
+        "    \\new Staff \\with {{ \\omit StringNumber }} {{ \\clef \"treble_8\" \\music }}\n",
+        "    \\new TabStaff \\with {{ stringTunings = \\stringTuning <{}> }} {{ \\music }}\n",
+        "  >>\n",
+        "  \\layout {{ }}\n",
+        "}}\n",
+    ), tuning.join(" ")));
+    ly
+}
+
+/// Returns the LilyPond name of a MIDI pitch, such as `cis'` for C#4. LilyPond names the octave starting
+/// at C3 without any marks, and every octave above or below it with a `'` or a `,`.
+fn lilypond_pitch(pitch: u32) -> String {
+    let name = Key::SHARPS[pitch as usize % 12].to_lowercase().replace('#', "is");
+    let octave = (pitch / 12) as i32 - 4;
+    let marks = if octave < 0 { ",".repeat(-octave as usize) } else { "'".repeat(octave as usize) };
+    format!("{}{}", name, marks)
+}
+
+/// Returns text as a LilyPond string.
+fn lilypond_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Converts a score into a scalable SVG image with a staff system for every staff, drawn below each other
+/// under the title, artist, and album. Every system has a horizontal line for every string, labelled with
+/// its note name, and a bar-line before every measure, with repeats drawn as thick bar-lines with dots
+/// and their count above. The time signature is drawn at the start of every system it changes at, and
+/// section labels and chord names above the system and lyrics below it. Frets are drawn on their string
+/// at the beat slot they are played at, with natural harmonics in angle brackets, ties in parentheses,
+/// and any other articulation above the fret; drum hits are drawn as their symbol and rests are left
+/// blank.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use data::{Token, TokenType, Literal};
+/// use parser::Parser;
+/// 
+/// let tokens = vec![
+///     Token::new(TokenType::Note, String::from("E"), Literal::None, 1),
+///     Token::new(TokenType::Number, String::from("3"), Literal::Number(3), 2),
+///     Token::new(TokenType::EndOfFile, String::new(), Literal::None, 2),
+/// ];
+/// 
+/// let score = Parser::new(&tokens).parse().unwrap();
+/// let svg = parser::export::svg(&score);
+/// 
+/// assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+/// assert!(svg.contains(">3</text>"));
+/// ```
+pub fn svg(score: &Score) -> String {
+    let mut body = vec![];
+    let mut y = SVG_MARGIN;
+    let mut width = 0;
+    for (text, size) in [(&score.metadata.title, 20), (&score.metadata.artist, 14), (&score.metadata.album, 14)] {
+        if let Some(text) = text {
+            y += size;
+            body.push(format!("<text x=\"{}\" y=\"{}\" font-size=\"{}\">{}</text>", SVG_MARGIN, y, size, escape(text)));
+            y += 6;
+        }
+    }
+
+    let mut signature = None;
+    for staff in score.staffs.iter() {
+        let strings = staff.strings.len().max(1) as u32;
+        y += SVG_SYSTEM_GAP - STRING_GAP;
+        if let Some(section) = &staff.section {
+            body.push(format!("<text x=\"{}\" y=\"{}\" font-size=\"13\" font-weight=\"bold\">[{}]</text>", SVG_MARGIN, y, escape(section)));
+            y += STRING_GAP + 4;
+        }
+        if !staff.chord_names.is_empty() {
+            y += STRING_GAP + 4;
+        }
+        let top = y;
+        let bottom = top + (strings - 1) * STRING_GAP;
+        // strings are drawn from the highest at the top, so string 0 is the bottom line
+        let string_y = |string: usize| bottom - string as u32 * STRING_GAP;
+
+        // note names are drawn before the system, followed by the time signature if it changed
+        for (string, name) in staff.strings.iter().enumerate() {
+            body.push(format!("<text x=\"{}\" y=\"{}\" font-size=\"10\" dominant-baseline=\"central\">{}</text>", SVG_MARGIN, string_y(string), escape(name)));
+        }
+        let mut x = SVG_MARGIN + 20;
+        let start = x;
+        if signature != Some(staff.time_signature) {
+            signature = Some(staff.time_signature);
+            let middle = (top + bottom) / 2;
+            for (number, at) in [(staff.time_signature.0, middle - 8), (staff.time_signature.1, middle + 8)] {
+                body.push(format!("<text x=\"{}\" y=\"{}\" font-size=\"16\" font-weight=\"bold\" text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>", x + 12, at, number));
+            }
+            x += 24;
+        }
+
+        // the x of every measure, used to place notes, chord names, and lyrics by their position
+        let mut measure_x = vec![];
+        for (i, measure) in staff.measures.iter().enumerate() {
+            let slots = (measure_end(staff, i) - measure.start_beat) * staff.slots_per_beat;
+            measure_x.push((measure.start_beat, x));
+            // a repeat end already closes the measure before
+            let closed = i > 0 && staff.measures[i - 1].repeat_end.is_some();
+            if measure.repeat_start {
+                body.push(svg_repeat_bar(x, top, bottom, true));
+            } else if !closed {
+                body.push(svg_bar(x, top, bottom));
+            }
+            x += BAR_PADDING + slots * SLOT_WIDTH;
+            if let Some(times) = measure.repeat_end {
+                body.push(svg_repeat_bar(x, top, bottom, false));
+                if times != 2 {
+                    body.push(format!("<text x=\"{}\" y=\"{}\" font-size=\"10\" text-anchor=\"end\">x{}</text>", x, top - 6, times));
+                }
+            }
+        }
+        if staff.measures.last().is_none_or(|measure| measure.repeat_end.is_none()) {
+            body.push(svg_bar(x, top, bottom));
+        }
+        for string in 0..staff.strings.len() {
+            body.push(format!("<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"#888\"/>", start, string_y(string), x, string_y(string)));
+        }
+        width = width.max(x);
+
+        let x_at = |position: Position| {
+            let (start_beat, x) = measure_x.iter().rev().find(|(start_beat, _)| *start_beat <= position.beat).copied().unwrap_or((0, start));
+            x + BAR_PADDING + ((position.beat - start_beat) * staff.slots_per_beat + position.slot) * SLOT_WIDTH + SLOT_WIDTH / 2
+        };
+        for event in staff.measures.iter().flat_map(|measure| measure.events.iter()) {
+            let (string, position, label, above) = match event {
+                Event::Note(note) => {
+                    let label = match (note.harmonic, note.articulation) {
+                        (Some(Harmonic::Natural), _) => format!("<{}>", note.fret),
+                        (_, Some('=')) => format!("({})", note.fret),
+                        _ => note.fret.to_string(),
+                    };
+                    let above = note.articulation.filter(|articulation| !matches!(articulation, '=')).map(String::from);
+                    (note.string, note.position, label, above)
+                },
+                Event::Hit(hit) => (hit.lane, hit.position, hit.symbol.to_string(), None),
+                Event::Rest(_) => continue,
+            };
+            let (x, y) = (x_at(position), string_y(string));
+            // frets are drawn on a blank background so they cut through their string
+            let background = label.chars().count() as u32 * 6 + 2;
+            body.push(format!("<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"white\"/>", x - background / 2, y - 5, background, 10));
+            body.push(format!("<text x=\"{}\" y=\"{}\" font-size=\"11\" text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>", x, y, escape(&label)));
+            if let Some(above) = above {
+                body.push(format!("<text x=\"{}\" y=\"{}\" font-size=\"8\" text-anchor=\"middle\">{}</text>", x - SLOT_WIDTH / 2, y - 6, escape(&above)));
+            }
+        }
+        for (position, name) in staff.chord_names.iter() {
+            body.push(format!("<text x=\"{}\" y=\"{}\" font-size=\"11\" font-weight=\"bold\" text-anchor=\"middle\">{}</text>", x_at(*position), top - 12, escape(name)));
+        }
+        y = bottom;
+        if !staff.lyrics.is_empty() {
+            y += STRING_GAP + 8;
+            for (position, lyric) in staff.lyrics.iter() {
+                body.push(format!("<text x=\"{}\" y=\"{}\" font-size=\"11\" font-style=\"italic\" text-anchor=\"middle\">{}</text>", x_at(*position), y, escape(lyric)));
+            }
+        }
+    }
+
+    let (width, height) = (width + SVG_MARGIN, y + SVG_MARGIN);
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\" font-family=\"sans-serif\">\n<rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n{}\n</svg>\n",
+        width, height, width, height, body.join("\n")
+    )
+}
+
+/// Returns an SVG bar-line at the provided x, running from the top string to the bottom string.
+fn svg_bar(x: u32, top: u32, bottom: u32) -> String {
+    format!("<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"black\"/>", x, top, x, bottom)
+}
+
+/// Returns an SVG repeat bar-line at the provided x: a thick bar-line with two dots after it for a
+/// repeat start, or before it for a repeat end.
+fn svg_repeat_bar(x: u32, top: u32, bottom: u32, start: bool) -> String {
+    let dots = if start { x + 5 } else { x - 5 };
+    let middle = (top + bottom) / 2;
+    format!(
+        "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"black\" stroke-width=\"3\"/>\n<circle cx=\"{}\" cy=\"{}\" r=\"1.5\"/>\n<circle cx=\"{}\" cy=\"{}\" r=\"1.5\"/>",
+        x, top, x, bottom, dots, middle - 4, dots, middle + 4
+    )
+}
+
+/// Returns the measures of the staff in the order they are played. A repeat end plays the measures from
+/// the last repeat start, or from the start of the staff or the last repeat end, again until they have
+/// been played as many times as it sets.
+fn play_order(staff: &Staff) -> Vec<&Measure> {
+    let mut order = vec![];
+    let mut from = 0;
+    for (i, measure) in staff.measures.iter().enumerate() {
+        if measure.repeat_start { from = i; }
+        order.push(measure);
+        if let Some(times) = measure.repeat_end {
+            for _ in 1..times {
+                order.extend(staff.measures[from..=i].iter());
+            }
+            from = i + 1;
+        }
+    }
+    order
+}
+
+/// A note sounding on a string of a MIDI file.
+#[derive(Clone, Copy)]
+struct Sounding {
+    pitch: u8,
+    /// The tick the note started at.
+    start: u32,
+    /// The tick the duration of the note ends at.
+    until: u32,
+    staccato: bool,
+}
+
+/// Adds a note off event for the note sounding on a string, if there is one, at the provided tick or
+/// the end of its duration, whichever comes first. Staccato notes are stopped halfway there instead.
+fn stop(sounding: &mut Option<Sounding>, at: u32, events: &mut Vec<(u32, u8, Vec<u8>)>) {
+    if let Some(Sounding { pitch, start, until, staccato }) = sounding.take() {
+        let end = at.min(until);
+        let end = if staccato { start + end.saturating_sub(start) / 2 } else { end };
+        events.push((end, 1, vec![0x80, pitch, 0]));
+    }
+}
+
+/// Returns the tick played at once swung by the ratio of the first eighth note of every quarter note to
+/// the second, with quarter notes counted from the tick the staff starts at. The second eighth note is
+/// delayed to the end of the first, and ticks within either half of the quarter note are stretched or
+/// squeezed to match. Ticks before the start of the staff, such as a grace note leading into its first
+/// beat, are not swung.
+fn swing(tick: u32, start: u32, (long, short): (u32, u32)) -> u32 {
+    if tick < start {
+        return tick
+    }
+    let quarter = tick - (tick - start) % TICKS_PER_QUARTER;
+    let offset = (tick - start) % TICKS_PER_QUARTER;
+    let half = TICKS_PER_QUARTER / 2;
+    let split = TICKS_PER_QUARTER * long / (long + short).max(1);
+    let swung = if offset < half {
+        offset * split / half
+    } else {
+        split + (offset - half) * (TICKS_PER_QUARTER - split) / half
+    };
+    quarter + swung
+}
+
+/// Returns the amount of ticks a note of the provided duration lasts; dotted notes last half as long
+/// again.
+fn ticks(duration: Duration) -> u32 {
+    let ticks = TICKS_PER_QUARTER * 4 / duration.length.max(1);
+    if duration.dotted { ticks * 3 / 2 } else { ticks }
+}
+
+/// Returns the MIDI pitch of every open string from the note names of the strings, from the lowest string.
+/// The lowest string is placed in the second octave and every other string at the first pitch above the
+/// string before it.
+pub(crate) fn pitches(strings: &[String]) -> Vec<u8> {
+    let mut pitches: Vec<u8> = vec![];
+    for string in strings.iter() {
+        let class = pitch_class(string).unwrap_or(0);
+        let pitch = match pitches.last() {
+            // the first pitch of the pitch class that is higher than the string before
+            Some(&previous) => previous + 1 + (class + 12 - (previous + 1) % 12) % 12,
+            // the second octave starts at C2 (36)
+            None => 36 + class,
+        };
+        pitches.push(pitch.min(127));
+    }
+    pitches
+}
+
+/// Encodes a value as a MIDI variable length quantity: 7 bits per byte, with the high bit set on every
+/// byte but the last.
+fn variable_length(value: u32) -> Vec<u8> {
+    let mut bytes = vec![(value & 0x7F) as u8];
+    let mut value = value >> 7;
+    while value > 0 {
+        bytes.push((value & 0x7F) as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.reverse();
+    bytes
+}
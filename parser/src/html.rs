@@ -0,0 +1,125 @@
+use crate::{Cell, Layout, Staff};
+
+/// The stylesheet of rendered HTML documents. Every element of a staff has its own class so pages
+/// embedding the tabs can theme them with their own colors.
+const STYLE: &str = "\
+pre { font-family: ui-monospace, Menlo, Consolas, monospace; line-height: 1.2; }
+.note { color: #555; font-weight: bold; }
+.bar { color: #888; }
+.fret { color: #000; font-weight: bold; }
+.fret:hover, .rest:hover, .muted:hover { background: #ffe98a; }
+.rest { color: #a33; }
+.muted { color: #888; }
+.beats, .measure-numbers, .heatmap { color: #999; }
+.markings, .techniques, .articulations, .fingering { color: #36c; }
+.chord-names, .hand-positions { color: #373; font-weight: bold; }
+.lyrics { color: #735; font-style: italic; }
+.header, .section { font-weight: bold; }
+.comment { color: #777; }
+";
+
+/// Escapes the chars of text that have a meaning in HTML.
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Wraps text in a span with the provided class, or returns nothing if the text is empty.
+pub(crate) fn span(class: &str, text: &str) -> String {
+    if text.is_empty() { String::new() } else { format!("<span class=\"{}\">{}</span>", class, escape(text)) }
+}
+
+/// Wraps lines of text in a `<pre>` block with the provided class. The text is escaped unless it is
+/// already markup.
+pub(crate) fn block(class: &str, text: &str, markup: bool) -> String {
+    let text = if markup { text.to_string() } else { escape(text) };
+    format!("<pre class=\"{}\">{}</pre>\n", class, text.trim_end_matches('\n'))
+}
+
+/// Wraps blocks in a standalone HTML document with the default stylesheet, titled by the title of the
+/// song if it has one.
+pub(crate) fn document(title: Option<&str>, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape(title.unwrap_or("Guitar tabs")),
+        STYLE,
+        body
+    )
+}
+
+/// Marks up a lane of the staff printed from the `start` beat column up to the `end` beat column as
+/// `text`: bar-lines as `bar`,
+/// tabs as `fret`, and rests as `rest`, where tabs and rests show the measure and beat they are played
+/// at when hovered. Right-to-left lanes are mirrored and padded at the start to the provided width.
+pub(crate) fn lane(staff: &Staff, cells: &[Cell], text: &str, (start, end): (usize, usize), layout: &Layout, first_measure: usize, mirror: Option<usize>) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let measure_length = staff.time.total_beats_per_measure().max(1) as usize;
+    let slots_per_beat = staff.time.slots_per_beat().max(1) as usize;
+
+    // the lane is split into segments of chars along with their class and hover text
+    let mut segments: Vec<(&str, String, Option<String>)> = vec![];
+    let gap = |segments: &mut Vec<(&str, String, Option<String>)>, gap: &[char]| {
+        // padding dashes are printed plainly and any other char between cells is part of a bar-line
+        let mut from = 0;
+        for i in 1..=gap.len() {
+            if i == gap.len() || (gap[i] == '-') != (gap[from] == '-') {
+                let class = if gap[from] == '-' { "" } else { "bar" };
+                segments.push((class, gap[from..i].iter().collect(), None));
+                from = i;
+            }
+        }
+    };
+    let mut at = 0;
+    for (column, offset, width) in columns(staff, start, end.min(cells.len()), layout) {
+        let (offset, end) = (offset.min(chars.len()), (offset + width).min(chars.len()));
+        gap(&mut segments, &chars[at.min(offset)..offset]);
+        let class = match cells[column] {
+            Cell::Tab { .. } => "fret",
+            Cell::Rest => "rest",
+            Cell::Muted => "muted",
+            Cell::Empty => "",
+        };
+        let beat = column % measure_length / slots_per_beat + 1;
+        let title = match staff.time.get_beat_at((column % measure_length) as u32).as_str() {
+            _ if column % slots_per_beat == 0 => format!("Measure {}, beat {}", first_measure + column / measure_length, beat),
+            "." => format!("Measure {}, beat {}, slot {}", first_measure + column / measure_length, beat, column % slots_per_beat + 1),
+            count => format!("Measure {}, beat {} {}", first_measure + column / measure_length, beat, count),
+        };
+        segments.push((class, chars[offset..end].iter().collect(), Some(title).filter(|_| !class.is_empty())));
+        at = end;
+    }
+    gap(&mut segments, &chars[at.min(chars.len())..]);
+
+    if let Some(width) = mirror {
+        segments.reverse();
+        for (_, text, _) in segments.iter_mut() {
+            *text = text.chars().rev().collect();
+        }
+        segments.insert(0, ("", " ".repeat(width.saturating_sub(chars.len())), None));
+    }
+    segments.into_iter()
+        .map(|(class, text, title)| match (class, title) {
+            ("", _) => escape(&text),
+            (class, Some(title)) => format!("<span class=\"{}\" title=\"{}\">{}</span>", class, escape(&title), escape(&text)),
+            (class, None) => span(class, &text),
+        })
+        .collect()
+}
+
+/// Returns every beat column of the staff from the `start` beat column up to the `end` beat column along
+/// with the char offset and char width it is printed at within a lane, not counting the note name.
+fn columns(staff: &Staff, start: usize, end: usize, layout: &Layout) -> Vec<(usize, usize, usize)> {
+    let width = |c| staff.column_width(c, Some(layout));
+    let measure_length = staff.time.total_beats_per_measure().max(1) as usize;
+    let mut columns = vec![];
+    let mut offset = 0;
+    for measure in staff.time.measures(start, end) {
+        let rendered = staff.time.render_measure(measure.clone(), staff.barline(measure.start), '-', layout.measure_width, |c| "-".repeat(width(c)));
+        let mut at = offset + if measure.start % measure_length == 0 { staff.barline(measure.start).len() } else { 0 };
+        for column in measure {
+            columns.push((column, at, width(column)));
+            at += width(column);
+        }
+        offset += rendered.chars().count();
+    }
+    columns
+}
@@ -0,0 +1,269 @@
+use data::{Diagnostic, Literal, Severity, Token, TokenType};
+
+use crate::ParseError;
+
+/// The amount of beat slots in a 4/4 measure at the default fidelity. Measures holding more columns of
+/// frets than this are imported at a higher fidelity.
+const MEASURE_SLOTS: usize = 16;
+
+/// A single string of plain ASCII tab: the note name of the string and the chars written after its first
+/// bar-line.
+struct TabLine {
+    string: String,
+    body: Vec<char>,
+}
+
+/// A fret written on a single string of plain ASCII tab.
+struct Fret {
+    /// The index of the line within its block, from the highest string.
+    lane: usize,
+    /// The char range of the fret number within the body of its line.
+    chars: std::ops::Range<usize>,
+    fret: u32,
+    /// Whether the fret is wrapped in angle brackets as a natural harmonic: `<12>`.
+    harmonic: bool,
+    /// Whether the fret is followed by a tilde: `5~`.
+    vibrato: bool,
+}
+
+/// A run of lines of plain ASCII tab: a staff of consecutive tab lines along with every fret written on
+/// them, or any other line of text.
+enum Block<'a> {
+    Staff(Vec<TabLine>, Vec<Fret>),
+    Text(&'a str),
+}
+
+/// Converts plain ASCII guitar tabs, such as `e|---3---5---|`, into the tokens of the same tabs written in
+/// tab notation, so they can be parsed, formatted, transposed, or exported the same as tokens from the
+/// lexer. Consecutive tab lines that start with the note name of their string and a bar-line form a
+/// staff, listed from the highest string. Frets written at the same char column on different strings
+/// are played together, and every measure between bar-lines is placed into the beat slots of a 4/4
+/// measure by where its frets are written, raising the fidelity when a measure holds more than 16
+/// columns of frets. Natural harmonics `<12>` and vibrato `5~` are kept; every other technique is
+/// imported as its plain fret. Any other line, such as a title, chord names, or lyrics, is kept as a
+/// comment.
+///
+/// # Errors
+///
+/// This function errors if no tab lines are found.
+///
+/// # Examples
+///
+/// ```
+/// let tokens = parser::import::ascii("e|--------|\nB|1-------|\nG|0---2---|").unwrap();
+///
+/// assert_eq!("G B E\n0 1, ;7 2, ;7\n", parser::format_source(&tokens));
+/// ```
+pub fn ascii(text: &str) -> Result<Vec<Token>, ParseError> {
+    // consecutive tab lines form a block; every other line is kept on its own
+    let mut blocks = vec![];
+    for line in text.lines() {
+        match (tab_line(line), blocks.last_mut()) {
+            (Some(tab), Some(Block::Staff(lines, _))) => lines.push(tab),
+            (Some(tab), _) => blocks.push(Block::Staff(vec![tab], vec![])),
+            (None, _) if line.trim().is_empty() => (),
+            (None, _) => blocks.push(Block::Text(line.trim())),
+        }
+    }
+    for block in blocks.iter_mut() {
+        if let Block::Staff(lines, frets) = block {
+            *frets = find_frets(lines);
+        }
+    }
+    if !blocks.iter().any(|block| matches!(block, Block::Staff(..))) {
+        return Err(ParseError::Invalid(vec![Diagnostic::new(
+            Severity::Error,
+            1,
+            "P013",
+            String::from("No ASCII tab lines were found. Tab lines start with the note name of their string and a bar-line: e|---3---|."),
+        )]))
+    }
+
+    // every measure must have a beat slot for each of its columns of frets
+    let slots = blocks.iter()
+        .filter_map(|block| match block {
+            Block::Staff(lines, frets) => Some((lines, frets)),
+            Block::Text(_) => None,
+        })
+        .flat_map(|(lines, frets)| measures(lines).into_iter().map(move |measure| columns(frets, &measure).len()))
+        .fold(MEASURE_SLOTS, |slots, columns| slots.max(columns.next_power_of_two()));
+
+    let mut tokens = vec![];
+    let mut line = 1;
+    if slots != MEASURE_SLOTS {
+        let options = format!("fidelity={}", slots);
+        tokens.push(Token::new(TokenType::Options, format!("[{}]", options), Literal::Options(options), line));
+        line += 1;
+    }
+    // a staff is closed by the first line written after it
+    let mut open_staff = false;
+    for block in blocks.iter() {
+        if open_staff {
+            tokens.push(Token::new(TokenType::StaffBreak, String::from("\n"), Literal::None, line));
+            line += 1;
+            open_staff = false;
+        }
+        match block {
+            Block::Text(text) => {
+                tokens.push(Token::new(TokenType::Comment, format!("// {}", text), Literal::Text(text.to_string()), line));
+                line += 1;
+            },
+            Block::Staff(lines, frets) => {
+                write_staff(lines, frets, slots, &mut tokens, &mut line);
+                open_staff = true;
+            },
+        }
+    }
+    tokens.push(Token::new(TokenType::EndOfFile, String::new(), Literal::None, line));
+    Ok(tokens)
+}
+
+/// Returns the line as a tab line if it starts with a note name and a bar-line and has at least two
+/// dashes after the bar-line. Text after the last bar-line without any dashes, such as a repeat count
+/// `x2`, is left out.
+fn tab_line(line: &str) -> Option<TabLine> {
+    let line = line.trim();
+    let first = line.chars().next().filter(|c| matches!(c.to_ascii_uppercase(), 'A'..='G'))?;
+    let rest = &line[1..];
+    let (modifier, rest) = match rest.strip_prefix(['#', 'b']) {
+        Some(after) => (&rest[..1], after),
+        None => ("", rest),
+    };
+    let mut body: Vec<char> = rest.trim_start().strip_prefix('|')?.chars().collect();
+    if body.iter().filter(|c| **c == '-').count() < 2 {
+        return None
+    }
+    if let Some(last) = body.iter().rposition(|c| *c == '|') {
+        if !body[last..].contains(&'-') {
+            body.truncate(last + 1);
+        }
+    }
+    Some(TabLine { string: format!("{}{}", first.to_ascii_uppercase(), modifier), body })
+}
+
+/// Returns every fret written on the lines of a block, ordered by the char column they start at.
+fn find_frets(lines: &[TabLine]) -> Vec<Fret> {
+    let mut frets = vec![];
+    for (lane, line) in lines.iter().enumerate() {
+        let body = &line.body;
+        let mut i = 0;
+        while i < body.len() {
+            if !body[i].is_ascii_digit() {
+                i += 1;
+                continue
+            }
+            let start = i;
+            while body.get(i).is_some_and(char::is_ascii_digit) {
+                i += 1;
+            }
+            let harmonic = start > 0 && body[start - 1] == '<' && body.get(i) == Some(&'>');
+            let vibrato = body.get(i + harmonic as usize) == Some(&'~');
+            let fret = body[start..i].iter().collect::<String>().parse().unwrap_or_default();
+            frets.push(Fret { lane, chars: start..i, fret, harmonic, vibrato });
+        }
+    }
+    frets.sort_by_key(|fret| fret.chars.start);
+    frets
+}
+
+/// Returns the char range of every measure of a block, split at the bar-lines of its highest string.
+/// Empty ranges between double bar-lines are left out.
+fn measures(lines: &[TabLine]) -> Vec<std::ops::Range<usize>> {
+    let end = lines.iter().map(|line| line.body.len()).max().unwrap_or(0);
+    let mut measures = vec![];
+    let mut start = 0;
+    for (i, c) in lines[0].body.iter().enumerate().chain(std::iter::once((end, &'|'))) {
+        if *c == '|' {
+            if i > start {
+                measures.push(start..i);
+            }
+            start = i + 1;
+        }
+    }
+    measures
+}
+
+/// Returns the frets written within a measure grouped into columns played together, each along with
+/// the char column it starts at. Frets overlapping the chars of a fret on another string, such as a
+/// `3` written under the second digit of a `10`, are played together.
+fn columns<'a>(frets: &'a [Fret], measure: &std::ops::Range<usize>) -> Vec<(usize, Vec<&'a Fret>)> {
+    let mut columns: Vec<(usize, usize, Vec<&Fret>)> = vec![];
+    for fret in frets.iter().filter(|fret| measure.contains(&fret.chars.start)) {
+        match columns.last_mut() {
+            Some((_, end, column)) if fret.chars.start < *end && !column.iter().any(|other| other.lane == fret.lane) => {
+                *end = (*end).max(fret.chars.end);
+                column.push(fret);
+            },
+            _ => columns.push((fret.chars.start, fret.chars.end, vec![fret])),
+        }
+    }
+    columns.into_iter().map(|(start, _, column)| (start, column)).collect()
+}
+
+/// Adds the tokens of a staff: the note names of its strings from the lowest string, then every beat
+/// slot of every measure on its own line.
+fn write_staff(lines: &[TabLine], frets: &[Fret], slots: usize, tokens: &mut Vec<Token>, line: &mut u32) {
+    for tab in lines.iter().rev() {
+        tokens.push(Token::new(TokenType::Note, tab.string.clone(), Literal::None, *line));
+    }
+    *line += 1;
+
+    for measure in measures(lines) {
+        let columns = columns(frets, &measure);
+        // columns are placed at the slot matching where they are written, after the slot of the column
+        // before and leaving a slot for every column after
+        let mut placed = vec![None; slots];
+        let mut next = 0;
+        for (i, (start, column)) in columns.iter().enumerate() {
+            let slot = (start - measure.start) * slots / measure.len();
+            let slot = slot.max(next).min(slots - (columns.len() - i));
+            placed[slot] = Some(column);
+            next = slot + 1;
+        }
+
+        let mut empty = 0;
+        for column in placed {
+            match column {
+                None => empty += 1,
+                Some(column) => {
+                    write_empty(&mut empty, tokens, line);
+                    // strings are written from the lowest, up to the highest string played
+                    let lanes = lines.len();
+                    let highest = column.iter().map(|fret| lanes - 1 - fret.lane).max().unwrap_or(0);
+                    for string in 0..=highest {
+                        match column.iter().find(|fret| lanes - 1 - fret.lane == string) {
+                            Some(fret) if fret.harmonic => tokens.push(Token::new(
+                                TokenType::NaturalHarmonic, format!("<{}>", fret.fret), Literal::Number(fret.fret), *line
+                            )),
+                            Some(fret) => tokens.push(Token::new(
+                                TokenType::Number, fret.fret.to_string(), Literal::Number(fret.fret), *line
+                            )),
+                            None => tokens.push(Token::new(TokenType::Empty, String::from("."), Literal::None, *line)),
+                        }
+                        if column.iter().any(|fret| lanes - 1 - fret.lane == string && fret.vibrato) {
+                            tokens.push(Token::new(TokenType::Vibrato, String::from("~"), Literal::None, *line));
+                        }
+                    }
+                    // a column played on every string is already complete
+                    if highest < lanes - 1 {
+                        tokens.push(Token::new(TokenType::Next, String::from(","), Literal::None, *line));
+                    }
+                    *line += 1;
+                },
+            }
+        }
+        write_empty(&mut empty, tokens, line);
+    }
+}
+
+/// Adds the tokens of a run of empty beat slots on a single line, then resets the run: a next command
+/// for a single slot or a next beat spread for more.
+fn write_empty(empty: &mut usize, tokens: &mut Vec<Token>, line: &mut u32) {
+    match *empty {
+        0 => return,
+        1 => tokens.push(Token::new(TokenType::Next, String::from(","), Literal::None, *line)),
+        amount => tokens.push(Token::new(TokenType::SpreadNext, format!(";{}", amount), Literal::Number(amount as u32), *line)),
+    }
+    *empty = 0;
+    *line += 1;
+}
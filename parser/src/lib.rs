@@ -1,25 +1,38 @@
-use data::{Token, TokenType, Literal, Watcher};
-use std::fmt;
+use data::{theory, Token, TokenType, Literal, Watcher, Diagnostic, Severity};
+use lexer::LexError;
+use std::{fmt, error::Error, iter::FromIterator, ops::Range, borrow::Cow};
+
+use serde::{Serialize, Deserialize};
+
+pub mod ast;
+pub mod export;
+mod html;
+pub mod import;
+mod pdf;
+mod source_format;
+#[cfg(feature = "playback")]
+mod synth;
+
+pub use source_format::format_source;
+pub use data::theory::pitch_class;
 
 /// Keeps track of time signature and smallest visible beat for a staff.
 struct Time {
     beats_per_measure: u32,
     dominant_beat: u32,
     fidelity: u32,
-    current_beat: u32,
     total_beats_counted: u32,
 }
 
 impl Time {
     /// Creates a new `Time` struct with default settings:
     /// 
-    /// `beats_per_measure = 4, dominant_beat = 4, fidelity = 16, current_beat = 0, total_beats_counted = 0`
+    /// `beats_per_measure = 4, dominant_beat = 4, fidelity = 16, total_beats_counted = 0`
     fn new() -> Time {
         Time {
             beats_per_measure: 4,
             dominant_beat: 4,
             fidelity: 16,
-            current_beat: 0,
             total_beats_counted: 0,
         }
     }
@@ -47,22 +60,111 @@ impl Time {
         self.fidelity
     }
 
-    /// Gets the current beat as the beat number, 'e', '&', or 'a'.
-    pub fn get_beat(&self) -> String {
-        self.get_beat_at(self.current_beat)
-    }
-
     /// Increments the current beat to the next beat.
     pub fn increment_beat(&mut self) {
-        self.current_beat = (self.current_beat + 1) % self.total_beats_per_measure();
         self.total_beats_counted += 1;
     }
 
+    /// Returns the number of beat slots taken up by a note or rest of the provided duration, where a
+    /// duration of `4` is a quarter note, `8` an eighth note, and so on. Returns `None` if the duration
+    /// does not fill a whole number of beat slots at the fidelity, such as a duration of `0`, durations
+    /// finer than the fidelity, or durations that split a beat slot.
+    pub fn duration_slots(&self, duration: u32) -> Option<u32> {
+        self.fidelity.checked_div(duration).filter(|_| self.fidelity.is_multiple_of(duration))
+    }
+
+    /// Returns the number of beat slots within a single counted beat; always at least `1`.
+    fn slots_per_beat(&self) -> u32 {
+        self.fidelity.checked_div(self.dominant_beat).unwrap_or(0).max(1)
+    }
+
+    /// Returns the number of counted beats, including a partially filled last beat.
+    fn beats_counted(&self) -> u32 {
+        self.total_beats_counted.div_ceil(self.slots_per_beat())
+    }
+
     /// Returns the total number of possible beats and fractional beats within a given measure.
     fn total_beats_per_measure(&self) -> u32 {
         self.beats_per_measure * (self.fidelity / self.dominant_beat)
     }
 
+    /// Returns the char width of a full measure: a bar-line character followed by 3 chars per beat.
+    fn measure_width(&self) -> usize {
+        1 + 3 * self.total_beats_per_measure() as usize
+    }
+
+    /// Returns the beat counts of every counted beat from the `start` beat column up to the `end` beat
+    /// column. If a measure width is provided, every measure is padded with spaces to that char width.
+    /// If `backwards` is set, the chars of every beat count are reversed so the line can be mirrored.
+    /// Every beat count is padded with spaces to the char width of its column given by `column_width`,
+    /// and every bar-line to the char width of the bar-line starting at its column given by `bar_width`.
+    fn beat_line(
+        &self,
+        measure_width: Option<usize>,
+        start: usize,
+        end: usize,
+        backwards: bool,
+        column_width: impl Fn(usize) -> usize,
+        bar_width: impl Fn(usize) -> usize
+    ) -> String {
+        let measure_length = self.total_beats_per_measure();
+
+        // notes have 3 starting spaces "Nm_" where 'N' is the note name, 'm' is the modifier, and '_' is
+        // a blank space; set beats to initially be 3 blank spaces
+        let mut beats = String::from("   ");
+        for measure in self.measures(start, end.min(self.total_beats_counted as usize)) {
+            // add a space for non-beat counted chars like bar-line characters
+            let bar = " ".repeat(bar_width(measure.start));
+            beats.push_str(&self.render_measure(measure, &bar, ' ', measure_width, |b| {
+                let beat = self.get_beat_at(b as u32 % measure_length);
+                // beats that are 1 char in length will be represented as "_n_" while 2 length beats are "_nn"
+                // where 'n' is a number and '_' is a space
+                let cell = format!(" {}{}", beat, if beat.len() == 1 { " " } else { "" });
+                let cell = format!("{:<width$}", cell, width = column_width(b));
+                if backwards { cell.chars().rev().collect() } else { cell }
+            }));
+        }
+        beats
+    }
+
+    /// Splits the beat columns from `start` up to `end` into the column ranges of each measure. The first
+    /// range is only part of a measure if `start` is not a downbeat.
+    fn measures(&self, start: usize, end: usize) -> Vec<Range<usize>> {
+        let measure_length = self.total_beats_per_measure() as usize;
+        let mut measures = vec![];
+        let mut column = start;
+        while column < end {
+            let measure_end = ((column / measure_length + 1) * measure_length).min(end);
+            measures.push(column..measure_end);
+            column = measure_end;
+        }
+        measures
+    }
+
+    /// Renders the provided measure columns using `cell` to render each column. Measures starting on a
+    /// downbeat start with the `bar` chars. If a measure width is provided, the measure is padded with the
+    /// `fill` char up to that char width, less the width of any columns cut off from its start.
+    fn render_measure(
+        &self,
+        columns: Range<usize>,
+        bar: &str,
+        fill: char,
+        measure_width: Option<usize>,
+        cell: impl Fn(usize) -> String
+    ) -> String {
+        let offset = columns.start % self.total_beats_per_measure() as usize;
+        let mut measure = if offset == 0 { bar.to_string() } else { String::new() };
+        for column in columns {
+            measure.push_str(&cell(column));
+        }
+        if let Some(width) = measure_width {
+            let skipped = if offset == 0 { 0 } else { 1 + 3 * offset };
+            let padding = width.saturating_sub(skipped + measure.chars().count());
+            measure.push_str(&fill.to_string().repeat(padding));
+        }
+        measure
+    }
+
     /// Gets the beat at the provided beat position within a measure.
     /// Returned result will either be the beat number, 'e', '&', or 'a'.
     fn get_beat_at(&self, pos: u32) -> String {
@@ -80,32 +182,748 @@ impl Time {
 
 impl fmt::Display for Time {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        // notes have 3 starting spaces "Nm_" where 'N' is the note name, 'm' is the modifier, and '_' is
-        // a blank space; set beats to initially be 3 blank spaces
-        let mut beats = String::from("   ");
-        for b in 0..self.total_beats_counted {
-            let beat = self.get_beat_at(b % self.total_beats_per_measure());
-            // add a space for non-beat counted chars like bar-line characters
-            if beat == "1" { beats.push_str(" "); }
-            // beats that are 1 char in length will be represented as "_n_" while 2 length beats are "_nn"
-            // where 'n' is a number and '_' is a space
-            beats.push_str(&format!(
-                " {}{}",
-                beat,
-                if beat.len() == 1 { " " } else { "" }
-            ));
+        write!(f, "{}", self.beat_line(None, 0, self.total_beats_counted as usize, false, |_| 3, |_| 1))
+    }
+}
+
+/// A dynamic marking printed above the staff at the beat it occurs.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use parser::Dynamic;
+/// 
+/// let dynamic = Dynamic::from_marking("mf").unwrap();
+/// 
+/// assert_eq!(Dynamic::MezzoForte, dynamic);
+/// assert_eq!("mf", dynamic.to_string());
+/// assert_eq!(80, dynamic.velocity());
+/// ```
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum Dynamic {
+    /// Very soft: `pp`
+    Pianissimo,
+    /// Soft: `p`
+    Piano,
+    /// Moderately soft: `mp`
+    MezzoPiano,
+    /// Moderately loud: `mf`
+    MezzoForte,
+    /// Loud: `f`
+    Forte,
+    /// Very loud: `ff`
+    Fortissimo,
+}
+
+impl Dynamic {
+    /// Creates a dynamic from its written marking; returns `None` if the marking is unknown.
+    pub fn from_marking(marking: &str) -> Option<Dynamic> {
+        match marking.trim() {
+            "pp" => Some(Dynamic::Pianissimo),
+            "p" => Some(Dynamic::Piano),
+            "mp" => Some(Dynamic::MezzoPiano),
+            "mf" => Some(Dynamic::MezzoForte),
+            "f" => Some(Dynamic::Forte),
+            "ff" => Some(Dynamic::Fortissimo),
+            _ => None,
+        }
+    }
+
+    /// Returns the MIDI velocity (`0` to `127`) used when playing notes at this dynamic.
+    pub fn velocity(&self) -> u8 {
+        match self {
+            Dynamic::Pianissimo => 33,
+            Dynamic::Piano => 49,
+            Dynamic::MezzoPiano => 64,
+            Dynamic::MezzoForte => 80,
+            Dynamic::Forte => 96,
+            Dynamic::Fortissimo => 112,
+        }
+    }
+}
+
+impl fmt::Display for Dynamic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", match self {
+            Dynamic::Pianissimo => "pp",
+            Dynamic::Piano => "p",
+            Dynamic::MezzoPiano => "mp",
+            Dynamic::MezzoForte => "mf",
+            Dynamic::Forte => "f",
+            Dynamic::Fortissimo => "ff",
+        })
+    }
+}
+
+/// A technique held across a span of beats, written as `pm{ ... }` for palm muting or `lr{ ... }` to let
+/// notes ring.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use parser::Technique;
+/// 
+/// assert_eq!(Some(Technique::PalmMute), Technique::from_name("pm"));
+/// assert_eq!("P.M.", Technique::PalmMute.to_string());
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum Technique {
+    /// Notes muted with the palm of the picking hand: `pm`
+    PalmMute,
+    /// Notes left to ring over each other: `lr`
+    LetRing,
+}
+
+impl Technique {
+    /// Every technique in the order their annotation lines are printed above a staff.
+    const ALL: [Technique; 2] = [Technique::PalmMute, Technique::LetRing];
+
+    /// Creates a technique from the name written before its span; returns `None` if the name is unknown.
+    pub fn from_name(name: &str) -> Option<Technique> {
+        match name.trim() {
+            "pm" => Some(Technique::PalmMute),
+            "lr" => Some(Technique::LetRing),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Technique {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", match self {
+            Technique::PalmMute => "P.M.",
+            Technique::LetRing => "let ring",
+        })
+    }
+}
+
+/// A harmonic played in place of a fretted note.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use parser::Harmonic;
+/// 
+/// assert_eq!(19, Harmonic::Natural.semitones(7));
+/// assert_eq!(24, Harmonic::Pinch.semitones(5));
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum Harmonic {
+    /// A harmonic sounded by lightly touching the string over the fret: `<12>`
+    Natural,
+    /// A harmonic sounded by catching the string with the thumb as it is picked: `5ph`
+    Pinch,
+}
+
+impl Harmonic {
+    /// Returns the amount of semitones the harmonic sounds above the open string when played at the
+    /// provided fret. Natural harmonics sound the pitch of the node at the fret, an octave at fret `12`,
+    /// an octave and a fifth at frets `7` and `19`, two octaves at frets `5` and `24`, two octaves and a
+    /// major third at frets `4`, `9`, and `16`, and two octaves and a fifth at fret `3`; any other fret
+    /// sounds as fretted. Pinch harmonics sound an octave and a fifth above the fretted note.
+    pub fn semitones(&self, fret: u32) -> u32 {
+        match (self, fret) {
+            (Harmonic::Natural, 12) => 12,
+            (Harmonic::Natural, 7 | 19) => 19,
+            (Harmonic::Natural, 5 | 24) => 24,
+            (Harmonic::Natural, 4 | 9 | 16) => 28,
+            (Harmonic::Natural, 3) => 31,
+            (Harmonic::Natural, fret) => fret,
+            (Harmonic::Pinch, fret) => fret + 19,
+        }
+    }
+}
+
+/// A finger of the fretting hand, written in parentheses directly after the fret it frets: `5(1)`.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use parser::Finger;
+/// 
+/// assert_eq!(Some(Finger::Index), Finger::from_symbol("1"));
+/// assert_eq!("T", Finger::Thumb.to_string());
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum Finger {
+    /// The thumb, wrapped over the neck: `T`
+    Thumb,
+    /// The index finger: `1`
+    Index,
+    /// The middle finger: `2`
+    Middle,
+    /// The ring finger: `3`
+    Ring,
+    /// The little finger: `4`
+    Pinky,
+}
+
+impl Finger {
+    /// Creates a finger from the symbol written within its parentheses; returns `None` if the symbol is
+    /// unknown.
+    pub fn from_symbol(symbol: &str) -> Option<Finger> {
+        match symbol {
+            "T" => Some(Finger::Thumb),
+            "1" => Some(Finger::Index),
+            "2" => Some(Finger::Middle),
+            "3" => Some(Finger::Ring),
+            "4" => Some(Finger::Pinky),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Finger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", match self {
+            Finger::Thumb => "T",
+            Finger::Index => "1",
+            Finger::Middle => "2",
+            Finger::Ring => "3",
+            Finger::Pinky => "4",
+        })
+    }
+}
+
+/// Where the fretting hand is placed along the neck, written before the tabs it is held for.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use parser::{Finger, HandPosition};
+/// 
+/// assert_eq!(Some(Finger::Index), HandPosition::Barre(5).finger(5));
+/// assert_eq!(Some(Finger::Ring), HandPosition::Position(5).finger(7));
+/// assert_eq!(None, HandPosition::Position(5).finger(9));
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum HandPosition {
+    /// The index finger is laid across every string at the fret: `B5`
+    Barre(u32),
+    /// The index finger is placed at the fret, with every other finger over the frets after it: `pos5`
+    Position(u32),
+}
+
+impl HandPosition {
+    /// Returns the fret the index finger is placed at.
+    pub fn fret(&self) -> u32 {
+        match self {
+            HandPosition::Barre(fret) | HandPosition::Position(fret) => *fret,
+        }
+    }
+
+    /// Returns the finger that frets the provided fret from the hand position, with one finger for every
+    /// fret from the index finger; returns `None` for open strings and frets out of reach.
+    pub fn finger(&self, fret: u32) -> Option<Finger> {
+        match fret.checked_sub(self.fret()) {
+            _ if fret == 0 => None,
+            Some(0) => Some(Finger::Index),
+            Some(1) => Some(Finger::Middle),
+            Some(2) => Some(Finger::Ring),
+            Some(3) => Some(Finger::Pinky),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for HandPosition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HandPosition::Barre(fret) => write!(f, "B{}", fret),
+            HandPosition::Position(fret) => write!(f, "pos{}", fret),
+        }
+    }
+}
+
+/// A marking printed above a staff at the beat it is set.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Marking {
+    Dynamic(Dynamic),
+    /// A change of tempo in beats per minute.
+    Tempo(u32),
+    /// The amount of times a repeated section is played, placed at its repeat end bar-line.
+    Repeat(u32),
+    /// The amount of beats of a tuplet and the amount of beats of space they fit into.
+    Tuplet(u32, u32),
+}
+
+impl fmt::Display for Marking {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Marking::Dynamic(dynamic) => write!(f, "{}", dynamic),
+            Marking::Tempo(bpm) => write!(f, "{} bpm", bpm),
+            Marking::Repeat(times) => write!(f, "x{}", times),
+            Marking::Tuplet(notes, space) => write!(f, "{}:{}", notes, space),
+        }
+    }
+}
+
+/// The descriptive details of a song, set by the `title`, `artist`, and `album` options and printed in
+/// the header above the first staff.
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct Metadata {
+    /// The title of the song, if set.
+    pub title: Option<String>,
+    /// The artist who performs the song, if set.
+    pub artist: Option<String>,
+    /// The album the song is from, if set.
+    pub album: Option<String>,
+}
+
+/// A change of tempo at a beat of the score, counted from the start of the first staff.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use parser::TempoChange;
+/// 
+/// let change = TempoChange { beat: 8, bpm: 120 };
+/// assert_eq!(500_000, change.micros_per_beat());
+/// ```
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub struct TempoChange {
+    /// The beat the tempo changes at.
+    pub beat: u32,
+    /// The new tempo in beats per minute.
+    pub bpm: u32,
+}
+
+impl TempoChange {
+    /// Returns the length of a beat in microseconds, as used by MIDI tempo events.
+    pub fn micros_per_beat(&self) -> u32 {
+        60_000_000 / self.bpm.max(1)
+    }
+}
+
+/// Counts of what was generated from a source file, for reporting.
+#[derive(Debug, PartialEq, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Summary {
+    /// The amount of staffs generated.
+    pub staffs: usize,
+    /// The amount of measures across every staff.
+    pub measures: usize,
+    /// The amount of tabs played across every staff.
+    pub notes: usize,
+    /// The amount of warnings logged.
+    pub warnings: usize,
+    /// The amount of errors logged.
+    pub errors: usize,
+}
+
+/// An error returned by a `Parser` or while transposing a score.
+#[derive(Debug, PartialEq, Clone)]
+pub enum ParseError {
+    /// The tokens could not be parsed. Holds every error diagnostic logged, each with the line it was
+    /// found on and its code.
+    Invalid(Vec<Diagnostic>),
+    /// A fret would go below fret `0` once transposed and no lower string is free to play it.
+    Unplayable {
+        /// The fret before it was transposed.
+        fret: u32,
+        /// The note name of the string the fret is played on.
+        string: String,
+        /// The source line the fret was written on.
+        line: u32,
+        /// The amount of semitones the fret was transposed by.
+        semitones: i32,
+    },
+    /// A staff or its options rejected an element of tab notation, such as a tab added before any notes.
+    Rejected {
+        /// The source line the element was written on, or `0` if it is not known.
+        line: u32,
+        /// The char column the element starts at within its line, or `0` if it is not known.
+        column: u32,
+        /// Why the element was rejected.
+        message: String,
+    },
+}
+
+impl ParseError {
+    /// Returns the error diagnostics of tokens that could not be parsed, or nothing for any other error.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        match self {
+            ParseError::Invalid(diagnostics) => diagnostics,
+            ParseError::Unplayable { .. } | ParseError::Rejected { .. } => &[],
+        }
+    }
+
+    /// Returns the error placed at the provided source line and column, keeping a line and column that
+    /// are already known.
+    pub fn at(self, line: u32, column: u32) -> ParseError {
+        match self {
+            ParseError::Rejected { line: 0, column: 0, message } => ParseError::Rejected { line, column, message },
+            ParseError::Rejected { line: known, column: 0, message } => ParseError::Rejected { line: known, column, message },
+            other => other,
+        }
+    }
+
+    /// Returns the error with the text of the line of every diagnostic taken from the provided source.
+    pub fn with_source(self, source: &str) -> ParseError {
+        match self {
+            ParseError::Invalid(diagnostics) => ParseError::Invalid(
+                diagnostics.into_iter().map(|diagnostic| diagnostic.with_source(source)).collect()
+            ),
+            other => other,
+        }
+    }
+
+    /// Returns the error with every diagnostic rendered along with the line it was found on, or the
+    /// same as its display for any other error.
+    pub fn render(&self) -> String {
+        match self {
+            ParseError::Invalid(diagnostics) => diagnostics.iter().map(Diagnostic::render).collect::<Vec<String>>().join("\n"),
+            ParseError::Unplayable { .. } | ParseError::Rejected { .. } => self.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Invalid(diagnostics) => {
+                let lines: Vec<String> = diagnostics.iter().map(|diagnostic| diagnostic.to_string()).collect();
+                write!(f, "{}", lines.join("\n"))
+            },
+            ParseError::Unplayable { fret, string, line, semitones } => write!(
+                f,
+                "Cannot transpose fret {} on the {} string on line {} down {} as it would be below fret 0 and no lower string is free to play it.",
+                fret, string, line, semitone_count(*semitones)
+            ),
+            ParseError::Rejected { message, .. } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+/// Returns the diagnostic placed at the column the provided token starts at and underlining the token, if
+/// the column of the token is known. Tokens written across lines are not placed at a column.
+fn at_token(diagnostic: Diagnostic, token: &Token) -> Diagnostic {
+    if token.column == 0 || token.value.contains('\n') {
+        return diagnostic
+    }
+    diagnostic.with_column(token.column).with_length(token.value.chars().count() as u32)
+}
+
+/// Returns the greatest common divisor of two numbers.
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+/// Returns an amount of semitones as a count, ignoring its direction: `1 semitone` or `2 semitones`.
+fn semitone_count(semitones: i32) -> String {
+    match semitones.unsigned_abs() {
+        1 => String::from("1 semitone"),
+        amount => format!("{} semitones", amount),
+    }
+}
+
+/// Returns the string and fret that play a fret of the provided string once it is transposed by an amount
+/// of semitones. A fret that would go below fret `0` is moved to the highest lower string that is free to
+/// play its pitch.
+/// 
+/// # Errors
+/// 
+/// This function errors if the fret would go below fret `0` and no lower string is free to play it.
+fn transpose_fret(strings: &[String], string: usize, fret: u32, line: u32, semitones: i32, is_free: impl Fn(usize) -> bool) -> Result<(usize, u32), ParseError> {
+    let transposed = fret as i32 + semitones;
+    if transposed >= 0 {
+        return Ok((string, transposed as u32))
+    }
+
+    let pitches = export::pitches(strings);
+    let pitch = pitches[string] as i32 + transposed;
+    (0..string).rev()
+        .find(|lower| pitches[*lower] as i32 <= pitch && is_free(*lower))
+        .map(|lower| (lower, (pitch - pitches[lower] as i32) as u32))
+        .ok_or_else(|| ParseError::Unplayable { fret, string: strings[string].clone(), line, semitones })
+}
+
+/// Returns the name of a MIDI pitch spelled with sharps and followed by its octave, where the fourth octave
+/// starts at middle C (`60`).
+/// 
+/// # Examples
+/// 
+/// ```
+/// assert_eq!("C4", parser::pitch_name(60));
+/// assert_eq!("F#2", parser::pitch_name(42));
+/// ```
+pub fn pitch_name(pitch: u32) -> String {
+    format!("{}{}", Key::SHARPS[pitch as usize % 12], (pitch / 12) as i32 - 1)
+}
+
+/// A musical key, used to spell accidentals consistently as either sharps or flats.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use parser::Key;
+/// 
+/// let key = Key::from_name("G").unwrap();
+/// assert_eq!("A#", key.spell("Bb"));
+/// assert_eq!("Bb", Key::from_name("Dm").unwrap().spell("A#"));
+/// ```
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Key {
+    /// The pitch class of the key's tonic.
+    tonic: u8,
+    minor: bool,
+    flats: bool,
+}
+
+impl Key {
+    const SHARPS: [&'static str; 12] = theory::SHARPS;
+    const FLATS: [&'static str; 12] = ["C", "Db", "D", "Eb", "E", "F", "Gb", "G", "Ab", "A", "Bb", "B"];
+
+    /// Creates a key from its name: a tonic note name followed by an `m` for minor keys. Returns `None`
+    /// if the name is not valid.
+    pub fn from_name(name: &str) -> Option<Key> {
+        let name = name.trim();
+        let (tonic_name, minor) = match name.strip_suffix('m') {
+            Some(tonic_name) => (tonic_name, true),
+            None => (name, false),
+        };
+        let tonic = pitch_class(tonic_name)?;
+        // a tonic spelled with a modifier decides the key's accidentals; natural tonics with flats in
+        // their key signature are F major and D, G, C, and F minor
+        let flats = if tonic_name.ends_with('b') {
+            true
+        } else if tonic_name.ends_with('#') {
+            false
+        } else if minor {
+            [2, 7, 0, 5].contains(&tonic)
+        } else {
+            tonic == 5
+        };
+        Some(Key { tonic, minor, flats })
+    }
+
+    /// Respells the provided note name using the accidentals of this key. Note names that are not
+    /// valid are returned unchanged.
+    pub fn spell(&self, note: &str) -> String {
+        match pitch_class(note) {
+            Some(pitch) if self.flats => Key::FLATS[pitch as usize].to_string(),
+            Some(pitch) => Key::SHARPS[pitch as usize].to_string(),
+            None => note.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.spell(Key::SHARPS[self.tonic as usize]), if self.minor { "m" } else { "" })
+    }
+}
+
+/// Settings shared by every staff of a score when rendering.
+#[derive(Default)]
+struct Layout {
+    /// The char width every measure is padded to.
+    measure_width: Option<usize>,
+    /// The amount of measures printed per block of lanes.
+    measures_per_line: Option<usize>,
+    /// The char width that no block of lanes goes past, unless a single measure is wider.
+    line_width: Option<usize>,
+    /// Mirror every block so the last beat is printed first.
+    rtl: bool,
+    /// Print every fret as the name of the note it plays instead of its number.
+    display_notes: bool,
+    /// The key used to spell note names; sharps are used without one.
+    key: Option<Key>,
+    /// Print the density of every measure as a bar below the beat counts.
+    heatmap: bool,
+    /// Print the number of every measure above its bar-line.
+    measure_numbers: bool,
+    /// Print the finger of every fingered tab on a line below the lanes.
+    fingering: bool,
+    /// Name the chord played by every beat column without a written chord name above the lanes.
+    analyze_chords: bool,
+    /// The fret the capo is placed at; raises the pitch of every note name without changing frets.
+    capo: u32,
+    /// Mark up every line as HTML, with a class for every element of the staff.
+    html: bool,
+}
+
+/// The bars used by the heatmap, from an empty measure up to the densest measure of a staff.
+const HEATMAP_BARS: [char; 5] = ['▁', '▂', '▃', '▅', '█'];
+
+/// A single cell within a string lane of a staff. Every cell is rendered at least 3 chars in length, and
+/// cells wider than that widen every cell of their beat column.
+#[derive(Debug, PartialEq, Clone)]
+enum Cell {
+    /// A fret number played on the string, optionally highlighted to make it stand out, articulated
+    /// from the tab before it, played with vibrato, accented, played staccato, given a duration, played
+    /// as a harmonic, led into by a grace note fret, or fretted with a finger, along with the source line
+    /// it was written on.
+    Tab {
+        value: String,
+        highlight: bool,
+        articulation: Option<char>,
+        vibrato: bool,
+        accent: bool,
+        staccato: bool,
+        duration: Option<ast::Duration>,
+        harmonic: Option<Harmonic>,
+        grace: Option<u32>,
+        finger: Option<Finger>,
+        line: u32,
+    },
+    /// A blank space used to pad out the staff.
+    Empty,
+    /// An intentional rest.
+    Rest,
+    /// A string muted within a chord.
+    Muted,
+}
+
+impl fmt::Display for Cell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            // single char tabs are formatted "-n-" and two char tabs "-nn-", widening their beat column;
+            // highlighted tabs replace the dashes with brackets "[n]" and "[nn]"; articulated tabs replace the leading
+            // char with the articulation "hn-", as do taps, slaps, pops, and ties "tn-"; grace notes replace it
+            // with their fret in parentheses "(g)n-"; highlighted taps, slaps, pops, and grace notes keep both
+            // brackets around them "[tn]" and "[(g)n]"; tabs with vibrato end with a tilde "-n~" and "-nn~";
+            // natural harmonics are wrapped in angle brackets "<n>" and "<nn>" and pinch harmonics end
+            // with "ph" "-nph"
+            Cell::Tab { value, highlight, articulation, vibrato, harmonic, grace, .. } => {
+                let (open, close) = match (harmonic, highlight) {
+                    (Some(Harmonic::Natural), _) => ('<', ">"),
+                    (_, true) => ('[', "]"),
+                    _ => ('-', "-"),
+                };
+                let close = match harmonic {
+                    _ if *vibrato => "~",
+                    Some(Harmonic::Pinch) => "ph",
+                    Some(Harmonic::Natural) | None => close,
+                };
+                match (grace, articulation) {
+                    (Some(grace), _) if open == '[' => write!(f, "[({}){}{}", grace, value, close),
+                    (Some(grace), _) => write!(f, "({}){}{}", grace, value, close),
+                    (None, Some(articulation)) if open == '[' => write!(f, "[{}{}{}", articulation, value, close),
+                    (None, articulation) => write!(f, "{}{}{}", articulation.unwrap_or(open), value, close),
+                }
+            },
+            Cell::Empty => write!(f, "---"),
+            Cell::Rest => write!(f, "-R-"),
+            Cell::Muted => write!(f, "-x-"),
         }
-        write!(f, "{}", beats)
     }
 }
 
+/// A repeat bar-line placed at the beat column it comes before.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Repeat {
+    /// The start of a repeated section.
+    Start,
+    /// The end of a repeated section along with the amount of times the section is played.
+    End(u32),
+}
+
+/// A tuplet being written to a staff, fitting the beat columns written within it into the space of a
+/// different amount of beat columns.
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct Tuplet {
+    /// The amount of beats the tuplet is written with.
+    notes: u32,
+    /// The beat column the tuplet starts at.
+    start: usize,
+    /// The beat column the tuplet ends at, however many beats are written within it.
+    end: usize,
+    /// The stride of the staff outside of the tuplet.
+    stride: usize,
+    /// The source line the tuplet was started on.
+    line: u32,
+}
+
+/// A tuplet or technique span being written to a staff, ended by the next `}`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Group {
+    Tuplet(Tuplet),
+    /// A technique span along with the beat column it starts at and the source line it was started on.
+    Span(Technique, usize, u32),
+}
+
+/// A single measure of a staff as it is printed, created by `Staff::measures`.
+#[derive(Debug, PartialEq)]
+pub struct MeasureView {
+    /// The beat the measure starts at, counted from the start of the staff.
+    pub start_beat: u32,
+    /// The beat column the measure starts at, counted from the start of the staff.
+    pub start_column: usize,
+    /// The printed cells of every lane within the measure, ordered the same as the printed lanes and
+    /// starting with the bar-line character.
+    pub lanes: Vec<String>,
+}
+
 /// Contains all of the tablature numbers and note names and manages formatting the printed results.
-struct Staff {
+/// 
+/// # Examples
+/// 
+/// ```
+/// use parser::Staff;
+/// 
+/// let mut staff = Staff::new();
+/// staff.add_note(String::from("E")).unwrap();
+/// staff.add_note(String::from("A")).unwrap();
+/// staff.add_tab("3");
+/// staff.add_next();
+/// 
+/// // re-render the staff onto a finer grid without losing the position of the tab
+/// let warnings = staff.rescale_fidelity(32).unwrap();
+/// 
+/// assert!(warnings.is_empty());
+/// ```
+pub struct Staff {
     notes: Vec<String>,
-    tabs: Vec<String>,
+    tabs: Vec<Vec<Cell>>,
+    markings: Vec<(usize, Marking)>,
     time: Time,
     has_tabs: bool,
     string_pos: usize,
+    /// The source line of the cells currently being added.
+    line: u32,
+    /// The char column within its line of the element currently being added.
+    source_column: u32,
+    /// Warnings found while adding cells, along with the source line they were found on and their code.
+    warnings: Vec<(u32, &'static str, String)>,
+    /// The lane and beat column of the most recently added tab.
+    last_tab: Option<(usize, usize)>,
+    /// The beat column cells are added to. Every voice after the first starts again from column `0`.
+    column: usize,
+    /// The voice cells are added to, counted from `1`.
+    voice: usize,
+    /// Every repeat bar-line along with the beat column it comes before.
+    repeats: Vec<(usize, Repeat)>,
+    /// The beat column the measure being written started at, after the last bar-line written.
+    bar_start: usize,
+    /// Every measure written before a bar-line that was not placed on a bar-line, as the beat columns
+    /// written from the start of the measure up to the bar-line, along with the source line of the bar-line.
+    misplaced_bars: Vec<(Range<usize>, u32)>,
+    /// Every lyric syllable along with the beat column it is sung at.
+    lyrics: Vec<(usize, String)>,
+    /// Every chord name annotation along with the beat column it labels.
+    chord_names: Vec<(usize, String)>,
+    /// Every barre and hand position along with the beat column it is placed from.
+    hand_positions: Vec<(usize, HandPosition)>,
+    /// The label of the song section the staff starts, if any.
+    section: Option<String>,
+    /// Comments kept from the source, printed as lines of text above the staff.
+    comments: Vec<String>,
+    /// The amount of strings declared by the `strings` option, checked against the notes once the
+    /// first tab is added.
+    declared_strings: Option<usize>,
+    /// The amount of beat columns every written beat advances by. Tuplets make the grid of the staff
+    /// finer, so every beat written after one spans more than a single beat column.
+    stride: usize,
+    /// The tuplets and technique spans currently being written, from the first started.
+    groups: Vec<Group>,
+    /// Every technique span along with the beat columns it covers.
+    spans: Vec<(Range<usize>, Technique)>,
+    /// Whether the staff is a drum staff, set by the `mode` option, whose lanes hold hits in place of frets.
+    drums: bool,
+    /// The swing ratio the staff is played with, set by the `feel` option, if it is swung.
+    swing: Option<(u32, u32)>,
 }
 
 impl Staff {
@@ -114,9 +932,30 @@ impl Staff {
         Staff {
             notes: vec![],
             tabs: vec![],
+            markings: vec![],
             time: Time::new(),
             has_tabs: false,
             string_pos: 0,
+            line: 0,
+            source_column: 0,
+            warnings: vec![],
+            last_tab: None,
+            column: 0,
+            voice: 1,
+            repeats: vec![],
+            bar_start: 0,
+            misplaced_bars: vec![],
+            lyrics: vec![],
+            chord_names: vec![],
+            hand_positions: vec![],
+            section: None,
+            comments: vec![],
+            declared_strings: None,
+            stride: 1,
+            groups: vec![],
+            spans: vec![],
+            drums: false,
+            swing: None,
         }
     }
 
@@ -125,12 +964,12 @@ impl Staff {
     /// # Errors
     /// 
     /// This function errors if tabs have already been added.
-    pub fn set_time_signature(&mut self, (beats_per_measure, dominant_beat): (u32, u32)) -> Result<(), String> {
+    pub fn set_time_signature(&mut self, (beats_per_measure, dominant_beat): (u32, u32)) -> Result<(), ParseError> {
         if !self.has_tabs {
             self.time.set_signature(beats_per_measure, dominant_beat);
             Ok(())
         } else {
-            Err(String::from("[IE_pr-st-fn(SIG)]: cannot set time signature after tabs have been added.\n"))
+            Err(self.reject(String::from("cannot set time signature after tabs have been added.")))
         }
     }
 
@@ -139,12 +978,12 @@ impl Staff {
     /// # Errors
     /// 
     /// This function errors if tabs have already been added.
-    pub fn set_time_fidelity(&mut self, fidelity: u32) -> Result<(), String> {
+    pub fn set_time_fidelity(&mut self, fidelity: u32) -> Result<(), ParseError> {
         if !self.has_tabs {
             self.time.set_fidelity(fidelity);
             Ok(())
         } else {
-            Err(String::from("[IE_pr-st-fn(FID)]: cannot set fidelity after tabs have been added.\n"))
+            Err(self.reject(String::from("cannot set fidelity after tabs have been added.")))
         }
     }
 
@@ -153,462 +992,5337 @@ impl Staff {
     /// # Errors
     /// 
     /// This function errors if tabs have already been added.
-    pub fn add_note(&mut self, note: String) -> Result<(), String> {
+    pub fn add_note(&mut self, note: String) -> Result<(), ParseError> {
         if !self.has_tabs {
             self.notes.push(note);
-            self.tabs.push(String::new());
+            self.tabs.push(vec![]);
             self.string_pos = self.notes.len() - 1;
             Ok(())
         } else {
-            Err(String::from("[IE_pr-st-fn(ADN)]: cannot add note after tabs have been added.\n"))
+            Err(self.reject(String::from("cannot add note after tabs have been added.")))
         }
     }
 
     /// Adds a guitar tab to the staff.
-    pub fn add_tab(&mut self, tab: &String) {
-        // checks the current beat; if current beat is a downbeat, add a bar-line character
-        self.check_beat();
-
-        // make sure the tabs vector has a string available at the string position
-        if let Some(tab_lane) = self.tabs.get_mut(self.string_pos) {
-            // format the tab so that single char tabs are formatted "-n-" while two char tabs are "-nn"
-            tab_lane.push_str(&format!(
-                "-{}{}",
-                tab,
-                if tab.len() == 1 { "-" } else { "" }
-            ));
-            self.has_tabs = true;
-            self.update_string_pos();
-        }
+    pub fn add_tab(&mut self, tab: &str) {
+        self.add_cell(Cell::Tab { value: tab.to_string(), highlight: false, articulation: None, vibrato: false, accent: false, staccato: false, duration: None, harmonic: None, grace: None, finger: None, line: self.line });
     }
 
-    /// Adds an empty tab to the staff.
-    pub fn add_empty(&mut self) {
-        // checks the current beat; if current beat is a downbeat, add a bar-line character
-        self.check_beat();
+    /// Adds a highlighted guitar tab to the staff. Highlighted tabs are wrapped in brackets to make
+    /// them stand out.
+    pub fn add_highlighted_tab(&mut self, tab: &str) {
+        self.add_cell(Cell::Tab { value: tab.to_string(), highlight: true, articulation: None, vibrato: false, accent: false, staccato: false, duration: None, harmonic: None, grace: None, finger: None, line: self.line });
+    }
 
-        // make sure the tabs vector has a string available at the string position
-        // format empty tabs as "---"; all tabs will be 3 chars in length
-        if let Some(tab_lane) = self.tabs.get_mut(self.string_pos) {
-            tab_lane.push_str("---");
-            self.has_tabs = true;
-            self.update_string_pos();
+    /// Adds a tab played from the tab before it with an articulation: `h` for a hammer-on, `p` for a
+    /// pull-off, `b` for a bend, `r` for a release, or `/` and `\` for a slide up or down. The tab is added to the same string as the tab
+    /// before it, one beat slot later.
+    pub fn add_articulated_tab(&mut self, tab: &str, articulation: char) {
+        // a full column of cells less one returns to the string of the tab before
+        for _ in 1..self.notes.len() {
+            self.add_cell(Cell::Empty);
         }
+        self.add_cell(Cell::Tab {
+            value: tab.to_string(),
+            highlight: false,
+            articulation: Some(articulation),
+            vibrato: false,
+            accent: false,
+            staccato: false,
+            duration: None,
+            harmonic: None,
+            grace: None,
+            finger: None,
+            line: self.line,
+        });
     }
 
-    /// Adds empty tabs to the staff until the string position resets back to its starting position.
-    pub fn add_next(&mut self) {
-        // loop through from the current string position to the first (and final) string position
-        for pos in (0..=self.string_pos).rev() {
-            // checks the current beat; if current beat is a downbeat, add a bar-line character
-            self.check_beat();
-
-            // make sure the tabs vector has a string available at the string position
-            // format empty tabs as "---"; all tabs will be 3 chars in length
-            if let Some(tab_lane) = self.tabs.get_mut(pos) {
-                tab_lane.push_str("---");
-                self.has_tabs = true;
+    /// Adds a chord of frets played together in a single beat column, from the lowest string; muted
+    /// strings are `None` and rendered as `x`, and strings after the last fret are left empty. A partially filled beat
+    /// column is filled with empty cells first so the whole chord shares one column.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if the chord has more frets than the staff has strings.
+    pub fn add_chord(&mut self, frets: &[Option<u32>], highlight: bool) -> Result<(), ParseError> {
+        if frets.len() > self.notes.len() {
+            return Err(self.reject(format!("the chord has {} frets but the staff only has {} strings.", frets.len(), self.notes.len())))
+        }
+        if self.string_pos != self.notes.len() - 1 {
+            self.fill_column(|| Cell::Empty);
+        }
+        for string in 0..self.notes.len() {
+            match frets.get(string) {
+                Some(Some(fret)) => self.add_cell(Cell::Tab {
+                    value: fret.to_string(),
+                    highlight,
+                    articulation: None,
+                    vibrato: false,
+                    accent: false,
+                    staccato: false,
+                    duration: None,
+                    harmonic: None,
+                    grace: None,
+                    finger: None,
+                    line: self.line,
+                }),
+                Some(None) => self.add_cell(Cell::Muted),
+                None => self.add_cell(Cell::Empty),
             }
-            self.update_string_pos();
         }
+        Ok(())
     }
 
-    /// Adds empty tabs for the provided amount.
-    pub fn add_spread_empty(&mut self, amt: u32) {
-        for _ in 0..amt {
-            self.add_empty();
+    /// Adds vibrato to the most recently added tab. Nothing is changed if no tab has been added yet.
+    pub fn add_vibrato(&mut self) {
+        if let Some(Some(Cell::Tab { vibrato, .. })) = self.last_tab.map(|(lane, column)| self.tabs[lane].get_mut(column)) {
+            *vibrato = true;
         }
     }
 
-    /// Adds empty tabs for the provided amount, each time adding empty tabs until the string position
-    /// resets back to its starting position.
-    pub fn add_spread_next(&mut self, amt: u32) {
-        for _ in 0..amt {
-            self.add_next();
+    /// Accents the most recently added tab. Nothing is changed if no tab has been added yet.
+    pub fn add_accent(&mut self) {
+        if let Some(Some(Cell::Tab { accent, .. })) = self.last_tab.map(|(lane, column)| self.tabs[lane].get_mut(column)) {
+            *accent = true;
         }
     }
 
-    /// Updates the current string position. String position starts at `note.len() - 1` and decrements
-    /// until `0` then resets.
-    fn update_string_pos(&mut self) {
-        self.string_pos = if self.string_pos == 0 {
-            self.time.increment_beat();
-            self.notes.len() - 1
-        } else {
-            self.string_pos - 1
-        };
+    /// Plays the most recently added tab staccato. Nothing is changed if no tab has been added yet.
+    pub fn add_staccato(&mut self) {
+        if let Some(Some(Cell::Tab { staccato, .. })) = self.last_tab.map(|(lane, column)| self.tabs[lane].get_mut(column)) {
+            *staccato = true;
+        }
     }
 
-    /// Checks if the current beat is a downbeat and add a bar-line character if so.
-    fn check_beat(&mut self) {
-        if self.time.get_beat() == "1" {
-            if let Some(tab_lane) = self.tabs.get_mut(self.string_pos) {
-                tab_lane.push_str("|");
-            }
+    /// Plays the most recently added tab with the provided stroke: `t` for a tap, `s` for a slap, or `P`
+    /// for a pop. The stroke is kept as the articulation of the tab. Nothing is changed if no tab has
+    /// been added yet.
+    pub fn add_stroke(&mut self, stroke: char) {
+        if let Some(Some(Cell::Tab { articulation, .. })) = self.last_tab.map(|(lane, column)| self.tabs[lane].get_mut(column)) {
+            *articulation = Some(stroke);
         }
     }
-}
 
-impl fmt::Display for Staff {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut tabs = String::new();
-        // zip together both notes and tabs to print to their respective lines
-        for (n, t) in self.notes.iter().rev().zip(self.tabs.iter()) {
-            tabs.push_str(&format!(
-                "{} {}\n",
-                if n.len() == 1 { format!("{} ", n) } else { n.to_string() },
-                t
-            ));
+    /// Leads into the most recently added tab with a grace note played on the provided fret of the same
+    /// string. Nothing is changed if no tab has been added yet.
+    pub fn add_grace(&mut self, fret: u32) {
+        if let Some(Some(Cell::Tab { grace, .. })) = self.last_tab.map(|(lane, column)| self.tabs[lane].get_mut(column)) {
+            *grace = Some(fret);
         }
-        write!(f, "{}\n{}\n", tabs, self.time)
     }
-}
 
-/// Parses and contains options provided from the source token input and outputs them in a
-/// friendly format.
-struct StaffOptions {
-    time: Time,
-}
+    /// Plays the most recently added tab as the provided harmonic. Nothing is changed if no tab has been
+    /// added yet.
+    pub fn add_harmonic(&mut self, harmonic: Harmonic) {
+        if let Some(Some(Cell::Tab { harmonic: tab_harmonic, .. })) = self.last_tab.map(|(lane, column)| self.tabs[lane].get_mut(column)) {
+            *tab_harmonic = Some(harmonic);
+        }
+    }
 
-impl StaffOptions {
-    /// Creates a new `StaffOptions` struct with default properties.
-    pub fn new() -> StaffOptions {
-        StaffOptions {
-            time: Time::new(),
+    /// Sets how long the most recently added tab rings. Nothing is changed if no tab has been added yet.
+    pub fn add_duration(&mut self, duration: ast::Duration) {
+        if let Some(Some(Cell::Tab { duration: tab_duration, .. })) = self.last_tab.map(|(lane, column)| self.tabs[lane].get_mut(column)) {
+            *tab_duration = Some(duration);
         }
     }
 
-    /// Parses provided options literal into formatted option data types.
+    /// Sets the finger the most recently added tab is fretted with. Nothing is changed if no tab has been
+    /// added yet.
+    pub fn add_finger(&mut self, finger: Finger) {
+        if let Some(Some(Cell::Tab { finger: tab_finger, .. })) = self.last_tab.map(|(lane, column)| self.tabs[lane].get_mut(column)) {
+            *tab_finger = Some(finger);
+        }
+    }
+
+    /// Adds a tie to the staff, continuing the last tab written before it on the same string, even across
+    /// bar-lines. The tie is added as a copy of the tab and its harmonic with `=` as its articulation.
     /// 
     /// # Errors
     /// 
-    /// This function errors if the provided literal is not an options literal, the options have syntax
-    /// errors, or if the option name or value is not valid.
-    pub fn set(&mut self, options: &str) -> Result<(), String> {
-        // used to log all errors that occur
-        let mut errors = String::new();
-
-        // each option will be separated by a semicolon
-        for op in options.split(';') {
-            // if an error occurs, log it and continue the loop
-            if let Err(e) = self.parse_option(op) {
-                errors.push_str(&e);
-            }
-        }
-
-        if errors.is_empty() {
-            Ok(())
-        } else {
-            Err(errors)
+    /// This function errors if no tab comes before the tie on its string, or a rest comes between them;
+    /// an empty cell is added in place of the tie.
+    pub fn add_tie(&mut self) -> Result<(), ParseError> {
+        let column = self.column;
+        let tied = self.tabs.get(self.string_pos).and_then(|lane| {
+            lane.iter().take(column).rev().find(|cell| **cell != Cell::Empty).cloned()
+        });
+        match tied {
+            Some(Cell::Tab { value, harmonic, .. }) => {
+                self.add_cell(Cell::Tab { value, highlight: false, articulation: Some('='), vibrato: false, accent: false, staccato: false, duration: None, harmonic, grace: None, finger: None, line: self.line });
+                Ok(())
+            },
+            _ => {
+                self.add_cell(Cell::Empty);
+                Err(self.reject(String::from("the tie does not follow a tab on the same string.")))
+            },
         }
     }
 
-    /// Gets the time signature.
-    pub fn get_time_signature(&self) -> (u32, u32) {
-        self.time.get_signature()
+    /// Adds an empty tab to the staff.
+    pub fn add_empty(&mut self) {
+        self.add_cell(Cell::Empty);
     }
 
-    /// Gets the beat fidelity.
-    pub fn get_time_fidelity(&self) -> u32 {
-        self.time.get_fidelity()
+    /// Adds empty tabs to the staff until the string position resets back to its starting position.
+    pub fn add_next(&mut self) {
+        self.fill_column(|| Cell::Empty);
     }
 
-    /// Parses provided option reference string into a formatted option data type.
-    /// 
-    /// # Errors
-    /// 
-    /// This function errors if the provided option is not set or the option does not exist.
-    fn parse_option(&mut self, option: &str) -> Result<(), String> {
-        // options will be structured as "option=value" and will be split based on that format
-        let o: Vec<&str> = option.trim().split('=').collect();
+    /// Adds empty tabs for the provided amount.
+    pub fn add_spread_empty(&mut self, amt: u32) {
+        for _ in 0..amt {
+            self.add_empty();
+        }
+    }
 
-        // check to make sure there are 2 values in the vector; if not, then return an error
-        if o.len() < 2 {
-            return Err(format!("\tOption \"{:?}\" has not been set to a value.\n", o))
+    /// Adds empty tabs for the provided amount, each time adding empty tabs until the string position
+    /// resets back to its starting position.
+    pub fn add_spread_next(&mut self, amt: u32) {
+        for _ in 0..amt {
+            self.add_next();
         }
+    }
 
-        // match based on the option name and the use the value for processing
-        match (o[0].trim(), o[1].trim()) {
-            // a time signature option will have the format "n/n" where 'n' is a number
-            // this will be further split at the '/' character to get the beats per measure
-            // and dominant beat values
-            ("time", time_sig) => self.parse_time_signature(time_sig),
-            // the fidelity value will be a single number value
-            ("fidelity", fidelity) => self.parse_fidelity(fidelity),
-            // any other option provided is an error
-            (unknown_option, _) => Err(format!("\tOption \"{}\" does not exist.\n", unknown_option)),
+    /// Adds rests across every string for the provided amount of beat slots. Rests are rendered
+    /// distinctly from empty tabs so an intentional rest can be told apart from padding.
+    pub fn add_rest(&mut self, amt: u32) {
+        for _ in 0..amt {
+            self.fill_column(|| Cell::Rest);
         }
     }
 
-    /// Parse provided reference string into a time signature.
+    /// Starts a tuplet that fits the provided amount of beats into the space of the largest power of two
+    /// below it: 3 beats into the space of 2, or 5, 6, and 7 beats into the space of 4. If the beats
+    /// cannot be placed on the grid of the staff, the fidelity of the staff is raised until they can. A
+    /// partially filled beat column is filled with empty cells first.
     /// 
     /// # Errors
     /// 
-    /// This function errors if the provided reference string is improperly formatted or the values
-    /// on either side of the '/' cannot be parsed into whole integers.
-    fn parse_time_signature(&mut self, time_signature: &str) -> Result<(), String> {
-        let t: Vec<&str> = time_signature.trim().split('/').collect();
-        if t.len() < 2 {
-            return Err(format!("\tTime signature option \"{}\" is improperly formatted. Format should equal \"n/n\" where 'n' is a whole integer.\n", time_signature))
+    /// This function errors if the tuplet has less than 2 beats or another tuplet is being written.
+    pub fn start_tuplet(&mut self, notes: u32) -> Result<(), ParseError> {
+        if notes < 2 {
+            return Err(self.reject(String::from("a tuplet must have at least 2 beats.")))
+        }
+        if self.groups.iter().any(|group| matches!(group, Group::Tuplet(_))) {
+            return Err(self.reject(String::from("tuplets cannot be written within other tuplets.")))
+        }
+        if self.string_pos != self.notes.len().saturating_sub(1) {
+            self.fill_column(|| Cell::Empty);
         }
 
-        match (t[0].trim().parse::<u32>(), t[1].trim().parse::<u32>()) {
-            (Ok(b), Ok(d)) => {
-                self.time.set_signature(b, d);
-                Ok(())
-            },
-            (Err(e_b), Err(e_d)) => {
-                Err(format!("\tCould not parse time signature \"{:?}\" into numbers: {:?}\n", (t[0], t[1]), (e_b, e_d)))
-            },
-            (Err(e_b), _) => {
-                Err(format!("\tCould not parse beats per measure (numerator) \"{}\" into a number: {}\n", t[0], e_b))
-            },
-            (_, Err(e_d)) => {
-                Err(format!("\tCould not parse dominant beat (denominator) \"{}\" into a number: {}\n", t[1], e_d))
-            },
+        let space = 1 << (31 - (notes - 1).leading_zeros());
+        let notes_len = notes as usize;
+        let mut span = space as usize * self.stride;
+        // every beat of the tuplet must start on a beat column, so the grid is made finer until the space
+        // of the tuplet splits evenly between its beats
+        if !span.is_multiple_of(notes_len) {
+            let scale = notes_len / gcd(notes_len, span);
+            self.rescale_fidelity(self.time.get_fidelity() * scale as u32)?;
+            self.stride *= scale;
+            span *= scale;
         }
+
+        self.markings.push((self.column, Marking::Tuplet(notes, space)));
+        self.groups.push(Group::Tuplet(Tuplet { notes, start: self.column, end: self.column + span, stride: self.stride, line: self.line }));
+        self.stride = span / notes_len;
+        Ok(())
     }
 
-    /// Parse the provided reference string into a beat fidelity (or resolution; granularity) whole integer.
+    /// Starts a span of beats played with the provided technique at the beat currently being filled.
+    pub fn start_span(&mut self, technique: Technique) {
+        self.groups.push(Group::Span(technique, self.column, self.line));
+    }
+
+    /// Ends the tuplet or technique span started most recently. A technique span covers every beat
+    /// column from where it started up to the last beat filled within it.
     /// 
     /// # Errors
     /// 
-    /// This function errors if the provided reference string is cannot be parsed into a number.
-    fn parse_fidelity(&mut self, fidelity: &str) -> Result<(), String> {
-        match fidelity.trim().parse::<u32>() {
-            Ok(f) => {
-                self.time.set_fidelity(f);
+    /// This function errors if no tuplet or technique span is being written.
+    pub fn end_group(&mut self) -> Result<(), ParseError> {
+        match self.groups.pop() {
+            Some(Group::Tuplet(tuplet)) => {
+                self.end_tuplet(tuplet);
                 Ok(())
             },
-            Err(e) => Err(format!("\tCould not parse beat fidelity \"{}\" into a number: {}\n", fidelity, e)),
+            Some(Group::Span(technique, start, _)) => {
+                // a partially filled beat column is covered by the span
+                let end = if self.string_pos != self.notes.len().saturating_sub(1) { self.column + 1 } else { self.column };
+                if end > start {
+                    self.spans.push((start..end, technique));
+                }
+                Ok(())
+            },
+            None => Err(self.reject(String::from("no tuplet or span has been started."))),
         }
     }
-}
-
-/// Manages a list of `Staff` structs by adding new staffs as needed and setting global options on them.
-struct StaffManager {
-    staffs: Vec<Staff>,
-    options: StaffOptions,
-}
 
-impl StaffManager {
-    /// Creates a new `StaffManager` with an empty list of staffs.
-    pub fn new() -> StaffManager {
-        StaffManager {
-            staffs: vec![],
-            options: StaffOptions::new(),
+    /// Ends every tuplet and technique span still being written with a warning, as they cannot continue
+    /// onto another staff.
+    fn close_groups(&mut self) {
+        while let Some(group) = self.groups.last().copied() {
+            let (name, line) = match group {
+                Group::Tuplet(tuplet) => (String::from("tuplet"), tuplet.line),
+                Group::Span(technique, _, line) => (format!("\"{}\" span", technique), line),
+            };
+            // a group is being written so it is okay to unwrap the result
+            self.end_group().unwrap();
+            self.warnings.push((line, "P012", format!("The {} on line {} was not ended with \"}}\" before the end of its staff.", name, line)));
         }
     }
 
-    /// Adds a note to the most recently added staff. If the staff list is empty, or the most recent staff
-    /// already has tabs (and therefore adding a new note would break it), then a new staff is created
-    /// with the provided note inserted into it.
-    /// 
-    /// # Errors
-    /// 
-    /// This function errors if a note insertion is attempted on a staff that has tabs.
-    pub fn add_note(&mut self, note: String) {
-        // these are the only possible values that can exist when checking the staff list:
-        // staff exists: if staff has tabs, create new staff; else, continue
-        // staff does not exist: create new staff
-        match self.staffs.last() {
-            Some(staff) if staff.has_tabs => self.create_staff(),
-            None => self.create_staff(),
-            _ => (),
+    /// Ends the provided tuplet. A tuplet written with fewer beats than it fits is padded with empty
+    /// cells up to its end. A partially filled beat column is filled with empty cells first.
+    fn end_tuplet(&mut self, tuplet: Tuplet) {
+        if self.string_pos != self.notes.len().saturating_sub(1) {
+            self.fill_column(|| Cell::Empty);
         }
 
-        // staff will either be a new staff or a staff with no tabs; safe to unwrap value
-        if let Some(staff) = self.staffs.last_mut() {
-            staff.add_note(note).unwrap();
+        let written = self.column.saturating_sub(tuplet.start) / self.stride;
+        self.stride = tuplet.stride;
+        if written != tuplet.notes as usize {
+            self.warnings.push((tuplet.line, "P012", format!(
+                "The tuplet on line {} fits {} beats but was written with {}.", tuplet.line, tuplet.notes, written
+            )));
+        }
+        if self.column < tuplet.end {
+            self.skip_to(tuplet.end);
         }
     }
 
-    /// Adds a tab to the most recently added staff.
-    pub fn add_tab(&mut self, tab: &String) {
-        if let Some(staff) = self.staffs.last_mut() {
-            staff.add_tab(tab);
-        }
+    /// Adds a dynamic marking at the beat currently being filled.
+    pub fn add_dynamic(&mut self, dynamic: Dynamic) {
+        self.markings.push((self.column, Marking::Dynamic(dynamic)));
     }
 
-    /// Adds an empty tab to the most recently added staff.
-    pub fn add_empty(&mut self) {
-        if let Some(staff) = self.staffs.last_mut() {
-            staff.add_empty();
-        }
+    /// Adds a tempo change in beats per minute at the beat currently being filled.
+    pub fn add_tempo(&mut self, bpm: u32) {
+        self.markings.push((self.column, Marking::Tempo(bpm)));
     }
 
-    /// Adds empty tabs to the most recently added staff until the guitar string position resets.
-    pub fn add_next(&mut self) {
-        if let Some(staff) = self.staffs.last_mut() {
-            staff.add_next();
-        }
+    /// Adds a lyric syllable sung at the beat currently being filled.
+    pub fn add_lyric(&mut self, lyric: &str) {
+        self.lyrics.push((self.column, lyric.to_string()));
     }
 
-    /// Adds empty tabs to the most recently added staff for the provided amount of times.
-    pub fn add_spread_empty(&mut self, amt: u32) {
-        if let Some(staff) = self.staffs.last_mut() {
-            staff.add_spread_empty(amt);
-        }
+    /// Adds a chord name printed above the beat currently being filled.
+    pub fn add_chord_name(&mut self, name: &str) {
+        self.chord_names.push((self.column, name.to_string()));
     }
 
-    /// Adds empty tabs to the most recently added staff for the provided amount of times, each time
-    /// until the guitar string position resets.
-    pub fn add_spread_next(&mut self, amt: u32) {
-        if let Some(staff) = self.staffs.last_mut() {
-            staff.add_spread_next(amt);
-        }
+    /// Adds a barre or hand position held from the beat currently being filled.
+    pub fn add_hand_position(&mut self, hand_position: HandPosition) {
+        self.hand_positions.push((self.column, hand_position));
     }
 
-    /// Sets global options on the staff manager based on the provided literal. Current
-    /// and new staffs will have these options applied to them.
-    /// 
-    /// # Errors
-    /// 
-    /// This function errors if provided options contain syntax errors or unknown option names or values.
-    pub fn set_options(&mut self, options: &str) -> Result<(), String> {
-        self.options.set(options)
+    /// Adds a repeat start bar-line. A repeat written partway through a measure fills the rest of the
+    /// measure with empty tabs first so the repeat is placed on a bar-line.
+    pub fn add_repeat_start(&mut self) {
+        self.fill_measure();
+        self.repeats.push((self.column, Repeat::Start));
+        self.bar_start = self.column;
     }
 
-    /// Creates a new staff with the current global options and appends it to the staff list.
-    fn create_staff(&mut self) {
-        let mut new_staff = Staff::new();
-        // new staff will never have tabs so it is okay to unwrap values
-        new_staff.set_time_signature(self.options.get_time_signature()).unwrap();
-        new_staff.set_time_fidelity(self.options.get_time_fidelity()).unwrap();
+    /// Adds a bar-line checking that the measure written before it is complete. A bar-line written
+    /// partway through a measure is kept to be reported as a short or overfull measure, and fills the rest
+    /// of the measure with empty tabs so the tabs after it start on a downbeat.
+    pub fn add_bar_line(&mut self) {
+        if self.notes.is_empty() { return }
 
-        self.staffs.push(new_staff);
+        if self.string_pos != self.notes.len() - 1 {
+            self.fill_column(|| Cell::Empty);
+        }
+        let measure_length = self.time.total_beats_per_measure().max(1) as usize;
+        if !self.column.is_multiple_of(measure_length) {
+            self.misplaced_bars.push((self.bar_start..self.column, self.line));
+            self.fill_measure();
+        }
+        self.bar_start = self.column;
     }
-}
 
-impl fmt::Display for StaffManager {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut staffs = String::new();
-        for staff in self.staffs.iter() {
-            staffs.push_str(&(staff.to_string() + "\n"));
+    /// Adds a repeat end bar-line for a section played the provided amount of times. A repeat written
+    /// partway through a measure fills the rest of the measure with empty tabs first so the repeat is
+    /// placed on a bar-line. Counts other than `2` are printed above the bar-line.
+    pub fn add_repeat_end(&mut self, times: u32) {
+        self.fill_measure();
+        let column = self.column;
+        self.repeats.push((column, Repeat::End(times)));
+        self.bar_start = column;
+        if times != 2 {
+            self.markings.push((column, Marking::Repeat(times)));
         }
-        write!(f, "{}", staffs)
     }
-}
 
-/// Used for parsing the provided source `Vec<Token>` into an output string representing
-/// guitar tablature notation.
-/// 
-/// # Examples
-/// 
-/// ```
-/// use data::{Token, TokenType, Literal};
-/// use parser::Parser;
-/// 
-/// let tokens = vec![
-///     Token::new(TokenType::Note, String::from("E"), Literal::None, 1),
-///     Token::new(TokenType::Note, String::from("A"), Literal::None, 1),
-///     Token::new(TokenType::Note, String::from("D"), Literal::None, 1),
-///     Token::new(TokenType::Note, String::from("G"), Literal::None, 1),
-///     Token::new(TokenType::Note, String::from("B"), Literal::None, 1),
-///     Token::new(TokenType::Note, String::from("E"), Literal::None, 1),
-///     Token::new(TokenType::Number, String::from("0"), Literal::Number(0), 2),
-///     Token::new(TokenType::Number, String::from("3"), Literal::Number(3), 2),
-///     Token::new(TokenType::Number, String::from("5"), Literal::Number(5), 2),
-///     Token::new(TokenType::EndOfFile, String::new(), Literal::None, 2),
-/// ];
-/// 
-/// let mut parser = Parser::new(&tokens);
-/// let tab_string = parser.generate_tabs();
-/// 
-/// match parser.generate_tabs() {
-///     Ok(tab_string) => println!("Guitar tabs:\n\n{}", tab_string),
-///     Err(e) => panic!("Could not generate tabs: {}", e),
-/// }
-/// ```
-pub struct Parser<'a> {
-    source: &'a Vec<Token>,
-    tabs: String,
-    watcher: Watcher,
-}
+    /// Fills the rest of the measure currently being filled with empty tabs so the next tab starts on
+    /// a downbeat.
+    fn fill_measure(&mut self) {
+        if self.notes.is_empty() { return }
 
-impl<'a> Parser<'a> {
-    /// Creates a new `Parser` for parsing through tokens and generating guitar tablature notation.
-    pub fn new(source: &Vec<Token>) -> Parser {
-        Parser {
-            source,
-            tabs: String::new(),
-            watcher: Watcher::new(),
+        if self.string_pos != self.notes.len() - 1 {
+            self.fill_column(|| Cell::Empty);
+        }
+        let measure_length = self.time.total_beats_per_measure().max(1) as usize;
+        // beats within a tuplet may step over the bar-line, so columns are filled until it is reached
+        let end = self.column.next_multiple_of(measure_length);
+        while self.column < end {
+            self.fill_column(|| Cell::Empty);
         }
     }
 
-    /// Creates a string representing guitar tablature notation from the provided source tokens.
-    pub fn generate_tabs(&mut self) -> Result<&str, String> {
-        if self.tabs.is_empty() {
-            // create a new staff manager to add token values to
-            let mut staff_manager = StaffManager::new();
-
-            for token in self.source.iter() {
-                // check the token type and add to the staff manager based on type
-                match token.type_of {
-                    TokenType::Note => staff_manager.add_note(token.value.to_string()),
-                    TokenType::Number => staff_manager.add_tab(&token.value),
-                    TokenType::Empty => staff_manager.add_empty(),
-                    TokenType::Next => staff_manager.add_next(),
-                    TokenType::SpreadEmpty => {
-                        if let Literal::Number(amt) = token.literal {
-                            staff_manager.add_spread_empty(amt);
+    /// Returns the bar-line printed before the beat column: `|`, or `||:` and `:||` for the start and
+    /// end of a repeat, or `:||:` where one repeat ends and the next starts.
+    fn barline(&self, column: usize) -> &'static str {
+        let start = self.repeats.iter().any(|(at, repeat)| *at == column && *repeat == Repeat::Start);
+        let end = self.repeats.iter().any(|(at, repeat)| *at == column && matches!(repeat, Repeat::End(_)));
+        match (end, start) {
+            (false, false) => "|",
+            (false, true) => "||:",
+            (true, false) => ":||",
+            (true, true) => ":||:",
+        }
+    }
+
+    /// Re-renders the existing cells of the staff onto the grid of a new beat fidelity, inserting
+    /// empty cells when the grid gets finer and merging cells when it gets coarser. Tabs that collide
+    /// with an earlier tab on the new grid are snapped to the next free beat slot of their lane, and are
+    /// only dropped when no free beat slot is left after them. Returns a list of warnings for tabs that
+    /// did not align to the new grid and had to be snapped or dropped.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if the last beat of the staff has only been partially filled.
+    pub fn rescale_fidelity(&mut self, fidelity: u32) -> Result<Vec<String>, ParseError> {
+        if self.string_pos != self.notes.len().saturating_sub(1) {
+            return Err(self.reject(String::from("cannot rescale fidelity while a beat is partially filled.")))
+        }
+
+        let old = self.time.get_fidelity();
+        self.time.set_fidelity(fidelity);
+        let new = self.time.get_fidelity();
+
+        // every lane has the same amount of cells since no beat is partially filled
+        let columns = self.time.total_beats_counted;
+        let new_columns = (columns * new).div_ceil(old);
+        let mut warnings = vec![];
+
+        for lane in self.tabs.iter_mut() {
+            let mut rescaled: Vec<Cell> = (0..new_columns).map(|_| Cell::Empty).collect();
+            for (pos, cell) in lane.drain(..).enumerate() {
+                let pos = pos as u32;
+                // the cell covers the new grid from its (snapped) start up until the next cell's start
+                let start = pos * new / old;
+                let end = ((pos + 1) * new).div_ceil(old).max(start + 1);
+                match cell {
+                    Cell::Tab { value: tab, highlight, articulation, vibrato, accent, staccato, duration, harmonic, grace, finger, line } => {
+                        let free = (start as usize..rescaled.len()).find(|slot| !matches!(rescaled[*slot], Cell::Tab { .. }));
+                        match (&rescaled[start as usize], free) {
+                            (Cell::Tab { value: kept, .. }, None) => warnings.push(format!(
+                                "Tab \"{}\" from line {} at beat slot {} collides with tab \"{}\" at fidelity {} and was dropped as no free beat slot is left after it.",
+                                tab, line, pos + 1, kept, new
+                            )),
+                            (Cell::Tab { value: kept, .. }, Some(slot)) => warnings.push(format!(
+                                "Tab \"{}\" from line {} at beat slot {} collides with tab \"{}\" at fidelity {} and was snapped to the next free beat slot {}.",
+                                tab, line, pos + 1, kept, new, slot + 1
+                            )),
+                            _ if !(pos * new).is_multiple_of(old) => warnings.push(format!(
+                                "Tab \"{}\" from line {} at beat slot {} does not align to fidelity {} and was snapped to beat slot {}.",
+                                tab, line, pos + 1, new, start + 1
+                            )),
+                            _ => (),
                         }
-                    },
-                    TokenType::SpreadNext => {
-                        if let Literal::Number(amt) = token.literal {
-                            staff_manager.add_spread_next(amt);
+                        if let Some(slot) = free {
+                            rescaled[slot] = Cell::Tab { value: tab, highlight, articulation, vibrato, accent, staccato, duration, harmonic, grace, finger, line };
                         }
                     },
-                    TokenType::Options => {
-                        if let Literal::Options(ops) = &token.literal {
-                            if let Err(e) = staff_manager.set_options(ops) {
-                                self.watcher.error(token.line, format!("\n{}", e));
-                            }
+                    // rests fill every cell they cover that is not already taken by a tab
+                    Cell::Rest => {
+                        for target in rescaled[start as usize..end as usize].iter_mut() {
+                            if *target == Cell::Empty { *target = Cell::Rest; }
                         }
                     },
-                    TokenType::EndOfFile => (),
+                    // muted strings keep their place unless a tab was already placed there
+                    Cell::Muted => {
+                        if rescaled[start as usize] == Cell::Empty { rescaled[start as usize] = Cell::Muted; }
+                    },
+                    Cell::Empty => (),
                 }
             }
-            self.tabs = staff_manager.to_string();
+            *lane = rescaled;
         }
-
-        // if there was a syntax error, return an error; otherwise return the token list
-        if self.watcher.had_error {
-            Err(self.watcher.to_string())
-        } else {
-            Ok(&self.tabs)
+        for (column, _) in self.markings.iter_mut() {
+            *column = *column * new as usize / old as usize;
         }
-    }
-}
+        for (column, _) in self.repeats.iter_mut() {
+            *column = *column * new as usize / old as usize;
+        }
+        for (column, _) in self.lyrics.iter_mut().chain(self.chord_names.iter_mut()) {
+            *column = *column * new as usize / old as usize;
+        }
+        for (column, _) in self.hand_positions.iter_mut() {
+            *column = *column * new as usize / old as usize;
+        }
+        let spans = self.spans.iter_mut().map(|(columns, _)| columns);
+        for columns in spans.chain(self.misplaced_bars.iter_mut().map(|(columns, _)| columns)) {
+            *columns = columns.start * new as usize / old as usize..columns.end * new as usize / old as usize;
+        }
+        self.bar_start = self.bar_start * new as usize / old as usize;
+        for group in self.groups.iter_mut() {
+            if let Group::Span(_, column, _) = group {
+                *column = *column * new as usize / old as usize;
+            }
+        }
+        self.time.total_beats_counted = new_columns;
+        self.column = (self.column * new as usize).div_ceil(old as usize);
+        self.last_tab = self.last_tab.map(|(lane, column)| (lane, column * new as usize / old as usize));
+
+        Ok(warnings)
+    }
+
+    /// Adds a cell to the lane at the current string position and beat column. Cells added to a column
+    /// already filled by an earlier voice are merged with it: tabs and rests only replace empty cells,
+    /// and a tab on a cell that already has a tab is dropped with a warning.
+    fn add_cell(&mut self, cell: Cell) {
+        // make sure the tabs vector has a lane available at the string position
+        let (string_pos, column, voice, line) = (self.string_pos, self.column, self.voice, self.line);
+        if let Some(tab_lane) = self.tabs.get_mut(string_pos) {
+            // lanes left short by an earlier voice are padded up to the column
+            while tab_lane.len() < column {
+                tab_lane.push(Cell::Empty);
+            }
+            let added = match (tab_lane.get_mut(column), cell) {
+                (None, cell) => {
+                    let added = matches!(cell, Cell::Tab { .. });
+                    tab_lane.push(cell);
+                    added
+                },
+                (Some(Cell::Tab { value: kept, .. }), Cell::Tab { value, .. }) => {
+                    self.warnings.push((line, "P007", format!(
+                        "Tab \"{}\" from voice {} on line {} is played on the same string and beat as tab \"{}\" and was dropped.",
+                        value, voice, line, kept
+                    )));
+                    false
+                },
+                (Some(existing), cell) if *existing == Cell::Empty || (*existing == Cell::Rest && matches!(cell, Cell::Tab { .. })) => {
+                    let added = matches!(cell, Cell::Tab { .. });
+                    *existing = cell;
+                    added
+                },
+                (Some(_), _) => false,
+            };
+            if added {
+                self.last_tab = Some((string_pos, column));
+            }
+            self.has_tabs = true;
+            self.update_string_pos();
+        } else {
+            // the cell is dropped, but the string position still moves on so the cells after it keep their strings
+            self.warnings.push((line, "P015", format!(
+                "A cell was dropped because there is no note lane at string position {}.", string_pos
+            )));
+            self.update_string_pos();
+        }
+    }
+
+    /// Starts the next voice of the staff from its first beat column, so an independent line such as
+    /// a bass line can be written under a melody. A partially filled beat column of the current voice
+    /// is filled with empty cells first.
+    pub fn add_voice(&mut self) {
+        if !self.notes.is_empty() && self.string_pos != self.notes.len() - 1 {
+            self.fill_column(|| Cell::Empty);
+        }
+        self.voice += 1;
+        self.column = 0;
+        self.bar_start = 0;
+        self.last_tab = None;
+    }
+
+    /// Sets the source line of the cells added from now on.
+    pub fn set_line(&mut self, line: u32) {
+        self.line = line;
+    }
+
+    /// Sets the char column within its line of the element added next, which errors are placed at.
+    pub fn set_column(&mut self, column: u32) {
+        self.source_column = column;
+    }
+
+    /// Returns an error rejecting the element currently being added for the provided reason.
+    fn reject(&self, message: String) -> ParseError {
+        ParseError::Rejected { line: self.line, column: self.source_column, message }
+    }
+
+    /// Adds cells from the current string position to the first (and final) string position.
+    fn fill_column(&mut self, cell: impl Fn() -> Cell) {
+        for _ in 0..=self.string_pos {
+            self.add_cell(cell());
+        }
+    }
+
+    /// Returns every measure of the staff in order, with the printed cells of each lane.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use parser::Staff;
+    /// 
+    /// let mut staff = Staff::new();
+    /// staff.add_note(String::from("E")).unwrap();
+    /// staff.set_time_fidelity(4).unwrap();
+    /// staff.add_spread_next(5);
+    /// 
+    /// let starts: Vec<u32> = staff.measures().map(|measure| measure.start_beat).collect();
+    /// assert_eq!(vec![0, 4], starts);
+    /// ```
+    pub fn measures(&self) -> impl Iterator<Item = MeasureView> + '_ {
+        let end = self.tabs.iter().map(|lane| lane.len()).max().unwrap_or(0);
+        let slots_per_beat = self.time.slots_per_beat() as usize;
+
+        self.time.measures(0, end).into_iter().map(move |measure| MeasureView {
+            start_beat: (measure.start / slots_per_beat) as u32,
+            start_column: measure.start,
+            lanes: self.tabs.iter().map(|lane| {
+                // lanes cut short by an unfinished beat only show the cells they have
+                let columns = measure.start..measure.end.min(lane.len()).max(measure.start);
+                self.time.render_measure(columns, self.barline(measure.start), '-', None, |c| {
+                    format!("{:-<width$}", lane[c].to_string(), width = self.column_width(c, None))
+                })
+            }).collect(),
+        })
+    }
+
+    /// Returns every distinct chord of the staff in the order they first occur. A chord is a beat
+    /// column where at least two strings are played; each chord holds the fret played on every string,
+    /// ordered the same as the notes, or `None` if the string is not played.
+    fn chords(&self) -> Vec<Vec<Option<u32>>> {
+        let columns = self.tabs.iter().map(|lane| lane.len()).max().unwrap_or(0);
+        let mut chords: Vec<Vec<Option<u32>>> = vec![];
+        for column in 0..columns {
+            // lanes are stored in reverse note order
+            let chord: Vec<Option<u32>> = self.tabs.iter().rev().map(|lane| match lane.get(column) {
+                Some(Cell::Tab { value, .. }) => value.parse::<u32>().ok(),
+                _ => None,
+            }).collect();
+
+            if chord.iter().flatten().count() >= 2 && !chords.contains(&chord) {
+                chords.push(chord);
+            }
+        }
+        chords
+    }
+
+    /// Returns the char width of the widest measure of this staff, which is at least the width of a full
+    /// measure of 3 char columns.
+    fn measure_width(&self) -> usize {
+        let end = self.tabs.iter().map(|lane| lane.len()).max().unwrap_or(0);
+        self.time.measures(0, end).into_iter()
+            .map(|measure| self.barline(measure.start).len() + measure.map(|column| self.column_width(column, None)).sum::<usize>())
+            .chain(std::iter::once(self.time.measure_width()))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Returns the char width of the provided beat column: the width of its widest cell as displayed
+    /// with the provided layout, and at least 3 chars.
+    fn column_width(&self, column: usize, layout: Option<&Layout>) -> usize {
+        self.tabs.iter().zip(self.notes.iter().rev())
+            .filter_map(|(lane, note)| lane.get(column).map(|cell| match layout {
+                Some(layout) => self.display_cell(cell, note, layout).to_string().chars().count(),
+                None if self.drums => Staff::drum_cell(cell).to_string().chars().count(),
+                None => cell.to_string().chars().count(),
+            }))
+            .fold(3, usize::max)
+    }
+
+    /// Returns the char offset of the provided beat column within a printed lane starting at the `start`
+    /// beat column, including the note name and bar-line characters that come before it.
+    fn column_offset(&self, column: usize, start: usize, layout: &Layout) -> usize {
+        let width = |c| self.column_width(c, Some(layout));
+        // notes take up the first 3 chars
+        let mut offset = 3;
+        for measure in self.time.measures(start, column + 1) {
+            let rendered = self.time.render_measure(measure.clone(), self.barline(measure.start), '-', layout.measure_width, |c| "-".repeat(width(c)));
+            if measure.contains(&column) {
+                // padding is only added after the last column, so the offset is counted from the start
+                let bar = if measure.start % self.time.total_beats_per_measure().max(1) as usize == 0 { self.barline(measure.start).len() } else { 0 };
+                return offset + bar + (measure.start..column).map(width).sum::<usize>()
+            }
+            offset += rendered.len();
+        }
+        offset
+    }
+
+    /// Renders the staff from the `start` beat column onwards using the provided layout. Measures are
+    /// numbered from `first_measure`, the number of the first measure of the staff.
+    fn render(&self, layout: &Layout, start: usize, first_measure: usize) -> String {
+        let end = self.tabs.iter().map(|lane| lane.len()).max().unwrap_or(0);
+        let measures = self.time.measures(start, end);
+
+        // break the staff into blocks of measures; without a limit the whole staff is one block
+        let per_block = layout.measures_per_line.unwrap_or(measures.len()).max(1);
+        let width = |c| self.column_width(c, Some(layout));
+        let mut blocks: Vec<Vec<Range<usize>>> = vec![];
+        // notes take up the first 3 chars of every block
+        let mut block_width = 3;
+        for measure in measures {
+            let measure_width = self.time.render_measure(measure.clone(), self.barline(measure.start), '-', layout.measure_width, |c| "-".repeat(width(c))).len();
+            // a measure that would go past the line width starts a new block, unless it is the first of its block
+            let wraps = layout.line_width.is_some_and(|line_width| block_width + measure_width > line_width);
+            match blocks.last_mut() {
+                Some(block) if block.len() < per_block && !wraps => {
+                    block.push(measure);
+                    block_width += measure_width;
+                },
+                _ => {
+                    blocks.push(vec![measure]);
+                    block_width = 3 + measure_width;
+                },
+            }
+        }
+        if blocks.is_empty() {
+            return self.render_block(layout, start, end, true, first_measure)
+        }
+
+        blocks.iter()
+            .enumerate()
+            .map(|(i, block)| {
+                self.render_block(layout, block[0].start, block[block.len() - 1].end, i == blocks.len() - 1, first_measure)
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// Renders the beat columns from `start` up to `end` as a block of lanes with their note names and
+    /// a beat count line below them. If the layout has a measure width, every measure (including a
+    /// partially filled last measure) is padded to that char width so measures line up across staffs.
+    /// The `last` block also shows any markings placed after the final beat column.
+    fn render_block(&self, layout: &Layout, start: usize, end: usize, last: bool, first_measure: usize) -> String {
+        let measure_width = layout.measure_width;
+        let mut tabs = String::new();
+
+        // right-to-left blocks render the chars of every cell backwards and then mirror each whole line,
+        // which puts the cells back in order while reversing the order of the cells and bar lines
+        let rtl = layout.rtl;
+        let cell = |text: String| if rtl { text.chars().rev().collect() } else { text };
+        // cells wider than 3 chars widen every cell of their column
+        let column_width = |c| self.column_width(c, Some(layout));
+        // lines other than lanes are marked up as a whole
+        let markup = |class: &str, line: &str| if layout.html { html::span(class, line) } else { line.to_string() };
+
+        let mut lanes: Vec<String> = self.tabs.iter().zip(self.notes.iter().rev()).map(|(lane, note)| {
+            let mut t = String::new();
+            for measure in self.time.measures(start, end.min(lane.len())) {
+                // add a bar-line before every downbeat
+                let bar = self.barline(measure.start);
+                t.push_str(&self.time.render_measure(measure, bar, '-', measure_width, |c| {
+                    cell(format!("{:-<width$}", self.display_cell(&lane[c], note, layout).to_string(), width = column_width(c)))
+                }));
+            }
+            t
+        }).collect();
+        // a repeat after the final beat column closes the last block with its bar-line
+        let closing = self.barline(end);
+        if last && closing != "|" {
+            let lane_width = lanes.iter().map(|t| t.chars().count()).max().unwrap_or(0);
+            for t in lanes.iter_mut() {
+                *t = format!("{:-<width$}{}", t, closing, width = lane_width);
+            }
+        }
+        let beats = self.time.beat_line(measure_width, start, end, rtl, column_width, |c| self.barline(c).len());
+        // mirrored lines are padded at the start so lanes of different lengths still line up
+        let width = lanes.iter().map(|t| t.chars().count()).chain(std::iter::once(beats.len() - 3)).max().unwrap_or(0);
+        let mirror = |line: &str| format!("{:>width$}", line.chars().rev().collect::<String>(), width = width);
+
+        // measure numbers are printed above the staff, starting at the bar-line of their measure
+        if layout.measure_numbers {
+            let measure_length = self.time.total_beats_per_measure() as usize;
+            let mut line = String::new();
+            // a measure cut short by seeking has no bar-line, so it is not numbered
+            for measure in self.time.measures(start, end).into_iter().filter(|measure| measure.start % measure_length == 0) {
+                let offset = self.column_offset(measure.start, start, layout) - self.barline(measure.start).len();
+                // numbers that would overlap are separated by a single space
+                let padding = if line.len() < offset { offset - line.len() } else { 1 };
+                line.push_str(&" ".repeat(padding));
+                line.push_str(&cell((first_measure + measure.start / measure_length).to_string()));
+            }
+            if rtl {
+                line = mirror(line.get(3..).unwrap_or_default()).trim_end().to_string();
+            }
+            if !line.is_empty() {
+                tabs.push_str(&format!("{}\n", markup("measure-numbers", &line)));
+            }
+        }
+
+        // lines of text lined up with the tab char of their beat column, or nothing if no text is placed
+        // within the block
+        let text_line = |texts: &[(usize, String)]| {
+            let texts: Vec<&(usize, String)> = texts.iter()
+                .filter(|(column, _)| *column >= start && (*column < end || last))
+                .collect();
+            if texts.is_empty() {
+                return None
+            }
+            let mut line = String::new();
+            for (column, text) in texts {
+                let offset = self.column_offset(*column, start, layout) + 1;
+                // texts that would overlap are separated by a single space
+                let padding = if line.chars().count() < offset { offset - line.chars().count() } else { 1 };
+                line.push_str(&" ".repeat(padding));
+                line.push_str(&cell(text.to_string()));
+            }
+            if rtl {
+                line = mirror(line.get(3..).unwrap_or_default()).trim_end().to_string();
+            }
+            Some(line)
+        };
+
+        // markings are printed above the staff, lined up with the tab char of their beat column
+        let markings: Vec<&(usize, Marking)> = self.markings.iter()
+            .filter(|(column, _)| *column >= start && (*column < end || last))
+            .collect();
+        if !markings.is_empty() {
+            let mut line = String::new();
+            for (column, marking) in markings {
+                let offset = match marking {
+                    // repeat counts are printed above the start of their bar-line
+                    Marking::Repeat(_) => self.column_offset(*column, start, layout) - self.barline(*column).len(),
+                    _ => self.column_offset(*column, start, layout) + 1,
+                };
+                // markings that would overlap are separated by a single space
+                let padding = if line.len() < offset { offset - line.len() } else { 1 };
+                line.push_str(&" ".repeat(padding));
+                line.push_str(&cell(marking.to_string()));
+            }
+            if rtl {
+                // the note names are printed after the lanes instead of before them
+                line = mirror(line.get(3..).unwrap_or_default()).trim_end().to_string();
+            }
+            tabs.push_str(&format!("{}\n", markup("markings", &line)));
+        }
+
+        // technique spans are printed above the staff with a line for every technique, running from the tab
+        // char of their first beat column to the tab char of their last, where spans ending in the block
+        // are closed with a '|'
+        for technique in Technique::ALL {
+            let spans: Vec<&Range<usize>> = self.spans.iter()
+                .filter(|(columns, kind)| *kind == technique && columns.start < end && columns.end > start)
+                .map(|(columns, _)| columns)
+                .collect();
+            if spans.is_empty() {
+                continue
+            }
+            let mut line = String::new();
+            for columns in spans {
+                let from = self.column_offset(columns.start.max(start), start, layout) + 1;
+                let to = self.column_offset(columns.end.min(end) - 1, start, layout) + 1;
+                let close = if columns.end <= end { '|' } else { '-' };
+                let text = format!("{:-<width$}{}", technique.to_string(), close, width = to.saturating_sub(from));
+                // spans that would overlap are separated by a single space
+                let padding = if line.chars().count() < from { from - line.chars().count() } else { 1 };
+                line.push_str(&" ".repeat(padding));
+                line.push_str(&cell(text));
+            }
+            if rtl {
+                line = mirror(line.get(3..).unwrap_or_default()).trim_end().to_string();
+            }
+            tabs.push_str(&format!("{}\n", markup("techniques", &line)));
+        }
+
+        // barres and hand positions are printed above the chord names
+        let hand_positions: Vec<(usize, String)> = self.hand_positions.iter()
+            .map(|(column, hand_position)| (*column, hand_position.to_string()))
+            .collect();
+        if let Some(line) = text_line(&hand_positions) {
+            tabs.push_str(&format!("{}\n", markup("hand-positions", &line)));
+        }
+
+        // chord names are printed above the lanes, along with the names of the chords analyzed from beat
+        // columns without one
+        let chord_names = match layout.analyze_chords {
+            true => self.analyzed_chord_names(layout),
+            false => self.chord_names.clone(),
+        };
+        if let Some(line) = text_line(&chord_names) {
+            tabs.push_str(&format!("{}\n", markup("chord-names", &line)));
+        }
+
+        // accents and staccato dots are printed directly above the lanes, lined up with the tab char of
+        // their beat column
+        let columns = self.tabs.iter().map(|lane| lane.len()).max().unwrap_or(0);
+        let marks: Vec<(usize, String)> = (0..columns)
+            .map(|column| {
+                let marked = |mark: fn(&Cell) -> bool| self.tabs.iter().any(|lane| lane.get(column).is_some_and(mark));
+                let accent = marked(|cell| matches!(cell, Cell::Tab { accent: true, .. }));
+                let staccato = marked(|cell| matches!(cell, Cell::Tab { staccato: true, .. }));
+                (column, format!("{}{}", if accent { ">" } else { "" }, if staccato { "." } else { "" }))
+            })
+            .filter(|(_, marks)| !marks.is_empty())
+            .collect();
+        if let Some(line) = text_line(&marks) {
+            tabs.push_str(&format!("{}\n", markup("articulations", &line)));
+        }
+
+        // zip together both notes and tabs to print to their respective lines
+        for ((n, t), lane) in self.notes.iter().rev().zip(lanes.iter()).zip(self.tabs.iter()) {
+            let n = if rtl { format!("{:>2}", n) } else { format!("{:<2}", n) };
+            let (n, t) = match (layout.html, rtl) {
+                (true, _) => (markup("note", &n), html::lane(self, lane, t, (start, end), layout, first_measure, Some(width).filter(|_| rtl))),
+                (false, true) => (n, mirror(t)),
+                (false, false) => (n, t.to_string()),
+            };
+            if rtl {
+                tabs.push_str(&format!("{} {}\n", t, n));
+            } else {
+                tabs.push_str(&format!("{} {}\n", n, t));
+            }
+        }
+        // fingerings are printed below the lanes, lined up with the tab char of their beat column, with the
+        // fingers of a beat column listed from the highest string
+        if layout.fingering {
+            let fingers: Vec<(usize, String)> = (0..columns)
+                .map(|column| (column, self.tabs.iter()
+                    .filter_map(|lane| match lane.get(column) {
+                        Some(Cell::Tab { finger: Some(finger), .. }) => Some(finger.to_string()),
+                        _ => None,
+                    })
+                    .collect::<String>()))
+                .filter(|(_, fingers)| !fingers.is_empty())
+                .collect();
+            if let Some(line) = text_line(&fingers) {
+                tabs.push_str(&format!("{}\n", markup("fingering", &line)));
+            }
+        }
+        let beats = if rtl { format!("{}   ", mirror(&beats[3..])) } else { beats };
+        let mut beats = markup("beats", &beats);
+
+        // lyrics are printed below the beat counts, lined up with the beat count of their beat column
+        if let Some(line) = text_line(&self.lyrics) {
+            beats.push_str(&format!("\n{}", markup("lyrics", &line)));
+        }
+        if !layout.heatmap {
+            return format!("{}\n{}\n", tabs, beats)
+        }
+
+        // the heatmap fills every measure below its beat counts with a bar as tall as its density
+        let densities = self.densities();
+        let measure_length = self.time.total_beats_per_measure() as usize;
+        let mut heat = String::new();
+        for measure in self.time.measures(start, end) {
+            let bar = HEATMAP_BARS[densities[measure.start / measure_length]];
+            let space = " ".repeat(self.barline(measure.start).len());
+            heat.push_str(&self.time.render_measure(measure, &space, bar, measure_width, |c| bar.to_string().repeat(column_width(c))));
+        }
+        let heat = if rtl { format!("{}   ", mirror(&heat)) } else { format!("   {}", heat) };
+        format!("{}\n{}\n{}\n", tabs, beats, markup("heatmap", &heat))
+    }
+
+    /// Returns the density of every measure of the staff: the amount of tabs played in the measure,
+    /// scaled against the densest measure of the staff to a level from `0` up to `4`.
+    fn densities(&self) -> Vec<usize> {
+        let end = self.tabs.iter().map(|lane| lane.len()).max().unwrap_or(0);
+        let attacks: Vec<usize> = self.time.measures(0, end).into_iter()
+            .map(|measure| self.tabs.iter()
+                .flat_map(|lane| lane.get(measure.clone()).unwrap_or_default())
+                .filter(|cell| matches!(cell, Cell::Tab { .. }))
+                .count())
+            .collect();
+        let densest = attacks.iter().copied().max().unwrap_or(0).max(1);
+        // any measure with a tab shows at least the lowest raised bar
+        attacks.iter().map(|amount| (amount * 4).div_ceil(densest)).collect()
+    }
+
+    /// Returns the written chord names of the staff along with the name of the chord played by every other
+    /// beat column, found from the pitches of its tabs with the tuning and capo. Beat columns that do not
+    /// play a known chord are left without a name.
+    fn analyzed_chord_names(&self, layout: &Layout) -> Vec<(usize, String)> {
+        let pitches = export::pitches(&self.notes);
+        let columns = self.tabs.iter().map(|lane| lane.len()).max().unwrap_or(0);
+        let mut chord_names = self.chord_names.clone();
+        for column in (0..columns).filter(|column| self.chord_names.iter().all(|(named, _)| named != column)) {
+            // lanes run from the highest string down, while pitches run from the lowest string up
+            let played: Vec<u32> = self.tabs.iter().zip(pitches.iter().rev())
+                .filter_map(|(lane, pitch)| match lane.get(column) {
+                    Some(Cell::Tab { value, harmonic: None, .. }) => value.parse::<u32>().ok().map(|fret| *pitch as u32 + layout.capo + fret),
+                    _ => None,
+                })
+                .collect();
+            if let Some(chord) = theory::Chord::from_pitches(&played) {
+                let name = chord.name(|name| layout.key.map_or(name.to_string(), |key| key.spell(name)));
+                chord_names.push((column, name));
+            }
+        }
+        chord_names.sort_by_key(|(column, _)| *column);
+        chord_names
+    }
+
+    /// Returns the cell as it is displayed on the lane of the provided note. With note display turned on,
+    /// tabs are replaced by the name of the note their fret plays. The lanes of drum staffs are labeled
+    /// with drums rather than notes, so their tabs are displayed as hits instead.
+    fn display_cell(&self, cell: &Cell, note: &str, layout: &Layout) -> Cell {
+        if self.drums {
+            return Staff::drum_cell(cell)
+        }
+        match (cell, pitch_class(note)) {
+            (Cell::Tab { value, highlight, articulation, vibrato, accent, staccato, duration, harmonic, grace, finger, line }, Some(open)) if layout.display_notes => match value.parse::<u32>() {
+                Ok(fret) => {
+                    let name = Key::SHARPS[((open as u32 + layout.capo + fret) % 12) as usize];
+                    let value = layout.key.map_or(name.to_string(), |key| key.spell(name));
+                    Cell::Tab {
+                        value,
+                        highlight: *highlight,
+                        articulation: *articulation,
+                        vibrato: *vibrato,
+                        accent: *accent,
+                        staccato: *staccato,
+                        duration: *duration,
+                        harmonic: *harmonic,
+                        grace: *grace,
+                        finger: *finger,
+                        line: *line,
+                    }
+                },
+                Err(_) => cell.clone(),
+            },
+            _ => cell.clone(),
+        }
+    }
+
+    /// Returns the cell of a drum lane as it is displayed: a hit symbol, optionally highlighted, in place
+    /// of a fret. Drums cannot be articulated, held, or played as harmonics, so every technique of a tab
+    /// is left out.
+    fn drum_cell(cell: &Cell) -> Cell {
+        match cell {
+            Cell::Tab { value, highlight, line, .. } => Cell::Tab {
+                value: value.to_string(),
+                highlight: *highlight,
+                articulation: None,
+                vibrato: false,
+                accent: false,
+                staccato: false,
+                duration: None,
+                harmonic: None,
+                grace: None,
+                finger: None,
+                line: *line,
+            },
+            _ => cell.clone(),
+        }
+    }
+
+    /// Updates the current string position. String position starts at `note.len() - 1` and decrements
+    /// until `0` then resets.
+    fn update_string_pos(&mut self) {
+        // a staff without notes has no string positions to move between
+        if self.notes.is_empty() { return }
+
+        self.string_pos = if self.string_pos == 0 {
+            self.skip_to(self.column + self.stride);
+            self.notes.len() - 1
+        } else {
+            self.string_pos - 1
+        };
+    }
+
+    /// Moves the current beat column forward to the provided column. Lanes that have not reached the
+    /// column are padded with empty cells up to it.
+    fn skip_to(&mut self, column: usize) {
+        self.column = column;
+        // only columns past the end of the staff are counted, as earlier voices already counted the rest
+        while (self.time.total_beats_counted as usize) < column {
+            self.time.increment_beat();
+        }
+        for lane in self.tabs.iter_mut() {
+            while lane.len() < column {
+                lane.push(Cell::Empty);
+            }
+        }
+    }
+}
+
+impl Default for Staff {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for Staff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.render(&Layout::default(), 0, 1))
+    }
+}
+
+/// Describes an option that can be set within an options sequence.
+struct OptionSpec {
+    /// The option name written before the `=` sign.
+    name: &'static str,
+    /// The format of the values the option can be set to.
+    values: &'static str,
+    /// The value used when the option is not set.
+    default: &'static str,
+    /// Parses the option value and applies it to the staff options.
+    apply: fn(&mut StaffOptions, &str) -> Result<(), String>,
+}
+
+/// Parses and contains options provided from the source token input and outputs them in a
+/// friendly format.
+struct StaffOptions {
+    time: Time,
+    title: Option<String>,
+    artist: Option<String>,
+    album: Option<String>,
+    tempo: Option<u32>,
+    dynamic: Option<Dynamic>,
+    equal_measures: bool,
+    fixed_width: bool,
+    measures_per_line: Option<usize>,
+    line_width: Option<usize>,
+    key: Option<Key>,
+    count_in: bool,
+    rtl: bool,
+    display_notes: bool,
+    heatmap: bool,
+    measure_numbers: bool,
+    fingering: bool,
+    analyze_chords: bool,
+    /// The strings set by the tuning option, used up by the next staff that tabs are added to.
+    tuning: Option<Vec<String>>,
+    capo: Option<u32>,
+    strings: Option<usize>,
+    drums: bool,
+    /// The ratio of the first eighth note of every pair to the second when swung, if the feel is swing.
+    swing: Option<(u32, u32)>,
+    click: bool,
+}
+
+impl StaffOptions {
+    /// Options that can be scoped to a single staff with `[[` and `]]`.
+    const SCOPED: &'static [&'static str] = &["time", "fidelity", "strings"];
+
+    /// Registry of every supported option. Options are parsed and listed from this registry only.
+    const OPTIONS: &'static [OptionSpec] = &[
+        // a time signature option will have the format "n/n" where 'n' is a number
+        OptionSpec {
+            name: "time",
+            values: "n/n where 'n' is a whole integer",
+            default: "4/4",
+            apply: StaffOptions::parse_time_signature,
+        },
+        // the fidelity value will be a single number value
+        OptionSpec {
+            name: "fidelity",
+            values: "n where 'n' is a whole integer",
+            default: "16",
+            apply: StaffOptions::parse_fidelity,
+        },
+        // the title can be any text and is printed in the header
+        OptionSpec {
+            name: "title",
+            values: "any text",
+            default: "none",
+            apply: StaffOptions::parse_title,
+        },
+        // the artist can be any text and is printed in the header
+        OptionSpec {
+            name: "artist",
+            values: "any text",
+            default: "none",
+            apply: StaffOptions::parse_artist,
+        },
+        // the album can be any text and is printed in the header
+        OptionSpec {
+            name: "album",
+            values: "any text",
+            default: "none",
+            apply: StaffOptions::parse_album,
+        },
+        // the tempo value will be a single number value of beats per minute
+        OptionSpec {
+            name: "tempo",
+            values: "n where 'n' is a whole integer (bpm)",
+            default: "none",
+            apply: StaffOptions::parse_tempo,
+        },
+        // the dynamic marking is placed above the staff at the beat it is set
+        OptionSpec {
+            name: "dyn",
+            values: "pp, p, mp, mf, f, or ff",
+            default: "none",
+            apply: StaffOptions::parse_dynamic,
+        },
+        // equal measures pads every measure of the score to the same width
+        OptionSpec {
+            name: "equalmeasures",
+            values: "on or off",
+            default: "off",
+            apply: StaffOptions::parse_equal_measures,
+        },
+        // fixed width pads every output line to the same char count for monospace embedding
+        OptionSpec {
+            name: "fixedwidth",
+            values: "on or off",
+            default: "off",
+            apply: StaffOptions::parse_fixed_width,
+        },
+        // the layout breaks every staff into blocks of a set amount of measures
+        OptionSpec {
+            name: "layout",
+            values: "measures-per-line=n where 'n' is a whole integer",
+            default: "none",
+            apply: StaffOptions::parse_layout,
+        },
+        // the width wraps every staff into blocks of measures that fit within a char count
+        OptionSpec {
+            name: "width",
+            values: "n where 'n' is a whole integer (chars)",
+            default: "none",
+            apply: StaffOptions::parse_width,
+        },
+        // the key decides how accidentals are spelled when they are normalized
+        OptionSpec {
+            name: "key",
+            values: "a note name followed by 'm' for minor keys",
+            default: "none",
+            apply: StaffOptions::parse_key,
+        },
+        // the tuning sets the strings of the staff without writing out every note
+        OptionSpec {
+            name: "tuning",
+            values: "note names from the lowest string, optionally separated by commas",
+            default: "none",
+            apply: StaffOptions::parse_tuning,
+        },
+        // drum mode reads lane labels and hit symbols instead of note names and frets
+        OptionSpec {
+            name: "mode",
+            values: "tabs or drums",
+            default: "tabs",
+            apply: StaffOptions::parse_mode,
+        },
+        // the string count is checked against the notes written for every staff
+        OptionSpec {
+            name: "strings",
+            values: "n where 'n' is a whole integer from 4 to 12",
+            default: "none",
+            apply: StaffOptions::parse_strings,
+        },
+        // the capo raises the pitch of every string without changing the frets written
+        OptionSpec {
+            name: "capo",
+            values: "n where 'n' is a fret from 0 to 24",
+            default: "0",
+            apply: StaffOptions::parse_capo,
+        },
+        // the feel only changes the timing of exported notes, not the printed tabs
+        OptionSpec {
+            name: "feel",
+            values: "straight, swing, or swing=n:n where 'n' is a whole integer",
+            default: "straight",
+            apply: StaffOptions::parse_feel,
+        },
+        // the click only adds a metronome to exported notes, not the printed tabs
+        OptionSpec {
+            name: "click",
+            values: "on or off",
+            default: "off",
+            apply: StaffOptions::parse_click,
+        },
+        // the count in prints one measure of beat counts above the first staff
+        OptionSpec {
+            name: "countin",
+            values: "on or off",
+            default: "off",
+            apply: StaffOptions::parse_count_in,
+        },
+        // the direction mirrors every staff so the last beat is printed first
+        OptionSpec {
+            name: "direction",
+            values: "ltr or rtl",
+            default: "ltr",
+            apply: StaffOptions::parse_direction,
+        },
+        // the display decides whether tabs are printed as fret numbers or the note names they play
+        OptionSpec {
+            name: "display",
+            values: "frets or notes",
+            default: "frets",
+            apply: StaffOptions::parse_display,
+        },
+        // the heatmap prints a bar below every measure showing how many tabs are played in it
+        OptionSpec {
+            name: "heatmap",
+            values: "on or off",
+            default: "off",
+            apply: StaffOptions::parse_heatmap,
+        },
+        // the measure numbers are printed above the bar-line of every measure
+        OptionSpec {
+            name: "measures",
+            values: "on or off",
+            default: "off",
+            apply: StaffOptions::parse_measure_numbers,
+        },
+        // the fingering prints the finger written after every tab on a line below the staff
+        OptionSpec {
+            name: "fingering",
+            values: "on or off",
+            default: "off",
+            apply: StaffOptions::parse_fingering,
+        },
+        // the analysis names the chords of the tabs played together that have no chord name written
+        OptionSpec {
+            name: "analyze",
+            values: "chords or none",
+            default: "none",
+            apply: StaffOptions::parse_analyze,
+        },
+    ];
+
+    /// Creates a new `StaffOptions` struct with default properties.
+    pub fn new() -> StaffOptions {
+        StaffOptions {
+            time: Time::new(),
+            title: None,
+            artist: None,
+            album: None,
+            tempo: None,
+            dynamic: None,
+            equal_measures: false,
+            fixed_width: false,
+            measures_per_line: None,
+            line_width: None,
+            key: None,
+            count_in: false,
+            rtl: false,
+            display_notes: false,
+            heatmap: false,
+            measure_numbers: false,
+            fingering: false,
+            analyze_chords: false,
+            tuning: None,
+            capo: None,
+            strings: None,
+            drums: false,
+            swing: None,
+            click: false,
+        }
+    }
+
+    /// Returns true if an option with the provided name exists.
+    pub fn exists(name: &str) -> bool {
+        StaffOptions::OPTIONS.iter().any(|spec| spec.name == name.trim())
+    }
+
+    /// Parses provided options literal into formatted option data types.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if the provided literal is not an options literal, the options have syntax
+    /// errors, or if the option name or value is not valid. The error is not placed at a source line or
+    /// column, as the options are parsed apart from their source; place it with `ParseError::at`.
+    pub fn set(&mut self, options: &str) -> Result<(), ParseError> {
+        // used to log all errors that occur
+        let mut errors = String::new();
+
+        // each option will be separated by a semicolon
+        for op in options.split(';') {
+            // if an error occurs, log it and continue the loop
+            if let Err(e) = self.parse_option(op) {
+                errors.push_str(&e);
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ParseError::Rejected { line: 0, column: 0, message: errors })
+        }
+    }
+
+    /// Sets a single option by name to the provided value, which is used as is and may contain the `;`
+    /// and `=` chars that separate options.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if the option name or value is not valid. The error is not placed at a source
+    /// line or column; place it with `ParseError::at`.
+    pub fn set_option(&mut self, name: &str, value: &str) -> Result<(), ParseError> {
+        self.apply_option(name, value).map_err(|message| ParseError::Rejected { line: 0, column: 0, message })
+    }
+
+    /// Gets the time signature.
+    pub fn get_time_signature(&self) -> (u32, u32) {
+        self.time.get_signature()
+    }
+
+    /// Gets the beat fidelity.
+    pub fn get_time_fidelity(&self) -> u32 {
+        self.time.get_fidelity()
+    }
+
+    /// Parses provided option reference string into a formatted option data type.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if the provided option is not set or the option does not exist.
+    fn parse_option(&mut self, option: &str) -> Result<(), String> {
+        // options will be structured as "option=value" and will be split based on that format
+        let o: Vec<&str> = option.trim().splitn(2, '=').collect();
+
+        // check to make sure there are 2 values in the vector; if not, then return an error
+        if o.len() < 2 {
+            return Err(format!("\tOption \"{:?}\" has not been set to a value.\n", o))
+        }
+
+        self.apply_option(o[0].trim(), o[1].trim())
+    }
+
+    /// Finds the option by name and uses the provided value for processing.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if the option does not exist or its value is not valid.
+    fn apply_option(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match StaffOptions::OPTIONS.iter().find(|spec| spec.name == name) {
+            Some(spec) => (spec.apply)(self, value),
+            None => Err(format!("\tOption \"{}\" does not exist.\n", name)),
+        }
+    }
+
+    /// Parse provided reference string into a time signature.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if the provided reference string is improperly formatted or the values
+    /// on either side of the '/' cannot be parsed into whole integers.
+    fn parse_time_signature(&mut self, time_signature: &str) -> Result<(), String> {
+        let t: Vec<&str> = time_signature.trim().split('/').collect();
+        if t.len() < 2 {
+            return Err(format!("\tTime signature option \"{}\" is improperly formatted. Format should equal \"n/n\" where 'n' is a whole integer.\n", time_signature))
+        }
+
+        match (t[0].trim().parse::<u32>(), t[1].trim().parse::<u32>()) {
+            (Ok(b), Ok(d)) => {
+                self.time.set_signature(b, d);
+                Ok(())
+            },
+            (Err(e_b), Err(e_d)) => {
+                Err(format!("\tCould not parse time signature \"{:?}\" into numbers: {:?}\n", (t[0], t[1]), (e_b, e_d)))
+            },
+            (Err(e_b), _) => {
+                Err(format!("\tCould not parse beats per measure (numerator) \"{}\" into a number: {}\n", t[0], e_b))
+            },
+            (_, Err(e_d)) => {
+                Err(format!("\tCould not parse dominant beat (denominator) \"{}\" into a number: {}\n", t[1], e_d))
+            },
+        }
+    }
+
+    /// Parse the provided reference string into a beat fidelity (or resolution; granularity) whole integer.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if the provided reference string is cannot be parsed into a number.
+    fn parse_fidelity(&mut self, fidelity: &str) -> Result<(), String> {
+        match fidelity.trim().parse::<u32>() {
+            Ok(f) => {
+                self.time.set_fidelity(f);
+                Ok(())
+            },
+            Err(e) => Err(format!("\tCould not parse beat fidelity \"{}\" into a number: {}\n", fidelity, e)),
+        }
+    }
+
+    /// Sets the title printed in the header.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if the provided title is empty.
+    fn parse_title(&mut self, title: &str) -> Result<(), String> {
+        if title.trim().is_empty() {
+            return Err(String::from("\tTitle option cannot be empty.\n"))
+        }
+        self.title = Some(title.trim().to_string());
+        Ok(())
+    }
+
+    /// Sets the artist printed in the header.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if the provided artist is empty.
+    fn parse_artist(&mut self, artist: &str) -> Result<(), String> {
+        if artist.trim().is_empty() {
+            return Err(String::from("\tArtist option cannot be empty.\n"))
+        }
+        self.artist = Some(artist.trim().to_string());
+        Ok(())
+    }
+
+    /// Sets the album printed in the header.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if the provided album is empty.
+    fn parse_album(&mut self, album: &str) -> Result<(), String> {
+        if album.trim().is_empty() {
+            return Err(String::from("\tAlbum option cannot be empty.\n"))
+        }
+        self.album = Some(album.trim().to_string());
+        Ok(())
+    }
+
+    /// Parse the provided reference string into a tempo in beats per minute.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if the provided reference string cannot be parsed into a number greater than 0.
+    fn parse_tempo(&mut self, tempo: &str) -> Result<(), String> {
+        match tempo.trim().parse::<u32>() {
+            Ok(0) => Err(String::from("\tTempo must be greater than 0.\n")),
+            Ok(t) => {
+                self.tempo = Some(t);
+                Ok(())
+            },
+            Err(e) => Err(format!("\tCould not parse tempo \"{}\" into a number: {}\n", tempo, e)),
+        }
+    }
+
+    /// Parse the provided reference string into the char width that lines are wrapped at.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if the provided reference string cannot be parsed into a number greater than 0.
+    fn parse_width(&mut self, width: &str) -> Result<(), String> {
+        match width.trim().parse::<usize>() {
+            Ok(0) => Err(String::from("\tWidth must be greater than 0.\n")),
+            Ok(w) => {
+                self.line_width = Some(w);
+                Ok(())
+            },
+            Err(e) => Err(format!("\tCould not parse width \"{}\" into a number: {}\n", width, e)),
+        }
+    }
+
+    /// Parse the provided reference string into a key.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if the provided reference string is not a valid key name.
+    fn parse_key(&mut self, key: &str) -> Result<(), String> {
+        match Key::from_name(key) {
+            Some(k) => {
+                self.key = Some(k);
+                Ok(())
+            },
+            None => Err(format!("\tKey \"{}\" is not a valid key. Format should be a note name followed by 'm' for minor keys.\n", key.trim())),
+        }
+    }
+
+    /// Parse the provided reference string into an on or off switch for equal measure widths.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if the provided reference string is not "on" or "off".
+    fn parse_equal_measures(&mut self, switch: &str) -> Result<(), String> {
+        self.equal_measures = StaffOptions::parse_switch("equalmeasures", switch)?;
+        Ok(())
+    }
+
+    /// Parse the provided reference string into an on or off switch for fixed width output lines.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if the provided reference string is not "on" or "off".
+    fn parse_fixed_width(&mut self, switch: &str) -> Result<(), String> {
+        self.fixed_width = StaffOptions::parse_switch("fixedwidth", switch)?;
+        Ok(())
+    }
+
+    /// Parse the provided reference string into an on or off switch for the count in.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if the provided reference string is not "on" or "off".
+    fn parse_count_in(&mut self, switch: &str) -> Result<(), String> {
+        self.count_in = StaffOptions::parse_switch("countin", switch)?;
+        Ok(())
+    }
+
+    /// Parse the provided reference string into an on or off switch for the fingering line.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if the provided reference string is not "on" or "off".
+    fn parse_fingering(&mut self, switch: &str) -> Result<(), String> {
+        self.fingering = StaffOptions::parse_switch("fingering", switch)?;
+        Ok(())
+    }
+
+    /// Parse the provided reference string into the analysis printed above every staff.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if the provided reference string is not "chords" or "none".
+    fn parse_analyze(&mut self, analyze: &str) -> Result<(), String> {
+        self.analyze_chords = match analyze.trim() {
+            "chords" => true,
+            "none" => false,
+            other => return Err(format!("\tOption \"analyze\" must be set to \"chords\" or \"none\", found \"{}\".\n", other)),
+        };
+        Ok(())
+    }
+
+    /// Parse the provided reference string into an on or off switch for the metronome click.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if the provided reference string is not "on" or "off".
+    fn parse_click(&mut self, switch: &str) -> Result<(), String> {
+        self.click = StaffOptions::parse_switch("click", switch)?;
+        Ok(())
+    }
+
+    /// Parse the provided reference string into a printing direction.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if the provided reference string is not "ltr" or "rtl".
+    fn parse_direction(&mut self, direction: &str) -> Result<(), String> {
+        self.rtl = match direction.trim() {
+            "ltr" => false,
+            "rtl" => true,
+            other => return Err(format!("\tOption \"direction\" must be set to \"ltr\" or \"rtl\", found \"{}\".\n", other)),
+        };
+        Ok(())
+    }
+
+    /// Parse the provided reference string into how tabs are displayed.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if the provided reference string is not "frets" or "notes".
+    fn parse_display(&mut self, display: &str) -> Result<(), String> {
+        self.display_notes = match display.trim() {
+            "frets" => false,
+            "notes" => true,
+            other => return Err(format!("\tOption \"display\" must be set to \"frets\" or \"notes\", found \"{}\".\n", other)),
+        };
+        Ok(())
+    }
+
+    /// Parse the provided reference string into whether the tabs that follow are drum tabs. The lexer
+    /// switches how it reads lane labels and cells when it finds this option.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if the provided reference string is not "tabs" or "drums".
+    fn parse_mode(&mut self, mode: &str) -> Result<(), String> {
+        self.drums = match mode.trim() {
+            "tabs" => false,
+            "drums" => true,
+            other => return Err(format!("\tOption \"mode\" must be set to \"tabs\" or \"drums\", found \"{}\".\n", other)),
+        };
+        Ok(())
+    }
+
+    /// Parse the provided reference string into the amount of strings every staff that follows must have.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if the provided reference string cannot be parsed into a number from 4 to 12.
+    fn parse_strings(&mut self, strings: &str) -> Result<(), String> {
+        match strings.trim().parse::<usize>() {
+            Ok(count) if (4..=12).contains(&count) => {
+                self.strings = Some(count);
+                Ok(())
+            },
+            Ok(count) => Err(format!("\tStrings must be from 4 to 12, found {}.\n", count)),
+            Err(e) => Err(format!("\tCould not parse strings \"{}\" into a number: {}\n", strings, e)),
+        }
+    }
+
+    /// Parse the provided reference string into the feel of the song: `straight`, or `swing` with an
+    /// optional ratio of the first eighth note of every pair to the second that defaults to `2:1`.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if the provided reference string is not a feel, or the swing ratio is
+    /// improperly formatted or has a side of `0`.
+    fn parse_feel(&mut self, feel: &str) -> Result<(), String> {
+        match feel.trim().split_once('=') {
+            None if feel.trim() == "straight" => self.swing = None,
+            None if feel.trim() == "swing" => self.swing = Some((2, 1)),
+            Some((name, ratio)) if name.trim() == "swing" => match StaffOptions::parse_ratio(ratio)? {
+                (0, _) | (_, 0) => return Err(format!("\tSwing ratio \"{}\" must have both sides greater than 0.\n", ratio.trim())),
+                ratio => self.swing = Some(ratio),
+            },
+            _ => return Err(format!("\tFeel \"{}\" does not exist. Format should equal \"straight\", \"swing\", or \"swing=n:n\".\n", feel.trim())),
+        }
+        Ok(())
+    }
+
+    /// Parse the provided reference string into the fret the capo is placed at. A capo at fret `0` is the
+    /// same as no capo.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if the provided reference string cannot be parsed into a fret from 0 to 24.
+    fn parse_capo(&mut self, capo: &str) -> Result<(), String> {
+        match capo.trim().parse::<u32>() {
+            Ok(fret) if fret > 24 => Err(format!("\tCapo must be placed at a fret from 0 to 24, found {}.\n", fret)),
+            Ok(fret) => {
+                self.capo = Some(fret).filter(|fret| *fret > 0);
+                Ok(())
+            },
+            Err(e) => Err(format!("\tCould not parse capo \"{}\" into a number: {}\n", capo, e)),
+        }
+    }
+
+    /// Parse the provided reference string into the note names of every string, from the lowest string
+    /// to the highest. Note names can be written together (`DADGAD`) or separated by commas
+    /// (`D,A,D,G,A,D`).
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if a note name is not valid or the tuning does not have from 4 to 12 strings.
+    fn parse_tuning(&mut self, tuning: &str) -> Result<(), String> {
+        let tuning = tuning.trim();
+        let strings: Vec<String> = if tuning.contains(',') {
+            tuning.split(',').map(|note| note.trim().to_string()).collect()
+        } else {
+            // every note name starts with a letter and may be followed by modifiers
+            let mut strings: Vec<String> = vec![];
+            for c in tuning.chars() {
+                match strings.last_mut() {
+                    Some(note) if !c.is_ascii_uppercase() => note.push(c),
+                    _ => strings.push(c.to_string()),
+                }
+            }
+            strings
+        };
+
+        if let Some(note) = strings.iter().find(|note| pitch_class(note).is_none()) {
+            return Err(format!("\tTuning \"{}\" has an invalid note name \"{}\".\n", tuning, note))
+        }
+        if !(4..=12).contains(&strings.len()) {
+            return Err(format!("\tTuning \"{}\" must have from 4 to 12 strings, found {}.\n", tuning, strings.len()))
+        }
+        self.tuning = Some(strings);
+        Ok(())
+    }
+
+    /// Parse the provided reference string into an on or off switch for the density heatmap.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if the provided reference string is not "on" or "off".
+    fn parse_heatmap(&mut self, switch: &str) -> Result<(), String> {
+        self.heatmap = StaffOptions::parse_switch("heatmap", switch)?;
+        Ok(())
+    }
+
+    /// Parse the provided reference string into an on or off switch for printing measure numbers.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if the provided reference string is not "on" or "off".
+    fn parse_measure_numbers(&mut self, switch: &str) -> Result<(), String> {
+        self.measure_numbers = StaffOptions::parse_switch("measures", switch)?;
+        Ok(())
+    }
+
+    /// Parse the provided reference string into a layout setting.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if the provided reference string is not a known layout or the amount of
+    /// measures per line cannot be parsed into a number greater than 0.
+    fn parse_layout(&mut self, layout: &str) -> Result<(), String> {
+        match layout.trim().split_once('=') {
+            Some((name, amount)) if name.trim() == "measures-per-line" => match amount.trim().parse::<usize>() {
+                Ok(0) => Err(String::from("\tMeasures per line must be greater than 0.\n")),
+                Ok(n) => {
+                    self.measures_per_line = Some(n);
+                    Ok(())
+                },
+                Err(e) => Err(format!("\tCould not parse measures per line \"{}\" into a number: {}\n", amount.trim(), e)),
+            },
+            _ => Err(format!("\tLayout \"{}\" does not exist. Format should equal \"measures-per-line=n\".\n", layout.trim())),
+        }
+    }
+
+    /// Parse the provided reference string into an on (`true`) or off (`false`) switch.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if the provided reference string is not "on" or "off".
+    fn parse_switch(name: &str, switch: &str) -> Result<bool, String> {
+        match switch.trim() {
+            "on" => Ok(true),
+            "off" => Ok(false),
+            other => Err(format!("\tOption \"{}\" must be set to \"on\" or \"off\", found \"{}\".\n", name, other)),
+        }
+    }
+
+    /// Parse the provided reference string into a dynamic marking.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if the provided reference string is not a known dynamic marking.
+    fn parse_dynamic(&mut self, dynamic: &str) -> Result<(), String> {
+        match Dynamic::from_marking(dynamic) {
+            Some(d) => {
+                self.dynamic = Some(d);
+                Ok(())
+            },
+            None => Err(format!("\tDynamic \"{}\" is not one of pp, p, mp, mf, f, or ff.\n", dynamic.trim())),
+        }
+    }
+
+    /// Parses an option value written as a ratio `n:n` where 'n' is a whole integer, such as `2:1`.
+    /// Shared by options that take ratios so they report errors consistently.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if the value is improperly formatted or either side of the ':' cannot be
+    /// parsed into a whole integer.
+    fn parse_ratio(value: &str) -> Result<(u32, u32), String> {
+        let (left, right) = match value.trim().split_once(':') {
+            Some(sides) => sides,
+            None => return Err(format!("\tRatio \"{}\" is improperly formatted. Format should equal \"n:n\" where 'n' is a whole integer.\n", value.trim())),
+        };
+
+        match (left.trim().parse::<u32>(), right.trim().parse::<u32>()) {
+            (Ok(l), Ok(r)) => Ok((l, r)),
+            (Err(e), _) => Err(format!("\tCould not parse \"{}\" of ratio \"{}\" into a number: {}\n", left.trim(), value.trim(), e)),
+            (_, Err(e)) => Err(format!("\tCould not parse \"{}\" of ratio \"{}\" into a number: {}\n", right.trim(), value.trim(), e)),
+        }
+    }
+}
+
+/// Returns a listing of every supported option with the values it accepts and its default value.
+/// 
+/// # Examples
+/// 
+/// ```
+/// let listing = parser::list_options();
+/// 
+/// assert!(listing.contains("fidelity"));
+/// ```
+pub fn list_options() -> String {
+    let mut listing = String::new();
+    for spec in StaffOptions::OPTIONS.iter() {
+        listing.push_str(&format!(
+            "{:<12}{:<40}default: {}\n",
+            spec.name,
+            spec.values,
+            spec.default
+        ));
+    }
+    listing
+}
+
+/// Pads every line of the provided text with spaces so all lines have the same char count. Chars are
+/// counted rather than bytes so lines containing multi-byte chars still line up in monospace fonts.
+fn pad_lines(text: &str) -> String {
+    let width = text.lines().map(|line| line.chars().count()).max().unwrap_or(0);
+    let mut padded = String::new();
+    for line in text.lines() {
+        padded.push_str(line);
+        padded.push_str(&" ".repeat(width - line.chars().count()));
+        padded.push('\n');
+    }
+    padded
+}
+
+/// Returns an ASCII fret diagram of the provided chord, where `notes` are the names of each string
+/// and `frets` the fret played on each string (`None` if the string is not played).
+fn chord_diagram(notes: &[String], frets: &[Option<u32>]) -> String {
+    // every string takes up 2 chars so string names with modifiers still line up
+    let row = |cell: &dyn Fn(Option<u32>) -> String| -> String {
+        frets.iter().map(|f| format!("{:<2}", cell(*f))).collect::<String>().trim_end().to_string()
+    };
+
+    // label the diagram with the frets played on each string
+    let mut diagram = frets.iter()
+        .map(|f| f.map_or(String::from("x"), |f| f.to_string()))
+        .collect::<Vec<String>>()
+        .join(" ");
+    diagram.push('\n');
+    // strings that are not played are marked with 'x' and open strings with 'o' above the nut
+    diagram.push_str(&row(&|f| String::from(match f { None => "x", Some(0) => "o", _ => "" })));
+    diagram.push('\n');
+
+    // show at least 3 frets; chords high up the neck start at their lowest fret instead of the nut
+    let fretted: Vec<u32> = frets.iter().flatten().copied().filter(|f| *f > 0).collect();
+    let lowest = fretted.iter().copied().min().unwrap_or(1);
+    let highest = fretted.iter().copied().max().unwrap_or(1);
+    let start = if highest <= 4 { 1 } else { lowest };
+    if start == 1 {
+        diagram.push_str(&"=".repeat(frets.len() * 2 - 1));
+        diagram.push('\n');
+    }
+    for fret in start..start + (highest - start + 1).max(3) {
+        diagram.push_str(&row(&|f| String::from(if f == Some(fret) { "*" } else { "|" })));
+        if fret == start && start > 1 {
+            diagram.push_str(&format!(" {}fr", fret));
+        }
+        diagram.push('\n');
+    }
+
+    diagram.push_str(notes.iter().map(|n| format!("{:<2}", n)).collect::<String>().trim_end());
+    diagram.push('\n');
+    diagram
+}
+
+/// The global options replaced by scoped options, restored once the staff the scoped options apply to
+/// has ended.
+struct ScopedOptions {
+    signature: (u32, u32),
+    fidelity: u32,
+    strings: Option<usize>,
+    /// Whether the staff the scoped options apply to has been created.
+    started: bool,
+}
+
+/// Manages a list of `Staff` structs by adding new staffs as needed and setting global options on them.
+struct StaffManager {
+    staffs: Vec<Staff>,
+    options: StaffOptions,
+    staff_break: bool,
+    /// The tempo set before any tabs were added; printed in the header.
+    start_tempo: Option<u32>,
+    /// The title, artist, and album of the song; printed in the header.
+    metadata: Metadata,
+    /// The source line of the token currently being added.
+    line: u32,
+    /// The char column within its line of the token currently being added.
+    column: u32,
+    /// The section label set after the last tabs were added; starts off the next staff.
+    section: Option<String>,
+    /// Comments kept since the last tabs were added; printed above the staff the next tabs are added to.
+    comments: Vec<String>,
+    /// Whether the time signature or fidelity changed since the last tabs were added; tabs continuing
+    /// the staff close its current measure first so the change is placed on a bar-line.
+    time_change: bool,
+    /// The global options to restore once the staff that scoped options apply to has ended, if scoped
+    /// options are in effect.
+    scoped: Option<ScopedOptions>,
+    /// Errors found while adding tokens, along with the source line they were found on and their code.
+    errors: Vec<(u32, &'static str, String)>,
+    /// Whether the staffs are rendered as HTML blocks instead of plain text.
+    html: bool,
+}
+
+impl StaffManager {
+    /// Creates a new `StaffManager` with an empty list of staffs.
+    pub fn new() -> StaffManager {
+        StaffManager {
+            staffs: vec![],
+            options: StaffOptions::new(),
+            staff_break: false,
+            start_tempo: None,
+            metadata: Metadata::default(),
+            line: 0,
+            column: 0,
+            section: None,
+            comments: vec![],
+            time_change: false,
+            scoped: None,
+            errors: vec![],
+            html: false,
+        }
+    }
+
+    /// Adds a note to the most recently added staff. If the staff list is empty, or the most recent staff
+    /// already has tabs (and therefore adding a new note would break it), then a new staff is created
+    /// with the provided note inserted into it.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if a note insertion is attempted on a staff that has tabs.
+    pub fn add_note(&mut self, note: String) {
+        // these are the only possible values that can exist when checking the staff list:
+        // staff exists: if staff has tabs, create new staff; else, continue
+        // staff does not exist: create new staff
+        match self.staffs.last() {
+            Some(staff) if staff.has_tabs => self.create_staff(),
+            None => self.create_staff(),
+            _ => (),
+        }
+        self.staff_break = false;
+        self.time_change = false;
+        // notes written after a tuning replace it
+        self.options.tuning = None;
+
+        // staff will either be a new staff or a staff with no tabs; safe to unwrap value
+        if let Some(staff) = self.staffs.last_mut() {
+            staff.add_note(note).unwrap();
+        }
+    }
+
+    /// Adds a tab to the most recently added staff.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if no notes have been added yet.
+    pub fn add_tab(&mut self, tab: &str) -> Result<(), ParseError> {
+        self.last_staff()?.add_tab(tab);
+        Ok(())
+    }
+
+    /// Adds a highlighted tab to the most recently added staff.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if no notes have been added yet.
+    pub fn add_highlighted_tab(&mut self, tab: &str) -> Result<(), ParseError> {
+        self.last_staff()?.add_highlighted_tab(tab);
+        Ok(())
+    }
+
+    /// Adds a tab articulated from the tab before it to the most recently added staff.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if no notes have been added yet.
+    pub fn add_articulated_tab(&mut self, tab: &str, articulation: char) -> Result<(), ParseError> {
+        self.last_staff()?.add_articulated_tab(tab, articulation);
+        Ok(())
+    }
+
+    /// Adds a chord of frets played together in a single beat column to the most recently added staff.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if no notes have been added yet or the chord has more frets than the staff
+    /// has strings.
+    pub fn add_chord(&mut self, frets: &[Option<u32>], highlight: bool) -> Result<(), ParseError> {
+        self.last_staff()?.add_chord(frets, highlight)
+    }
+
+    /// Adds vibrato to the most recently added tab of the most recently added staff.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if no notes have been added yet.
+    pub fn add_vibrato(&mut self) -> Result<(), ParseError> {
+        self.last_staff()?.add_vibrato();
+        Ok(())
+    }
+
+    /// Accents the most recently added tab of the most recently added staff.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if no notes have been added yet.
+    pub fn add_accent(&mut self) -> Result<(), ParseError> {
+        self.last_staff()?.add_accent();
+        Ok(())
+    }
+
+    /// Plays the most recently added tab of the most recently added staff staccato.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if no notes have been added yet.
+    pub fn add_staccato(&mut self) -> Result<(), ParseError> {
+        self.last_staff()?.add_staccato();
+        Ok(())
+    }
+
+    /// Plays the most recently added tab of the most recently added staff with the provided stroke.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if no notes have been added yet.
+    pub fn add_stroke(&mut self, stroke: char) -> Result<(), ParseError> {
+        self.last_staff()?.add_stroke(stroke);
+        Ok(())
+    }
+
+    /// Leads into the most recently added tab of the most recently added staff with a grace note played on
+    /// the provided fret.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if no notes have been added yet.
+    pub fn add_grace(&mut self, fret: u32) -> Result<(), ParseError> {
+        self.last_staff()?.add_grace(fret);
+        Ok(())
+    }
+
+    /// Plays the most recently added tab of the most recently added staff as the provided harmonic.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if no notes have been added yet.
+    pub fn add_harmonic(&mut self, harmonic: Harmonic) -> Result<(), ParseError> {
+        self.last_staff()?.add_harmonic(harmonic);
+        Ok(())
+    }
+
+    /// Sets how long the most recently added tab of the most recently added staff rings.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if no notes have been added yet.
+    pub fn add_duration(&mut self, duration: ast::Duration) -> Result<(), ParseError> {
+        self.last_staff()?.add_duration(duration);
+        Ok(())
+    }
+
+    /// Sets the finger the most recently added tab of the most recently added staff is fretted with.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if no notes have been added yet.
+    pub fn add_finger(&mut self, finger: Finger) -> Result<(), ParseError> {
+        self.last_staff()?.add_finger(finger);
+        Ok(())
+    }
+
+    /// Adds a tie to the most recently added staff.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if no notes have been added yet, or if no tab comes before the tie on its
+    /// string.
+    pub fn add_tie(&mut self) -> Result<(), ParseError> {
+        self.last_staff()?.add_tie()
+    }
+
+    /// Adds an empty tab to the most recently added staff.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if no notes have been added yet.
+    pub fn add_empty(&mut self) -> Result<(), ParseError> {
+        self.last_staff()?.add_empty();
+        Ok(())
+    }
+
+    /// Adds empty tabs to the most recently added staff until the guitar string position resets.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if no notes have been added yet.
+    pub fn add_next(&mut self) -> Result<(), ParseError> {
+        self.last_staff()?.add_next();
+        Ok(())
+    }
+
+    /// Adds empty tabs to the most recently added staff for the provided amount of times.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if no notes have been added yet.
+    pub fn add_spread_empty(&mut self, amt: u32) -> Result<(), ParseError> {
+        self.last_staff()?.add_spread_empty(amt);
+        Ok(())
+    }
+
+    /// Adds empty tabs to the most recently added staff for the provided amount of times, each time
+    /// until the guitar string position resets.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if no notes have been added yet.
+    pub fn add_spread_next(&mut self, amt: u32) -> Result<(), ParseError> {
+        self.last_staff()?.add_spread_next(amt);
+        Ok(())
+    }
+
+    /// Adds rests to the most recently added staff for the provided duration. A duration that does not
+    /// fill a whole number of beat slots at the fidelity of the staff is logged as an error and no rest
+    /// is added, as it would leave the tabs after it out of line with the beat slots.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if no notes have been added yet.
+    pub fn add_rest(&mut self, duration: u32) -> Result<(), ParseError> {
+        let line = self.line;
+        let staff = self.last_staff()?;
+        let fidelity = staff.time.get_fidelity();
+        match staff.time.duration_slots(duration) {
+            Some(slots) => staff.add_rest(slots),
+            None => self.errors.push((line, "P014", format!(
+                "A rest of duration {} does not fill a whole number of beat slots at a fidelity of {}; use a duration that divides the fidelity.",
+                duration, fidelity
+            ))),
+        }
+        Ok(())
+    }
+
+    /// Starts a tuplet of the provided amount of beats on the most recently added staff.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if no notes have been added yet, the tuplet has less than 2 beats, or another
+    /// tuplet is being written.
+    pub fn start_tuplet(&mut self, notes: u32) -> Result<(), ParseError> {
+        self.last_staff()?.start_tuplet(notes)
+    }
+
+    /// Starts a span of beats played with the provided technique on the most recently added staff.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if no notes have been added yet.
+    pub fn start_span(&mut self, technique: Technique) -> Result<(), ParseError> {
+        self.last_staff()?.start_span(technique);
+        Ok(())
+    }
+
+    /// Ends the tuplet or technique span started most recently on the most recently added staff.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if no notes have been added yet or no tuplet or span is being written.
+    pub fn end_group(&mut self) -> Result<(), ParseError> {
+        self.last_staff()?.end_group()
+    }
+
+    /// Ends every tuplet and technique span left open on the most recently added staff with a warning.
+    pub fn close_groups(&mut self) {
+        if let Some(staff) = self.staffs.last_mut() {
+            staff.close_groups();
+        }
+    }
+
+    /// Starts the next voice of the most recently added staff from its first beat column.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if no notes have been added yet.
+    pub fn add_voice(&mut self) -> Result<(), ParseError> {
+        self.last_staff()?.add_voice();
+        Ok(())
+    }
+
+    /// Adds a lyric syllable at the current beat of the most recently added staff.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if no notes have been added yet.
+    pub fn add_lyric(&mut self, lyric: &str) -> Result<(), ParseError> {
+        self.last_staff()?.add_lyric(lyric);
+        Ok(())
+    }
+
+    /// Adds a chord name above the current beat of the most recently added staff.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if no notes have been added yet.
+    pub fn add_chord_name(&mut self, name: &str) -> Result<(), ParseError> {
+        self.last_staff()?.add_chord_name(name);
+        Ok(())
+    }
+
+    /// Adds a barre or hand position from the current beat of the most recently added staff.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if no notes have been added yet.
+    pub fn add_hand_position(&mut self, hand_position: HandPosition) -> Result<(), ParseError> {
+        self.last_staff()?.add_hand_position(hand_position);
+        Ok(())
+    }
+
+    /// Adds a repeat start bar-line to the most recently added staff.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if no notes have been added yet.
+    pub fn add_repeat_start(&mut self) -> Result<(), ParseError> {
+        self.last_staff()?.add_repeat_start();
+        Ok(())
+    }
+
+    /// Adds a bar-line checking the measure before it to the most recently added staff.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if no notes have been added yet.
+    pub fn add_bar_line(&mut self) -> Result<(), ParseError> {
+        self.last_staff()?.add_bar_line();
+        Ok(())
+    }
+
+    /// Adds a repeat end bar-line for a section played the provided amount of times to the most recently
+    /// added staff.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if no notes have been added yet.
+    pub fn add_repeat_end(&mut self, times: u32) -> Result<(), ParseError> {
+        self.last_staff()?.add_repeat_end(times);
+        Ok(())
+    }
+
+    /// Labels the staffs that follow as a section of the song. A staff without tabs is labeled in place,
+    /// otherwise the most recently added staff is ended and the label starts off the next staff.
+    pub fn add_section(&mut self, label: &str) {
+        match self.staffs.last_mut() {
+            Some(staff) if !staff.has_tabs => staff.section = Some(label.to_string()),
+            _ => {
+                self.section = Some(label.to_string());
+                self.staff_break = true;
+            },
+        }
+    }
+
+    /// Keeps a comment from the source to be printed as a line of text above the staff the next tabs are
+    /// added to, or below the last staff if no tabs are added after it.
+    pub fn add_comment(&mut self, comment: &str) {
+        self.comments.push(comment.to_string());
+    }
+
+    /// Ends the most recently added staff so the next tabs are added to a new staff. The new staff
+    /// keeps the notes of the most recently added staff unless new notes are provided.
+    pub fn break_staff(&mut self) {
+        self.staff_break = true;
+        self.time_change = false;
+    }
+
+    /// Sets the source line of the token currently being added so the cells it adds can cite it.
+    pub fn set_line(&mut self, line: u32) {
+        self.line = line;
+    }
+
+    /// Sets the char column within its line of the token currently being added, which errors are placed at.
+    pub fn set_column(&mut self, column: u32) {
+        self.column = column;
+    }
+
+    /// Returns an error rejecting the token currently being added for the provided reason.
+    fn reject(&self, message: String) -> ParseError {
+        ParseError::Rejected { line: self.line, column: self.column, message }
+    }
+
+    /// Removes and returns the warnings found by every staff while cells were added, along with the
+    /// source line they were found on and their code.
+    pub fn take_warnings(&mut self) -> Vec<(u32, &'static str, String)> {
+        self.staffs.iter_mut().flat_map(|staff| staff.warnings.drain(..)).collect()
+    }
+
+    /// Returns a warning for every string that leaves a measure short and every measure that a bar-line
+    /// finds short or overfull, along with the source line of the last tab of the measure or of the
+    /// bar-line and its code. Every string must fill every beat slot of a measure, except in the last
+    /// measure of a staff, which may be left unfinished as long as every string is filled to the same beat
+    /// slot. Cells past the last beat slot of a measure start the next measure, so a measure is only found
+    /// overfull when a bar-line is written after it spills into the next measure.
+    pub fn validate_measures(&self) -> Vec<(u32, &'static str, String)> {
+        let mut warnings = vec![];
+        let mut measure_offset = 0;
+        for (index, staff) in self.staffs.iter().enumerate() {
+            let end = staff.tabs.iter().map(|lane| lane.len()).max().unwrap_or(0);
+            let measures = staff.time.measures(0, end);
+            let measure_length = staff.time.total_beats_per_measure().max(1) as usize;
+            for (written, line) in staff.misplaced_bars.iter() {
+                // a bar-line more than a measure after the last one is closing a short measure of its own
+                let warning = if written.len() > measure_length && written.len() < 2 * measure_length {
+                    format!(
+                        "Measure {} of staff {} is overfull: {} of {} beat slots are filled before the bar-line.",
+                        measure_offset + written.start / measure_length + 1, index + 1, written.len(), measure_length
+                    )
+                } else {
+                    format!(
+                        "Measure {} of staff {} is short: {} of {} beat slots are filled before the bar-line.",
+                        measure_offset + written.end / measure_length + 1, index + 1, written.end % measure_length, measure_length
+                    )
+                };
+                warnings.push((*line, "P011", warning));
+            }
+            for (i, columns) in measures.iter().enumerate() {
+                let line = staff.tabs.iter()
+                    .flat_map(|lane| lane.get(columns.start..columns.end.min(lane.len())).unwrap_or_default())
+                    .filter_map(|cell| match cell {
+                        Cell::Tab { line, .. } => Some(*line),
+                        _ => None,
+                    })
+                    .max()
+                    .unwrap_or(staff.line);
+                // lanes are stored in reverse note order
+                for (lane, note) in staff.tabs.iter().zip(staff.notes.iter().rev()) {
+                    let filled = lane.len().min(columns.end).saturating_sub(columns.start);
+                    // the last measure may be left unfinished, so strings are only short of the others there
+                    if filled < columns.len() {
+                        warnings.push((line, "P011", format!(
+                            "Measure {} of staff {} is short on the {} string: {} of {} beat slots are filled.",
+                            measure_offset + i + 1, index + 1, note, filled, measure_length
+                        )));
+                    }
+                }
+            }
+            measure_offset += measures.len();
+        }
+        warnings
+    }
+
+    /// Removes and returns the errors found while tokens were added, along with the source line they were
+    /// found on and their code.
+    pub fn take_errors(&mut self) -> Vec<(u32, &'static str, String)> {
+        self.errors.drain(..).collect()
+    }
+
+    /// Returns the amount of staffs, the beat column tabs are added to in the most recently added staff,
+    /// and the amount of beat columns in its measures, or `None` if no staff exists yet.
+    fn position(&self) -> Option<(usize, usize, usize)> {
+        let staff = self.staffs.last()?;
+        Some((self.staffs.len(), staff.column, staff.time.total_beats_per_measure().max(1) as usize))
+    }
+
+    /// Returns the most recently added staff. If the staff was ended by a staff break and already has
+    /// tabs, a new staff with the same notes is created and returned instead. A staff that tabs are about
+    /// to be added to for the first time is checked against the string count of the `strings` option.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if no staff exists because no notes have been added yet.
+    fn last_staff(&mut self) -> Result<&mut Staff, ParseError> {
+        // a tuning set since the last tabs were added starts a new staff with its strings
+        if let Some(tuning) = self.options.tuning.take() {
+            self.staff_break = false;
+            self.time_change = false;
+            match self.staffs.last_mut() {
+                // a staff without tabs is retuned in place
+                Some(staff) if !staff.has_tabs => {
+                    staff.notes.clear();
+                    staff.tabs.clear();
+                },
+                _ => self.create_staff(),
+            }
+            for note in tuning {
+                // the staff has no tabs so it is okay to unwrap values
+                self.staffs.last_mut().unwrap().add_note(note).unwrap();
+            }
+        }
+
+        if self.staff_break {
+            self.staff_break = false;
+            if std::mem::take(&mut self.time_change) {
+                if let Some(staff) = self.staffs.last_mut() {
+                    staff.fill_measure();
+                }
+            }
+            if let Some(notes) = self.staffs.last().filter(|staff| staff.has_tabs).map(|staff| staff.notes.clone()) {
+                self.create_staff();
+                for note in notes {
+                    // new staff has no tabs so it is okay to unwrap values
+                    self.staffs.last_mut().unwrap().add_note(note).unwrap();
+                }
+            }
+        }
+
+        let line = self.line;
+        match self.staffs.last_mut() {
+            Some(staff) => {
+                // the string count is only checked once, as the notes cannot change after the first tab
+                if let Some(declared) = staff.declared_strings.take().filter(|declared| *declared != staff.notes.len()) {
+                    self.errors.push((line, "P010", format!(
+                        "The staff has {} strings but the strings option declares {}; write a note for every string before the tabs.",
+                        staff.notes.len(), declared
+                    )));
+                }
+                staff.comments.append(&mut self.comments);
+                staff.set_line(line);
+                staff.set_column(self.column);
+                Ok(staff)
+            },
+            None => Err(ParseError::Rejected { line, column: self.column, message: String::from("no notes have been added yet.") }),
+        }
+    }
+
+    /// Sets global options on the staff manager based on the provided literal. Current
+    /// and new staffs will have these options applied to them.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if provided options contain syntax errors or unknown option names or values.
+    pub fn set_options(&mut self, options: &str) -> Result<(), ParseError> {
+        self.update_options(|staff_options| staff_options.set(options))
+    }
+
+    /// Sets a single global option on the staff manager by name, the same as `set_options`. The value is
+    /// used as is, so it may contain the `;` and `=` chars that separate options.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if the option name or value is not valid.
+    pub fn set_option(&mut self, name: &str, value: &str) -> Result<(), ParseError> {
+        self.update_options(|staff_options| staff_options.set_option(name, value))
+    }
+
+    /// Sets global options with the provided function, keeping the global options restored once scoped
+    /// options end up to date.
+    fn update_options(&mut self, set: impl Fn(&mut StaffOptions) -> Result<(), ParseError>) -> Result<(), ParseError> {
+        let result = self.apply_options(&set);
+
+        // global options set while scoped options are in effect are the ones restored once they end
+        if let Some(scoped) = self.scoped.as_mut() {
+            let mut global = StaffOptions::new();
+            global.time.set_signature(scoped.signature.0, scoped.signature.1);
+            global.time.set_fidelity(scoped.fidelity);
+            global.strings = scoped.strings;
+            // errors were already returned when the options were applied
+            let _ = set(&mut global);
+            scoped.signature = global.get_time_signature();
+            scoped.fidelity = global.get_time_fidelity();
+            scoped.strings = global.strings;
+        }
+        result
+    }
+
+    /// Sets options that apply only to a single staff: the current staff if it has no tabs yet, or
+    /// otherwise the next staff. The global options they replace are restored for the staff after it.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if provided options contain syntax errors, unknown option names or values, or
+    /// options that cannot be scoped to a single staff.
+    pub fn set_scoped_options(&mut self, options: &str) -> Result<(), ParseError> {
+        let mut errors = String::new();
+        for op in options.split(';').filter(|op| !op.trim().is_empty()) {
+            let name = op.split('=').next().unwrap_or_default().trim();
+            if StaffOptions::exists(name) && !StaffOptions::SCOPED.contains(&name) {
+                errors.push_str(&format!(
+                    "\tOption \"{}\" cannot be scoped to a single staff; only {} can.\n",
+                    name, StaffOptions::SCOPED.join(", ")
+                ));
+            }
+        }
+        if !errors.is_empty() {
+            return Err(self.reject(errors))
+        }
+
+        // scoped options set while others are in effect still restore the global options from before both
+        let mut scoped = self.scoped.take().unwrap_or(ScopedOptions {
+            signature: self.options.get_time_signature(),
+            fidelity: self.options.get_time_fidelity(),
+            strings: self.options.strings,
+            started: false,
+        });
+        let result = self.apply_options(|staff_options| staff_options.set(options));
+        scoped.started = self.staffs.last().is_some_and(|staff| !staff.has_tabs);
+        self.scoped = Some(scoped);
+        result
+    }
+
+    /// Applies options to the staff manager and the staffs they affect.
+    fn apply_options(&mut self, set: impl Fn(&mut StaffOptions) -> Result<(), ParseError>) -> Result<(), ParseError> {
+        let tempo = self.options.tempo;
+        let time = (self.options.get_time_signature(), self.options.get_time_fidelity());
+        let (line, column) = (self.line, self.column);
+        let result = set(&mut self.options).map_err(|e| e.at(line, column));
+
+        // a time signature or fidelity set before the tabs of the current staff applies to it; once tabs
+        // have been added, the next tabs start a new staff with it
+        let (signature, fidelity) = (self.options.get_time_signature(), self.options.get_time_fidelity());
+        if (signature, fidelity) != time {
+            match self.staffs.last_mut() {
+                Some(staff) if !staff.has_tabs => {
+                    // the staff has no tabs so it is okay to unwrap values
+                    staff.set_time_signature(signature).unwrap();
+                    staff.set_time_fidelity(fidelity).unwrap();
+                },
+                Some(_) => {
+                    self.staff_break = true;
+                    self.time_change = true;
+                },
+                None => (),
+            }
+        }
+
+        // a string count, mode, or feel set before the tabs of the current staff applies to it
+        if let Some(staff) = self.staffs.last_mut().filter(|staff| !staff.has_tabs) {
+            staff.declared_strings = self.options.strings;
+            staff.drums = self.options.drums;
+            staff.swing = self.options.swing;
+        }
+
+        // a tempo set once tabs have been added is a tempo change placed on the current staff at the
+        // beat it is set; any other tempo is the starting tempo
+        if let (Some(bpm), true) = (self.options.tempo, self.options.tempo != tempo) {
+            let started = self.staffs.len() > 1;
+            match self.staffs.last_mut() {
+                Some(staff) if started || staff.has_tabs => staff.add_tempo(bpm),
+                _ => self.start_tempo = Some(bpm),
+            }
+        }
+
+        // metadata options are collected as they are set, wherever they are written
+        if let Some(title) = self.options.title.take() {
+            self.metadata.title = Some(title);
+        }
+        if let Some(artist) = self.options.artist.take() {
+            self.metadata.artist = Some(artist);
+        }
+        if let Some(album) = self.options.album.take() {
+            self.metadata.album = Some(album);
+        }
+
+        // dynamics are placed on the current staff at the beat they are set
+        if let Some(staff) = self.staffs.last_mut() {
+            if let Some(dynamic) = self.options.dynamic.take() {
+                staff.add_dynamic(dynamic);
+            }
+        }
+        result
+    }
+
+    /// Renders the header and every staff, skipping the provided amount of beats from the start of the
+    /// tabs. Bar-lines and beat counts after the skipped beats stay aligned as if nothing was skipped.
+    /// Each post processor is run in order on every rendered staff.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if the amount of beats to skip reaches past the end of the tabs.
+    pub fn render(&self, seek: u32, post_processors: &mut [PostProcessor]) -> Result<String, ParseError> {
+        let blocks = self.render_blocks(seek, post_processors)?;
+        // every part of the output is a block of its own when rendering HTML
+        if self.html {
+            return Ok(blocks.iter().map(|(class, text)| html::block(class, text, *class == "staff")).collect())
+        }
+        let staffs: String = blocks.into_iter().map(|(_, text)| text).collect();
+        Ok(if self.options.fixed_width { pad_lines(&staffs) } else { staffs })
+    }
+
+    /// Renders the header and every staff the same as `render`, as separate blocks of text along with
+    /// what they hold: `header`, `section`, `comment`, or `staff`.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if the amount of beats to skip reaches past the end of the tabs.
+    pub fn render_blocks(&self, seek: u32, post_processors: &mut [PostProcessor]) -> Result<Vec<(&'static str, String)>, ParseError> {
+        // find the beat column each staff starts rendering at; staffs before the seek position are skipped
+        let mut remaining = seek;
+        let mut starts = vec![];
+        for staff in self.staffs.iter() {
+            let slots_per_beat = staff.time.slots_per_beat();
+            let beats = staff.time.beats_counted();
+            if remaining > 0 && remaining >= beats {
+                remaining -= beats;
+                starts.push(None);
+            } else {
+                starts.push(Some((remaining * slots_per_beat) as usize));
+                remaining = 0;
+            }
+        }
+        if seek > 0 && starts.iter().all(|start| start.is_none()) {
+            return Err(ParseError::Rejected { line: 0, column: 0, message: format!("Cannot seek to beat {} as it is past the end of the tabs.", seek) })
+        }
+
+        // print the header above the first staff if any header options were set
+        let mut header = String::new();
+        if let Some(title) = &self.metadata.title {
+            header.push_str(&format!("{}\n", title));
+        }
+        if let Some(artist) = &self.metadata.artist {
+            header.push_str(&format!("Artist: {}\n", artist));
+        }
+        if let Some(album) = &self.metadata.album {
+            header.push_str(&format!("Album: {}\n", album));
+        }
+        if let Some(tempo) = self.start_tempo {
+            header.push_str(&format!("Tempo: {} bpm\n", tempo));
+        }
+        if let Some(capo) = self.options.capo {
+            header.push_str(&format!("Capo {}\n", capo));
+        }
+        if !header.is_empty() { header.push('\n'); }
+
+        // the count in is one measure of beats in the time signature of the first staff printed
+        let first = self.staffs.iter().zip(starts.iter()).find(|(_, start)| start.is_some());
+        if let (true, Some((staff, _))) = (self.options.count_in, first) {
+            let beats: Vec<String> = (1..=staff.time.get_signature().0).map(|beat| beat.to_string()).collect();
+            header.push_str(&format!("Count in: {}\n\n", beats.join(" ")));
+        }
+
+        let layout = Layout {
+            // with equal measures, every measure is padded to the width of the widest measure in the score
+            measure_width: if self.options.equal_measures {
+                self.staffs.iter().map(|staff| staff.measure_width()).max()
+            } else {
+                None
+            },
+            measures_per_line: self.options.measures_per_line,
+            line_width: self.options.line_width,
+            rtl: self.options.rtl,
+            display_notes: self.options.display_notes,
+            key: self.options.key,
+            heatmap: self.options.heatmap,
+            measure_numbers: self.options.measure_numbers,
+            fingering: self.options.fingering,
+            analyze_chords: self.options.analyze_chords,
+            capo: self.options.capo.unwrap_or(0),
+            html: self.html,
+        };
+
+        let mut blocks = vec![];
+        if !header.is_empty() {
+            blocks.push(("header", header));
+        }
+        // measures are numbered from 1 across every staff, including staffs skipped by seeking
+        let mut first_measure = 1;
+        for (staff, start) in self.staffs.iter().zip(starts) {
+            let end = staff.tabs.iter().map(|lane| lane.len()).max().unwrap_or(0);
+            let measures = staff.time.measures(0, end).len();
+            if let Some(start) = start {
+                // section labels are printed as a header above the staff they start
+                if let Some(section) = &staff.section {
+                    blocks.push(("section", format!("[{}]\n", section)));
+                }
+                for comment in staff.comments.iter() {
+                    blocks.push(("comment", format!("{}\n", comment)));
+                }
+                let mut rendered = staff.render(&layout, start, first_measure);
+                for post_processor in post_processors.iter_mut() {
+                    post_processor(&mut rendered);
+                }
+                blocks.push(("staff", rendered + "\n"));
+            }
+            first_measure += measures;
+        }
+        // comments after the last tabs are printed below the last staff
+        for comment in self.comments.iter() {
+            blocks.push(("comment", format!("{}\n", comment)));
+        }
+        Ok(blocks)
+    }
+
+    /// Replaces every staff with a single staff holding the measures from `first` up to `last`, counted
+    /// from `1` across every staff, repeated the provided amount of times. Measures cut short by the
+    /// end of their staff are filled with empty tabs so every repeat starts on a downbeat.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if the measures are out of range or span staffs with different notes or
+    /// time settings.
+    pub fn loop_measures(&mut self, first: usize, last: usize, times: u32) -> Result<(), ParseError> {
+        // find the staff and columns of every measure across the score
+        let mut measures = vec![];
+        for staff in self.staffs.iter() {
+            let end = staff.tabs.iter().map(|lane| lane.len()).max().unwrap_or(0);
+            let measure_length = staff.time.total_beats_per_measure() as usize;
+            for measure in staff.time.measures(0, end) {
+                measures.push((staff, measure.start..measure.start + measure_length));
+            }
+        }
+        if first == 0 || first > last || last > measures.len() {
+            return Err(ParseError::Rejected { line: 0, column: 0, message: format!(
+                "Cannot loop measures {}-{} as the tabs have measures 1-{}.",
+                first, last, measures.len()
+            ) })
+        }
+        let selected = &measures[first - 1..last];
+
+        let (model, _) = selected[0];
+        let same_staff = |staff: &Staff| staff.notes == model.notes
+            && staff.time.get_signature() == model.time.get_signature()
+            && staff.time.get_fidelity() == model.time.get_fidelity();
+        if !selected.iter().all(|(staff, _)| same_staff(staff)) {
+            return Err(ParseError::Rejected { line: 0, column: 0, message: format!(
+                "Cannot loop measures {}-{} as they span staffs with different notes or time settings.",
+                first, last
+            ) })
+        }
+
+        let mut looped = Staff::new();
+        for note in model.notes.iter() {
+            // new staff has no tabs so it is okay to unwrap values
+            looped.add_note(note.to_string()).unwrap();
+        }
+        looped.set_time_signature(model.time.get_signature()).unwrap();
+        looped.set_time_fidelity(model.time.get_fidelity()).unwrap();
+        looped.drums = model.drums;
+        looped.swing = model.swing;
+        for _ in 0..times {
+            for (staff, columns) in selected.iter() {
+                for (lane, looped_lane) in staff.tabs.iter().zip(looped.tabs.iter_mut()) {
+                    looped_lane.extend(columns.clone().map(|c| lane.get(c).cloned().unwrap_or(Cell::Empty)));
+                }
+            }
+        }
+        looped.has_tabs = true;
+        looped.column = looped.tabs.iter().map(|lane| lane.len()).max().unwrap_or(0);
+        looped.time.total_beats_counted = looped.column as u32;
+
+        self.staffs = vec![looped];
+        Ok(())
+    }
+
+    /// Transposes every tab of every staff by the provided amount of semitones. A tab that would go below
+    /// fret `0` is moved to the highest lower string that is free to play it, with a warning.
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if a tab would go below fret `0` and no lower string is free to play it.
+    pub fn transpose(&mut self, semitones: i32) -> Result<(), ParseError> {
+        for staff in self.staffs.iter_mut() {
+            // lanes are stored in reverse note order
+            let strings = staff.tabs.len();
+            let end = staff.tabs.iter().map(|lane| lane.len()).max().unwrap_or(0);
+            for column in 0..end {
+                for string in 0..strings {
+                    let (fret, line) = match staff.tabs[strings - 1 - string].get(column) {
+                        Some(Cell::Tab { value, line, .. }) => match value.parse::<u32>() {
+                            Ok(fret) => (fret, *line),
+                            Err(_) => continue,
+                        },
+                        _ => continue,
+                    };
+                    let tabs = &staff.tabs;
+                    let is_free = |lower: usize| !matches!(tabs[strings - 1 - lower].get(column), Some(Cell::Tab { .. }));
+                    let (to, transposed) = transpose_fret(&staff.notes, string, fret, line, semitones, is_free)?;
+
+                    let mut cell = std::mem::replace(&mut staff.tabs[strings - 1 - string][column], Cell::Empty);
+                    if let Cell::Tab { value, grace, .. } = &mut cell {
+                        *value = transposed.to_string();
+                        // grace notes keep their interval to the tab they lead into
+                        *grace = grace.map(|grace| (grace + transposed).saturating_sub(fret));
+                    }
+                    if to != string {
+                        staff.warnings.push((line, "P009", format!(
+                            "Fret {} on the {} string was moved to fret {} on the {} string as transposing it down {} went below fret 0.",
+                            fret, staff.notes[string], transposed, staff.notes[to], semitone_count(semitones)
+                        )));
+                    }
+                    let lane = &mut staff.tabs[strings - 1 - to];
+                    if lane.len() <= column {
+                        lane.resize(column + 1, Cell::Empty);
+                    }
+                    lane[column] = cell;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the amount of staffs, measures, and tabs played across every staff. Warning and error
+    /// counts are left at `0`.
+    pub fn summary(&self) -> Summary {
+        Summary {
+            staffs: self.staffs.len(),
+            measures: self.staffs.iter().map(|staff| staff.measures().count()).sum(),
+            notes: self.staffs.iter()
+                .flat_map(|staff| staff.tabs.iter().flatten())
+                .filter(|cell| matches!(cell, Cell::Tab { .. }))
+                .count(),
+            ..Summary::default()
+        }
+    }
+
+    /// Returns the starting tempo and every tempo change of the score in order.
+    pub fn tempo_changes(&self) -> Vec<TempoChange> {
+        let mut changes: Vec<TempoChange> = self.start_tempo.map(|bpm| TempoChange { beat: 0, bpm }).into_iter().collect();
+        // beats are counted across staffs, so every staff starts at the beat the previous staff ended
+        let mut offset = 0;
+        for staff in self.staffs.iter() {
+            for (column, marking) in staff.markings.iter() {
+                if let Marking::Tempo(bpm) = marking {
+                    changes.push(TempoChange { beat: offset + *column as u32 / staff.time.slots_per_beat(), bpm: *bpm });
+                }
+            }
+            offset += staff.time.beats_counted();
+        }
+        changes
+    }
+
+    /// Returns a fret diagram for every distinct chord found across all staffs.
+    pub fn chord_diagrams(&self) -> String {
+        let mut found: Vec<(&Vec<String>, Vec<Option<u32>>)> = vec![];
+        for staff in self.staffs.iter() {
+            for chord in staff.chords() {
+                if !found.iter().any(|(notes, frets)| *notes == &staff.notes && *frets == chord) {
+                    found.push((&staff.notes, chord));
+                }
+            }
+        }
+
+        found.iter()
+            .map(|(notes, frets)| chord_diagram(notes, frets))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// Creates a new staff with the current global options and appends it to the staff list.
+    fn create_staff(&mut self) {
+        self.close_groups();
+        // scoped options end with the staff they apply to, so the staff after it has the global options
+        match self.scoped.as_mut() {
+            Some(scoped) if scoped.started => {
+                self.options.time.set_signature(scoped.signature.0, scoped.signature.1);
+                self.options.time.set_fidelity(scoped.fidelity);
+                self.options.strings = scoped.strings;
+                self.scoped = None;
+            },
+            Some(scoped) => scoped.started = true,
+            None => (),
+        }
+
+        let mut new_staff = Staff::new();
+        // new staff will never have tabs so it is okay to unwrap values
+        new_staff.set_time_signature(self.options.get_time_signature()).unwrap();
+        new_staff.set_time_fidelity(self.options.get_time_fidelity()).unwrap();
+        // a dynamic set before any staff existed starts off the new staff
+        if let Some(dynamic) = self.options.dynamic.take() {
+            new_staff.add_dynamic(dynamic);
+        }
+        new_staff.section = self.section.take();
+        new_staff.declared_strings = self.options.strings;
+        new_staff.drums = self.options.drums;
+        new_staff.swing = self.options.swing;
+
+        self.staffs.push(new_staff);
+    }
+}
+
+impl fmt::Display for StaffManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // rendering from the start of the tabs never errors
+        write!(f, "{}", self.render(0, &mut []).unwrap_or_default())
+    }
+}
+
+/// A function run on the rendered string of every staff before the staffs are joined into the output.
+pub type PostProcessor<'a> = Box<dyn FnMut(&mut String) + 'a>;
+
+/// Used for parsing the provided source `Vec<Token>` into an output string representing
+/// guitar tablature notation.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use data::{Token, TokenType, Literal};
+/// use parser::Parser;
+/// 
+/// let tokens = vec![
+///     Token::new(TokenType::Note, String::from("E"), Literal::None, 1),
+///     Token::new(TokenType::Note, String::from("A"), Literal::None, 1),
+///     Token::new(TokenType::Note, String::from("D"), Literal::None, 1),
+///     Token::new(TokenType::Note, String::from("G"), Literal::None, 1),
+///     Token::new(TokenType::Note, String::from("B"), Literal::None, 1),
+///     Token::new(TokenType::Note, String::from("E"), Literal::None, 1),
+///     Token::new(TokenType::Number, String::from("0"), Literal::Number(0), 2),
+///     Token::new(TokenType::Number, String::from("3"), Literal::Number(3), 2),
+///     Token::new(TokenType::Number, String::from("5"), Literal::Number(5), 2),
+///     Token::new(TokenType::EndOfFile, String::new(), Literal::None, 2),
+/// ];
+/// 
+/// let mut parser = Parser::new(&tokens);
+/// let tab_string = parser.generate_tabs();
+/// 
+/// match parser.generate_tabs() {
+///     Ok(tab_string) => println!("Guitar tabs:\n\n{}", tab_string),
+///     Err(e) => panic!("Could not generate tabs: {}", e),
+/// }
+/// ```
+pub struct Parser<'a> {
+    source: Cow<'a, [Token]>,
+    tabs: String,
+    seek: u32,
+    line_width: Option<usize>,
+    html: bool,
+    chord_diagrams: bool,
+    normalize_accidentals: bool,
+    ignore_unknown_options: bool,
+    deny_warnings: bool,
+    keep_comments: bool,
+    practice_loop: Option<(usize, usize, u32)>,
+    transpose: i32,
+    click: bool,
+    tempo_changes: Vec<TempoChange>,
+    summary: Summary,
+    post_processors: Vec<PostProcessor<'a>>,
+    /// The rest of the stream of tokens the parser was created from; every token taken from it is kept
+    /// in the source so the tabs can be generated again.
+    stream: Option<Box<dyn Iterator<Item = Result<Token, LexError>> + 'a>>,
+    /// The syntax errors found by the stream, logged again every time the source tokens are added.
+    stream_errors: Vec<Diagnostic>,
+    watcher: Watcher,
+}
+
+impl<'a> Parser<'a> {
+    /// Creates a new `Parser` for parsing through tokens and generating guitar tablature notation.
+    pub fn new(source: &[Token]) -> Parser<'_> {
+        Parser::with_source(Cow::Borrowed(source))
+    }
+
+    /// Creates a new `Parser` from either borrowed or owned source tokens.
+    fn with_source(source: Cow<'a, [Token]>) -> Parser<'a> {
+        Parser {
+            source,
+            tabs: String::new(),
+            seek: 0,
+            line_width: None,
+            html: false,
+            chord_diagrams: false,
+            normalize_accidentals: false,
+            ignore_unknown_options: false,
+            deny_warnings: false,
+            keep_comments: false,
+            practice_loop: None,
+            transpose: 0,
+            click: false,
+            tempo_changes: vec![],
+            summary: Summary::default(),
+            post_processors: vec![],
+            stream: None,
+            stream_errors: vec![],
+            watcher: Watcher::new(),
+        }
+    }
+
+    /// Creates a new `Parser` that takes its tokens from a stream, such as a `Lexer`, adding each token to
+    /// the staffs as soon as it is generated instead of needing every token up front. Syntax errors found
+    /// by the stream are logged as errors of the tabs, and the tokens after them are still added.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use lexer::Lexer;
+    /// use parser::Parser;
+    /// 
+    /// let mut parser = Parser::from_stream(Lexer::from("E\n3 5"));
+    /// assert!(parser.generate_tabs().is_ok());
+    /// 
+    /// let mut parser = Parser::from_stream(Lexer::from("E\n3 (5"));
+    /// assert!(parser.generate_tabs().is_err());
+    /// ```
+    pub fn from_stream(tokens: impl Iterator<Item = Result<Token, LexError>> + 'a) -> Parser<'a> {
+        let mut parser = Parser::with_source(Cow::Owned(vec![]));
+        parser.stream = Some(Box::new(tokens));
+        parser
+    }
+
+    /// Sets whether a fret diagram of every distinct chord is appended below the tabs.
+    pub fn set_chord_diagrams(&mut self, chord_diagrams: bool) {
+        self.chord_diagrams = chord_diagrams;
+    }
+
+    /// Sets whether note names are respelled with the accidentals of the key set by the `key` option.
+    pub fn set_normalize_accidentals(&mut self, normalize_accidentals: bool) {
+        self.normalize_accidentals = normalize_accidentals;
+    }
+
+    /// Sets whether unknown options are skipped with a warning instead of stopping the tabs from being
+    /// generated with an error.
+    pub fn set_ignore_unknown_options(&mut self, ignore_unknown_options: bool) {
+        self.ignore_unknown_options = ignore_unknown_options;
+    }
+
+    /// Sets whether comments are printed as lines of text above the staff they were written in, instead
+    /// of being ignored.
+    pub fn set_keep_comments(&mut self, keep_comments: bool) {
+        self.keep_comments = keep_comments;
+    }
+
+    /// Sets whether warnings are promoted to errors that stop the tabs from being generated.
+    pub fn set_deny_warnings(&mut self, deny_warnings: bool) {
+        self.deny_warnings = deny_warnings;
+    }
+
+    /// Sets the range of measures, counted from `1` across every staff, that are repeated the provided
+    /// amount of times in place of the whole score.
+    pub fn set_loop(&mut self, first: usize, last: usize, times: u32) {
+        self.practice_loop = Some((first, last, times));
+    }
+
+    /// Sets the amount of semitones every tab is transposed by, up for positive amounts and down for
+    /// negative amounts.
+    pub fn set_transpose(&mut self, semitones: i32) {
+        self.transpose = semitones;
+    }
+
+    /// Sets whether exports of the score play a metronome click, unless the `click` option is set in the
+    /// source.
+    pub fn set_click(&mut self, click: bool) {
+        self.click = click;
+    }
+
+    /// Registers a post processor that is run on the rendered string of every staff before the staffs
+    /// are joined into the output. Post processors are run in the order they were registered.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use data::{Token, TokenType, Literal};
+    /// use parser::Parser;
+    /// 
+    /// let tokens = vec![
+    ///     Token::new(TokenType::Note, String::from("E"), Literal::None, 1),
+    ///     Token::new(TokenType::Number, String::from("3"), Literal::Number(3), 1),
+    ///     Token::new(TokenType::EndOfFile, String::new(), Literal::None, 1),
+    /// ];
+    /// 
+    /// let mut parser = Parser::new(&tokens);
+    /// parser.add_post_processor(|staff: &mut String| *staff = staff.to_uppercase());
+    /// ```
+    pub fn add_post_processor(&mut self, post_processor: impl FnMut(&mut String) + 'a) {
+        self.post_processors.push(Box::new(post_processor));
+    }
+
+    /// Sets the amount of beats to skip from the start of the tabs before output begins.
+    pub fn set_seek(&mut self, beats: u32) {
+        self.seek = beats;
+    }
+
+    /// Sets the char width that every staff is wrapped at, unless the `width` option is set in the source.
+    pub fn set_line_width(&mut self, width: Option<usize>) {
+        self.line_width = width;
+    }
+
+    /// Sets whether the tabs are generated as a standalone HTML document instead of plain text. Every
+    /// staff is wrapped in a `<pre>` block with a class for every element, such as `note`, `bar`, `fret`,
+    /// and `beats`, and every fret shows the measure and beat it is played at when hovered. The header,
+    /// section labels, comments, and chord diagrams are wrapped in `<pre>` blocks of their own.
+    pub fn set_html(&mut self, html: bool) {
+        self.html = html;
+    }
+
+    /// Creates a string representing guitar tablature notation from the provided source tokens.
+    pub fn generate_tabs(&mut self) -> Result<&str, ParseError> {
+        if self.tabs.is_empty() {
+            let staff_manager = self.build_staffs();
+            match staff_manager.render(self.seek, &mut self.post_processors) {
+                Ok(tabs) => {
+                    self.tabs = tabs;
+                    self.tempo_changes = staff_manager.tempo_changes();
+                    if self.chord_diagrams {
+                        let diagrams = staff_manager.chord_diagrams();
+                        self.tabs.push_str(&if self.html { html::block("chord-diagrams", &diagrams, false) } else { diagrams });
+                    }
+                    if self.html {
+                        self.tabs = html::document(staff_manager.metadata.title.as_deref(), &self.tabs);
+                    }
+                },
+                Err(e) => {
+                    let line = self.source.last().map_or(1, |token| token.line);
+                    self.watcher.error(line, "P004", e.to_string());
+                },
+            }
+        }
+
+        // if there was a syntax error, return an error; otherwise return the token list
+        if self.watcher.had_error() {
+            Err(ParseError::Invalid(self.watcher.errors()))
+        } else {
+            Ok(&self.tabs)
+        }
+    }
+
+    /// Creates a printable PDF songbook of the tabs from the provided source tokens: the same tabs as
+    /// `generate_tabs` laid out on numbered A4 pages in a monospace font, with the title printed at the
+    /// top of every page and every staff starting a new page when it does not fit on the current one.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use data::{Token, TokenType, Literal};
+    /// use parser::Parser;
+    /// 
+    /// let tokens = vec![
+    ///     Token::new(TokenType::Note, String::from("E"), Literal::None, 1),
+    ///     Token::new(TokenType::Number, String::from("3"), Literal::Number(3), 2),
+    ///     Token::new(TokenType::EndOfFile, String::new(), Literal::None, 2),
+    /// ];
+    /// 
+    /// let pdf = Parser::new(&tokens).generate_pdf().unwrap();
+    /// 
+    /// assert_eq!(b"%PDF-1.4", &pdf[..8]);
+    /// ```
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if the source tokens contain syntax errors.
+    pub fn generate_pdf(&mut self) -> Result<Vec<u8>, ParseError> {
+        let staff_manager = self.build_staffs();
+        let mut pdf = vec![];
+        match staff_manager.render_blocks(self.seek, &mut self.post_processors) {
+            Ok(mut blocks) => {
+                self.tempo_changes = staff_manager.tempo_changes();
+                if self.chord_diagrams {
+                    blocks.push(("chord-diagrams", staff_manager.chord_diagrams()));
+                }
+                pdf = pdf::document(staff_manager.metadata.title.as_deref(), &blocks);
+            },
+            Err(e) => {
+                let line = self.source.last().map_or(1, |token| token.line);
+                self.watcher.error(line, "P004", e.to_string());
+            },
+        }
+
+        if self.watcher.had_error() {
+            Err(ParseError::Invalid(self.watcher.errors()))
+        } else {
+            Ok(pdf)
+        }
+    }
+
+    /// Returns the structured score of the source tokens, with every staff split into measures of notes
+    /// and rests at their beat positions, so the tabs can be analyzed or re-rendered without parsing the
+    /// printed output.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use data::{Token, TokenType, Literal};
+    /// use parser::{Parser, ast::Event};
+    /// 
+    /// let tokens = vec![
+    ///     Token::new(TokenType::Note, String::from("E"), Literal::None, 1),
+    ///     Token::new(TokenType::Note, String::from("A"), Literal::None, 1),
+    ///     Token::new(TokenType::Number, String::from("3"), Literal::Number(3), 2),
+    ///     Token::new(TokenType::EndOfFile, String::new(), Literal::None, 2),
+    /// ];
+    /// 
+    /// let score = Parser::new(&tokens).parse().unwrap();
+    /// 
+    /// match &score.staffs[0].measures[0].events[0] {
+    ///     Event::Note(note) => assert_eq!((0, 3), (note.string, note.fret)),
+    ///     _ => panic!("Expected a note."),
+    /// }
+    /// ```
+    /// 
+    /// # Errors
+    /// 
+    /// This function errors if the source tokens contain option errors.
+    pub fn parse(&mut self) -> Result<ast::Score, ParseError> {
+        let staff_manager = self.build_staffs();
+
+        if self.watcher.had_error() {
+            Err(ParseError::Invalid(self.watcher.errors()))
+        } else {
+            Ok(ast::Score::from_staffs(&staff_manager))
+        }
+    }
+
+    /// Adds every source token to a new staff manager, logging diagnostics to a fresh watcher and counting
+    /// what was added for the summary.
+    fn build_staffs(&mut self) -> StaffManager {
+        self.build_staffs_with(|_, _| ())
+    }
+
+    /// Adds every source token to a new staff manager like `build_staffs`, calling `on_token` with the index
+    /// of every token and the staff manager once the token has been added.
+    fn build_staffs_with(&mut self, mut on_token: impl FnMut(usize, &StaffManager)) -> StaffManager {
+        self.watcher = Watcher::new();
+        self.watcher.set_deny_warnings(self.deny_warnings);
+
+        // create a new staff manager to add token values to
+        let mut staff_manager = StaffManager::new();
+        // the line width set by the caller can still be changed by the width option
+        staff_manager.options.line_width = self.line_width;
+        staff_manager.options.click = self.click;
+        staff_manager.html = self.html;
+        // set by a highlight token and used up by the number token that follows it
+        let mut highlight = false;
+        // set by an articulation token and used up by the number token that follows it
+        let mut articulation = None;
+        // set by a tap, slap, or pop token and kept as the articulation of the tab of the number after it
+        let mut stroke = None;
+        // set by a grace note token and kept as the grace note of the tab of the number after it
+        let mut grace = None;
+
+        for diagnostic in self.stream_errors.iter() {
+            self.watcher.report(diagnostic.clone());
+        }
+        let mut index = 0;
+        loop {
+            // tokens are taken from the stream once every token taken before them has been added
+            if index == self.source.len() {
+                match self.stream.as_mut().and_then(Iterator::next) {
+                    Some(Ok(token)) => self.source.to_mut().push(token),
+                    Some(Err(e)) => {
+                        for diagnostic in e.diagnostics() {
+                            self.watcher.report(diagnostic.clone());
+                            self.stream_errors.push(diagnostic.clone());
+                        }
+                        continue
+                    },
+                    None => break,
+                }
+            }
+            let token = &self.source[index];
+            index += 1;
+            staff_manager.set_line(token.line);
+            staff_manager.set_column(token.column);
+            // check the token type and add to the staff manager based on type
+            let added = match token.type_of {
+                TokenType::Note => {
+                    let note = match staff_manager.options.key {
+                        Some(key) if self.normalize_accidentals => key.spell(&token.value),
+                        _ => token.value.to_string(),
+                    };
+                    staff_manager.add_note(note);
+                    Ok(())
+                },
+                TokenType::Number if articulation.is_some() => {
+                    staff_manager.add_articulated_tab(&token.value, articulation.take().unwrap_or('-'))
+                },
+                TokenType::Number if highlight => {
+                    highlight = false;
+                    staff_manager.add_highlighted_tab(&token.value)
+                },
+                TokenType::Number => staff_manager.add_tab(&token.value),
+                // drum hits are placed the same as tabs, with the hit symbol in place of a fret
+                TokenType::Hit if highlight => {
+                    highlight = false;
+                    staff_manager.add_highlighted_tab(&token.value)
+                },
+                TokenType::Hit => staff_manager.add_tab(&token.value),
+                TokenType::Highlight => {
+                    highlight = true;
+                    Ok(())
+                },
+                TokenType::HammerOn | TokenType::PullOff | TokenType::Bend | TokenType::Release
+                | TokenType::SlideUp | TokenType::SlideDown => {
+                    articulation = token.value.chars().next();
+                    Ok(())
+                },
+                TokenType::Tap | TokenType::Slap | TokenType::Pop => {
+                    stroke = token.value.chars().next();
+                    Ok(())
+                },
+                TokenType::GraceNote => {
+                    if let Literal::Number(fret) = token.literal {
+                        grace = Some(fret);
+                    }
+                    Ok(())
+                },
+                TokenType::Vibrato => staff_manager.add_vibrato(),
+                TokenType::Accent => staff_manager.add_accent(),
+                TokenType::Staccato => staff_manager.add_staccato(),
+                TokenType::PinchHarmonic => staff_manager.add_harmonic(Harmonic::Pinch),
+                TokenType::NaturalHarmonic => match token.literal {
+                    Literal::Number(fret) => {
+                        let fret = fret.to_string();
+                        let added = if std::mem::take(&mut highlight) {
+                            staff_manager.add_highlighted_tab(&fret)
+                        } else {
+                            staff_manager.add_tab(&fret)
+                        };
+                        added.and_then(|_| staff_manager.add_harmonic(Harmonic::Natural))
+                    },
+                    _ => Ok(()),
+                },
+                TokenType::Fingering => match &token.literal {
+                    Literal::Text(symbol) => match Finger::from_symbol(symbol) {
+                        Some(finger) => staff_manager.add_finger(finger),
+                        None => Err(staff_manager.reject(format!("\"{}\" is not a known finger.", symbol))),
+                    },
+                    _ => Ok(()),
+                },
+                TokenType::Duration => match token.literal {
+                    Literal::Duration(length, dotted) => staff_manager.add_duration(ast::Duration { length, dotted }),
+                    _ => Ok(()),
+                },
+                TokenType::Chord => match &token.literal {
+                    // a highlight before a chord highlights every fret of it
+                    Literal::Chord(frets) => staff_manager.add_chord(frets, std::mem::take(&mut highlight)),
+                    _ => Ok(()),
+                },
+                TokenType::Empty => staff_manager.add_empty(),
+                TokenType::Tie => staff_manager.add_tie(),
+                TokenType::Next => staff_manager.add_next(),
+                TokenType::SpreadEmpty => match token.literal {
+                    Literal::Number(amt) => staff_manager.add_spread_empty(amt),
+                    _ => Ok(()),
+                },
+                TokenType::SpreadNext => match token.literal {
+                    Literal::Number(amt) => staff_manager.add_spread_next(amt),
+                    _ => Ok(()),
+                },
+                TokenType::Rest => match token.literal {
+                    Literal::Number(duration) => staff_manager.add_rest(duration),
+                    _ => Ok(()),
+                },
+                TokenType::RepeatStart => staff_manager.add_repeat_start(),
+                TokenType::BarLine => staff_manager.add_bar_line(),
+                TokenType::Voice => staff_manager.add_voice(),
+                TokenType::TupletStart => match token.literal {
+                    Literal::Number(notes) => staff_manager.start_tuplet(notes),
+                    _ => Ok(()),
+                },
+                TokenType::SpanStart => match &token.literal {
+                    Literal::Text(name) => match Technique::from_name(name) {
+                        Some(technique) => staff_manager.start_span(technique),
+                        None => Err(staff_manager.reject(format!("\"{}\" is not a known technique.", name))),
+                    },
+                    _ => Ok(()),
+                },
+                TokenType::GroupEnd => staff_manager.end_group(),
+                TokenType::Lyric => match &token.literal {
+                    Literal::Text(lyric) => staff_manager.add_lyric(lyric),
+                    _ => Ok(()),
+                },
+                TokenType::ChordName => match &token.literal {
+                    Literal::Text(name) => staff_manager.add_chord_name(name),
+                    _ => Ok(()),
+                },
+                TokenType::Barre => match token.literal {
+                    Literal::Number(fret) => staff_manager.add_hand_position(HandPosition::Barre(fret)),
+                    _ => Ok(()),
+                },
+                TokenType::HandPosition => match token.literal {
+                    Literal::Number(fret) => staff_manager.add_hand_position(HandPosition::Position(fret)),
+                    _ => Ok(()),
+                },
+                TokenType::Section => {
+                    if let Literal::Text(label) = &token.literal {
+                        staff_manager.add_section(label);
+                    }
+                    Ok(())
+                },
+                TokenType::RepeatEnd => match token.literal {
+                    Literal::Number(times) => staff_manager.add_repeat_end(times),
+                    _ => Ok(()),
+                },
+                TokenType::Options if !self.ignore_unknown_options => {
+                    if let Literal::Options(ops) = &token.literal {
+                        if let Err(e) = staff_manager.set_options(ops) {
+                            self.watcher.report(at_token(Diagnostic::new(Severity::Error, token.line, "P001", format!("\n{}", e)), token));
+                        }
+                    }
+                    Ok(())
+                },
+                TokenType::Options => {
+                    if let Literal::Options(ops) = &token.literal {
+                        for op in ops.split(';').filter(|op| !op.trim().is_empty()) {
+                            // unknown options are skipped with a warning instead of an error when they are
+                            // being ignored
+                            let name = op.split('=').next().unwrap_or_default().trim();
+                            if !StaffOptions::exists(name) {
+                                self.watcher.report(at_token(Diagnostic::new(
+                                    Severity::Warning,
+                                    token.line,
+                                    "P002",
+                                    format!("Option \"{}\" is not a known option and was ignored.", name)
+                                ), token));
+                            } else if let Err(e) = staff_manager.set_options(op) {
+                                self.watcher.report(at_token(Diagnostic::new(Severity::Error, token.line, "P001", format!("\n{}", e)), token));
+                            }
+                        }
+                    }
+                    Ok(())
+                },
+                TokenType::FrontMatter => {
+                    if let Literal::FrontMatter(entries) = &token.literal {
+                        // every entry is reported at its own line, and unknown keys are skipped with a warning
+                        for (line, key, value) in entries.iter() {
+                            staff_manager.set_line(*line);
+                            staff_manager.set_column(0);
+                            if !StaffOptions::exists(key) {
+                                self.watcher.report(Diagnostic::new(
+                                    Severity::Warning,
+                                    *line,
+                                    "P002",
+                                    format!("Front matter key \"{}\" is not a known option and was ignored.", key)
+                                ));
+                            } else if let Err(e) = staff_manager.set_option(key, value) {
+                                self.watcher.report(Diagnostic::new(Severity::Error, *line, "P001", format!("\n{}", e)));
+                            }
+                        }
+                    }
+                    Ok(())
+                },
+                TokenType::ScopedOptions => {
+                    if let Literal::Options(ops) = &token.literal {
+                        if let Err(e) = staff_manager.set_scoped_options(ops) {
+                            self.watcher.report(at_token(Diagnostic::new(Severity::Error, token.line, "P001", format!("\n{}", e)), token));
+                        }
+                    }
+                    Ok(())
+                },
+                TokenType::StaffBreak => {
+                    staff_manager.break_staff();
+                    Ok(())
+                },
+                TokenType::Comment => {
+                    if let (true, Literal::Text(comment)) = (self.keep_comments, &token.literal) {
+                        staff_manager.add_comment(comment);
+                    }
+                    Ok(())
+                },
+                TokenType::EndOfFile => {
+                    staff_manager.close_groups();
+                    Ok(())
+                },
+            };
+
+            // a highlight only applies to the tab directly after it, along with the strokes and grace notes
+            // written before that tab
+            if !matches!(
+                token.type_of,
+                TokenType::Highlight | TokenType::Tap | TokenType::Slap | TokenType::Pop | TokenType::GraceNote
+            ) {
+                highlight = false;
+            }
+
+            let added = match (token.type_of == TokenType::Number, added) {
+                (true, Ok(())) => stroke.take().map_or(Ok(()), |stroke| staff_manager.add_stroke(stroke))
+                    .and_then(|_| grace.take().map_or(Ok(()), |fret| staff_manager.add_grace(fret))),
+                (_, added) => added,
+            };
+
+            // tabs that have no staff to be added to are skipped with a warning
+            if let Err(e) = added {
+                let warning = Diagnostic::new(Severity::Warning, token.line, "P003", format!("\"{}\" was ignored because {}", token.value, e));
+                self.watcher.report(at_token(warning, token));
+            }
+            for (line, code, warning) in staff_manager.take_warnings() {
+                self.watcher.warning(line, code, warning);
+            }
+            for (line, code, error) in staff_manager.take_errors() {
+                self.watcher.error(line, code, error);
+            }
+            on_token(index - 1, &staff_manager);
+        }
+        for (line, code, warning) in staff_manager.validate_measures() {
+            self.watcher.warning(line, code, warning);
+        }
+        if self.transpose != 0 {
+            let transposed = staff_manager.transpose(self.transpose);
+            for (line, code, warning) in staff_manager.take_warnings() {
+                self.watcher.warning(line, code, warning);
+            }
+            if let Err(e) = transposed {
+                let line = self.source.last().map_or(1, |token| token.line);
+                self.watcher.error(line, "P008", e.to_string());
+            }
+        }
+        if let Some((first, last, times)) = self.practice_loop {
+            if let Err(e) = staff_manager.loop_measures(first, last, times) {
+                let line = self.source.last().map_or(1, |token| token.line);
+                self.watcher.error(line, "P005", e.to_string());
+            }
+        }
+        self.summary = staff_manager.summary();
+        staff_manager
+    }
+
+    /// Returns all warnings logged while generating tabs, one per line.
+    pub fn warnings(&self) -> String {
+        self.watcher.warnings()
+    }
+
+    /// Returns the starting tempo and every tempo change found while generating tabs, for use as MIDI
+    /// tempo events.
+    pub fn tempo_changes(&self) -> &[TempoChange] {
+        &self.tempo_changes
+    }
+
+    /// Returns every diagnostic logged while generating tabs.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        self.watcher.diagnostics()
+    }
+
+    /// Returns the amount of staffs, measures, tabs played, warnings, and errors found while generating
+    /// tabs. Staffs, measures, and tabs are counted even if the tabs could not be rendered.
+    pub fn summary(&self) -> Summary {
+        Summary {
+            warnings: self.watcher.count(Severity::Warning),
+            errors: self.watcher.count(Severity::Error),
+            ..self.summary
+        }
+    }
+}
+
+impl From<Vec<Token>> for Parser<'_> {
+    /// Creates a new `Parser` that owns the provided tokens.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use data::{Token, TokenType, Literal};
+    /// use parser::Parser;
+    /// 
+    /// let tokens = vec![
+    ///     Token::new(TokenType::Note, String::from("E"), Literal::None, 1),
+    ///     Token::new(TokenType::Number, String::from("3"), Literal::Number(3), 1),
+    ///     Token::new(TokenType::EndOfFile, String::new(), Literal::None, 1),
+    /// ];
+    /// 
+    /// let mut parser = Parser::from(tokens);
+    /// assert_eq!("E  |-3-\n\n     1 \n\n", parser.generate_tabs().unwrap());
+    /// ```
+    fn from(tokens: Vec<Token>) -> Self {
+        Parser::with_source(Cow::Owned(tokens))
+    }
+}
+
+impl FromIterator<Token> for Parser<'_> {
+    /// Creates a new `Parser` that owns the tokens of the provided iterator, collecting every token before
+    /// any is parsed. Use `Parser::from_stream` to parse the tokens of a `Lexer` as they are generated.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use lexer::{Lexer, LexError};
+    /// use parser::Parser;
+    /// 
+    /// let mut parser: Parser = Lexer::from("E\n3").collect::<Result<Parser, LexError>>().unwrap();
+    /// assert_eq!("E  |-3-\n\n     1 \n\n", parser.generate_tabs().unwrap());
+    /// ```
+    fn from_iter<I: IntoIterator<Item = Token>>(tokens: I) -> Self {
+        Parser::from(tokens.into_iter().collect::<Vec<Token>>())
+    }
+}
+
+#[cfg(test)]
+mod parser_tests {
+    use super::*;
+
+    #[test]
+    fn tab_output() {
+        let tokens = vec![
+            Token::new(TokenType::Note, String::from("E"), Literal::None, 1),
+            Token::new(TokenType::Note, String::from("A"), Literal::None, 1),
+            Token::new(TokenType::Note, String::from("D"), Literal::None, 1),
+            Token::new(TokenType::Note, String::from("G"), Literal::None, 1),
+            Token::new(TokenType::Note, String::from("B"), Literal::None, 1),
+            Token::new(TokenType::Note, String::from("E"), Literal::None, 1),
+            Token::new(TokenType::Number, String::from("0"), Literal::Number(0), 2),
+            Token::new(TokenType::Number, String::from("3"), Literal::Number(3), 2),
+            Token::new(TokenType::Number, String::from("5"), Literal::Number(5), 2),
+            Token::new(TokenType::Next, String::from(","), Literal::None, 2),
+            Token::new(TokenType::EndOfFile, String::new(), Literal::None, 2),
+        ];
+
+        let mut parser = Parser::new(&tokens);
+        let expected = String::from("E  |---\nB  |---\nG  |---\nD  |-5-\nA  |-3-\nE  |-0-\n\n     1 \n\n");
+
+        match parser.generate_tabs() {
+            Ok(found) => {
+                assert_eq!(expected, found);
+            },
+            Err(e) => panic!("Could not generate tabs: {}", e),
+        }
+    }
+
+    #[test]
+    fn rest_output() {
+        let mut staff = Staff::new();
+        for note in ["E", "A"].iter() {
+            staff.add_note(note.to_string()).unwrap();
+        }
+        staff.set_time_fidelity(4).unwrap();
+        staff.add_empty();
+        staff.add_empty();
+        staff.add_rest(1);
+        staff.add_tab("3");
+        staff.add_empty();
+
+        let expected = String::from("A  |----R----\nE  |----R--3-\n\n     1  2  3 \n");
+
+        assert_eq!(expected, staff.to_string());
+    }
+
+    #[test]
+    fn rescale_fidelity() {
+        let mut staff = Staff::new();
+        for note in ["E", "A"].iter() {
+            staff.add_note(note.to_string()).unwrap();
+        }
+        staff.set_time_signature((2, 2)).unwrap();
+        staff.set_time_fidelity(4).unwrap();
+        staff.add_tab("3");
+        staff.add_tab("5");
+        staff.add_tab("7");
+        staff.add_empty();
+        staff.add_next();
+        let original = staff.to_string();
+
+        let warnings = staff.rescale_fidelity(8).unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(
+            "A  |-5----------------\nE  |-3-----7----------\n\n     1  e  &  a  2  e \n",
+            staff.to_string()
+        );
+
+        let warnings = staff.rescale_fidelity(4).unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(original, staff.to_string());
+
+        // attacks that land on an already taken beat slot of a coarser grid snap to the next free one
+        let warnings = staff.rescale_fidelity(2).unwrap();
+        assert_eq!(
+            vec![String::from("Tab \"7\" from line 0 at beat slot 2 collides with tab \"3\" at fidelity 2 and was snapped to the next free beat slot 2.")],
+            warnings
+        );
+        assert_eq!("A  |-5----\nE  |-3--7-\n\n     1  2 \n", staff.to_string());
+
+        // with no free beat slot left after it, the attack is dropped
+        let mut staff = Staff::new();
+        staff.add_note(String::from("E")).unwrap();
+        staff.set_time_signature((2, 2)).unwrap();
+        staff.set_time_fidelity(4).unwrap();
+        for tab in ["3", "5", "7"].iter() {
+            staff.add_tab(tab);
+        }
+        let warnings = staff.rescale_fidelity(2).unwrap();
+        assert_eq!(2, warnings.len());
+        assert!(warnings[1].ends_with("and was dropped as no free beat slot is left after it."));
+        assert_eq!("E  |-3--5-\n\n     1  2 \n", staff.to_string());
+    }
+
+    #[test]
+    fn option_listing() {
+        let listing = list_options();
+
+        assert!(listing.contains("time        n/n where 'n' is a whole integer"));
+        assert!(listing.contains("default: 4/4"));
+        assert!(listing.contains("fidelity    n where 'n' is a whole integer"));
+        assert!(listing.contains("default: 16"));
+    }
+
+    #[test]
+    fn front_matter_header() {
+        let tokens = vec![
+            Token::new(
+                TokenType::FrontMatter,
+                String::from("---\ntitle: My Song; Live=1\ntempo: 120\nmood: happy\ncapo: high\n---\n"),
+                Literal::FrontMatter(vec![
+                    (2, String::from("title"), String::from("My Song; Live=1")),
+                    (3, String::from("tempo"), String::from("120")),
+                    (4, String::from("mood"), String::from("happy")),
+                    (5, String::from("capo"), String::from("high")),
+                ]),
+                1
+            ),
+            Token::new(TokenType::Note, String::from("E"), Literal::None, 6),
+            Token::new(TokenType::Number, String::from("0"), Literal::Number(0), 7),
+            Token::new(TokenType::EndOfFile, String::new(), Literal::None, 7),
+        ];
+
+        let mut parser = Parser::new(&tokens);
+        let expected = String::from("My Song; Live=1\nTempo: 120 bpm\n\nE  |-0-\n\n     1 \n\n");
+
+        // values keep their ";" and "=" chars, and every entry is reported at its own line
+        match parser.generate_tabs() {
+            Ok(found) => panic!("Expected an error for the capo value, found {}", found),
+            Err(e) => assert!(e.to_string().starts_with("[5] Error: \n")),
+        }
+        assert_eq!("[4] Warning: Front matter key \"mood\" is not a known option and was ignored.", parser.warnings());
+
+        let mut tokens = tokens.clone();
+        if let Literal::FrontMatter(entries) = &mut tokens[0].literal {
+            entries.pop();
+        }
+        let mut parser = Parser::new(&tokens);
+        assert_eq!(Ok(expected.as_str()), parser.generate_tabs());
+    }
+
+    #[test]
+    fn tabs_before_notes() {
+        let mut staff = Staff::new();
+        staff.add_tab("3");
+        staff.add_next();
+        staff.add_rest(2);
+        assert!(staff.rescale_fidelity(8).unwrap().is_empty());
+
+        let tokens = vec![
+            Token::new(TokenType::Number, String::from("3"), Literal::Number(3), 1),
+            Token::new(TokenType::Note, String::from("E"), Literal::None, 2),
+            Token::new(TokenType::Number, String::from("0"), Literal::Number(0), 3),
+            Token::new(TokenType::EndOfFile, String::new(), Literal::None, 3),
+        ];
+
+        let mut parser = Parser::new(&tokens);
+
+        match parser.generate_tabs() {
+            Ok(found) => assert_eq!("E  |-0-\n\n     1 \n\n", found),
+            Err(e) => panic!("Could not generate tabs: {}", e),
+        }
+        assert_eq!("[1] Warning: \"3\" was ignored because no notes have been added yet.", parser.warnings());
+    }
+
+    #[test]
+    fn dynamic_output() {
+        let tokens = vec![
+            Token::new(TokenType::Options, String::from("[fidelity=4]"), Literal::Options(String::from("fidelity=4")), 1),
+            Token::new(TokenType::Note, String::from("E"), Literal::None, 1),
+            Token::new(TokenType::Note, String::from("A"), Literal::None, 1),
+            Token::new(TokenType::Number, String::from("0"), Literal::Number(0), 2),
+            Token::new(TokenType::Next, String::from(","), Literal::None, 2),
+            Token::new(TokenType::Options, String::from("[dyn=mf]"), Literal::Options(String::from("dyn=mf")), 3),
+            Token::new(TokenType::Number, String::from("12"), Literal::Number(12), 3),
+            Token::new(TokenType::Next, String::from(","), Literal::None, 3),
+            Token::new(TokenType::EndOfFile, String::new(), Literal::None, 3),
+        ];
+
+        let mut parser = Parser::new(&tokens);
+        let expected = String::from("        mf\nA  |-------\nE  |-0--12-\n\n     1  2  \n\n");
+
+        match parser.generate_tabs() {
+            Ok(found) => assert_eq!(expected, found),
+            Err(e) => panic!("Could not generate tabs: {}", e),
+        }
+        assert_eq!(80, Dynamic::MezzoForte.velocity());
+    }
+
+    #[test]
+    fn equal_measures() {
+        let tokens = vec![
+            Token::new(TokenType::Options, String::from("[equalmeasures=on; fidelity=4]"), Literal::Options(String::from("equalmeasures=on; fidelity=4")), 1),
+            Token::new(TokenType::Note, String::from("E"), Literal::None, 2),
+            Token::new(TokenType::Number, String::from("3"), Literal::Number(3), 2),
+            Token::new(TokenType::Options, String::from("[fidelity=8]"), Literal::Options(String::from("fidelity=8")), 3),
+            Token::new(TokenType::Note, String::from("E"), Literal::None, 4),
+            Token::new(TokenType::Number, String::from("3"), Literal::Number(3), 4),
+            Token::new(TokenType::Number, String::from("5"), Literal::Number(5), 4),
+            Token::new(TokenType::Number, String::from("7"), Literal::Number(7), 4),
+            Token::new(TokenType::Number, String::from("8"), Literal::Number(8), 4),
+            Token::new(TokenType::Number, String::from("10"), Literal::Number(10), 4),
+            Token::new(TokenType::Number, String::from("8"), Literal::Number(8), 4),
+            Token::new(TokenType::Number, String::from("7"), Literal::Number(7), 4),
+            Token::new(TokenType::Number, String::from("5"), Literal::Number(5), 4),
+            Token::new(TokenType::EndOfFile, String::new(), Literal::None, 4),
+        ];
+
+        let mut parser = Parser::new(&tokens);
+        let expected = String::from(concat!(
+            "E  |-3-----------------------\n\n     1                       \n\n",
+            "E  |-3--5--7--8--10--8--7--5-\n\n     1  &  2  &  3   &  4  & \n\n",
+        ));
+
+        match parser.generate_tabs() {
+            Ok(found) => assert_eq!(expected, found),
+            Err(e) => panic!("Could not generate tabs: {}", e),
+        }
+    }
+
+    #[test]
+    fn fixed_width_lines() {
+        let tokens = vec![
+            Token::new(TokenType::Options, String::from("[fixedwidth=on; title=Étude]"), Literal::Options(String::from("fixedwidth=on; title=Étude")), 1),
+            Token::new(TokenType::Note, String::from("E"), Literal::None, 2),
+            Token::new(TokenType::Note, String::from("A"), Literal::None, 2),
+            Token::new(TokenType::Note, String::from("D"), Literal::None, 2),
+            Token::new(TokenType::Number, String::from("3"), Literal::Number(3), 3),
+            Token::new(TokenType::Number, String::from("12"), Literal::Number(12), 3),
+            Token::new(TokenType::Next, String::from(","), Literal::None, 3),
+            Token::new(TokenType::Number, String::from("5"), Literal::Number(5), 4),
+            Token::new(TokenType::Rest, String::from("r8"), Literal::Number(8), 4),
+            Token::new(TokenType::EndOfFile, String::new(), Literal::None, 4),
+        ];
+
+        let mut parser = Parser::new(&tokens);
+
+        match parser.generate_tabs() {
+            Ok(found) => {
+                let width = found.lines().next().unwrap().chars().count();
+                assert!(found.lines().count() > 5);
+                assert!(found.lines().all(|line| line.chars().count() == width));
+            },
+            Err(e) => panic!("Could not generate tabs: {}", e),
+        }
+    }
+
+    #[test]
+    fn seek_output() {
+        let tokens = lexer::Lexer::from("[time=2/4; fidelity=8]\nE\n0 1 2 3 4 5 6").generate_tokens().unwrap().clone();
+
+        // seeking past the first measure starts the output at the second measure's downbeat
+        let mut parser = Parser::new(&tokens);
+        parser.set_seek(2);
+        match parser.generate_tabs() {
+            Ok(found) => assert_eq!("E  |-4--5--6-\n\n     1  &  2 \n\n", found),
+            Err(e) => panic!("Could not generate tabs: {}", e),
+        }
+
+        // seeking to an offbeat keeps the following bar-lines aligned
+        let mut parser = Parser::new(&tokens);
+        parser.set_seek(1);
+        match parser.generate_tabs() {
+            Ok(found) => assert_eq!("E  -2--3-|-4--5--6-\n\n    2  &   1  &  2 \n\n", found),
+            Err(e) => panic!("Could not generate tabs: {}", e),
+        }
+
+        let mut parser = Parser::new(&tokens);
+        parser.set_seek(4);
+        assert!(parser.generate_tabs().is_err());
+    }
+
+    #[test]
+    fn post_processor_output() {
+        let tokens = vec![
+            Token::new(TokenType::Options, String::from("[title=Song]"), Literal::Options(String::from("title=Song")), 1),
+            Token::new(TokenType::Note, String::from("E"), Literal::None, 2),
+            Token::new(TokenType::Number, String::from("3"), Literal::Number(3), 2),
+            Token::new(TokenType::EndOfFile, String::new(), Literal::None, 2),
+        ];
+
+        let mut parser = Parser::new(&tokens);
+        parser.add_post_processor(|staff: &mut String| *staff = staff.replace('-', "="));
+
+        match parser.generate_tabs() {
+            Ok(found) => assert_eq!("Song\n\nE  |=3=\n\n     1 \n\n", found),
+            Err(e) => panic!("Could not generate tabs: {}", e),
+        }
+    }
+
+    #[test]
+    fn chord_diagram_output() {
+        let mut tokens = vec![];
+        for note in ["E", "A", "D", "G", "B", "E"].iter() {
+            tokens.push(Token::new(TokenType::Note, note.to_string(), Literal::None, 1));
+        }
+        tokens.push(Token::new(TokenType::Empty, String::from("."), Literal::None, 2));
+        for fret in ["3", "2", "0", "1", "0"].iter() {
+            tokens.push(Token::new(TokenType::Number, fret.to_string(), Literal::None, 2));
+        }
+        tokens.push(Token::new(TokenType::EndOfFile, String::new(), Literal::None, 2));
+
+        let mut parser = Parser::new(&tokens);
+        parser.set_chord_diagrams(true);
+        let expected = String::from(concat!(
+            "x 3 2 0 1 0\n",
+            "x     o   o\n",
+            "===========\n",
+            "| | | | * |\n",
+            "| | * | | |\n",
+            "| * | | | |\n",
+            "E A D G B E\n",
+        ));
+
+        match parser.generate_tabs() {
+            Ok(found) => assert!(found.ends_with(&expected), "{}", found),
+            Err(e) => panic!("Could not generate tabs: {}", e),
+        }
+    }
+
+    #[test]
+    fn sticky_notes() {
+        let mut tokens = vec![
+            Token::new(TokenType::Options, String::from("[fidelity=4]"), Literal::Options(String::from("fidelity=4")), 1),
+        ];
+        for note in ["E", "A", "D", "G", "B", "E"].iter() {
+            tokens.push(Token::new(TokenType::Note, note.to_string(), Literal::None, 2));
+        }
+        tokens.push(Token::new(TokenType::Number, String::from("3"), Literal::Number(3), 3));
+        tokens.push(Token::new(TokenType::Next, String::from(","), Literal::None, 3));
+        tokens.push(Token::new(TokenType::StaffBreak, String::from("\n"), Literal::None, 4));
+        tokens.push(Token::new(TokenType::Number, String::from("5"), Literal::Number(5), 5));
+        tokens.push(Token::new(TokenType::Next, String::from(","), Literal::None, 5));
+        tokens.push(Token::new(TokenType::EndOfFile, String::new(), Literal::None, 5));
+
+        let mut parser = Parser::new(&tokens);
+        let staff = |fret: &str| format!(
+            "E  |---\nB  |---\nG  |---\nD  |---\nA  |---\nE  |-{}-\n\n     1 \n\n",
+            fret
+        );
+
+        match parser.generate_tabs() {
+            Ok(found) => assert_eq!(staff("3") + &staff("5"), found),
+            Err(e) => panic!("Could not generate tabs: {}", e),
+        }
+    }
+
+    #[test]
+    fn highlight_output() {
+        let tokens = vec![
+            Token::new(TokenType::Note, String::from("E"), Literal::None, 1),
+            Token::new(TokenType::Highlight, String::from("*"), Literal::None, 2),
+            Token::new(TokenType::Number, String::from("5"), Literal::Number(5), 2),
+            Token::new(TokenType::Number, String::from("7"), Literal::Number(7), 2),
+            Token::new(TokenType::EndOfFile, String::new(), Literal::None, 2),
+        ];
+
+        let mut parser = Parser::new(&tokens);
+
+        match parser.generate_tabs() {
+            Ok(found) => assert_eq!("E  |[5]-7-\n\n     1  e \n\n", found),
+            Err(e) => panic!("Could not generate tabs: {}", e),
+        }
+
+        // a highlighted two digit fret keeps its closing bracket and widens its beat column
+        let tokens = lexer::Lexer::from("E\n*12 5").generate_tokens().unwrap().clone();
+        let mut parser = Parser::new(&tokens);
+        assert_eq!(Ok("E  |[12]-5-\n\n     1   e \n\n"), parser.generate_tabs());
+
+        // a highlight is dropped by any token other than the tab after it
+        let tokens = lexer::Lexer::from("E\n* . 5").generate_tokens().unwrap().clone();
+        let mut parser = Parser::new(&tokens);
+        assert_eq!(Ok("E  |----5-\n\n     1  e \n\n"), parser.generate_tabs());
+    }
+
+    #[test]
+    fn ratio_values() {
+        assert_eq!(Ok((2, 1)), StaffOptions::parse_ratio("2:1"));
+        assert_eq!(Ok((3, 2)), StaffOptions::parse_ratio(" 3 : 2 "));
+        assert_eq!(
+            Err(String::from("\tRatio \"2/1\" is improperly formatted. Format should equal \"n:n\" where 'n' is a whole integer.\n")),
+            StaffOptions::parse_ratio("2/1")
+        );
+        assert!(StaffOptions::parse_ratio("a:1").is_err());
+        assert!(StaffOptions::parse_ratio("2:").is_err());
+    }
+
+    #[test]
+    fn measures_per_line() {
+        let tokens = lexer::Lexer::from("[time=2/4; fidelity=4; layout=measures-per-line=2]\nE\n1 2 3 4 5 6 7 8").generate_tokens().unwrap().clone();
+
+        let mut parser = Parser::new(&tokens);
+        let expected = String::from(concat!(
+            "E  |-1--2-|-3--4-\n\n     1  2   1  2 \n",
+            "\n",
+            "E  |-5--6-|-7--8-\n\n     1  2   1  2 \n\n",
+        ));
+
+        match parser.generate_tabs() {
+            Ok(found) => assert_eq!(expected, found),
+            Err(e) => panic!("Could not generate tabs: {}", e),
+        }
+    }
+
+    #[test]
+    fn format_source() {
+        let messy = "\n\n[fidelity=8 ;time=3/4]\nE  A D\n0   12,  3,\n*5 ,r4 10,\n\n\n\nE A D\n:2  3,\n\n";
+        let canonical = "[time=3/4; fidelity=8]\nE A D\n0 12, 3, *5, r4 10,\n\nE A D\n:2 3,\n";
+
+        let mut lex = lexer::Lexer::new(String::from(messy));
+        assert_eq!(canonical, crate::format_source(lex.generate_tokens().unwrap()));
+
+        // formatting canonical source leaves it unchanged
+        let mut lex = lexer::Lexer::new(String::from(canonical));
+        assert_eq!(canonical, crate::format_source(lex.generate_tokens().unwrap()));
+
+        // tabs are written one measure per line with the beats of every measure aligned, while tuplets and
+        // ties written directly after a tab are kept together
+        let messy = "[time=2/4; fidelity=4]\nE A\n0\n3 5 7 10 12\n3{1 2 3} 0 12\n7= 5 7\n3 =\n";
+        let canonical = "[time=2/4; fidelity=4]\nE A\n0 3   5 7\n10 12 3{1 2 3}\n0 12\n7=    5 7\n3 =\n";
+        assert_eq!(canonical, crate::format_source(lexer::Lexer::from(messy).generate_tokens().unwrap()));
+        assert_eq!(canonical, crate::format_source(lexer::Lexer::from(canonical).generate_tokens().unwrap()));
+
+        // front matter values keep their ";" and "=" chars
+        let messy = "---\ntempo:120\ntitle:  Verse; Chorus=2\n---\nE\n0\n";
+        let canonical = "---\ntitle: Verse; Chorus=2\ntempo: 120\n---\nE\n0\n";
+        assert_eq!(canonical, crate::format_source(lexer::Lexer::from(messy).generate_tokens().unwrap()));
+    }
+
+    #[test]
+    fn twelve_lanes() {
+        let mut staff = Staff::new();
+        for note in ["C", "D", "E", "F", "G", "A", "B", "C", "D", "E", "F", "G"].iter() {
+            staff.add_note(note.to_string()).unwrap();
+        }
+        for fret in 0..12 {
+            staff.add_tab(&fret.to_string());
+        }
+        staff.add_tab("7");
+        staff.add_next();
+        staff.add_empty();
+        staff.add_empty();
+        staff.add_tab("12");
+        staff.add_next();
+        staff.add_spread_next(2);
+
+        // every lane lines up no matter how many lanes there are, with the 13th tab wrapping onto the next beat
+        let expected = String::from(
+            "G  |-11--------------\nF  |-10--------------\nE  |-9---------------\nD  |-8---------------\n\
+             C  |-7---------------\nB  |-6---------------\nA  |-5---------------\nG  |-4---------------\n\
+             F  |-3---------------\nE  |-2------12-------\nD  |-1---------------\nC  |-0---7-----------\n\
+             \n     1   e  &   a  2 \n"
+        );
+        assert_eq!(expected, staff.to_string());
+    }
+
+    #[test]
+    fn normalize_accidentals() {
+        let tokens = vec![
+            Token::new(TokenType::Options, String::from("[key=G]"), Literal::Options(String::from("key=G")), 1),
+            Token::new(TokenType::Note, String::from("Bb"), Literal::None, 2),
+            Token::new(TokenType::Note, String::from("Db"), Literal::None, 2),
+            Token::new(TokenType::Note, String::from("E"), Literal::None, 2),
+            Token::new(TokenType::Number, String::from("3"), Literal::Number(3), 3),
+            Token::new(TokenType::Next, String::from(","), Literal::None, 3),
+            Token::new(TokenType::EndOfFile, String::new(), Literal::None, 3),
+        ];
+
+        let mut parser = Parser::new(&tokens);
+        parser.set_normalize_accidentals(true);
+        let expected = String::from("E  |---\nC# |---\nA# |-3-\n\n     1 \n\n");
+        assert_eq!(expected, parser.generate_tabs().unwrap());
+
+        // note names are left as written unless normalizing is turned on
+        let mut parser = Parser::new(&tokens);
+        let expected = String::from("E  |---\nDb |---\nBb |-3-\n\n     1 \n\n");
+        assert_eq!(expected, parser.generate_tabs().unwrap());
+    }
+
+    #[test]
+    fn count_in() {
+        for (time, expected) in [("4/4", "Count in: 1 2 3 4\n\n"), ("3/4", "Count in: 1 2 3\n\n")].iter() {
+            let options = format!("countin=on; time={}", time);
+            let tokens = vec![
+                Token::new(TokenType::Options, format!("[{}]", options), Literal::Options(options), 1),
+                Token::new(TokenType::Note, String::from("E"), Literal::None, 2),
+                Token::new(TokenType::Number, String::from("3"), Literal::Number(3), 2),
+                Token::new(TokenType::EndOfFile, String::new(), Literal::None, 2),
+            ];
+
+            let mut parser = Parser::new(&tokens);
+            let found = parser.generate_tabs().unwrap();
+            assert!(found.starts_with(&format!("{}E  |-3-", expected)), "{}", found);
+        }
+    }
+
+    #[test]
+    fn right_to_left() {
+        let tokens = |options: &str| vec![
+            Token::new(TokenType::Options, format!("[{}]", options), Literal::Options(options.to_string()), 1),
+            Token::new(TokenType::Note, String::from("E"), Literal::None, 2),
+            Token::new(TokenType::Note, String::from("A"), Literal::None, 2),
+            Token::new(TokenType::Number, String::from("0"), Literal::Number(0), 3),
+            Token::new(TokenType::Number, String::from("3"), Literal::Number(3), 3),
+            Token::new(TokenType::Number, String::from("5"), Literal::Number(5), 3),
+            Token::new(TokenType::Next, String::from(","), Literal::None, 3),
+            Token::new(TokenType::Number, String::from("7"), Literal::Number(7), 3),
+            Token::new(TokenType::Next, String::from(","), Literal::None, 3),
+            Token::new(TokenType::EndOfFile, String::new(), Literal::None, 3),
+        ];
+
+        let ltr_tokens = tokens("time=2/4; fidelity=4");
+        let mut ltr = Parser::new(&ltr_tokens);
+        let ltr = ltr.generate_tabs().unwrap().to_string();
+        let rtl_tokens = tokens("time=2/4; fidelity=4; direction=rtl");
+        let mut rtl = Parser::new(&rtl_tokens);
+        let rtl = rtl.generate_tabs().unwrap().to_string();
+
+        assert_eq!("A  |-3----|---\nE  |-0--5-|-7-\n\n     1  2   1 \n\n", ltr);
+        assert_eq!("---|----3-|  A\n-7-|-5--0-|  E\n\n 1   2  1     \n\n", rtl);
+
+        // every line is the mirror image of the left-to-right line
+        for (ltr_line, rtl_line) in ltr.lines().zip(rtl.lines()) {
+            assert_eq!(ltr_line.chars().rev().collect::<String>(), rtl_line);
+        }
+    }
+
+    #[test]
+    fn measure_views() {
+        let mut staff = Staff::new();
+        for note in ["E", "A"].iter() {
+            staff.add_note(note.to_string()).unwrap();
+        }
+        staff.set_time_signature((2, 4)).unwrap();
+        staff.set_time_fidelity(4).unwrap();
+        for tab in ["0", "3"].iter() {
+            staff.add_tab(tab);
+        }
+        staff.add_tab("12");
+        staff.add_next();
+        staff.add_empty();
+        staff.add_tab("5");
+        staff.add_next();
+
+        let measures: Vec<MeasureView> = staff.measures().collect();
+        assert_eq!(
+            vec![
+                MeasureView { start_beat: 0, start_column: 0, lanes: vec![String::from("|-3-----"), String::from("|-0--12-")] },
+                MeasureView { start_beat: 2, start_column: 2, lanes: vec![String::from("|-5----"), String::from("|------")] },
+            ],
+            measures
+        );
+    }
+
+    #[test]
+    fn tempo_changes() {
+        let tokens = vec![
+            Token::new(TokenType::Options, String::from("[tempo=120]"), Literal::Options(String::from("tempo=120")), 1),
+            Token::new(TokenType::Note, String::from("E"), Literal::None, 2),
+            Token::new(TokenType::SpreadNext, String::from(";4"), Literal::Number(4), 3),
+            Token::new(TokenType::Options, String::from("[tempo=140]"), Literal::Options(String::from("tempo=140")), 4),
+            Token::new(TokenType::Number, String::from("3"), Literal::Number(3), 5),
+            Token::new(TokenType::Next, String::from(","), Literal::None, 5),
+            Token::new(TokenType::EndOfFile, String::new(), Literal::None, 5),
+        ];
+
+        let mut parser = Parser::new(&tokens);
+        let found = parser.generate_tabs().unwrap().to_string();
+        assert!(found.starts_with("Tempo: 120 bpm\n\n"), "{}", found);
+        assert!(found.contains("\n                 140 bpm\nE  |"), "{}", found);
+        assert_eq!(
+            &[TempoChange { beat: 0, bpm: 120 }, TempoChange { beat: 1, bpm: 140 }],
+            parser.tempo_changes()
+        );
+        assert_eq!(vec![500_000, 428_571], parser.tempo_changes().iter().map(|t| t.micros_per_beat()).collect::<Vec<u32>>());
+    }
+
+    #[test]
+    fn display_notes() {
+        let tokens = vec![
+            Token::new(TokenType::Options, String::from("[display=notes]"), Literal::Options(String::from("display=notes")), 1),
+            Token::new(TokenType::Note, String::from("E"), Literal::None, 2),
+            Token::new(TokenType::Note, String::from("A"), Literal::None, 2),
+            Token::new(TokenType::Number, String::from("5"), Literal::Number(5), 3),
+            Token::new(TokenType::Number, String::from("1"), Literal::Number(1), 3),
+            Token::new(TokenType::EndOfFile, String::new(), Literal::None, 3),
+        ];
+
+        // fret 5 on the low E string plays an A and fret 1 on the A string plays an A#
+        let mut parser = Parser::new(&tokens);
+        let expected = String::from("A  |-A#-\nE  |-A--\n\n     1  \n\n");
+        assert_eq!(expected, parser.generate_tabs().unwrap());
+    }
+
+    #[test]
+    fn ignore_unknown_options() {
+        let tokens = vec![
+            Token::new(TokenType::Options, String::from("[swing=on; time=3/4]"), Literal::Options(String::from("swing=on; time=3/4")), 1),
+            Token::new(TokenType::Note, String::from("E"), Literal::None, 2),
+            Token::new(TokenType::Number, String::from("3"), Literal::Number(3), 3),
+            Token::new(TokenType::EndOfFile, String::new(), Literal::None, 3),
+        ];
+
+        // unknown options are errors by default
+        let mut parser = Parser::new(&tokens);
+        assert!(parser.generate_tabs().is_err());
+
+        // ignoring unknown options warns and still renders with the known options applied
+        let mut parser = Parser::new(&tokens);
+        parser.set_ignore_unknown_options(true);
+        assert_eq!("E  |-3-\n\n     1 \n\n", parser.generate_tabs().unwrap());
+        assert_eq!("[1] Warning: Option \"swing\" is not a known option and was ignored.", parser.warnings());
+
+        // denied warnings stop the tabs from being generated
+        let mut parser = Parser::new(&tokens);
+        parser.set_ignore_unknown_options(true);
+        parser.set_deny_warnings(true);
+        assert_eq!(
+            Err(String::from("[1] Error: Option \"swing\" is not a known option and was ignored.")),
+            parser.generate_tabs().map_err(|e| e.to_string())
+        );
+        assert_eq!((0, 1), (parser.summary().warnings, parser.summary().errors));
+    }
+
+    #[test]
+    fn heatmap() {
+        // a dense first measure of four tabs followed by a sparse measure of a single tab and a rest
+        let tokens = lexer::Lexer::from("[heatmap=on; time=2/4; fidelity=4]\nE A\n0 3 2 5 7, r4").generate_tokens().unwrap().clone();
+
+        let mut parser = Parser::new(&tokens);
+        let expected = String::from("A  |-3--5-|----R-\nE  |-0--2-|-7--R-\n\n     1  2   1  2 \n    ██████ ▂▂▂▂▂▂\n\n");
+        assert_eq!(expected, parser.generate_tabs().unwrap());
+    }
+
+    #[test]
+    fn missing_lane() {
+        let mut staff_manager = StaffManager::new();
+        staff_manager.add_note(String::from("E"));
+        staff_manager.add_note(String::from("A"));
+        assert_eq!(Ok(()), staff_manager.add_tab("3"));
+        assert_eq!(Ok(()), staff_manager.add_tab("0"));
+
+        // force the string position past the last lane, as a partial edit could
+        staff_manager.staffs[0].string_pos = 2;
+        staff_manager.set_line(3);
+        assert_eq!(Ok(()), staff_manager.add_tab("7"));
+        assert_eq!(
+            vec![(3, "P015", String::from("A cell was dropped because there is no note lane at string position 2."))],
+            staff_manager.take_warnings()
+        );
+
+        // the string position moves on past the missing lane, so the next tabs are added to the lanes again
+        assert_eq!(Ok(()), staff_manager.add_tab("5"));
+        assert_eq!(Ok(()), staff_manager.add_tab("2"));
+        assert!(staff_manager.take_warnings().is_empty());
+        assert_eq!("A  |-0--2-\nE  |-3--5-\n\n     1  e \n\n", staff_manager.to_string());
+    }
+
+    #[test]
+    fn from_conversions() {
+        let mut lex = lexer::Lexer::from("E A\n0 3");
+        let tokens = lex.generate_tokens().unwrap().clone();
+
+        let mut parser: Parser = tokens.into();
+        assert_eq!("A  |-3-\nE  |-0-\n\n     1 \n\n", parser.generate_tabs().unwrap());
+    }
+
+    #[test]
+    fn from_stream() {
+        let taken = std::cell::Cell::new(0);
+        let stream = lexer::Lexer::from("E A\n0 3").inspect(|_| taken.set(taken.get() + 1));
+        let mut parser = Parser::from_stream(stream);
+        // no token is taken until the tabs are generated, and every token is kept to generate them again
+        assert_eq!(0, taken.get());
+        assert_eq!("A  |-3-\nE  |-0-\n\n     1 \n\n", parser.generate_tabs().unwrap());
+        let count = taken.get();
+        assert_eq!("A  |-3-\nE  |-0-\n\n     1 \n\n", parser.generate_tabs().unwrap());
+        assert_eq!(count, taken.get());
+
+        // syntax errors are logged every time, and the tokens after them are still added
+        let mut parser = Parser::from_stream(lexer::Lexer::from("E A\n0 ?\n3"));
+        for _ in 0..2 {
+            match parser.generate_tabs() {
+                Err(ParseError::Invalid(errors)) => assert_eq!(vec![(2, String::from("Unknown character value: ?"))],
+                    errors.iter().map(|e| (e.line, e.message.clone())).collect::<Vec<_>>()),
+                other => panic!("expected the syntax error, got {:?}", other),
+            }
+        }
+        assert_eq!(2, parser.summary().notes);
+    }
+
+    #[test]
+    fn practice_loop() {
+        let tokens = lexer::Lexer::from("[time=2/4; fidelity=4]\nE\n1 2 3 4 5 6 7").generate_tokens().unwrap().clone();
+
+        // measures 2 and 3 hold the tabs 3 to 6
+        let mut parser = Parser::new(&tokens);
+        parser.set_loop(2, 3, 2);
+        let expected = String::from("E  |-3--4-|-5--6-|-3--4-|-5--6-\n\n     1  2   1  2   1  2   1  2 \n\n");
+        assert_eq!(expected, parser.generate_tabs().unwrap());
+
+        // the last measure is cut short by the end of the tabs and is filled to a whole measure
+        let mut parser = Parser::new(&tokens);
+        parser.set_loop(4, 4, 2);
+        let expected = String::from("E  |-7----|-7----\n\n     1  2   1  2 \n\n");
+        assert_eq!(expected, parser.generate_tabs().unwrap());
+
+        let mut parser = Parser::new(&tokens);
+        parser.set_loop(3, 5, 1);
+        assert_eq!(
+            Err(String::from("[3] Error: Cannot loop measures 3-5 as the tabs have measures 1-4.")),
+            parser.generate_tabs().map_err(|e| e.to_string())
+        );
+    }
+
+    #[test]
+    fn measure_warning_lines() {
+        // tabs spilling over onto the next beat are not a warning, as several beats are written per line
+        let tokens = lexer::Lexer::from("E A\n0 3 5 7 1 2 3 4").generate_tokens().unwrap().clone();
+        let mut parser = Parser::new(&tokens);
+        parser.generate_tabs().unwrap();
+        assert_eq!("", parser.warnings());
+
+        // a measure warning names the line of the last tab written in the measure
+        let tokens = lexer::Lexer::from("[time=2/4; fidelity=4]\nE A D\n0 2 2\n0 2 2 1\n0").generate_tokens().unwrap().clone();
+        let mut parser = Parser::new(&tokens);
+        parser.generate_tabs().unwrap();
+        assert_eq!(
+            vec![(5, "P011")],
+            parser.diagnostics().iter().map(|d| (d.line, d.code)).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn parse_score() {
+        use crate::ast::{Event, Note, Rest, Position};
+
+        let mut lex = lexer::Lexer::from("[title=Song]\nE A\n0 3,\nr8\n. 5,");
+        let tokens = lex.generate_tokens().unwrap().clone();
+
+        let score = Parser::new(&tokens).parse().unwrap();
+        assert_eq!(Some(String::from("Song")), score.metadata.title);
+        assert_eq!(vec![String::from("E"), String::from("A")], score.staffs[0].strings);
+        assert_eq!(4, score.staffs[0].slots_per_beat);
+        assert_eq!(1, score.staffs[0].measures.len());
+        assert_eq!(
+            vec![
+                Event::Note(Note { string: 0, fret: 0, highlight: false, articulation: None, vibrato: false, accent: false, staccato: false, duration: None, harmonic: None, grace: None, finger: None, techniques: vec![], position: Position { beat: 0, slot: 0 }, line: 3 }),
+                Event::Note(Note { string: 1, fret: 3, highlight: false, articulation: None, vibrato: false, accent: false, staccato: false, duration: None, harmonic: None, grace: None, finger: None, techniques: vec![], position: Position { beat: 0, slot: 0 }, line: 3 }),
+                Event::Rest(Rest { position: Position { beat: 0, slot: 2 }, slots: 2 }),
+                Event::Note(Note { string: 1, fret: 5, highlight: false, articulation: None, vibrato: false, accent: false, staccato: false, duration: None, harmonic: None, grace: None, finger: None, techniques: vec![], position: Position { beat: 1, slot: 0 }, line: 5 }),
+            ],
+            score.staffs[0].measures[0].events
+        );
+    }
+
+    #[test]
+    fn tuning_option() {
+        let tabs = |source: &str| {
+            let mut lex = lexer::Lexer::from(source);
+            let tokens = lex.generate_tokens().unwrap().clone();
+            let mut parser = Parser::new(&tokens);
+            let result = parser.generate_tabs().map(str::to_string);
+            result
+        };
+
+        let expected = tabs("D A D G A D\n0 2 2 0 0 0,").unwrap();
+        assert_eq!(expected, tabs("[tuning=DADGAD]\n0 2 2 0 0 0,").unwrap());
+        assert_eq!(expected, tabs("[tuning=D,A,D,G,A,D]\n0 2 2 0 0 0,").unwrap());
+
+        let mut options = StaffOptions::new();
+        assert_eq!(Ok(()), options.set("tuning=EbAbDbGbBbEb"));
+        assert_eq!(Some(vec!["Eb", "Ab", "Db", "Gb", "Bb", "Eb"]), options.tuning.as_ref().map(|t| t.iter().map(String::as_str).collect()));
+        assert_eq!(
+            Err(String::from("\tTuning \"DAD\" must have from 4 to 12 strings, found 3.\n")),
+            options.set("tuning=DAD").map_err(|e| e.to_string())
+        );
+        // options are parsed apart from their source, so their errors are placed by the caller
+        assert_eq!(
+            Err(ParseError::Rejected { line: 2, column: 1, message: String::from("\tTuning \"D,A,H,G\" has an invalid note name \"H\".\n") }),
+            options.set("tuning=D,A,H,G").map_err(|e| e.at(2, 1))
+        );
+    }
+
+    #[test]
+    fn articulation_output() {
+        let mut lex = lexer::Lexer::from("E A D\n5h7 . 2,\n7p5");
+        let tokens = lex.generate_tokens().unwrap().clone();
+
+        let mut parser = Parser::new(&tokens);
+        assert_eq!(
+            "D  |----2-------\nA  |------------\nE  |-5-h7-----7-p5-\n\n     1  e  &  a \n\n",
+            parser.generate_tabs().unwrap()
+        );
+    }
+
+    #[test]
+    fn bend_release_vibrato_output() {
+        let mut lex = lexer::Lexer::from("E A\n7b9 . 9r7 .\n5~ 12~\n5h12~ .");
+        let tokens = lex.generate_tokens().unwrap().clone();
+
+        // the column of a cell wider than 3 chars is widened across every lane and the beat counts
+        let mut parser = Parser::new(&tokens);
+        assert_eq!(
+            "A  |-------------12~-------\nE  |-7-b9--9-r7--5~--5-h12~\n\n     1  e  &  a  2   e  &  \n\n",
+            parser.generate_tabs().unwrap()
+        );
+    }
+
+    #[test]
+    fn slide_output() {
+        let mut lex = lexer::Lexer::from("E A\n5/7 .\n12/14~ 7\\5");
+        let tokens = lex.generate_tokens().unwrap().clone();
+
+        let mut parser = Parser::new(&tokens);
+        assert_eq!(
+            "A  |-----------7--\\5-\nE  |-5-/7--12-/14~---\n\n     1  e  &   a   2 \n\n",
+            parser.generate_tabs().unwrap()
+        );
+    }
+
+    #[test]
+    fn chord_output() {
+        let mut lex = lexer::Lexer::from("E A D G\nx320 0\n(3 2 0 0)\n(3 2 0 0 0)");
+        let tokens = lex.generate_tokens().unwrap().clone();
+
+        // the number after a chord starts the next beat column and the chord after it fills the rest of
+        // that column before starting its own
+        let mut parser = Parser::new(&tokens);
+        assert_eq!(
+            "G  |-0-----0-\nD  |-2-----0-\nA  |-3-----2-\nE  |-x--0--3-\n\n     1  e  & \n\n",
+            parser.generate_tabs().unwrap()
+        );
+        assert_eq!(
+            "[4:1] Warning: \"(3 2 0 0 0)\" was ignored because the chord has 5 frets but the staff only has 4 strings.",
+            parser.warnings()
+        );
+    }
+
+    #[test]
+    fn midi_export() {
+        let mut lex = lexer::Lexer::from("[fidelity=4]\nE\n0 r4 5");
+        let tokens = lex.generate_tokens().unwrap().clone();
+
+        let score = Parser::new(&tokens).parse().unwrap();
+        let mut expected = vec![
+            // header: format 0, 1 track, 480 ticks per quarter note
+            0x4D, 0x54, 0x68, 0x64, 0x00, 0x00, 0x00, 0x06, 0x00, 0x00, 0x00, 0x01, 0x01, 0xE0,
+            0x4D, 0x54, 0x72, 0x6B, 0x00, 0x00, 0x00, 0x22,
+        ];
+        expected.extend([
+            // program change and 4/4 time signature
+            0x00, 0xC0, 0x18,
+            0x00, 0xFF, 0x58, 0x04, 0x04, 0x02, 0x18, 0x08,
+            // E2 is played for a beat until the rest
+            0x00, 0x90, 0x28, 0x50,
+            0x83, 0x60, 0x80, 0x28, 0x00,
+            // A2 (fret 5 on E2) is played after the rest until the end of the staff
+            0x83, 0x60, 0x90, 0x2D, 0x50,
+            0x83, 0x60, 0x80, 0x2D, 0x00,
+            0x00, 0xFF, 0x2F, 0x00,
+        ]);
+        assert_eq!(expected, export::midi(&score));
+    }
+
+    #[test]
+    fn midi_timeline() {
+        let mut lex = lexer::Lexer::from("[tempo=60; time=6/8; fidelity=8]\nE\n0 . 5\n[tempo=120]\n7");
+        let tokens = lex.generate_tokens().unwrap().clone();
+
+        let score = Parser::new(&tokens).parse().unwrap();
+        let timeline = export::midi_timeline(&score);
+        // the tempo counts eighth notes in 6/8, so every beat lasts a second until the tempo doubles
+        let times: Vec<(f64, u8)> = timeline.iter().map(|(seconds, bytes)| (*seconds, bytes[0])).collect();
+        assert_eq!(vec![(0.0, 0xC0), (0.0, 0x90), (2.0, 0x80), (2.0, 0x90), (3.0, 0x80), (3.0, 0x90), (3.5, 0x80)], times);
+    }
+
+    #[test]
+    #[cfg(feature = "playback")]
+    fn wav_export() {
+        let mut lex = lexer::Lexer::from("[tempo=120; fidelity=4]\nE\n0 r4 5");
+        let tokens = lex.generate_tokens().unwrap().clone();
+
+        let score = Parser::new(&tokens).parse().unwrap();
+        let wav = export::wav(&score);
+        // 16-bit mono at 44.1kHz
+        assert_eq!(b"RIFF", &wav[..4]);
+        assert_eq!(b"WAVEfmt ", &wav[8..16]);
+        assert_eq!([1, 0, 1, 0, 0x44, 0xAC, 0, 0], wav[20..28]);
+        // three beats at 120 beats per minute, and the fade of the last note
+        let samples = u32::from_le_bytes([wav[40], wav[41], wav[42], wav[43]]) / 2;
+        assert_eq!((1.53 * 44_100.0_f64).ceil() as u32, samples);
+        // the rest is silent
+        let sample = |second: f64| {
+            let at = 44 + 2 * (second * 44_100.0) as usize;
+            i16::from_le_bytes([wav[at], wav[at + 1]])
+        };
+        assert_eq!(0, sample(0.75));
+        assert_ne!(0, sample(0.25));
+        assert_ne!(0, sample(1.25));
+    }
 
-#[cfg(test)]
-mod parser_tests {
-    use super::*;
+    #[test]
+    fn line_width() {
+        let tokens = lexer::Lexer::from("[time=2/4; fidelity=4; width=20]\nE\n1 2 3 4 5 6").generate_tokens().unwrap().clone();
+
+        // every block is wrapped before a measure that would go past 20 chars
+        let mut parser = Parser::new(&tokens);
+        let expected = String::from(concat!(
+            "E  |-1--2-|-3--4-\n\n     1  2   1  2 \n",
+            "\n",
+            "E  |-5--6-\n\n     1  2 \n\n",
+        ));
+
+        match parser.generate_tabs() {
+            Ok(found) => assert_eq!(expected, found),
+            Err(e) => panic!("Could not generate tabs: {}", e),
+        }
+
+        // the width option overrides the line width set on the parser
+        let mut parser = Parser::new(&tokens);
+        parser.set_line_width(Some(200));
+        assert_eq!(Ok(expected.as_str()), parser.generate_tabs());
+
+        // without the option, the line width set on the parser is used
+        let tokens = lexer::Lexer::from("[time=2/4; fidelity=4]\nE\n1 2 3 4 5 6").generate_tokens().unwrap().clone();
+        let mut parser = Parser::new(&tokens);
+        parser.set_line_width(Some(12));
+        assert!(parser.generate_tabs().unwrap().starts_with("E  |-1--2-\n\n     1  2 \n\nE  |-3--4-\n"));
+    }
 
     #[test]
-    fn tab_output() {
+    fn measure_numbers() {
+        let tokens = |options: &str| vec![
+            Token::new(TokenType::Options, format!("[{}]", options), Literal::Options(options.to_string()), 1),
+            Token::new(TokenType::Note, String::from("E"), Literal::None, 2),
+            Token::new(TokenType::Number, String::from("1"), Literal::Number(1), 3),
+            Token::new(TokenType::Number, String::from("2"), Literal::Number(2), 3),
+            Token::new(TokenType::Number, String::from("3"), Literal::Number(3), 3),
+            Token::new(TokenType::StaffBreak, String::from("\n"), Literal::None, 4),
+            Token::new(TokenType::Number, String::from("4"), Literal::Number(4), 5),
+            Token::new(TokenType::EndOfFile, String::new(), Literal::None, 5),
+        ];
+
+        // measures are numbered above their bar-lines across every staff
+        let numbered = tokens("time=2/4; fidelity=4; measures=on");
+        let mut parser = Parser::new(&numbered);
+        assert_eq!(
+            Ok("   1      2\nE  |-1--2-|-3-\n\n     1  2   1 \n\n   3\nE  |-4-\n\n     1 \n\n"),
+            parser.generate_tabs(),
+        );
+
+        // a measure cut short by seeking has no bar-line and is not numbered
+        let mut parser = Parser::new(&numbered);
+        parser.set_seek(1);
+        assert_eq!(
+            Ok("      2\nE  -2-|-3-\n\n    2   1 \n\n   3\nE  |-4-\n\n     1 \n\n"),
+            parser.generate_tabs(),
+        );
+
+        let rtl = tokens("time=2/4; fidelity=4; measures=on; direction=rtl");
+        let mut parser = Parser::new(&rtl);
+        assert!(parser.generate_tabs().unwrap().starts_with("   2      1\n-3-|-2--1-|  E\n"));
+    }
+
+    #[test]
+    fn rest_durations() {
         let tokens = vec![
-            Token::new(TokenType::Note, String::from("E"), Literal::None, 1),
-            Token::new(TokenType::Note, String::from("A"), Literal::None, 1),
-            Token::new(TokenType::Note, String::from("D"), Literal::None, 1),
-            Token::new(TokenType::Note, String::from("G"), Literal::None, 1),
-            Token::new(TokenType::Note, String::from("B"), Literal::None, 1),
-            Token::new(TokenType::Note, String::from("E"), Literal::None, 1),
-            Token::new(TokenType::Number, String::from("0"), Literal::Number(0), 2),
-            Token::new(TokenType::Number, String::from("3"), Literal::Number(3), 2),
-            Token::new(TokenType::Number, String::from("5"), Literal::Number(5), 2),
-            Token::new(TokenType::Next, String::from(","), Literal::None, 2),
-            Token::new(TokenType::EndOfFile, String::new(), Literal::None, 2),
+            Token::new(TokenType::Options, String::from("[time=2/4; fidelity=8]"), Literal::Options(String::from("time=2/4; fidelity=8")), 1),
+            Token::new(TokenType::Note, String::from("E"), Literal::None, 2),
+            Token::new(TokenType::Rest, String::from("r4"), Literal::Number(4), 3),
+            Token::new(TokenType::Rest, String::from("r8"), Literal::Number(8), 3),
+            Token::new(TokenType::Number, String::from("5"), Literal::Number(5), 3),
+            Token::new(TokenType::EndOfFile, String::new(), Literal::None, 3),
         ];
 
+        // a quarter rest takes a whole beat and an eighth rest half of one
         let mut parser = Parser::new(&tokens);
-        let expected = String::from("E  |---\nB  |---\nG  |---\nD  |-5-\nA  |-3-\nE  |-0-\n\n     1 \n\n");
+        assert_eq!(Ok("E  |-R--R--R--5-\n\n     1  &  2  & \n\n"), parser.generate_tabs());
+        assert_eq!(0, parser.summary().warnings);
 
-        match parser.generate_tabs() {
-            Ok(found) => {
-                assert_eq!(expected, found);
-            },
-            Err(e) => panic!("Could not generate tabs: {}", e),
+        // durations that do not fill whole beat slots are errors, as the tabs after them would be out of line
+        for (rest, duration) in [("r0", 0), ("r3", 3), ("r16", 16)] {
+            let source = format!("[fidelity=8]\nE\n{} 5", rest);
+            let tokens = lexer::Lexer::from(source.as_str()).generate_tokens().unwrap().clone();
+            let mut parser = Parser::new(&tokens);
+            assert_eq!(
+                Err(format!(
+                    "[3] Error: A rest of duration {} does not fill a whole number of beat slots at a fidelity of 8; use a duration that divides the fidelity.",
+                    duration
+                )),
+                parser.generate_tabs().map_err(|e| e.to_string())
+            );
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn note_durations() {
+        let mut lex = lexer::Lexer::from("[fidelity=4]\nE\n0:8 5:4.");
+        let tokens = lex.generate_tokens().unwrap().clone();
+
+        // durations are kept in the score but not rendered
+        let mut parser = Parser::new(&tokens);
+        assert_eq!(Ok("E  |-0--5-\n\n     1  2 \n\n"), parser.generate_tabs());
+
+        let score = Parser::new(&tokens).parse().unwrap();
+        let durations: Vec<Option<ast::Duration>> = score.staffs[0].measures[0].events.iter()
+            .filter_map(|event| match event {
+                ast::Event::Note(note) => Some(note.duration),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(vec![Some(ast::Duration { length: 8, dotted: false }), Some(ast::Duration { length: 4, dotted: true })], durations);
+
+        // the eighth note is stopped after 240 ticks instead of ringing until the next note
+        let midi = export::midi(&score);
+        assert!(midi.windows(5).any(|bytes| bytes == [0x81, 0x70, 0x80, 0x28, 0x00]));
+    }
+
+    #[test]
+    fn repeat_output() {
+        let mut lex = lexer::Lexer::from("[time=2/4; fidelity=4]\nE A\n|: 0 3 :| 5 |: 7 :|x3");
+        let tokens = lex.generate_tokens().unwrap().clone();
+
+        // repeats written partway through a measure fill the rest of the measure first
+        let mut parser = Parser::new(&tokens);
+        assert_eq!(
+            Ok(concat!(
+                "                              x3\n",
+                "A  ||:-3----:||------||:------:||\n",
+                "E  ||:-0----:||-5----||:-7----:||\n",
+                "\n       1  2     1  2     1  2 \n\n",
+            )),
+            parser.generate_tabs(),
+        );
+
+        let score = Parser::new(&tokens).parse().unwrap();
+        let repeats: Vec<(bool, Option<u32>)> = score.staffs[0].measures.iter()
+            .map(|measure| (measure.repeat_start, measure.repeat_end))
+            .collect();
+        assert_eq!(vec![(true, Some(2)), (false, None), (true, Some(3))], repeats);
+
+        // the first measure is played twice and the last measure three times
+        let note_ons = export::midi(&score).windows(3).filter(|bytes| bytes[0] == 0x90 && bytes[2] == 0x50).count();
+        assert_eq!(2 * 2 + 1 + 3, note_ons);
+    }
+
+    #[test]
+    fn voices() {
+        let mut lex = lexer::Lexer::from("[time=2/4; fidelity=4]\nE A\n0 . 5 .\n@\n. 3 . 7 . 9\n@\n2");
+        let tokens = lex.generate_tokens().unwrap().clone();
+
+        // every voice starts from the first beat and is merged into the same lanes
+        let mut parser = Parser::new(&tokens);
+        assert_eq!(Ok("A  |-3--7-|-9-\nE  |-0--5-|---\n\n     1  2   1 \n\n"), parser.generate_tabs());
+
+        // a tab on a string and beat already played by an earlier voice is dropped with a warning
+        assert_eq!(
+            "[7] Warning: Tab \"2\" from voice 3 on line 7 is played on the same string and beat as tab \"0\" and was dropped.",
+            parser.warnings()
+        );
+    }
+
+    #[test]
+    fn lyric_output() {
+        let mut lex = lexer::Lexer::from("[time=2/4; fidelity=4]\nE\n\"Hel\" 0 \"lo\" 3 , \"world\" 5");
+        let tokens = lex.generate_tokens().unwrap().clone();
+
+        // every syllable is lined up with the beat count of the beat it is sung at
+        let mut parser = Parser::new(&tokens);
+        assert_eq!(
+            Ok("E  |-0--3-|----5-\n\n     1  2   1  2 \n     Hel lo    world\n\n"),
+            parser.generate_tabs(),
+        );
+
+        let score = Parser::new(&tokens).parse().unwrap();
+        let syllables: Vec<&str> = score.staffs[0].lyrics.iter().map(|(_, lyric)| lyric.as_str()).collect();
+        assert_eq!(vec!["Hel", "lo", "world"], syllables);
+    }
+
+    #[test]
+    fn chord_name_output() {
+        let mut lex = lexer::Lexer::from("[time=2/4; fidelity=4; dyn=mf]\nE A\n<Am> 0 3 <C7> . 12");
+        let tokens = lex.generate_tokens().unwrap().clone();
+
+        // chord names are printed directly above the lanes, lined up with the tabs they label
+        let mut parser = Parser::new(&tokens);
+        assert_eq!(
+            Ok("     mf\n     Am C7\nA  |-3--12-\nE  |-0-----\n\n     1  2  \n\n"),
+            parser.generate_tabs(),
+        );
+    }
+
+    #[test]
+    fn analyzed_chord_output() {
+        let tokens = |source: &str| lexer::Lexer::from(source).generate_tokens().unwrap().clone();
+
+        // beat columns playing a known chord are named, while written chord names and single notes are kept
+        let source = "[fidelity=4]\nE A D\n(0 2 2) <Esus> (0 2 4) 3 (1 3 3)";
+        assert_eq!(
+            Ok("        Esus\nD  |-2--4-----3-\nA  |-2--2-----3-\nE  |-0--0--3--1-\n\n     1  2  3  4 \n\n"),
+            Parser::new(&tokens(source)).generate_tabs()
+        );
+        assert_eq!(
+            Ok("     E5 Esus  F5\nD  |-2--4-----3-\nA  |-2--2-----3-\nE  |-0--0--3--1-\n\n     1  2  3  4 \n\n"),
+            Parser::new(&tokens(&format!("[analyze=chords]\n{}", source))).generate_tabs()
+        );
+
+        // chords are named from the pitches they sound at with the capo, spelled with the key
+        assert_eq!(
+            Ok("Capo 1\n\n     Bbm\nB  |-1-\nG  |-2-\nD  |-2-\nA  |-0-\nE  |-x-\n\n     1 \n\n"),
+            Parser::new(&tokens("[analyze=chords; capo=1; key=F]\nE A D G B\n(x 0 2 2 1)")).generate_tabs()
+        );
+        assert!(Parser::new(&tokens("[analyze=fast]\nE A\n0 3")).generate_tabs().is_err());
+    }
+
+    #[test]
+    fn section_output() {
+        let mut lex = lexer::Lexer::from("[time=2/4; fidelity=4]\n@verse\nE A\n0 3\n@section(\"Bridge\")\n. 5");
+        let tokens = lex.generate_tokens().unwrap().clone();
+
+        // a section label ends the staff before it and is printed as a header above the next staff
+        let mut parser = Parser::new(&tokens);
+        assert_eq!(
+            Ok("[Verse]\nA  |-3-\nE  |-0-\n\n     1 \n\n[Bridge]\nA  |-5-\nE  |---\n\n     1 \n\n"),
+            parser.generate_tabs(),
+        );
+
+        let score = Parser::new(&tokens).parse().unwrap();
+        assert_eq!(
+            vec![Some(String::from("Verse")), Some(String::from("Bridge"))],
+            score.staffs.iter().map(|staff| staff.section.clone()).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn metadata_header() {
+        let mut lex = lexer::Lexer::from("[title=Song; artist=The Band; album=First; tempo=90]\nE\n3");
+        let tokens = lex.generate_tokens().unwrap().clone();
+
+        let mut parser = Parser::new(&tokens);
+        assert_eq!(
+            Ok("Song\nArtist: The Band\nAlbum: First\nTempo: 90 bpm\n\nE  |-3-\n\n     1 \n\n"),
+            parser.generate_tabs(),
+        );
+
+        let score = Parser::new(&tokens).parse().unwrap();
+        assert_eq!(
+            (Some(String::from("The Band")), Some(String::from("First"))),
+            (score.metadata.artist, score.metadata.album)
+        );
+    }
+
+    #[test]
+    fn capo_output() {
+        let mut lex = lexer::Lexer::from("[capo=3]\nE\n2");
+        let tokens = lex.generate_tokens().unwrap().clone();
+
+        // frets are printed as written while the capo is noted in the header
+        let mut parser = Parser::new(&tokens);
+        assert_eq!(Ok("Capo 3\n\nE  |-2-\n\n     1 \n\n"), parser.generate_tabs());
+
+        // fret 2 behind a capo at fret 3 on an E2 string plays an A2
+        let score = Parser::new(&tokens).parse().unwrap();
+        assert_eq!(Some(3), score.capo);
+        assert!(export::midi(&score).windows(3).any(|bytes| bytes == [0x90, 0x2D, 0x50]));
+
+        let mut lex = lexer::Lexer::from("[capo=3; display=notes]\nE\n2");
+        let tokens = lex.generate_tokens().unwrap().clone();
+        let mut parser = Parser::new(&tokens);
+        assert_eq!(Ok("Capo 3\n\nE  |-A-\n\n     1 \n\n"), parser.generate_tabs());
+
+        let mut lex = lexer::Lexer::from("[capo=25]\nE\n2");
+        let tokens = lex.generate_tokens().unwrap().clone();
+        assert!(Parser::new(&tokens).generate_tabs().is_err());
+    }
+
+    #[test]
+    fn transpose_output() {
+        let mut lex = lexer::Lexer::from("E A\n0 3\n. 1");
+        let tokens = lex.generate_tokens().unwrap().clone();
+
+        let mut parser = Parser::new(&tokens);
+        parser.set_transpose(2);
+        assert_eq!(Ok("A  |-5--3-\nE  |-2----\n\n     1  e \n\n"), parser.generate_tabs());
+
+        // fret 0 on the lowest string has no lower string to be moved to
+        let mut parser = Parser::new(&tokens);
+        parser.set_transpose(-1);
+        assert_eq!(Err(String::from(
+            "[3] Error: Cannot transpose fret 0 on the E string on line 2 down 1 semitone as it would be below fret 0 and no lower string is free to play it."
+        )), parser.generate_tabs().map(str::to_string).map_err(|e| e.to_string()));
+        let mut score = Parser::new(&tokens).parse().unwrap();
+        assert_eq!(
+            Err(ParseError::Unplayable { fret: 0, string: String::from("E"), line: 2, semitones: -1 }),
+            score.transpose(-1)
+        );
+
+        let mut lex = lexer::Lexer::from("E A\n. 1");
+        let tokens = lex.generate_tokens().unwrap().clone();
+        // transposing fret 1 on the A string down 2 semitones would go below fret 0, so its G# is played
+        // at fret 4 on the E string instead
+        let mut parser = Parser::new(&tokens);
+        parser.set_transpose(-2);
+        assert_eq!(Ok("A  |---\nE  |-4-\n\n     1 \n\n"), parser.generate_tabs());
+        assert_eq!(
+            "[2] Warning: Fret 1 on the A string was moved to fret 4 on the E string as transposing it down 2 semitones went below fret 0.",
+            parser.warnings()
+        );
+    }
+
+    #[test]
+    fn string_count() {
+        // staffs have as many strings as notes written before their first tab
+        let mut lex = lexer::Lexer::from("[strings=4]\nE A D G\n0 2 2 0\n\n[strings=7]\nB E A D G B E\n0 1 2 3 4 5 6");
+        let tokens = lex.generate_tokens().unwrap().clone();
+        let mut parser = Parser::new(&tokens);
+        assert_eq!(
+            Ok(concat!(
+                "G  |-0-\nD  |-2-\nA  |-2-\nE  |-0-\n\n     1 \n\n",
+                "E  |-6-\nB  |-5-\nG  |-4-\nD  |-3-\nA  |-2-\nE  |-1-\nB  |-0-\n\n     1 \n\n",
+            )),
+            parser.generate_tabs(),
+        );
+
+        let mut lex = lexer::Lexer::from("[strings=7]\nE A D G B E\n0 1 2 3 4 5");
+        let tokens = lex.generate_tokens().unwrap().clone();
+        let mut parser = Parser::new(&tokens);
+        assert_eq!(
+            Err(String::from("[3] Error: The staff has 6 strings but the strings option declares 7; write a note for every string before the tabs.")),
+            parser.generate_tabs().map(str::to_string).map_err(|e| e.to_string())
+        );
+    }
+
+    #[test]
+    fn drum_output() {
+        let mut lex = lexer::Lexer::from("[mode=drums; time=2/4; fidelity=8]\nBD SD HH\nx . x\n. . x\n. g o\n. . *x");
+        let tokens = lex.generate_tokens().unwrap().clone();
+
+        // lane labels are printed in the margin and hits in place of frets
+        let mut parser = Parser::new(&tokens);
+        assert_eq!(
+            Ok("HH |-x--x--o-[x]\nSD |-------g----\nBD |-x----------\n\n     1  &  2  & \n\n"),
+            parser.generate_tabs(),
+        );
+
+        let score = Parser::new(&tokens).parse().unwrap();
+        let hits: Vec<(usize, char)> = score.staffs[0].measures[0].events.iter()
+            .filter_map(|event| match event {
+                ast::Event::Hit(hit) => Some((hit.lane, hit.symbol)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(vec![(0, 'x'), (2, 'x'), (2, 'x'), (1, 'g'), (2, 'o'), (2, 'x')], hits);
+        assert!(score.staffs[0].drums);
+
+        // staffs after the mode is set back to tabs hold notes again
+        let tokens = lexer::Lexer::from("[mode=drums]\nBD SD\nx g\n\n[mode=tabs]\nE A\n0 3").generate_tokens().unwrap().clone();
+        let score = Parser::new(&tokens).parse().unwrap();
+        assert_eq!(vec![true, false], score.staffs.iter().map(|staff| staff.drums).collect::<Vec<_>>());
+        assert!(score.staffs[1].measures[0].events.iter().all(|event| matches!(event, ast::Event::Note(_))));
+    }
+
+    #[test]
+    fn diagnostic_columns() {
+        let source = "E A\n0 3 [time=x]\n5";
+        let tokens = lexer::Lexer::from(source).generate_tokens().unwrap().clone();
+        let error = Parser::new(&tokens).generate_tabs().unwrap_err().with_source(source);
+
+        assert_eq!((2, Some(5), 8), (error.diagnostics()[0].line, error.diagnostics()[0].column, error.diagnostics()[0].length));
+        assert!(error.render().ends_with("\n  |\n2 | 0 3 [time=x]\n  |     ^^^^^^^^"));
+
+        // tokens made without a column are reported at their line only
+        let tokens = vec![
+            Token::new(TokenType::Number, String::from("3"), Literal::Number(3), 1),
+            Token::new(TokenType::EndOfFile, String::new(), Literal::None, 1),
+        ];
+        let mut par = Parser::new(&tokens);
+        par.generate_tabs().unwrap();
+        assert_eq!(None, par.diagnostics()[0].column);
+    }
+
+    #[test]
+    fn short_measures() {
+        let tokens = lexer::Lexer::from("[time=2/4; fidelity=4]\nE A D\n0 3 5 7 2 1 0 0").generate_tokens().unwrap().clone();
+        let mut parser = Parser::new(&tokens);
+        parser.generate_tabs().unwrap();
+
+        assert_eq!(
+            vec!["Measure 2 of staff 1 is short on the D string: 0 of 2 beat slots are filled."],
+            parser.diagnostics().iter().filter(|d| d.code == "P011").map(|d| d.message.as_str()).collect::<Vec<&str>>()
+        );
+
+        // an unfinished last measure is not short when every string is filled to the same beat slot
+        let tokens = lexer::Lexer::from("E A D\n0 3 5\n\nE A\n0 3 , 5 ,").generate_tokens().unwrap().clone();
+        let mut parser = Parser::new(&tokens);
+        parser.generate_tabs().unwrap();
+        assert_eq!("", parser.warnings());
+
+        // bar-lines written partway through a measure find it short or overfull, and the tabs after them
+        // start on the next downbeat
+        let tokens = lexer::Lexer::from("[time=2/4; fidelity=4]\nE A\n0 3 5 7 |\n0 3 5 7 2 1 |\n5 |\n0 3").generate_tokens().unwrap().clone();
+        let mut parser = Parser::new(&tokens);
+        assert_eq!(
+            Ok("A  |-3--7-|-3--7-|-1----|------|-3-\nE  |-0--5-|-0--5-|-2----|-5----|-0-\n\n     1  2   1  2   1  2   1  2   1 \n\n"),
+            parser.generate_tabs()
+        );
+        assert_eq!(
+            vec![
+                (4, String::from("Measure 2 of staff 1 is overfull: 3 of 2 beat slots are filled before the bar-line.")),
+                (5, String::from("Measure 4 of staff 1 is short: 1 of 2 beat slots are filled before the bar-line.")),
+            ],
+            parser.diagnostics().iter().map(|d| (d.line, d.message.clone())).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn multi_digit_frets() {
+        let tokens = lexer::Lexer::from("[time=2/4; fidelity=4]\nE A\n0 100 , 12 3 , 5 ,").generate_tokens().unwrap().clone();
+        let mut parser = Parser::new(&tokens);
+
+        // every beat column is as wide as its widest cell, and the beat counts line up with the columns
+        let expected = String::from("A  |-100----|-3-----|---\nE  |-0------|-12----|-5-\n\n     1    2   1   2   1 \n\n");
+        assert_eq!(Ok(expected.as_str()), parser.generate_tabs().map_err(|e| e.to_string()));
+    }
+
+    #[test]
+    fn kept_comments() {
+        let tokens = lexer::Lexer::from("// intro\nE A\n0 3 // fill\n\n/* solo */\n5 7\n// end").generate_tokens().unwrap().clone();
+
+        // comments are ignored unless they are kept
+        assert_eq!(
+            Parser::new(&tokens[1..]).generate_tabs().map(str::to_string),
+            Parser::new(&tokens).generate_tabs().map(str::to_string),
+        );
+
+        // kept comments are printed above the staff of the next tabs, or below the last staff
+        let mut parser = Parser::new(&tokens);
+        parser.set_keep_comments(true);
+        assert_eq!(
+            Ok("intro\nA  |-3-\nE  |-0-\n\n     1 \n\nfill\nsolo\nA  |-7-\nE  |-5-\n\n     1 \n\nend\n"),
+            parser.generate_tabs(),
+        );
+
+        // formatting keeps every comment in place
+        let source = "// intro\nE A\n0 3 // fill\n\n/* solo */\n5 7\n// end\n";
+        assert_eq!(source, crate::format_source(&tokens));
+        assert_eq!(source, crate::format_source(lexer::Lexer::from(source).generate_tokens().unwrap()));
+    }
+
+    #[test]
+    fn time_change() {
+        let tokens = lexer::Lexer::from("[fidelity=4]\nE A\n0 3 5 7 2\n[time=3/4]\n5 7 , 3 , 2 ,").generate_tokens().unwrap().clone();
+
+        // the measure is closed and the tabs after the change start a new staff in the new time signature
+        let mut parser = Parser::new(&tokens);
+        assert_eq!(
+            Ok(concat!(
+                "A  |-3--7-------\nE  |-0--5--2----\n\n     1  2  3  4 \n\n",
+                "A  |-7-------|---\nE  |-5-----3-|-2-\n\n     1  2  3   1 \n\n",
+            )),
+            parser.generate_tabs(),
+        );
+        assert!(parser.diagnostics().iter().all(|d| d.code != "P011"));
+
+        let score = Parser::new(&tokens).parse().unwrap();
+        assert_eq!(vec![(4, 4), (3, 4)], score.staffs.iter().map(|staff| staff.time_signature).collect::<Vec<_>>());
+        assert_eq!(vec![0, 4], score.staffs.iter().map(|staff| staff.start_beat).collect::<Vec<_>>());
+
+        // a time signature set after the notes of a staff but before its tabs applies to it
+        let tokens = lexer::Lexer::from("E\n[time=3/4]\n0").generate_tokens().unwrap().clone();
+        assert_eq!((3, 4), Parser::new(&tokens).parse().unwrap().staffs[0].time_signature);
+    }
+
+    #[test]
+    fn scoped_options() {
+        let slots = |source: &str| {
+            let tokens = lexer::Lexer::from(source).generate_tokens().unwrap().clone();
+            let score = Parser::new(&tokens).parse().unwrap();
+            score.staffs.iter().map(|staff| staff.slots_per_beat).collect::<Vec<_>>()
+        };
+        // scoped options apply to the next staff only
+        assert_eq!(vec![4, 8, 4], slots("E A\n0 3\n\n[[fidelity=32]]\n5 7\n\n2 0"));
+        // scoped options set before the tabs of a staff apply to it
+        assert_eq!(vec![8, 4], slots("[[fidelity=32]]\nE\n0\n\n2"));
+        // global options set while scoped options are in effect are kept once they end
+        assert_eq!(vec![8, 2], slots("[[fidelity=32]]\nE\n0\n[fidelity=8]\n\n2"));
+
+        let tokens = lexer::Lexer::from("[[tempo=90]]\nE\n0").generate_tokens().unwrap().clone();
+        let mut parser = Parser::new(&tokens);
+        assert!(parser.parse().is_err());
+        assert!(parser.diagnostics().iter().any(|d| d.code == "P001" && d.message.contains("cannot be scoped")));
+    }
+
+    #[test]
+    fn tuplets() {
+        let tokens = lexer::Lexer::from("[fidelity=4]\nE\n0 3{5 7 8} 2 0").generate_tokens().unwrap().clone();
+
+        // the grid is made three times finer so the three beats of the triplet fit into two beats
+        let mut parser = Parser::new(&tokens);
+        assert_eq!(
+            Ok(concat!(
+                "              3:2\n",
+                "E  |-0--------5-----7-----8-----2-------|-0-------\n\n",
+                "     1  .  .  2  .  .  3  .  .  4  .  .   1  .  . \n\n",
+            )),
+            parser.generate_tabs(),
+        );
+        assert!(parser.warnings().is_empty());
+
+        let score = Parser::new(&tokens).parse().unwrap();
+        let positions: Vec<(u32, u32)> = score.staffs[0].measures[0].events.iter()
+            .filter_map(|event| match event {
+                ast::Event::Note(note) => Some((note.position.beat, note.position.slot)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(vec![(0, 0), (1, 0), (1, 2), (2, 1), (3, 0)], positions);
+
+        // a tuplet written with fewer beats than it fits is padded up to its end
+        let tokens = lexer::Lexer::from("[fidelity=4]\nE\n3{5 7} 2").generate_tokens().unwrap().clone();
+        let mut parser = Parser::new(&tokens);
+        assert!(parser.generate_tabs().unwrap().contains("-5-----7-----------2-"));
+        assert!(parser.diagnostics().iter().any(|d| d.code == "P012"));
+    }
+
+    #[test]
+    fn swing_feel() {
+        let tokens = |source: &str| lexer::Lexer::from(source).generate_tokens().unwrap().clone();
+        let straight = tokens("[fidelity=8]\nE\n0 5");
+        let swung = tokens("[fidelity=8; feel=swing]\nE\n0 5");
+
+        // the tabs are printed the same, while the second eighth note is played two thirds into the beat
+        assert_eq!(Parser::new(&straight).generate_tabs(), Parser::new(&swung).generate_tabs());
+        let midi = |tokens: &Vec<Token>| export::midi(&Parser::new(tokens).parse().unwrap());
+        assert!(midi(&straight).windows(5).any(|bytes| bytes == [0x81, 0x70, 0x80, 0x28, 0x00]));
+        assert!(midi(&swung).windows(5).any(|bytes| bytes == [0x82, 0x40, 0x80, 0x28, 0x00]));
+        assert!(midi(&tokens("[fidelity=8; feel=swing=3:1]\nE\n0 5")).windows(5).any(|bytes| bytes == [0x82, 0x68, 0x80, 0x28, 0x00]));
+
+        // quarter notes are swung from the start of their staff, even after a staff that ends between two
+        // quarter notes
+        let score = Parser::new(&tokens("[time=3/8; fidelity=8]\nE\n0 0 0\n\n[time=2/4; feel=swing]\nE\n0 5")).parse().unwrap();
+        let starts: Vec<f64> = export::midi_timeline(&score).into_iter()
+            .filter(|(_, bytes)| bytes[..2] == [0x90, 45])
+            .map(|(seconds, _)| seconds)
+            .collect();
+        assert_eq!(1, starts.len());
+        assert!((starts[0] - (1.5 + 2.0 / 3.0) * 0.5).abs() < 1e-6);
+
+        // the feel is kept per staff, so a feel set after the tabs of a staff starts with the next staff
+        let score = Parser::new(&tokens("[feel=swing]\nE\n0 5\n\n[feel=straight]\nE\n0 5\n[feel=swing=3:1]\n0\n\nE\n5")).parse().unwrap();
+        assert_eq!(vec![Some((2, 1)), None, Some((3, 1))], score.staffs.iter().map(|staff| staff.swing).collect::<Vec<_>>());
+
+        assert!(Parser::new(&tokens("[feel=swing=0:1]\nE\n0")).parse().is_err());
+        assert!(Parser::new(&tokens("[feel=shuffle]\nE\n0")).parse().is_err());
+    }
+
+    #[test]
+    fn fingerings() {
+        let tokens = |source: &str| lexer::Lexer::from(source).generate_tokens().unwrap().clone();
+
+        // fingers are printed below the lanes only when the fingering line is turned on, listed from the
+        // highest string of every beat column
+        let source = "E A\n3(2) 5(T) 7 2(1)";
+        assert_eq!(Ok("A  |-5--2-\nE  |-3--7-\n\n     1  e \n\n"), Parser::new(&tokens(source)).generate_tabs());
+        assert_eq!(
+            Ok("A  |-5--2-\nE  |-3--7-\n     T2 1\n\n     1  e \n\n"),
+            Parser::new(&tokens(&format!("[fingering=on]\n{}", source))).generate_tabs()
+        );
+
+        let score = Parser::new(&tokens(source)).parse().unwrap();
+        let fingers: Vec<Option<Finger>> = score.staffs[0].measures[0].events.iter()
+            .filter_map(|event| match event {
+                ast::Event::Note(note) => Some(note.finger),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(vec![Some(Finger::Middle), Some(Finger::Thumb), None, Some(Finger::Index)], fingers);
+        // alphaTex counts fingers from the thumb, while LilyPond writes the thumb as its own mark
+        assert!(export::alphatex(&score).contains("(3.2{lf 3} 5.1{lf 1})"));
+        assert!(export::to_lilypond(&score).contains("<g,\\2-2 d\\1\\thumb>"));
+    }
+
+    #[test]
+    fn hand_positions() {
+        let tokens = lexer::Lexer::from("E A\nB5 5 7, pos3 3 6(1), 0 9").generate_tokens().unwrap().clone();
+
+        // barres and hand positions are printed above the beat column they are placed at
+        assert_eq!(
+            Ok("     B5    pos3\nA  |-7-----6-----9-\nE  |-5-----3-----0-\n\n     1  e  &  a  2 \n\n"),
+            Parser::new(&tokens).generate_tabs()
+        );
+
+        // fingers are suggested one per fret from the last hand position, unless written after the fret
+        let score = Parser::new(&tokens).parse().unwrap();
+        let staff = &score.staffs[0];
+        assert_eq!(vec![HandPosition::Barre(5), HandPosition::Position(3)], staff.hand_positions.iter().map(|(_, hand_position)| *hand_position).collect::<Vec<_>>());
+        let fingers: Vec<Option<Finger>> = staff.measures[0].events.iter()
+            .filter_map(|event| match event {
+                ast::Event::Note(note) => Some(staff.finger(note)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(vec![Some(Finger::Index), Some(Finger::Ring), Some(Finger::Index), Some(Finger::Index), None, None], fingers);
+    }
+
+    #[test]
+    fn articulation_marks() {
+        let tokens = lexer::Lexer::from("[tempo=60; fidelity=4]\nE A\n3> 5'\n7>' 2").generate_tokens().unwrap().clone();
+
+        // marks are printed above the beat column of the tabs they are written after
+        assert_eq!(
+            Ok("Tempo: 60 bpm\n\n     >. >.\nA  |-5--2-\nE  |-3--7-\n\n     1  2 \n\n"),
+            Parser::new(&tokens).generate_tabs()
+        );
+
+        // accented notes are played louder and staccato notes are stopped halfway to the next note
+        let score = Parser::new(&tokens).parse().unwrap();
+        let timeline = export::midi_timeline(&score);
+        let notes: Vec<(f64, Vec<u8>)> = timeline.into_iter().filter(|(_, bytes)| bytes[0] != 0xC0).collect();
+        assert_eq!(vec![
+            (0.0, vec![0x90, 43, 100]),
+            (0.0, vec![0x90, 50, 80]),
+            (0.5, vec![0x80, 50, 0]),
+            (1.0, vec![0x80, 43, 0]),
+            (1.0, vec![0x90, 47, 100]),
+            (1.0, vec![0x90, 47, 80]),
+            (1.5, vec![0x80, 47, 0]),
+            (2.0, vec![0x80, 47, 0]),
+        ], notes);
+        assert!(export::alphatex(&score).contains("(7.2{ac st} 2.1)"));
+        assert!(export::to_lilypond(&score).contains("<b,\\2->-. b,\\1>4"));
+    }
+
+    #[test]
+    fn click_track() {
+        let tokens = |source: &str| lexer::Lexer::from(source).generate_tokens().unwrap().clone();
+        let clicks = |tokens: &Vec<Token>, click: bool| {
+            let mut parser = Parser::new(tokens);
+            parser.set_click(click);
+            let timeline = export::midi_timeline(&parser.parse().unwrap());
+            timeline.into_iter().filter(|(_, bytes)| bytes[0] == 0x99).collect::<Vec<(f64, Vec<u8>)>>()
+        };
+
+        // the downbeat is accented and the eighth notes between beats are quieter than the beats
+        let clicked = tokens("[tempo=60; time=3/4; fidelity=8; click=on]\nE\n0 . . . . .");
+        let expected: Vec<(f64, Vec<u8>)> = vec![
+            (0.0, vec![0x99, 76, 110]),
+            (0.5, vec![0x99, 77, 50]),
+            (1.0, vec![0x99, 77, 90]),
+            (1.5, vec![0x99, 77, 50]),
+            (2.0, vec![0x99, 77, 90]),
+            (2.5, vec![0x99, 77, 50]),
+        ];
+        assert_eq!(expected, clicks(&clicked, false));
+
+        // the click can be turned on by the caller and still be turned off by the option
+        let source = tokens("[tempo=60; time=3/4; fidelity=8]\nE\n0 . . . . .");
+        assert!(clicks(&source, false).is_empty());
+        assert_eq!(expected, clicks(&source, true));
+        assert!(clicks(&tokens("[click=off]\nE\n0 . . . . ."), true).is_empty());
+
+        // the printed tabs are not changed
+        assert_eq!(Parser::new(&source).generate_tabs(), Parser::new(&clicked).generate_tabs());
+
+        assert!(Parser::new(&tokens("[click=loud]\nE\n0")).parse().is_err());
+    }
+
+    #[test]
+    fn technique_spans() {
+        let tokens = lexer::Lexer::from("E\npm{0 3 3} 5").generate_tokens().unwrap().clone();
+
+        // the span is printed above the beats it covers and kept on every note within it
+        let mut parser = Parser::new(&tokens);
+        assert_eq!(Ok("     P.M.--|\nE  |-0--3--3--5-\n\n     1  e  &  a \n\n"), parser.generate_tabs());
+        let score = Parser::new(&tokens).parse().unwrap();
+        let techniques: Vec<usize> = score.staffs[0].measures[0].events.iter()
+            .filter_map(|event| match event {
+                ast::Event::Note(note) => Some(note.techniques.len()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(vec![1, 1, 1, 0], techniques);
+
+        // a span left open is ended with the staff
+        let tokens = lexer::Lexer::from("E\nlr{0 3").generate_tokens().unwrap().clone();
+        let mut parser = Parser::new(&tokens);
+        assert!(parser.generate_tabs().unwrap().starts_with("     let ring|"));
+        assert!(parser.diagnostics().iter().any(|d| d.code == "P012"));
+    }
+
+    #[test]
+    fn harmonics() {
+        let tokens = lexer::Lexer::from("E A\n<12> 5ph").generate_tokens().unwrap().clone();
+
+        let mut parser = Parser::new(&tokens);
+        assert_eq!(Ok("A  |-5ph\nE  |<12>\n\n     1  \n\n"), parser.generate_tabs());
+
+        // the natural harmonic sounds an octave above the open E string and the pinch harmonic an octave
+        // and a fifth above the fretted D
+        let midi = export::midi(&Parser::new(&tokens).parse().unwrap());
+        assert!(midi.windows(3).any(|bytes| bytes == [0x90, 0x34, 0x50]));
+        assert!(midi.windows(3).any(|bytes| bytes == [0x90, 0x45, 0x50]));
+    }
+
+    #[test]
+    fn strokes() {
+        let tokens = lexer::Lexer::from("E A\nt12 s3, *P5").generate_tokens().unwrap().clone();
+
+        let mut parser = Parser::new(&tokens);
+        assert_eq!(Ok("A  |s3-----\nE  |t12----[P5]\n\n     1   e \n\n"), parser.generate_tabs());
+
+        let score = Parser::new(&tokens).parse().unwrap();
+        let articulations: Vec<Option<char>> = score.staffs[0].measures[0].events.iter().map(|event| match event {
+            ast::Event::Note(note) => note.articulation,
+            _ => None,
+        }).collect();
+        assert_eq!(vec![Some('t'), Some('s'), Some('P')], articulations);
+    }
+
+    #[test]
+    fn grace_notes() {
+        let tokens = lexer::Lexer::from("E A\n7 5 g3~5 .").generate_tokens().unwrap().clone();
+
+        let mut parser = Parser::new(&tokens);
+        assert_eq!(Ok("A  |-5------\nE  |-7-(3)5-\n\n     1  e   \n\n"), parser.generate_tabs());
+
+        // a highlighted grace note keeps both brackets
+        let highlighted = lexer::Lexer::from("E A\n*g3~7 .").generate_tokens().unwrap().clone();
+        assert_eq!(Ok("A  |------\nE  |[(3)7]\n\n     1    \n\n"), Parser::new(&highlighted).generate_tabs());
+
+        // the grace note G2 is played a 32nd note before the A2 it leads into, stopping the B2 before it
+        let score = Parser::new(&tokens).parse().unwrap();
+        assert!(matches!(&score.staffs[0].measures[0].events[2], ast::Event::Note(note) if note.grace == Some(3)));
+        let midi = export::midi(&score);
+        assert!(midi.windows(8).any(|bytes| bytes == [0x3C, 0x80, 0x2F, 0x00, 0x00, 0x90, 0x2B, 0x50]));
+        assert!(midi.windows(8).any(|bytes| bytes == [0x3C, 0x80, 0x2B, 0x00, 0x00, 0x90, 0x2D, 0x50]));
+    }
+
+    #[test]
+    fn ties() {
+        let tokens = lexer::Lexer::from("[time=1/4]\nE A\n5 7 . . . . . . . = . .").generate_tokens().unwrap().clone();
+
+        let mut parser = Parser::new(&tokens);
+        assert_eq!(Ok("A  |-7----------|=7----\nE  |-5----------|------\n\n     1  e  &  a   1  e \n\n"), parser.generate_tabs());
+
+        // the tied E3 is played once and sustains across the bar-line until the end of the staff
+        let midi = export::midi(&Parser::new(&tokens).parse().unwrap());
+        assert_eq!(1, midi.windows(2).filter(|bytes| *bytes == [0x90, 0x34]).count());
+        assert!(midi.windows(4).any(|bytes| bytes == [0x87, 0x40, 0x80, 0x2D]));
+
+        let tokens = lexer::Lexer::from("E A\n=").generate_tokens().unwrap().clone();
+        let mut parser = Parser::new(&tokens);
+        parser.parse().unwrap();
+        assert!(parser.diagnostics().iter().any(|d| d.code == "P003"));
+    }
+
+    #[test]
+    fn import_ascii() {
+        let text = "Intro\ne|---------------|-3--|\nB|---------------|----|\nG|---------------|----|\nD|---------------|----|\nA|-------0-----2-|----|\nE|-0--3----<12>~-|----| x2\n";
+        let tokens = crate::import::ascii(text).unwrap();
+        assert_eq!(
+            "// Intro\nE A D G B E\n,  0,          ;2  3, ;2 . 0, ;2 <12>~, ;2 . 2, ;2\n;4 . . . . . 3 ;11\n",
+            crate::format_source(&tokens)
+        );
+
+        let mut parser = Parser::new(&tokens);
+        assert!(parser.generate_tabs().is_ok());
+
+        assert!(crate::import::ascii("E A\n0 3").is_err());
+    }
+
+    #[test]
+    fn alphatex_export() {
+        let source = "[title=Intro; tempo=90; time=2/4]\nE A D G B E\n|: 3 5h7 . . 0~ ,\npm{ 5 5, } t12 , . <12>, = ;2 :|x2\n\n[tempo=120]\nE A D G B E\n5";
+        let tokens = lexer::Lexer::from(source).generate_tokens().unwrap().clone();
+        let score = Parser::new(&tokens).parse().unwrap();
+        assert_eq!(
+            "\\title \"Intro\"\n\\tempo 90\n.\n\\tuning E4 B3 G3 D3 A2 E2\n\
+            \\ts 2 4 \\ro \\rc 2 (3.6 5.5{h}).16 (7.5 0.2{v}).16 (5.6{pm} 5.5{pm}).16 (12.6).16{t} (12.5{nh}).16 (-.6).8{d} |\n\
+            \\tempo 120 (5.6).4\n",
+            export::alphatex(&score)
+        );
+    }
+
+    #[test]
+    fn lilypond_export() {
+        let source = "[title=Intro; artist=Band; tempo=90; time=2/4; capo=2]\nE A D G B\n|: 3 5h7 . g7~9 , 3 ,\n= <12>, 2/4 ;2 :|\n\n[[time=3/4]]\nE A D G B\n5 t3";
+        let tokens = lexer::Lexer::from(source).generate_tokens().unwrap().clone();
+        let score = Parser::new(&tokens).parse().unwrap();
+        let lilypond = export::to_lilypond(&score);
+        assert!(lilypond.starts_with("\\version \"2.24.0\"\n\n\\header {\n  title = \"Intro\"\n  composer = \"Band\"\n}\n\n"));
+        assert!(lilypond.contains(
+            "music = {\n  \\time 2/4\n  \\repeat volta 2 {\n    \
+            \\tempo 4 = 90 <a,\\5 e\\4>16( \\grace { e'16\\2 } <fis\\4 fis'\\2>16) a,16\\5~ <a,\\5 b\\4\\harmonic>16 \
+            gis,16\\5\\glissando ais,8.\\5 |\n  }\n  \\time 3/4\n  <b,\\5 d\\4>4^\"T\" |\n}\n"
+        ));
+        assert!(lilypond.contains("\\stringTuning <fis, b, e a cis'>"));
+    }
+
+    #[test]
+    fn html_output() {
+        let source = "[title=Riffs & Licks]\nE A\n0 *2, 3h5 <12>,";
+        let tokens = lexer::Lexer::from(source).generate_tokens().unwrap().clone();
+        let mut parser = Parser::new(&tokens);
+        parser.set_html(true);
+        let html = parser.generate_tabs().unwrap();
+        assert!(html.starts_with("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Riffs &amp; Licks</title>\n"));
+        assert!(html.contains("<pre class=\"header\">Riffs &amp; Licks</pre>\n<pre class=\"staff\">"));
+        assert!(html.contains(
+            "<span class=\"note\">A </span> <span class=\"bar\">|</span><span class=\"fret\" title=\"Measure 1, beat 1\">[2]</span>------\
+            <span class=\"fret\" title=\"Measure 1, beat 1 a\">&lt;12&gt;</span>---\n"
+        ));
+        assert!(html.contains("<span class=\"fret\" title=\"Measure 1, beat 1 &amp;\">-3-</span><span class=\"fret\" title=\"Measure 1, beat 1 a\">h5--</span>"));
+        assert!(html.contains("<span class=\"beats\">     1  e  &amp;  a   2 </span></pre>\n</body>\n</html>\n"));
+    }
+
+    #[test]
+    fn svg_export() {
+        let source = "[title=Riffs & Licks]\nE A\n|: 0 2, 3h5 <12>, = :|x3\n5";
+        let tokens = lexer::Lexer::from(source).generate_tokens().unwrap().clone();
+        let score = Parser::new(&tokens).parse().unwrap();
+        let svg = export::svg(&score);
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"420\" height=\"102\" viewBox=\"0 0 420 102\""));
+        assert!(svg.contains("<text x=\"20\" y=\"40\" font-size=\"20\">Riffs &amp; Licks</text>"));
+        // the strings, the time signature, and the repeat bar-lines
+        assert!(svg.contains("<line x1=\"40\" y1=\"82\" x2=\"400\" y2=\"82\" stroke=\"#888\"/>"));
+        assert!(svg.contains("dominant-baseline=\"central\">4</text>"));
+        assert!(svg.contains("<line x1=\"64\" y1=\"70\" x2=\"64\" y2=\"82\" stroke=\"black\" stroke-width=\"3\"/>"));
+        assert!(svg.contains("<text x=\"328\" y=\"64\" font-size=\"10\" text-anchor=\"end\">x3</text>"));
+        // frets on their strings, with articulations above them
+        assert!(svg.contains("<text x=\"80\" y=\"82\" font-size=\"11\" text-anchor=\"middle\" dominant-baseline=\"central\">0</text>"));
+        assert!(svg.contains("<text x=\"80\" y=\"70\" font-size=\"11\" text-anchor=\"middle\" dominant-baseline=\"central\">2</text>"));
+        assert!(svg.contains("dominant-baseline=\"central\">&lt;12&gt;</text>"));
+        assert!(svg.contains("font-size=\"8\" text-anchor=\"middle\">h</text>"));
+        assert!(svg.contains("dominant-baseline=\"central\">(5)</text>"));
+    }
+
+    #[test]
+    fn pdf_output() {
+        let source = String::from("[title=Riffs (Live); artist=Band]\n") + &"E A\n0 2, 3\n\n".repeat(41);
+        let tokens = lexer::Lexer::from(source.as_str()).generate_tokens().unwrap().clone();
+        let pdf = String::from_utf8(Parser::new(&tokens).generate_pdf().unwrap()).unwrap();
+        assert!(pdf.starts_with("%PDF-1.4\n1 0 obj\n<< /Type /Catalog /Pages 2 0 R >>\nendobj\n"));
+        assert!(pdf.contains("<< /Type /Pages /Kids [6 0 R 8 0 R 10 0 R 12 0 R] /Count 4 >>"));
+        assert!(pdf.ends_with("%%EOF\n"));
+        // the title is printed large on the first page, then above every other page
+        assert!(pdf.contains("BT /F3 18.00 Tf 243.50 776.00 Td (Riffs \\(Live\\)) Tj ET\nBT /F1 9.00 Tf 48.00 747.20 Td (Artist: Band) Tj ET\n"));
+        assert!(pdf.contains("BT /F2 9.00 Tf 270.50 818.00 Td (Riffs \\(Live\\)) Tj ET\nBT /F1 9.00 Tf 48.00 783.20 Td (A  |-2----) Tj ET\n"));
+        assert!(pdf.contains("BT /F2 9.00 Tf 272.75 24.00 Td (Page 1 of 4) Tj ET\n"));
+        assert!(pdf.contains("(Page 4 of 4)"));
+    }
+}
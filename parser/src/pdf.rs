@@ -0,0 +1,142 @@
+/// The width and height of an A4 page in PDF points, and the margin left around its content.
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const PAGE_MARGIN: f32 = 48.0;
+/// The largest font size of the tabs; wider tabs are printed smaller so every line fits the page.
+const TAB_FONT_SIZE: f32 = 9.0;
+/// The width of every char of the Courier font, in ems.
+const COURIER_WIDTH: f32 = 0.6;
+/// The rough width of a char of the Helvetica font, in ems, used to center text.
+const HELVETICA_WIDTH: f32 = 0.5;
+const TITLE_FONT_SIZE: f32 = 18.0;
+const MARGIN_FONT_SIZE: f32 = 9.0;
+
+/// Lays out rendered blocks of tabs as a printable PDF songbook of A4 pages. The title is printed large
+/// at the top of the first page, followed by the rest of the header, and is repeated at the top of every
+/// other page, while every page is numbered at its bottom. Blocks are printed in a monospace font as
+/// small as needed for the widest line to fit the page, and every staff starts a new page rather than
+/// being split across pages, along with the section labels and comments above it, unless it is taller
+/// than a whole page.
+pub(crate) fn document(title: Option<&str>, blocks: &[(&str, String)]) -> Vec<u8> {
+    let widest = blocks.iter().flat_map(|(_, text)| text.lines()).map(|line| line.chars().count()).max().unwrap_or(0);
+    let size = TAB_FONT_SIZE.min((PAGE_WIDTH - 2.0 * PAGE_MARGIN) / (widest.max(1) as f32 * COURIER_WIDTH));
+    let leading = size * 1.2;
+
+    // staffs are kept together with the blocks written above them
+    let mut groups: Vec<Vec<&str>> = vec![];
+    let mut above = vec![];
+    for (kind, text) in blocks.iter() {
+        // the title is already printed above the header
+        let lines = text.lines().skip((*kind == "header" && title.is_some()) as usize);
+        above.extend(lines);
+        if !matches!(*kind, "section" | "comment") {
+            groups.push(std::mem::take(&mut above));
+        }
+    }
+    if !above.is_empty() {
+        groups.push(above);
+    }
+
+    let top = PAGE_HEIGHT - PAGE_MARGIN;
+    let first_top = top - if title.is_some() { TITLE_FONT_SIZE * 2.0 } else { 0.0 };
+    // the amount of lines that fit on a page, where the first page leaves room for the title
+    let capacity = |page: usize| ((if page == 0 { first_top } else { top } - PAGE_MARGIN) / leading).max(1.0) as usize;
+    let mut pages: Vec<Vec<&str>> = vec![vec![]];
+    for group in groups {
+        let last = pages.len() - 1;
+        if !pages[last].is_empty() && pages[last].len() + group.len() > capacity(last) {
+            pages.push(vec![]);
+        }
+        for line in group {
+            let last = pages.len() - 1;
+            if pages[last].len() >= capacity(last) {
+                pages.push(vec![]);
+            }
+            // pages never start with a blank line
+            let page = pages.last_mut().unwrap();
+            if !(page.is_empty() && line.trim().is_empty()) {
+                page.push(line);
+            }
+        }
+    }
+
+    let count = pages.len();
+    let contents: Vec<String> = pages.iter().enumerate().map(|(i, lines)| {
+        let mut content = String::new();
+        let mut y = top;
+        match (i, title) {
+            (0, Some(title)) => {
+                content.push_str(&text("F3", TITLE_FONT_SIZE, centered(title, TITLE_FONT_SIZE), top - TITLE_FONT_SIZE, title));
+                y = first_top;
+            },
+            (_, Some(title)) => content.push_str(&text("F2", MARGIN_FONT_SIZE, centered(title, MARGIN_FONT_SIZE), PAGE_HEIGHT - PAGE_MARGIN / 2.0, title)),
+            (_, None) => (),
+        }
+        for line in lines {
+            y -= leading;
+            content.push_str(&text("F1", size, PAGE_MARGIN, y, line));
+        }
+        let number = format!("Page {} of {}", i + 1, count);
+        content.push_str(&text("F2", MARGIN_FONT_SIZE, centered(&number, MARGIN_FONT_SIZE), PAGE_MARGIN / 2.0, &number));
+        content
+    }).collect();
+
+    // objects 1 to 5 are the catalog, the page tree, and the fonts, followed by every page and its
+    // contents, and lastly the document information
+    let mut objects = vec![
+        String::from("<< /Type /Catalog /Pages 2 0 R >>"),
+        format!("<< /Type /Pages /Kids [{}] /Count {} >>", (0..count).map(|i| format!("{} 0 R", 6 + 2 * i)).collect::<Vec<String>>().join(" "), count),
+        String::from("<< /Type /Font /Subtype /Type1 /BaseFont /Courier /Encoding /WinAnsiEncoding >>"),
+        String::from("<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"),
+        String::from("<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>"),
+    ];
+    for (i, content) in contents.iter().enumerate() {
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R /F2 4 0 R /F3 5 0 R >> >> /Contents {} 0 R >>",
+            PAGE_WIDTH, PAGE_HEIGHT, 7 + 2 * i
+        ));
+        objects.push(format!("<< /Length {} >>\nstream\n{}endstream", content.len(), content));
+    }
+    objects.push(format!("<< /Title {} /Producer (tab_notation) >>", string(title.unwrap_or("Guitar tabs"))));
+
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = vec![];
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).as_bytes());
+    }
+    let xref = pdf.len();
+    pdf.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes());
+    for offset in offsets {
+        pdf.extend(format!("{:010} 00000 n \n", offset).as_bytes());
+    }
+    pdf.extend(format!("trailer\n<< /Size {} /Root 1 0 R /Info {} 0 R >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, objects.len(), xref).as_bytes());
+    pdf
+}
+
+/// Returns the x that centers text of the provided font size on the page.
+fn centered(text: &str, size: f32) -> f32 {
+    ((PAGE_WIDTH - text.chars().count() as f32 * size * HELVETICA_WIDTH) / 2.0).max(PAGE_MARGIN)
+}
+
+/// Returns the content stream operators that print a line of text at the provided position.
+fn text(font: &str, size: f32, x: f32, y: f32, line: &str) -> String {
+    format!("BT /{} {:.2} Tf {:.2} {:.2} Td {} Tj ET\n", font, size, x, y, string(line))
+}
+
+/// Returns text as a PDF string. Chars outside of Latin-1 are printed as `?`, apart from block elements,
+/// such as the bars of the heatmap, which are printed as `#`.
+fn string(text: &str) -> String {
+    let mut string = String::from("(");
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => string.push_str(&format!("\\{}", c)),
+            ' '..='~' => string.push(c),
+            '\u{a0}'..='\u{ff}' => string.push_str(&format!("\\{:03o}", c as u32)),
+            '\u{2580}'..='\u{259f}' => string.push('#'),
+            _ => string.push('?'),
+        }
+    }
+    string.push(')');
+    string
+}
@@ -0,0 +1,334 @@
+use data::{Token, TokenType, Literal};
+
+use crate::{Parser, StaffOptions};
+
+/// Re-emits tab notation source from its tokens in a canonical form: tokens separated by single
+/// spaces, next commands attached to the token before them, options ordered the same as the option
+/// listing, repeated blank lines collapsed, tabs written on one line per measure, and the cells of
+/// consecutive measure lines aligned into columns. Formatting already formatted source returns it
+/// unchanged.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use data::{Token, TokenType, Literal};
+/// 
+/// let tokens = vec![
+///     Token::new(TokenType::Options, String::from("[fidelity=8;time=3/4]"), Literal::Options(String::from("fidelity=8;time=3/4")), 1),
+///     Token::new(TokenType::Note, String::from("E"), Literal::None, 2),
+///     Token::new(TokenType::Note, String::from("A"), Literal::None, 2),
+///     Token::new(TokenType::Number, String::from("3"), Literal::Number(3), 3),
+///     Token::new(TokenType::Next, String::from(","), Literal::None, 3),
+///     Token::new(TokenType::EndOfFile, String::new(), Literal::None, 3),
+/// ];
+/// 
+/// assert_eq!("[time=3/4; fidelity=8]\nE A\n3,\n", parser::format_source(&tokens));
+/// ```
+pub fn format_source(tokens: &[Token]) -> String {
+    let ends = beat_ends(tokens);
+
+    // group the tokens into lines along with whether they end a beat; tabs are joined across source lines
+    // and split after every measure, while every other line is kept as written and blank lines are kept as
+    // empty groups
+    let mut lines: Vec<Vec<(&Token, bool)>> = vec![];
+    // the source line of the last token grouped and whether it was part of the tabs
+    let mut last: Option<(u32, bool)> = None;
+    let mut measure_ended = false;
+    for (i, token) in tokens.iter().enumerate() {
+        match token.type_of {
+            TokenType::EndOfFile => (),
+            TokenType::StaffBreak => {
+                // leading and repeated blank lines are dropped
+                if lines.last().is_some_and(|line| !line.is_empty()) {
+                    lines.push(vec![]);
+                }
+                last = None;
+                measure_ended = false;
+            },
+            _ => {
+                let tab = is_tab(token);
+                let new_line = match last {
+                    None => true,
+                    // tokens attached to the cell before them and comments after it stay on its line
+                    Some((line, _)) if is_attached(token, lines.last().and_then(|line| line.last()).map(|(before, _)| *before))
+                        || (token.type_of == TokenType::Comment && token.line == line) => false,
+                    // every voice starts again from the first beat of the staff
+                    _ if measure_ended || token.type_of == TokenType::Voice => true,
+                    Some((line, last_tab)) => line != token.line && !(tab && last_tab),
+                };
+                if new_line {
+                    lines.push(vec![]);
+                    measure_ended = false;
+                }
+                let (beat_ended, measure_end) = ends[i];
+                if let Some(line) = lines.last_mut() {
+                    line.push((token, beat_ended));
+                }
+                measure_ended |= measure_end;
+                last = Some((token.line, tab));
+            },
+        }
+    }
+    // trailing blank lines are dropped
+    while lines.last().is_some_and(|line| line.is_empty()) {
+        lines.pop();
+    }
+
+    let rows: Vec<Row> = lines.iter().map(|line| Row::from_tokens(line)).collect();
+
+    // consecutive measure lines are aligned into columns by padding the cells of every beat to the widest
+    // cell of its column
+    let mut formatted = String::new();
+    let mut i = 0;
+    while i < rows.len() {
+        let block_end = rows[i..].iter().position(|row| !row.aligned).map_or(rows.len(), |len| i + len).max(i + 1);
+        let block = &rows[i..block_end];
+        let columns = block.iter().map(|row| row.cells.len()).max().unwrap_or(0);
+        let widths: Vec<usize> = (0..columns)
+            .map(|c| block.iter().filter_map(|row| row.cells.get(c)).map(|cell| cell.len()).max().unwrap_or(0))
+            .collect();
+
+        for row in block {
+            let mut line = String::new();
+            for (c, cell) in row.cells.iter().enumerate() {
+                if c > 0 { line.push(' '); }
+                line.push_str(cell);
+                if row.aligned {
+                    line.push_str(&" ".repeat(widths[c] - cell.len()));
+                }
+            }
+            formatted.push_str(line.trim_end());
+            formatted.push('\n');
+        }
+        i = block_end;
+    }
+    formatted
+}
+
+/// Returns whether every token fills the last string of a beat, and whether it fills the last beat of
+/// a measure, found by adding the tokens to staffs.
+fn beat_ends(tokens: &[Token]) -> Vec<(bool, bool)> {
+    let mut ends = vec![(false, false); tokens.len()];
+    let mut last = None;
+    Parser::new(tokens).build_staffs_with(|index, staff_manager| {
+        let position = staff_manager.position();
+        if let (Some((staff, column, length)), Some((last_staff, last_column, last_length))) = (position, last) {
+            // the grid of the staff is made finer by tuplets, so columns are compared as parts of a measure
+            if staff == last_staff {
+                ends[index] = (column * last_length > last_column * length, column / length > last_column / last_length);
+            }
+        }
+        last = position;
+    });
+    ends
+}
+
+/// Returns whether a token is written as part of the tabs of a staff rather than on a line of its own.
+fn is_tab(token: &Token) -> bool {
+    !matches!(
+        token.type_of,
+        TokenType::Options | TokenType::ScopedOptions | TokenType::FrontMatter | TokenType::Note | TokenType::Comment
+            | TokenType::Section
+    )
+}
+
+/// Returns whether a token is written as part of the cell of the token before it.
+fn is_attached(token: &Token, before: Option<&Token>) -> bool {
+    if !before.is_some_and(is_tab) {
+        return false
+    }
+    match token.type_of {
+        TokenType::Next | TokenType::GroupEnd | TokenType::Vibrato | TokenType::PinchHarmonic | TokenType::Duration
+        | TokenType::Fingering | TokenType::Accent | TokenType::Staccato | TokenType::HammerOn | TokenType::PullOff
+        | TokenType::Bend | TokenType::Release | TokenType::SlideUp | TokenType::SlideDown => true,
+        // ties are kept attached to the tab before them when they are written directly after it
+        TokenType::Tie => before.is_some_and(|before| is_adjacent(before, token)),
+        // the tab after an articulation is joined with the tab before it
+        TokenType::Number => before.is_some_and(|before| matches!(
+            before.type_of,
+            TokenType::HammerOn | TokenType::PullOff | TokenType::Bend | TokenType::Release | TokenType::SlideUp
+                | TokenType::SlideDown
+        )),
+        _ => false,
+    }
+}
+
+/// Returns whether a token was written directly after the token before it, without a space between them.
+fn is_adjacent(before: &Token, token: &Token) -> bool {
+    token.column > 0 && before.line == token.line && token.column == before.column + before.value.chars().count() as u32
+}
+
+
+/// A single line of formatted source split into the cells of every beat.
+struct Row {
+    cells: Vec<String>,
+    /// Rows made up of only tabs are aligned with the rows around them.
+    aligned: bool,
+}
+
+impl Row {
+    /// Creates a row from the tokens of a single line, along with whether each token ends a beat.
+    fn from_tokens(tokens: &[(&Token, bool)]) -> Row {
+        // the tokens are written as words separated by spaces, which are joined into a cell for every beat
+        let mut cells: Vec<String> = vec![];
+        // the amount of words written when every beat ended
+        let mut beats: Vec<usize> = vec![];
+        let mut aligned = !tokens.is_empty();
+        let mut highlight = false;
+        let mut articulated = false;
+        // the start of a tuplet or technique span is attached to the cell after it
+        let mut group = String::new();
+        // the beats of a tuplet or technique span are kept in a single cell, so the span is not padded apart
+        // when the row is aligned
+        let mut depth = 0;
+        let mut before: Option<&Token> = None;
+        // taps, slaps, pops, and grace notes are attached directly before the tab after them
+        let mut stroke = String::new();
+
+        for (token, beat_ended) in tokens.iter() {
+            match (&token.type_of, &token.literal) {
+                // next commands are attached to the cell before them
+                (TokenType::Next, _) => match cells.last_mut() {
+                    Some(cell) if !highlight => cell.push(','),
+                    _ => cells.push(String::from(",")),
+                },
+                // ties written directly after a tab are attached to it
+                (TokenType::Tie, _) if !highlight && before.is_some_and(|before| is_adjacent(before, token)) => {
+                    if let Some(cell) = cells.last_mut() {
+                        cell.push_str(&token.value);
+                    }
+                },
+                // highlights are attached to the cell after them
+                (TokenType::Highlight, _) => highlight = true,
+                (TokenType::TupletStart | TokenType::SpanStart, _) => {
+                    group.push_str(&token.value);
+                    depth += 1;
+                },
+                (TokenType::Tap | TokenType::Slap | TokenType::Pop | TokenType::GraceNote, _) => stroke.push_str(&token.value),
+                // the end of a tuplet or technique span is attached to the cell before it
+                (TokenType::GroupEnd, _) => {
+                    match cells.last_mut() {
+                        Some(cell) => cell.push('}'),
+                        None => cells.push(String::from("}")),
+                    }
+                    depth = (depth - 1).max(0);
+                    if depth == 0 {
+                        beats.push(cells.len());
+                    }
+                },
+                // articulations join the tabs on either side of them into a single cell
+                (
+                    TokenType::HammerOn | TokenType::PullOff | TokenType::Bend | TokenType::Release
+                    | TokenType::SlideUp | TokenType::SlideDown,
+                    _
+                ) => match cells.last_mut() {
+                    Some(cell) => {
+                        cell.push_str(&token.value);
+                        articulated = true;
+                    },
+                    None => cells.push(token.value.to_string()),
+                },
+                // vibrato is attached to the tab before it
+                (TokenType::Vibrato, _) => match cells.last_mut() {
+                    Some(cell) => cell.push('~'),
+                    None => cells.push(String::from("~")),
+                },
+                // pinch harmonics are attached to the tab before them
+                (TokenType::PinchHarmonic, _) => match cells.last_mut() {
+                    Some(cell) => cell.push_str(&token.value),
+                    None => cells.push(token.value.to_string()),
+                },
+                // durations, fingerings, accents, and staccato marks are attached to the tab before them
+                (TokenType::Duration | TokenType::Fingering | TokenType::Accent | TokenType::Staccato, _) => match cells.last_mut() {
+                    Some(cell) => cell.push_str(&token.value),
+                    None => cells.push(token.value.to_string()),
+                },
+                (TokenType::Number, _) if articulated => {
+                    articulated = false;
+                    if let Some(cell) = cells.last_mut() {
+                        cell.push_str(&token.value);
+                    }
+                },
+                (TokenType::Options, Literal::Options(options)) => {
+                    aligned = false;
+                    let options: Vec<String> = sorted_options(options).iter()
+                        .map(|(name, value)| format!("{}={}", name, value))
+                        .collect();
+                    cells.push(format!("[{}]", options.join("; ")));
+                },
+                (TokenType::ScopedOptions, Literal::Options(options)) => {
+                    aligned = false;
+                    let options: Vec<String> = sorted_options(options).iter()
+                        .map(|(name, value)| format!("{}={}", name, value))
+                        .collect();
+                    cells.push(format!("[[{}]]", options.join("; ")));
+                },
+                (TokenType::FrontMatter, Literal::FrontMatter(entries)) => {
+                    aligned = false;
+                    let mut front_matter = String::from("---");
+                    let mut entries: Vec<(&str, &str)> = entries.iter().map(|(_, key, value)| (key.as_str(), value.as_str())).collect();
+                    sort_options(&mut entries);
+                    for (name, value) in entries {
+                        front_matter.push_str(&format!("\n{}: {}", name, value));
+                    }
+                    front_matter.push_str("\n---");
+                    cells.push(front_matter);
+                },
+                // a line of only comments is not aligned with the tab lines around it
+                (TokenType::Comment, _) => {
+                    if cells.is_empty() { aligned = false; }
+                    cells.push(token.value.to_string());
+                },
+                (type_of, _) => {
+                    if *type_of == TokenType::Note { aligned = false; }
+                    let prefix = if highlight { "*" } else { "" };
+                    let cell = format!("{}{}{}{}", std::mem::take(&mut group), prefix, std::mem::take(&mut stroke), token.value);
+                    cells.push(cell);
+                    highlight = false;
+                },
+            }
+            if *beat_ended && depth == 0 {
+                beats.push(cells.len());
+            }
+            before = Some(token);
+        }
+        if highlight {
+            group.push('*');
+        }
+        if !group.is_empty() {
+            cells.push(group);
+        }
+
+        let mut words = cells.into_iter();
+        let mut cells = vec![];
+        let mut start = 0;
+        for end in beats.into_iter().chain(std::iter::once(usize::MAX)) {
+            let beat: Vec<String> = words.by_ref().take(end.saturating_sub(start)).collect();
+            if !beat.is_empty() {
+                cells.push(beat.join(" "));
+            }
+            start = start.max(end);
+        }
+        Row { cells, aligned }
+    }
+}
+
+/// Splits an options literal into trimmed `(name, value)` pairs ordered the same as the option
+/// listing. Unknown options are kept in their original order after all known options.
+fn sorted_options(options: &str) -> Vec<(&str, &str)> {
+    let mut pairs: Vec<(&str, &str)> = options.split(';')
+        .filter(|option| !option.trim().is_empty())
+        .map(|option| match option.split_once('=') {
+            Some((name, value)) => (name.trim(), value.trim()),
+            None => (option.trim(), ""),
+        })
+        .collect();
+    sort_options(&mut pairs);
+    pairs
+}
+
+/// Orders `(name, value)` pairs the same as the option listing, keeping unknown options in their
+/// original order after all known options.
+fn sort_options(pairs: &mut [(&str, &str)]) {
+    pairs.sort_by_key(|(name, _)| StaffOptions::OPTIONS.iter().position(|spec| spec.name == *name).unwrap_or(usize::MAX));
+}
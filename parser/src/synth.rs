@@ -0,0 +1,106 @@
+/// The amount of samples of synthesized audio played every second.
+const SAMPLE_RATE: u32 = 44_100;
+/// How much of its energy a plucked string keeps every time its wave travels along it; lower values
+/// ring out sooner.
+const DECAY: f32 = 0.996;
+/// How long a note takes to fade out once it is stopped, in seconds, so stopped strings do not click.
+const RELEASE: f64 = 0.03;
+/// The loudness of a note played at the highest velocity, leaving room for chords to be played together.
+const GAIN: f32 = 0.3;
+/// How long a metronome click rings for in seconds, and the pitch of accented and other clicks in hertz.
+const CLICK_LENGTH: f64 = 0.02;
+const CLICK_ACCENT: f64 = 2000.0;
+const CLICK: f64 = 1500.0;
+
+/// A note plucked at a pitch and velocity, from its start until its end in seconds.
+pub(crate) struct Pluck {
+    pub(crate) start: f64,
+    pub(crate) end: f64,
+    pub(crate) pitch: u8,
+    pub(crate) velocity: u8,
+}
+
+/// A metronome click at a velocity, played at its start in seconds.
+pub(crate) struct Click {
+    pub(crate) start: f64,
+    pub(crate) accent: bool,
+    pub(crate) velocity: u8,
+}
+
+/// Synthesizes plucked notes into samples with the Karplus-Strong algorithm: every note starts as a burst
+/// of noise as long as a single period of its pitch, which is averaged with itself as it is played over
+/// and over, so high frequencies die out first the way they do on a plucked string. The noise is seeded
+/// by the order of the notes, so the same notes always sound the same. Clicks are sine waves that fade out
+/// quickly, pitched higher when accented. Samples are scaled down if notes played together would be louder
+/// than the samples can hold.
+pub(crate) fn render(plucks: &[Pluck], clicks: &[Click]) -> Vec<i16> {
+    let length = plucks.iter().map(|pluck| pluck.end + RELEASE)
+        .chain(clicks.iter().map(|click| click.start + CLICK_LENGTH))
+        .fold(0.0, f64::max);
+    let mut mix = vec![0.0f32; (length * SAMPLE_RATE as f64).ceil() as usize];
+
+    let mut seed: u32 = 0x2545_F491;
+    for pluck in plucks.iter() {
+        let frequency = 440.0 * 2f64.powf((pluck.pitch as f64 - 69.0) / 12.0);
+        let period = ((SAMPLE_RATE as f64 / frequency).round() as usize).max(2);
+        let mut wave: Vec<f32> = (0..period).map(|_| {
+            // xorshift noise between -1 and 1
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            seed as f32 / u32::MAX as f32 * 2.0 - 1.0
+        }).collect();
+
+        let gain = GAIN * pluck.velocity as f32 / 127.0;
+        let start = (pluck.start * SAMPLE_RATE as f64) as usize;
+        let end = (pluck.end * SAMPLE_RATE as f64) as usize;
+        let release = (RELEASE * SAMPLE_RATE as f64) as usize;
+        for (i, sample) in mix.iter_mut().skip(start).take((end + release).saturating_sub(start)).enumerate() {
+            let at = i % period;
+            let fade = match (start + i).checked_sub(end) {
+                Some(after) => 1.0 - after as f32 / release as f32,
+                None => 1.0,
+            };
+            *sample += wave[at] * gain * fade;
+            wave[at] = DECAY * 0.5 * (wave[at] + wave[(at + 1) % period]);
+        }
+    }
+
+    for click in clicks.iter() {
+        let frequency = if click.accent { CLICK_ACCENT } else { CLICK };
+        let gain = GAIN * click.velocity as f32 / 127.0;
+        let start = (click.start * SAMPLE_RATE as f64) as usize;
+        let length = (CLICK_LENGTH * SAMPLE_RATE as f64) as usize;
+        for (i, sample) in mix.iter_mut().skip(start).take(length).enumerate() {
+            let phase = std::f64::consts::TAU * frequency * i as f64 / SAMPLE_RATE as f64;
+            *sample += phase.sin() as f32 * gain * (1.0 - i as f32 / length as f32);
+        }
+    }
+
+    let peak = mix.iter().fold(1.0f32, |peak, sample| peak.max(sample.abs()));
+    mix.iter().map(|sample| (sample / peak * i16::MAX as f32) as i16).collect()
+}
+
+/// Writes samples as the bytes of a WAV file of 16-bit mono audio.
+pub(crate) fn wav(samples: &[i16]) -> Vec<u8> {
+    let data = samples.len() as u32 * 2;
+    let mut file = vec![];
+    file.extend(b"RIFF");
+    file.extend((36 + data).to_le_bytes());
+    file.extend(b"WAVE");
+    file.extend(b"fmt ");
+    file.extend(16u32.to_le_bytes());
+    // uncompressed samples on a single channel
+    file.extend(1u16.to_le_bytes());
+    file.extend(1u16.to_le_bytes());
+    file.extend(SAMPLE_RATE.to_le_bytes());
+    file.extend((SAMPLE_RATE * 2).to_le_bytes());
+    file.extend(2u16.to_le_bytes());
+    file.extend(16u16.to_le_bytes());
+    file.extend(b"data");
+    file.extend(data.to_le_bytes());
+    for sample in samples {
+        file.extend(sample.to_le_bytes());
+    }
+    file
+}
@@ -0,0 +1,151 @@
+use data::Diagnostic;
+use lexer::{Lexer, LexError};
+use parser::{Parser, ParseError};
+
+use pyo3::{create_exception, exceptions::PyValueError, prelude::*, types::PyDict, wrap_pyfunction};
+
+create_exception!(
+    tab_notation,
+    TabError,
+    PyValueError,
+    "Raised when tab notation cannot be interpreted. Holds the `line`, `column`, and `code` of the first \
+error found, or `None` when not known, along with `errors`, a list of dicts holding the `line`, `column`, \
+`length`, `code`, and `message` of every error found."
+);
+
+/// An error found on a line of the source and at a column within it when known.
+struct Located<'a> {
+    line: Option<u32>,
+    column: Option<u32>,
+    length: u32,
+    code: Option<&'static str>,
+    message: &'a str,
+}
+
+impl<'a> From<&'a Diagnostic> for Located<'a> {
+    fn from(diagnostic: &'a Diagnostic) -> Self {
+        Located {
+            line: Some(diagnostic.line),
+            column: diagnostic.column,
+            length: diagnostic.length,
+            code: Some(diagnostic.code),
+            message: &diagnostic.message,
+        }
+    }
+}
+
+/// Returns a `TabError` with the rendered message of an error and every error it holds.
+fn tab_error(py: Python<'_>, message: String, errors: &[Located]) -> PyResult<PyErr> {
+    let err = TabError::new_err(message);
+    let value = err.value(py);
+    let first = errors.first();
+    value.setattr("line", first.and_then(|error| error.line))?;
+    value.setattr("column", first.and_then(|error| error.column))?;
+    value.setattr("code", first.and_then(|error| error.code))?;
+    let dicts = errors.iter()
+        .map(|error| {
+            let dict = PyDict::new(py);
+            dict.set_item("line", error.line)?;
+            dict.set_item("column", error.column)?;
+            dict.set_item("length", error.length)?;
+            dict.set_item("code", error.code)?;
+            dict.set_item("message", error.message)?;
+            Ok(dict)
+        })
+        .collect::<PyResult<Vec<_>>>()?;
+    value.setattr("errors", dicts)?;
+    Ok(err)
+}
+
+/// Converts a lex error into a `TabError`.
+fn lex_error(py: Python<'_>, error: LexError) -> PyErr {
+    let message = error.to_string();
+    let errors: Vec<Located> = match error.diagnostics() {
+        [] => vec![Located { line: None, column: None, length: 1, code: None, message: &message }],
+        diagnostics => diagnostics.iter().map(Located::from).collect(),
+    };
+    tab_error(py, error.render(), &errors).unwrap_or_else(|e| e)
+}
+
+/// Converts a parse error of the provided source into a `TabError`.
+fn parse_error(py: Python<'_>, error: ParseError, source: &str) -> PyErr {
+    let error = error.with_source(source);
+    let message = error.to_string();
+    let errors: Vec<Located> = match &error {
+        ParseError::Invalid(diagnostics) => diagnostics.iter().map(Located::from).collect(),
+        ParseError::Unplayable { line, .. } => vec![Located { line: Some(*line), column: None, length: 1, code: None, message: &message }],
+        ParseError::Rejected { line, column, .. } => vec![Located {
+            line: Some(*line).filter(|line| *line > 0),
+            column: Some(*column).filter(|column| *column > 0),
+            length: 1,
+            code: None,
+            message: &message,
+        }],
+    };
+    tab_error(py, error.render(), &errors).unwrap_or_else(|e| e)
+}
+
+/// Renders tab notation as guitar tabs.
+#[pyfunction]
+fn render(py: Python<'_>, source: &str) -> PyResult<String> {
+    let mut lex = Lexer::from(source);
+    let tokens = lex.generate_tokens().map_err(|e| lex_error(py, e))?;
+    let mut par = Parser::new(tokens);
+    let tabs = par.generate_tabs().map_err(|e| parse_error(py, e, source))?;
+    Ok(tabs.to_string())
+}
+
+/// Parses tab notation into a score, as a dict of lists and dicts matching `--emit score-json`.
+#[pyfunction]
+fn parse<'py>(py: Python<'py>, source: &str) -> PyResult<Bound<'py, PyAny>> {
+    let mut lex = Lexer::from(source);
+    let tokens = lex.generate_tokens().map_err(|e| lex_error(py, e))?;
+    let score = Parser::new(tokens).parse().map_err(|e| parse_error(py, e, source))?;
+    let json = serde_json::to_string(&score).expect("scores are always valid JSON");
+    py.import("json")?.call_method1("loads", (json,))
+}
+
+/// Interprets tab notation from Python.
+#[pymodule]
+fn tab_notation(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(render, m)?)?;
+    m.add_function(wrap_pyfunction!(parse, m)?)?;
+    m.add("TabError", m.py().get_type::<TabError>())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod python_tests {
+    use super::*;
+
+    #[test]
+    fn render_and_parse() {
+        Python::attach(|py| {
+            assert_eq!("A  |-3-\nE  |-0-\n\n     1 \n\n", render(py, "E A\n0 3").unwrap());
+            let score = parse(py, "[title=Riffs]\nE A\n0 3").unwrap();
+            assert_eq!("Riffs", score.get_item("title").unwrap().extract::<String>().unwrap());
+            assert_eq!(1, score.get_item("staffs").unwrap().len().unwrap());
+        });
+    }
+
+    #[test]
+    fn errors_carry_lines() {
+        Python::attach(|py| {
+            let err = render(py, "E A\n0 3\nE ? 3").unwrap_err();
+            assert!(err.is_instance_of::<TabError>(py));
+            assert!(err.is_instance_of::<PyValueError>(py));
+            let value = err.value(py);
+            assert_eq!(Some(3), value.getattr("line").unwrap().extract::<Option<u32>>().unwrap());
+            assert_eq!(Some(3), value.getattr("column").unwrap().extract::<Option<u32>>().unwrap());
+            assert_eq!("L001", value.getattr("code").unwrap().extract::<String>().unwrap());
+            let errors = value.getattr("errors").unwrap();
+            assert_eq!(1, errors.len().unwrap());
+            let first = errors.get_item(0).unwrap();
+            assert_eq!("Unknown character value: ?", first.get_item("message").unwrap().extract::<String>().unwrap());
+            assert_eq!("[3:3] Error: Unknown character value: ?\n  |\n3 | E ? 3\n  |   ^", err.value(py).str().unwrap().to_string());
+
+            let err = parse(py, "[tempo=fast]\nE A\n0 3").unwrap_err();
+            assert_eq!(Some(1), err.value(py).getattr("line").unwrap().extract::<Option<u32>>().unwrap());
+        });
+    }
+}
@@ -0,0 +1,174 @@
+use data::Diagnostic;
+use lexer::{Lexer, LexError};
+use parser::{Parser, ParseError};
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+/// An error surfaced to JavaScript as an object, found on a line of the source and at a column within it
+/// when known.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct SourceError {
+    /// The line the error was found on, starting at `1`, if known.
+    pub line: Option<u32>,
+    /// The column the error was found at within its line, starting at `1`, if known.
+    pub column: Option<u32>,
+    /// The amount of characters from the column the error spans; at least `1`.
+    pub length: u32,
+    /// The code of the diagnostic the error comes from, if any.
+    pub code: Option<&'static str>,
+    /// The message to display to the user.
+    pub message: String,
+}
+
+impl From<&Diagnostic> for SourceError {
+    fn from(diagnostic: &Diagnostic) -> Self {
+        SourceError {
+            line: Some(diagnostic.line),
+            column: diagnostic.column,
+            length: diagnostic.length,
+            code: Some(diagnostic.code),
+            message: diagnostic.message.clone(),
+        }
+    }
+}
+
+/// Returns an error for every diagnostic of a lex error, or a single error without a line for any other
+/// error.
+fn lex_errors(error: LexError) -> Vec<SourceError> {
+    match error.diagnostics() {
+        [] => vec![SourceError { line: None, column: None, length: 1, code: None, message: error.to_string() }],
+        diagnostics => diagnostics.iter().map(SourceError::from).collect(),
+    }
+}
+
+/// Returns an error for every diagnostic of a parse error, or a single error for any other error.
+fn parse_errors(error: ParseError) -> Vec<SourceError> {
+    match &error {
+        ParseError::Invalid(diagnostics) => diagnostics.iter().map(SourceError::from).collect(),
+        ParseError::Unplayable { line, .. } => vec![SourceError { line: Some(*line), column: None, length: 1, code: None, message: error.to_string() }],
+        ParseError::Rejected { line, column, .. } => vec![SourceError {
+            line: Some(*line).filter(|line| *line > 0),
+            column: Some(*column).filter(|column| *column > 0),
+            length: 1,
+            code: None,
+            message: error.to_string(),
+        }],
+    }
+}
+
+/// Generates the tokens of a source, written as JSON.
+///
+/// # Errors
+///
+/// This function will error with every error found if the source has incorrect syntax.
+pub fn tokens(source: &str) -> Result<String, Vec<SourceError>> {
+    let mut lex = Lexer::from(source);
+    let tokens = lex.generate_tokens().map_err(lex_errors)?;
+    Ok(serde_json::to_string(tokens).expect("tokens are always valid JSON"))
+}
+
+/// Parses a source into a score, written as JSON.
+///
+/// # Errors
+///
+/// This function will error with every error found if the source has incorrect syntax or cannot be parsed.
+pub fn score(source: &str) -> Result<String, Vec<SourceError>> {
+    let mut lex = Lexer::from(source);
+    let tokens = lex.generate_tokens().map_err(lex_errors)?;
+    let score = Parser::new(tokens).parse().map_err(parse_errors)?;
+    Ok(serde_json::to_string(&score).expect("scores are always valid JSON"))
+}
+
+/// Renders a source as guitar tabs.
+///
+/// # Examples
+///
+/// ```
+/// let tabs = wasm::tabs("E A\n0 3").unwrap();
+///
+/// assert_eq!("A  |-3-\nE  |-0-\n\n     1 \n\n", tabs);
+/// ```
+///
+/// # Errors
+///
+/// This function will error with every error found if the source has incorrect syntax or cannot be parsed.
+pub fn tabs(source: &str) -> Result<String, Vec<SourceError>> {
+    let mut lex = Lexer::from(source);
+    let tokens = lex.generate_tokens().map_err(lex_errors)?;
+    let mut par = Parser::new(tokens);
+    let tabs = par.generate_tabs().map_err(parse_errors)?;
+    Ok(tabs.to_string())
+}
+
+/// Converts JSON into a JavaScript value.
+fn js(json: &str) -> JsValue {
+    js_sys::JSON::parse(json).expect("JSON written by serde is always valid")
+}
+
+/// Converts errors into a JavaScript array of objects with a `line`, `column`, `length`, `code`, and
+/// `message`, where unknown fields are `null`.
+fn js_errors(errors: Vec<SourceError>) -> JsValue {
+    js(&serde_json::to_string(&errors).expect("errors are always valid JSON"))
+}
+
+/// Generates the tokens of a source as an array of objects.
+///
+/// # Errors
+///
+/// Throws an array of error objects if the source has incorrect syntax.
+#[wasm_bindgen]
+pub fn lex(source: &str) -> Result<JsValue, JsValue> {
+    tokens(source).map(|json| js(&json)).map_err(js_errors)
+}
+
+/// Parses a source into a score object.
+///
+/// # Errors
+///
+/// Throws an array of error objects if the source has incorrect syntax or cannot be parsed.
+#[wasm_bindgen]
+pub fn parse(source: &str) -> Result<JsValue, JsValue> {
+    score(source).map(|json| js(&json)).map_err(js_errors)
+}
+
+/// Renders a source as a string of guitar tabs.
+///
+/// # Errors
+///
+/// Throws an array of error objects if the source has incorrect syntax or cannot be parsed.
+#[wasm_bindgen]
+pub fn render(source: &str) -> Result<String, JsValue> {
+    tabs(source).map_err(js_errors)
+}
+
+#[cfg(test)]
+mod wasm_tests {
+    use super::*;
+
+    #[test]
+    fn lex_errors_are_located() {
+        let errors = tokens("E A\n0 3\nE ? 3").unwrap_err();
+        assert_eq!(1, errors.len());
+        assert_eq!((Some(3), Some(3), Some("L001")), (errors[0].line, errors[0].column, errors[0].code));
+        assert_eq!(
+            "[{\"line\":3,\"column\":3,\"length\":1,\"code\":\"L001\",\"message\":\"Unknown character value: ?\"}]",
+            serde_json::to_string(&errors).unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_errors_are_located() {
+        let errors = tabs("[tempo=fast]\nE A\n0 3").unwrap_err();
+        assert_eq!(Some(1), errors[0].line);
+        assert!(errors[0].code.unwrap().starts_with('P'));
+        assert!(score("[tempo=fast]\nE A\n0 3").is_err());
+    }
+
+    #[test]
+    fn sources_are_rendered() {
+        assert_eq!("A  |-3-\nE  |-0-\n\n     1 \n\n", tabs("E A\n0 3").unwrap());
+        assert!(tokens("E A\n0 3").unwrap().starts_with("[{"));
+        assert!(score("E A\n0 3").unwrap().starts_with('{'));
+    }
+}